@@ -1,41 +1,348 @@
-use crate::models::ConnectionConfig;
-use crate::utils::SchemaComparison;
+use super::connection::connect_options;
+use crate::constants::{SCRATCH_DATABASE_PREFIX, WARNING_CHECK_STATEMENT_TIMEOUT};
+use crate::models::{ConnectionConfig, EnhancedDatabaseSchema, SchemaSource};
+use crate::storage::load_snapshot;
+use crate::utils::preflight::{build_preflight_probes, preflight_finding, PreflightReport};
+use crate::utils::renames::resolved_warnings;
+use crate::utils::schema_diff::{ComparisonWarning, WarningSeverity};
+use crate::utils::sql::quote_ident;
+use crate::utils::{
+    get_app_dir, read_ddl_files, resolve_ddl_directory, DdlFile, MigrationScriptOptions,
+    SchemaComparison,
+};
+use chrono::Utc;
+use sqlx::postgres::{PgConnection, PgPool};
+use sqlx::{ConnectOptions, Connection, Executor};
 
 #[tauri::command]
 pub async fn compare_schemas(
-    source_config: ConnectionConfig,
-    target_config: ConnectionConfig,
+    source: SchemaSource,
+    target: SchemaSource,
     schema: Option<String>,
 ) -> Result<SchemaComparison, String> {
-    // Fetch source schema
-    let source_schema = fetch_enhanced_schema(&source_config, schema.clone()).await?;
-
-    // Fetch target schema
-    let target_schema = fetch_enhanced_schema(&target_config, schema).await?;
-
-    // Compare schemas
-    let comparison = crate::utils::compare_schemas(
-        &source_schema,
-        &target_schema,
-        source_config.name.clone(),
-        target_config.name.clone(),
+    let schema_name = schema.unwrap_or_else(|| "public".to_string());
+
+    // Fetch or load source schema
+    let source_schema = resolve_schema_source(&source, &schema_name).await?;
+
+    // Fetch or load target schema
+    let target_schema = resolve_schema_source(&target, &schema_name).await?;
+
+    // Compare schemas; the script is written for the target's schema
+    let mut comparison = crate::utils::compare_schemas(
+        &source_schema.schema,
+        &target_schema.schema,
+        source_schema.label,
+        target_schema.label,
+        target_schema.schema_name,
     );
 
+    // Confirm data-dependent warnings against the target's actual rows. A
+    // snapshot has no rows, so its warnings stay as generated.
+    if let SchemaSource::Connection { config } = &target {
+        check_warnings_against_target(config, &mut comparison).await?;
+    }
+
     Ok(comparison)
 }
 
+/// Warnings once the accepted renames are applied, confirmed against the
+/// target's rows the way `compare_schemas` confirms the original ones
+#[tauri::command]
+pub async fn check_resolved_warnings(
+    target: SchemaSource,
+    comparison: SchemaComparison,
+) -> Result<Vec<ComparisonWarning>, String> {
+    let mut resolved = comparison;
+    resolved.warnings = resolved_warnings(&resolved);
+
+    if let SchemaSource::Connection { config } = &target {
+        check_warnings_against_target(config, &mut resolved).await?;
+    }
+
+    Ok(resolved.warnings)
+}
+
 #[tauri::command]
-pub fn generate_migration_sql(comparison: SchemaComparison) -> Result<String, String> {
-    let migration_script = crate::utils::generate_migration_script(&comparison);
+pub fn generate_migration_sql(
+    comparison: SchemaComparison,
+    options: Option<MigrationScriptOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let migration_script = crate::utils::generate_migration_script(&comparison, &options);
     Ok(migration_script)
 }
 
+#[tauri::command]
+pub fn generate_rollback_sql(
+    comparison: SchemaComparison,
+    options: Option<MigrationScriptOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let rollback_script = crate::utils::generate_rollback_script(&comparison, &options);
+    Ok(rollback_script)
+}
+
+#[tauri::command]
+pub async fn preflight_migration(
+    config: ConnectionConfig,
+    comparison: SchemaComparison,
+    options: Option<MigrationScriptOptions>,
+) -> Result<PreflightReport, String> {
+    let options = options.unwrap_or_default();
+    let (plan, probes) = build_preflight_probes(&comparison, &options);
+
+    let pool = PgPool::connect_with(connect_options(&config))
+        .await
+        .map_err(|e| format!("Error connecting to database: {}", e))?;
+
+    let mut findings = Vec::with_capacity(probes.len());
+    for probe in &probes {
+        let result = match &probe.skipped_because {
+            Some(reason) => Err(reason.clone()),
+            None => query_count_read_only(&pool, &probe.query).await,
+        };
+        let step_description = &plan.steps[probe.step_index].description;
+        findings.push(preflight_finding(probe, step_description, result));
+    }
+
+    pool.close().await;
+
+    let blocking_count = findings
+        .iter()
+        .filter(|f| matches!(f.severity, WarningSeverity::High))
+        .count();
+
+    Ok(PreflightReport {
+        findings,
+        blocking_count,
+    })
+}
+
+struct ResolvedSchema {
+    schema: EnhancedDatabaseSchema,
+    label: String,
+    schema_name: String,
+}
+
+// Live connections are read in the requested schema; snapshots keep the
+// schema they were taken from
+async fn resolve_schema_source(
+    source: &SchemaSource,
+    schema_name: &str,
+) -> Result<ResolvedSchema, String> {
+    match source {
+        SchemaSource::Connection { config } => Ok(ResolvedSchema {
+            schema: fetch_enhanced_schema(config, Some(schema_name.to_string())).await?,
+            label: config.name.clone(),
+            schema_name: schema_name.to_string(),
+        }),
+        SchemaSource::Snapshot { file_name } => {
+            let snapshot = load_snapshot(file_name, get_app_dir()?)?;
+            Ok(ResolvedSchema {
+                label: format!("{} (snapshot {})", snapshot.connection_name, snapshot.created_at),
+                schema_name: snapshot.schema_name,
+                schema: snapshot.schema,
+            })
+        }
+        SchemaSource::DdlDirectory { path, scratch } => Ok(ResolvedSchema {
+            schema: load_ddl_schema(path, scratch, schema_name).await?,
+            label: format!("{} (SQL files)", path),
+            schema_name: schema_name.to_string(),
+        }),
+    }
+}
+
+// Apply a directory of DDL files to a throwaway database and introspect it,
+// so the result matches what a live connection reports. The scratch database
+// is dropped again even when a file fails to apply; on PostgreSQL 13+ the drop
+// is forced, so a connection left behind by a failed step can't block it.
+async fn load_ddl_schema(
+    path: &str,
+    scratch: &ConnectionConfig,
+    schema_name: &str,
+) -> Result<EnhancedDatabaseSchema, String> {
+    let files = read_ddl_files(&resolve_ddl_directory(path)?)?;
+    if files.is_empty() {
+        return Err(format!("No .sql files found in {}", path));
+    }
+
+    let scratch_database = format!(
+        "{}{}",
+        SCRATCH_DATABASE_PREFIX,
+        Utc::now().format("%Y%m%d%H%M%S%6f")
+    );
+
+    let admin_pool = PgPool::connect_with(connect_options(scratch))
+        .await
+        .map_err(|e| format!("Error connecting to scratch server: {}", e))?;
+
+    sqlx::query(&format!("CREATE DATABASE {}", quote_ident(&scratch_database)))
+        .execute(&admin_pool)
+        .await
+        .map_err(|e| format!("Failed to create scratch database: {}", e))?;
+
+    let scratch_config = ConnectionConfig {
+        database: scratch_database.clone(),
+        ..scratch.clone()
+    };
+    let result = apply_ddl_files(&scratch_config, &files, schema_name).await;
+    let result = match result {
+        Ok(()) => fetch_enhanced_schema(&scratch_config, Some(schema_name.to_string())).await,
+        Err(e) => Err(e),
+    };
+
+    let dropped = drop_scratch_database(&admin_pool, &scratch_database).await;
+    admin_pool.close().await;
+
+    match (result, dropped) {
+        (Ok(schema), Ok(())) => Ok(schema),
+        (Ok(_), Err(drop_error)) => Err(drop_error),
+        (Err(e), Ok(())) => Err(e),
+        (Err(e), Err(drop_error)) => Err(format!("{}; {}", e, drop_error)),
+    }
+}
+
+async fn drop_scratch_database(admin_pool: &PgPool, scratch_database: &str) -> Result<(), String> {
+    let server_version: Option<i32> = sqlx::query_scalar::<_, String>("SHOW server_version_num")
+        .fetch_one(admin_pool)
+        .await
+        .ok()
+        .and_then(|version| version.parse().ok());
+    let force = if server_version.is_some_and(|v| v >= 130000) {
+        " WITH (FORCE)"
+    } else {
+        ""
+    };
+
+    sqlx::query(&format!("DROP DATABASE IF EXISTS {}{}", quote_ident(scratch_database), force))
+        .execute(admin_pool)
+        .await
+        .map_err(|e| format!("Failed to drop scratch database {}: {}", scratch_database, e))?;
+
+    Ok(())
+}
+
+// Files run on one connection so the search_path applies to all of them;
+// unqualified names land in the compared schema
+async fn apply_ddl_files(
+    config: &ConnectionConfig,
+    files: &[DdlFile],
+    schema_name: &str,
+) -> Result<(), String> {
+    let mut conn = connect_options(config)
+        .connect()
+        .await
+        .map_err(|e| format!("Error connecting to scratch database: {}", e))?;
+
+    // Closed whether or not the files applied, so the scratch database can be dropped
+    let applied = run_ddl_files(&mut conn, files, schema_name).await;
+    let closed = conn.close().await;
+
+    applied?;
+    closed.map_err(|e| format!("Failed to close scratch connection: {}", e))?;
+
+    Ok(())
+}
+
+async fn run_ddl_files(
+    conn: &mut PgConnection,
+    files: &[DdlFile],
+    schema_name: &str,
+) -> Result<(), String> {
+    let setup = format!(
+        "CREATE SCHEMA IF NOT EXISTS {schema}; SET search_path TO {schema}, public",
+        schema = quote_ident(schema_name)
+    );
+    conn.execute(sqlx::raw_sql(&setup))
+        .await
+        .map_err(|e| format!("Failed to prepare scratch database: {}", e))?;
+
+    for file in files {
+        conn.execute(sqlx::raw_sql(&file.contents))
+            .await
+            .map_err(|e| format!("Failed to apply {}: {}", file.relative_path, e))?;
+    }
+
+    Ok(())
+}
+
 // Helper function to fetch enhanced schema
 async fn fetch_enhanced_schema(
     config: &ConnectionConfig,
     schema: Option<String>,
-) -> Result<crate::models::EnhancedDatabaseSchema, String> {
+) -> Result<EnhancedDatabaseSchema, String> {
     // Reuse the existing get_enhanced_database_schema logic
     use crate::commands::get_enhanced_database_schema;
     get_enhanced_database_schema(config.clone(), schema).await
 }
+
+// Run each warning's check query on the target. Warnings with affected rows
+// become high risk; warnings the data can't trigger are downgraded.
+async fn check_warnings_against_target(
+    config: &ConnectionConfig,
+    comparison: &mut SchemaComparison,
+) -> Result<(), String> {
+    if comparison.warnings.iter().all(|w| w.check_query.is_none()) {
+        return Ok(());
+    }
+
+    let pool = PgPool::connect_with(connect_options(config))
+        .await
+        .map_err(|e| format!("Error connecting to database: {}", e))?;
+
+    for warning in &mut comparison.warnings {
+        let Some(query) = &warning.check_query else {
+            continue;
+        };
+
+        // A failing check (e.g. a timeout on a huge table) leaves the warning as is
+        let Ok(affected_rows) = query_count_read_only(&pool, query).await else {
+            continue;
+        };
+
+        if affected_rows > 0 {
+            warning.severity = WarningSeverity::High;
+            warning.details = Some(match &warning.details {
+                Some(details) => format!("{} row(s) on the target are affected. {}", affected_rows, details),
+                None => format!("{} row(s) on the target are affected", affected_rows),
+            });
+        } else {
+            warning.severity = WarningSeverity::Low;
+            warning.details = Some("Checked on the target: no rows are affected".to_string());
+        }
+    }
+
+    pool.close().await;
+
+    Ok(())
+}
+
+// Each check runs in its own read-only transaction so one failure doesn't
+// abort the others
+async fn query_count_read_only(pool: &PgPool, query: &str) -> Result<i64, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("SET TRANSACTION READ ONLY")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to start read-only transaction: {}", e))?;
+    sqlx::query(&format!("SET LOCAL statement_timeout = '{}'", WARNING_CHECK_STATEMENT_TIMEOUT))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to set statement timeout: {}", e))?;
+
+    let count: i64 = sqlx::query_scalar(query)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to run check query: {}", e))?;
+
+    tx.rollback()
+        .await
+        .map_err(|e| format!("Failed to end transaction: {}", e))?;
+
+    Ok(count)
+}
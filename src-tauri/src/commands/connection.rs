@@ -1,13 +1,21 @@
+use super::history::record_query_run;
+use crate::constants::{HISTORY_STATUS_ERROR, TYPE_KIND_COMPOSITE, TYPE_KIND_DOMAIN, TYPE_KIND_ENUM};
 use crate::models::{
-    ColumnInfo, ConnectionConfig, DatabaseSchema, ForeignKeyInfo, QueryResult, TableInfo,
-    EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, IndexInfo, RoutineInfo, ViewInfo,
+    CheckConstraintInfo, ColumnInfo, ConnectionConfig, DatabaseSchema, ForeignKeyInfo, HistoryEntryDetails, QueryResult, TableInfo,
+    EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, ExtensionInfo, IndexInfo,
+    IndexKeyColumn, RoutineInfo, TypeInfo, ViewInfo,
 };
-use sqlx::postgres::{PgConnectOptions, PgPool};
-use sqlx::{Column, ConnectOptions, Row};
-
-#[tauri::command]
-pub async fn test_postgres_connection(config: ConnectionConfig) -> Result<String, String> {
-    let mut options = PgConnectOptions::new()
+use crate::storage::ProjectDb;
+use crate::utils::sql::{quote_ident, quote_literal};
+use futures_util::TryStreamExt;
+use sqlx::postgres::{PgConnectOptions, PgPool, PgQueryResult, PgRow};
+use sqlx::{Column, ConnectOptions, Either, Executor, Row};
+use tauri::State;
+
+/// Options for connecting to `config`, shared by every command that opens a
+/// PostgreSQL connection or pool
+pub(crate) fn connect_options(config: &ConnectionConfig) -> PgConnectOptions {
+    let options = PgConnectOptions::new()
         .host(&config.host)
         .port(config.port)
         .username(&config.username)
@@ -15,9 +23,12 @@ pub async fn test_postgres_connection(config: ConnectionConfig) -> Result<String
         .database(&config.database);
 
     // Disable statement logging to prevent password leakage
-    options = options.disable_statement_logging();
+    options.disable_statement_logging()
+}
 
-    let pool = PgPool::connect_with(options)
+#[tauri::command]
+pub async fn test_postgres_connection(config: ConnectionConfig) -> Result<String, String> {
+    let pool = PgPool::connect_with(connect_options(&config))
         .await
         .map_err(|e| format!("Error connecting to database: {}", e))?;
 
@@ -31,6 +42,7 @@ pub async fn test_postgres_connection(config: ConnectionConfig) -> Result<String
 
 #[tauri::command]
 pub async fn execute_query(
+    db: State<'_, ProjectDb>,
     config: ConnectionConfig,
     query: String,
 ) -> Result<QueryResult, String> {
@@ -50,27 +62,26 @@ pub async fn execute_query(
 
     let start = std::time::Instant::now();
 
-    let mut options = PgConnectOptions::new()
-        .host(&config.host)
-        .port(config.port)
-        .username(&config.username)
-        .password(&config.password)
-        .database(&config.database);
-
-    // Disable statement logging to prevent password leakage
-    options = options.disable_statement_logging();
-
-    let pool = PgPool::connect_with(options)
+    let pool = PgPool::connect_with(connect_options(&config))
         .await
         .map_err(|e| format!("Error connecting to database: {}", e))?;
 
-    let rows = sqlx::query(&query)
-        .fetch_all(&pool)
+    let result = (&pool)
+        .fetch_many(sqlx::query(&query))
+        .try_collect::<Vec<_>>()
         .await
-        .map_err(|e| format!("Error executing query: {}", e))?;
+        .map(split_results);
 
     pool.close().await;
 
+    let (rows, affected_rows) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            record_failed_query(&db, &config, &query, start.elapsed().as_millis() as i64, &e).await;
+            return Err(format!("Error executing query: {}", e));
+        }
+    };
+
     // Extract column names
     let mut columns = Vec::new();
     if let Some(first_row) = rows.first() {
@@ -110,25 +121,57 @@ pub async fn execute_query(
         rows: result_rows,
         row_count,
         execution_time_ms,
+        affected_rows,
     })
 }
 
+// The rows a statement returned, and the rows it changed when it returned
+// none. Postgres reports both, so this holds for CTEs, RETURNING and
+// statements whose text merely mentions a keyword.
+fn split_results(results: Vec<Either<PgQueryResult, PgRow>>) -> (Vec<PgRow>, Option<u64>) {
+    let mut rows = Vec::new();
+    let mut affected = 0;
+    for result in results {
+        match result {
+            Either::Left(done) => affected += done.rows_affected(),
+            Either::Right(row) => rows.push(row),
+        }
+    }
+    let affected_rows = rows.is_empty().then_some(affected);
+    (rows, affected_rows)
+}
+
+// Failed runs are recorded here rather than by the caller, which only gets
+// the error text. Not being able to record one mustn't mask the query error.
+async fn record_failed_query(
+    db: &ProjectDb,
+    config: &ConnectionConfig,
+    query: &str,
+    execution_time_ms: i64,
+    error: &sqlx::Error,
+) {
+    let database_error = error.as_database_error();
+    let details = HistoryEntryDetails {
+        status: Some(HISTORY_STATUS_ERROR.to_string()),
+        error_message: Some(
+            database_error
+                .map(|e| e.message().to_string())
+                .unwrap_or_else(|| error.to_string()),
+        ),
+        sqlstate: database_error.and_then(|e| e.code()).map(|code| code.to_string()),
+        database_name: Some(config.database.clone()),
+        ..Default::default()
+    };
+
+    let _ = record_query_run(db, query, &config.name, execution_time_ms, 0, details).await;
+}
+
 #[tauri::command]
 pub async fn get_database_schema(
     config: ConnectionConfig,
     schema: Option<String>,
 ) -> Result<DatabaseSchema, String> {
-    let mut options = PgConnectOptions::new()
-        .host(&config.host)
-        .port(config.port)
-        .username(&config.username)
-        .password(&config.password)
-        .database(&config.database);
-
-    // Disable statement logging to prevent password leakage
-    options = options.disable_statement_logging();
-
-    let pool = PgPool::connect_with(options)
+    let pool = PgPool::connect_with(connect_options(&config))
         .await
         .map_err(|e| format!("Connection failed: {}", e))?;
 
@@ -273,17 +316,7 @@ pub async fn get_database_schema(
 
 #[tauri::command]
 pub async fn get_database_schemas(config: ConnectionConfig) -> Result<Vec<String>, String> {
-    let mut options = PgConnectOptions::new()
-        .host(&config.host)
-        .port(config.port)
-        .username(&config.username)
-        .password(&config.password)
-        .database(&config.database);
-
-    // Disable statement logging to prevent password leakage
-    options = options.disable_statement_logging();
-
-    let pool = PgPool::connect_with(options)
+    let pool = PgPool::connect_with(connect_options(&config))
         .await
         .map_err(|e| format!("Connection failed: {}", e))?;
 
@@ -317,17 +350,7 @@ pub async fn get_enhanced_database_schema(
     config: ConnectionConfig,
     schema: Option<String>,
 ) -> Result<EnhancedDatabaseSchema, String> {
-    let mut options = PgConnectOptions::new()
-        .host(&config.host)
-        .port(config.port)
-        .username(&config.username)
-        .password(&config.password)
-        .database(&config.database);
-
-    // Disable statement logging to prevent password leakage
-    options = options.disable_statement_logging();
-
-    let pool = PgPool::connect_with(options)
+    let pool = PgPool::connect_with(connect_options(&config))
         .await
         .map_err(|e| format!("Connection failed: {}", e))?;
 
@@ -335,7 +358,9 @@ pub async fn get_enhanced_database_schema(
 
     // Fetch tables
     let table_rows = sqlx::query(
-        "SELECT table_name
+        "SELECT
+            table_name,
+            obj_description(format('%I.%I', table_schema, table_name)::regclass, 'pg_class') AS comment
          FROM information_schema.tables
          WHERE table_schema = $1
          AND table_type = 'BASE TABLE'
@@ -352,6 +377,7 @@ pub async fn get_enhanced_database_schema(
         let table_name: String = table_row
             .try_get("table_name")
             .map_err(|e| format!("Failed to get table name: {}", e))?;
+        let table_comment: Option<String> = table_row.try_get("comment").ok().flatten();
 
         // Get primary key columns for this table
         let pk_rows = sqlx::query(
@@ -383,13 +409,21 @@ pub async fn get_enhanced_database_schema(
         let column_rows = sqlx::query(
             "SELECT
                 column_name,
-                data_type,
+                -- Domain columns are reported by their domain, not its base type
+                CASE WHEN domain_name IS NOT NULL THEN 'USER-DEFINED' ELSE data_type END AS data_type,
+                coalesce(domain_name, udt_name) AS udt_name,
+                coalesce(domain_schema, udt_schema) AS udt_schema,
                 is_nullable,
                 column_default,
                 character_maximum_length,
                 numeric_precision,
                 numeric_scale,
-                ordinal_position
+                ordinal_position,
+                collation_name,
+                col_description(
+                    format('%I.%I', table_schema, table_name)::regclass,
+                    ordinal_position::int
+                ) AS comment
              FROM information_schema.columns
              WHERE table_schema = $1
              AND table_name = $2
@@ -423,6 +457,10 @@ pub async fn get_enhanced_database_schema(
                 ordinal_position: col_row
                     .try_get("ordinal_position")
                     .map_err(|e| format!("Failed to get ordinal_position: {}", e))?,
+                comment: col_row.try_get("comment").ok().flatten(),
+                collation_name: col_row.try_get("collation_name").ok().flatten(),
+                udt_name: col_row.try_get("udt_name").ok(),
+                udt_schema: col_row.try_get("udt_schema").ok(),
             });
         }
 
@@ -469,20 +507,27 @@ pub async fn get_enhanced_database_schema(
             });
         }
 
-        // Fetch indexes for this table
+        // Fetch indexes for this table, matched by table OID so same-named
+        // tables in other schemas are not picked up
         let index_rows = sqlx::query(
             "SELECT
-                i.indexname AS index_name,
-                i.tablename AS table_name,
+                idx.indexrelid::int8 AS index_oid,
+                ic.relname AS index_name,
+                am.amname AS access_method,
                 idx.indisunique AS is_unique,
                 idx.indisprimary AS is_primary,
+                idx.indisvalid AS is_valid,
+                idx.indnkeyatts::int4 AS key_count,
+                pg_get_expr(idx.indpred, idx.indrelid) AS predicate,
                 pg_get_indexdef(idx.indexrelid) AS definition
-             FROM pg_indexes i
-             JOIN pg_class c ON c.relname = i.indexname
-             JOIN pg_index idx ON idx.indexrelid = c.oid
-             WHERE i.schemaname = $1
-               AND i.tablename = $2
-             ORDER BY i.indexname",
+             FROM pg_index idx
+             JOIN pg_class ic ON ic.oid = idx.indexrelid
+             JOIN pg_class tc ON tc.oid = idx.indrelid
+             JOIN pg_namespace n ON n.oid = tc.relnamespace
+             JOIN pg_am am ON am.oid = ic.relam
+             WHERE n.nspname = $1
+               AND tc.relname = $2
+             ORDER BY ic.relname",
         )
         .bind(&schema_name)
         .bind(&table_name)
@@ -492,27 +537,133 @@ pub async fn get_enhanced_database_schema(
 
         let mut indexes = Vec::new();
         for idx_row in index_rows {
-            let index_name: String = idx_row
-                .try_get("index_name")
-                .map_err(|e| format!("Failed to get index name: {}", e))?;
-            let definition: String = idx_row
-                .try_get("definition")
-                .map_err(|e| format!("Failed to get index definition: {}", e))?;
-
-            // Extract columns from index definition (simplified approach)
-            let columns = extract_index_columns(&definition);
+            let index_oid: i64 = idx_row
+                .try_get("index_oid")
+                .map_err(|e| format!("Failed to get index oid: {}", e))?;
+            let key_count: i32 = idx_row
+                .try_get("key_count")
+                .map_err(|e| format!("Failed to get index key count: {}", e))?;
+
+            // Key columns/expressions and INCLUDE columns, in index order.
+            // Plain columns come back as their bare names, to be quoted
+            // where they're written into SQL.
+            let index_column_rows = sqlx::query(
+                "SELECT
+                    k.ord AS position,
+                    COALESCE(a.attname::text, pg_get_indexdef(idx.indexrelid, k.ord, true)) AS expression,
+                    idx.indkey[k.ord - 1] = 0 AS is_expression,
+                    COALESCE((idx.indoption[k.ord - 1]::int4 & 1) = 1, false) AS descending,
+                    COALESCE((idx.indoption[k.ord - 1]::int4 & 2) = 2, false) AS nulls_first,
+                    CASE WHEN opc.opcdefault THEN NULL ELSE opc.opcname::text END AS opclass
+                 FROM pg_index idx
+                 CROSS JOIN LATERAL generate_series(1, idx.indnatts::int4) AS k(ord)
+                 LEFT JOIN pg_opclass opc ON opc.oid = idx.indclass[k.ord - 1]
+                 LEFT JOIN pg_attribute a
+                   ON a.attrelid = idx.indrelid AND a.attnum = idx.indkey[k.ord - 1] AND a.attnum > 0
+                 WHERE idx.indexrelid = $1::int8::oid
+                 ORDER BY k.ord",
+            )
+            .bind(index_oid)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| format!("Failed to fetch index columns: {}", e))?;
+
+            let mut key_columns = Vec::new();
+            let mut include_columns = Vec::new();
+            for col_row in index_column_rows {
+                let position: i32 = col_row
+                    .try_get("position")
+                    .map_err(|e| format!("Failed to get index column position: {}", e))?;
+                let expression: String = col_row
+                    .try_get("expression")
+                    .map_err(|e| format!("Failed to get index column expression: {}", e))?;
+
+                if position > key_count {
+                    include_columns.push(expression);
+                    continue;
+                }
+
+                key_columns.push(IndexKeyColumn {
+                    expression,
+                    is_expression: col_row
+                        .try_get("is_expression")
+                        .map_err(|e| format!("Failed to get is_expression: {}", e))?,
+                    descending: col_row
+                        .try_get("descending")
+                        .map_err(|e| format!("Failed to get descending: {}", e))?,
+                    nulls_first: col_row
+                        .try_get("nulls_first")
+                        .map_err(|e| format!("Failed to get nulls_first: {}", e))?,
+                    opclass: col_row.try_get("opclass").ok().flatten(),
+                });
+            }
 
             indexes.push(IndexInfo {
-                index_name,
+                index_name: idx_row
+                    .try_get("index_name")
+                    .map_err(|e| format!("Failed to get index name: {}", e))?,
                 table_name: table_name.clone(),
-                columns,
+                columns: key_columns.iter().map(|k| k.expression.clone()).collect(),
                 is_unique: idx_row
                     .try_get("is_unique")
                     .map_err(|e| format!("Failed to get is_unique: {}", e))?,
                 is_primary: idx_row
                     .try_get("is_primary")
                     .map_err(|e| format!("Failed to get is_primary: {}", e))?,
-                definition,
+                definition: idx_row
+                    .try_get("definition")
+                    .map_err(|e| format!("Failed to get index definition: {}", e))?,
+                access_method: idx_row
+                    .try_get("access_method")
+                    .map_err(|e| format!("Failed to get access method: {}", e))?,
+                key_columns,
+                include_columns,
+                predicate: idx_row.try_get("predicate").ok().flatten(),
+                is_valid: idx_row
+                    .try_get("is_valid")
+                    .map_err(|e| format!("Failed to get is_valid: {}", e))?,
+            });
+        }
+
+        // Fetch CHECK constraints. NOT VALID is left off the definition: whether
+        // the source validated its rows says nothing about the target's.
+        let check_rows = sqlx::query(
+            "SELECT
+                c.conname AS constraint_name,
+                pg_get_constraintdef(c.oid, true) AS definition,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(c.conkey) AS k(attnum)
+                    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                    ORDER BY a.attnum
+                ) AS columns
+             FROM pg_constraint c
+             JOIN pg_class tc ON tc.oid = c.conrelid
+             JOIN pg_namespace n ON n.oid = tc.relnamespace
+             WHERE c.contype = 'c'
+               AND n.nspname = $1
+               AND tc.relname = $2
+             ORDER BY c.conname",
+        )
+        .bind(&schema_name)
+        .bind(&table_name)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to fetch check constraints: {}", e))?;
+
+        let mut check_constraints = Vec::new();
+        for check_row in check_rows {
+            let definition: String = check_row
+                .try_get("definition")
+                .map_err(|e| format!("Failed to get check definition: {}", e))?;
+            check_constraints.push(CheckConstraintInfo {
+                constraint_name: check_row
+                    .try_get("constraint_name")
+                    .map_err(|e| format!("Failed to get constraint name: {}", e))?,
+                definition: definition.trim_end_matches(" NOT VALID").to_string(),
+                columns: check_row
+                    .try_get("columns")
+                    .map_err(|e| format!("Failed to get check columns: {}", e))?,
             });
         }
 
@@ -521,6 +672,8 @@ pub async fn get_enhanced_database_schema(
             columns,
             foreign_keys,
             indexes,
+            comment: table_comment,
+            check_constraints,
         });
     }
 
@@ -528,7 +681,8 @@ pub async fn get_enhanced_database_schema(
     let view_rows = sqlx::query(
         "SELECT
             table_name AS view_name,
-            view_definition AS definition
+            view_definition AS definition,
+            obj_description(format('%I.%I', table_schema, table_name)::regclass, 'pg_class') AS comment
          FROM information_schema.views
          WHERE table_schema = $1
          ORDER BY table_name",
@@ -538,28 +692,121 @@ pub async fn get_enhanced_database_schema(
     .await
     .map_err(|e| format!("Failed to fetch views: {}", e))?;
 
+    // Fetch the relations and columns each view reads from, via its rewrite
+    // rule, plus the routines it calls. Only objects in this schema are kept;
+    // the plan never touches anything else.
+    let view_dependency_rows = sqlx::query(
+        "SELECT DISTINCT
+            v.relname AS view_name,
+            ref.relname AS referenced_name,
+            att.attname AS column_name
+         FROM pg_class v
+         JOIN pg_namespace vn ON vn.oid = v.relnamespace
+         JOIN pg_rewrite rw ON rw.ev_class = v.oid
+         JOIN pg_depend d
+           ON d.classid = 'pg_rewrite'::regclass
+           AND d.objid = rw.oid
+           AND d.refclassid = 'pg_class'::regclass
+         JOIN pg_class ref ON ref.oid = d.refobjid
+         JOIN pg_namespace refn ON refn.oid = ref.relnamespace
+         LEFT JOIN pg_attribute att
+           ON att.attrelid = ref.oid
+           AND att.attnum = d.refobjsubid
+           AND d.refobjsubid > 0
+         WHERE vn.nspname = $1
+           AND refn.nspname = $1
+           AND v.relkind = 'v'
+           AND ref.oid <> v.oid
+         UNION
+         SELECT DISTINCT
+            v.relname,
+            p.proname || '(' || pg_get_function_identity_arguments(p.oid) || ')',
+            NULL
+         FROM pg_class v
+         JOIN pg_namespace vn ON vn.oid = v.relnamespace
+         JOIN pg_rewrite rw ON rw.ev_class = v.oid
+         JOIN pg_depend d
+           ON d.classid = 'pg_rewrite'::regclass
+           AND d.objid = rw.oid
+           AND d.refclassid = 'pg_proc'::regclass
+         JOIN pg_proc p ON p.oid = d.refobjid
+         WHERE vn.nspname = $1
+           AND p.pronamespace = vn.oid
+           AND v.relkind = 'v'
+         ORDER BY 1, 2, 3",
+    )
+    .bind(&schema_name)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to fetch view dependencies: {}", e))?;
+
+    let mut view_dependencies: std::collections::HashMap<String, (Vec<String>, Vec<String>)> =
+        std::collections::HashMap::new();
+    for dep_row in view_dependency_rows {
+        let view_name: String = dep_row
+            .try_get("view_name")
+            .map_err(|e| format!("Failed to get view name: {}", e))?;
+        let referenced_name: String = dep_row
+            .try_get("referenced_name")
+            .map_err(|e| format!("Failed to get referenced relation: {}", e))?;
+        let column_name: Option<String> = dep_row.try_get("column_name").ok().flatten();
+
+        let (relations, columns) = view_dependencies.entry(view_name).or_default();
+        if !relations.contains(&referenced_name) {
+            relations.push(referenced_name.clone());
+        }
+        if let Some(column_name) = column_name {
+            columns.push(format!("{}.{}", referenced_name, column_name));
+        }
+    }
+
     let mut views = Vec::new();
     for view_row in view_rows {
+        let view_name: String = view_row
+            .try_get("view_name")
+            .map_err(|e| format!("Failed to get view name: {}", e))?;
+        let (depends_on, column_dependencies) =
+            view_dependencies.remove(&view_name).unwrap_or_default();
+
         views.push(ViewInfo {
-            view_name: view_row
-                .try_get("view_name")
-                .map_err(|e| format!("Failed to get view name: {}", e))?,
+            view_name,
             definition: view_row
                 .try_get("definition")
                 .map_err(|e| format!("Failed to get view definition: {}", e))?,
+            comment: view_row.try_get("comment").ok().flatten(),
+            depends_on,
+            column_dependencies,
         });
     }
 
-    // Fetch routines (functions and procedures)
+    // Fetch routines (functions and procedures). pg_get_functiondef names
+    // the routine with its schema; that is cut out so the statement can be
+    // run against another schema. Routines owned by an extension come with it.
     let routine_rows = sqlx::query(
         "SELECT
-            routine_name,
-            routine_type,
-            routine_definition AS definition,
-            data_type AS return_type
-         FROM information_schema.routines
-         WHERE routine_schema = $1
-         ORDER BY routine_name",
+            p.proname AS routine_name,
+            CASE p.prokind WHEN 'p' THEN 'PROCEDURE' ELSE 'FUNCTION' END AS routine_type,
+            overlay(
+                fd.definition
+                PLACING ''
+                FROM position(quote_ident(n.nspname) || '.' IN fd.definition)
+                FOR length(quote_ident(n.nspname)) + 1
+            ) AS definition,
+            CASE WHEN p.prokind = 'p' THEN NULL ELSE pg_get_function_result(p.oid) END AS return_type,
+            obj_description(p.oid, 'pg_proc') AS comment,
+            pg_get_function_identity_arguments(p.oid) AS identity_arguments
+         FROM pg_proc p
+         JOIN pg_namespace n ON n.oid = p.pronamespace
+         CROSS JOIN LATERAL pg_get_functiondef(p.oid) AS fd(definition)
+         WHERE n.nspname = $1
+           AND p.prokind IN ('f', 'p')
+           AND NOT EXISTS (
+               SELECT 1 FROM pg_depend d
+               WHERE d.classid = 'pg_proc'::regclass
+                 AND d.objid = p.oid
+                 AND d.deptype = 'e'
+           )
+         ORDER BY 1, 6",
     )
     .bind(&schema_name)
     .fetch_all(&pool)
@@ -576,31 +823,209 @@ pub async fn get_enhanced_database_schema(
                 .try_get("routine_type")
                 .map_err(|e| format!("Failed to get routine type: {}", e))?,
             definition: routine_row.try_get("definition").ok(),
-            return_type: routine_row.try_get("return_type").ok(),
+            return_type: routine_row.try_get("return_type").ok().flatten(),
+            comment: routine_row.try_get("comment").ok().flatten(),
+            identity_arguments: routine_row.try_get("identity_arguments").ok(),
         });
     }
 
+    // Fetch installed extensions (database-wide, not limited to the schema)
+    let extension_rows = sqlx::query(
+        "SELECT
+            e.extname AS extension_name,
+            e.extversion AS version,
+            n.nspname AS schema_name
+         FROM pg_extension e
+         JOIN pg_namespace n ON n.oid = e.extnamespace
+         ORDER BY e.extname",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to fetch extensions: {}", e))?;
+
+    let mut extensions = Vec::new();
+    for extension_row in extension_rows {
+        extensions.push(ExtensionInfo {
+            extension_name: extension_row
+                .try_get("extension_name")
+                .map_err(|e| format!("Failed to get extension name: {}", e))?,
+            version: extension_row
+                .try_get("version")
+                .map_err(|e| format!("Failed to get extension version: {}", e))?,
+            schema_name: extension_row
+                .try_get("schema_name")
+                .map_err(|e| format!("Failed to get extension schema: {}", e))?,
+        });
+    }
+
+    let types = fetch_types(&pool, &schema_name).await?;
+
+    let server_version_num: String = sqlx::query_scalar("SHOW server_version_num")
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("Failed to fetch server version: {}", e))?;
+
     pool.close().await;
 
     Ok(EnhancedDatabaseSchema {
         tables,
         views,
         routines,
+        extensions,
+        types,
+        server_version_num: server_version_num.parse().ok(),
     })
 }
 
-// Helper function to extract column names from index definition
-fn extract_index_columns(definition: &str) -> Vec<String> {
-    // Simple extraction - looks for content between parentheses
-    // Example: "CREATE INDEX idx_name ON table (col1, col2)" -> ["col1", "col2"]
-    if let Some(start) = definition.find('(') {
-        if let Some(end) = definition.find(')') {
-            let cols_str = &definition[start + 1..end];
-            return cols_str
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .collect();
+// Enums, composite types and domains of the schema, leaving out types that
+// belong to an extension. Types of the schema itself are named without it.
+async fn fetch_types(pool: &PgPool, schema_name: &str) -> Result<Vec<TypeInfo>, String> {
+    let schema_prefix = format!("{}.", quote_ident(schema_name));
+    let local_type = |type_sql: String| match type_sql.strip_prefix(&schema_prefix) {
+        Some(unqualified) => unqualified.to_string(),
+        None => type_sql,
+    };
+    let mut types = Vec::new();
+
+    let enum_rows = sqlx::query(
+        "SELECT
+            t.typname AS type_name,
+            array_agg(e.enumlabel::text ORDER BY e.enumsortorder) AS labels
+         FROM pg_type t
+         JOIN pg_namespace n ON n.oid = t.typnamespace
+         JOIN pg_enum e ON e.enumtypid = t.oid
+         WHERE n.nspname = $1
+         AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.objid = t.oid AND d.deptype = 'e')
+         GROUP BY t.typname",
+    )
+    .bind(schema_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch enum types: {}", e))?;
+
+    for enum_row in enum_rows {
+        let labels: Vec<String> = enum_row
+            .try_get("labels")
+            .map_err(|e| format!("Failed to get enum labels: {}", e))?;
+        types.push(TypeInfo {
+            type_name: enum_row
+                .try_get("type_name")
+                .map_err(|e| format!("Failed to get type name: {}", e))?,
+            kind: TYPE_KIND_ENUM.to_string(),
+            definition: format!(
+                "AS ENUM ({})",
+                labels.iter().map(|l| quote_literal(l)).collect::<Vec<_>>().join(", ")
+            ),
+            enum_labels: labels,
+            depends_on: Vec::new(),
+        });
+    }
+
+    // One row per attribute; depends_on is the attribute's type (or array
+    // element type) when it is another user-defined type of the schema
+    let attribute_rows = sqlx::query(
+        "SELECT
+            t.typname AS type_name,
+            a.attname AS attribute_name,
+            format_type(a.atttypid, a.atttypmod) AS attribute_type,
+            CASE WHEN en.nspname = $1 AND et.typtype IN ('e', 'c', 'd') THEN et.typname END AS depends_on
+         FROM pg_type t
+         JOIN pg_namespace n ON n.oid = t.typnamespace
+         JOIN pg_class c ON c.oid = t.typrelid AND c.relkind = 'c'
+         JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+         JOIN pg_type at ON at.oid = a.atttypid
+         JOIN pg_type et ON et.oid = CASE WHEN at.typcategory = 'A' THEN at.typelem ELSE at.oid END
+         JOIN pg_namespace en ON en.oid = et.typnamespace
+         WHERE n.nspname = $1
+         AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.objid = t.oid AND d.deptype = 'e')
+         ORDER BY t.typname, a.attnum",
+    )
+    .bind(schema_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch composite types: {}", e))?;
+
+    let mut composites: Vec<(String, Vec<String>, Vec<String>)> = Vec::new();
+    for attribute_row in attribute_rows {
+        let type_name: String = attribute_row
+            .try_get("type_name")
+            .map_err(|e| format!("Failed to get type name: {}", e))?;
+        let attribute_name: String = attribute_row
+            .try_get("attribute_name")
+            .map_err(|e| format!("Failed to get attribute name: {}", e))?;
+        let attribute_type: String = attribute_row
+            .try_get("attribute_type")
+            .map_err(|e| format!("Failed to get attribute type: {}", e))?;
+        let depends_on: Option<String> = attribute_row.try_get("depends_on").ok().flatten();
+
+        if composites.last().is_none_or(|(name, _, _)| *name != type_name) {
+            composites.push((type_name, Vec::new(), Vec::new()));
+        }
+        let (_, attributes, dependencies) = composites.last_mut().expect("pushed above");
+        attributes.push(format!("{} {}", quote_ident(&attribute_name), local_type(attribute_type)));
+        if let Some(dependency) = depends_on.filter(|d| !dependencies.contains(d)) {
+            dependencies.push(dependency);
+        }
+    }
+    for (type_name, attributes, depends_on) in composites {
+        types.push(TypeInfo {
+            type_name,
+            kind: TYPE_KIND_COMPOSITE.to_string(),
+            definition: format!("AS ({})", attributes.join(", ")),
+            enum_labels: Vec::new(),
+            depends_on,
+        });
+    }
+
+    let domain_rows = sqlx::query(
+        "SELECT
+            t.typname AS type_name,
+            format_type(t.typbasetype, t.typtypmod) AS base_type,
+            t.typnotnull AS not_null,
+            t.typdefault AS default_value,
+            (SELECT string_agg(format('CONSTRAINT %I %s', con.conname, pg_get_constraintdef(con.oid, true)), ' ' ORDER BY con.conname)
+             FROM pg_constraint con
+             WHERE con.contypid = t.oid AND con.contype = 'c') AS constraints,
+            CASE WHEN en.nspname = $1 AND et.typtype IN ('e', 'c', 'd') THEN et.typname END AS depends_on
+         FROM pg_type t
+         JOIN pg_namespace n ON n.oid = t.typnamespace
+         JOIN pg_type bt ON bt.oid = t.typbasetype
+         JOIN pg_type et ON et.oid = CASE WHEN bt.typcategory = 'A' THEN bt.typelem ELSE bt.oid END
+         JOIN pg_namespace en ON en.oid = et.typnamespace
+         WHERE n.nspname = $1
+         AND t.typtype = 'd'
+         AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.objid = t.oid AND d.deptype = 'e')",
+    )
+    .bind(schema_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch domains: {}", e))?;
+
+    for domain_row in domain_rows {
+        let base_type: String = domain_row
+            .try_get("base_type")
+            .map_err(|e| format!("Failed to get domain base type: {}", e))?;
+        let mut definition = format!("AS {}", local_type(base_type));
+        if let Some(default_value) = domain_row.try_get::<Option<String>, _>("default_value").ok().flatten() {
+            definition.push_str(&format!(" DEFAULT {}", default_value));
+        }
+        if domain_row.try_get("not_null").unwrap_or(false) {
+            definition.push_str(" NOT NULL");
         }
+        if let Some(constraints) = domain_row.try_get::<Option<String>, _>("constraints").ok().flatten() {
+            definition.push_str(&format!(" {}", constraints));
+        }
+        types.push(TypeInfo {
+            type_name: domain_row
+                .try_get("type_name")
+                .map_err(|e| format!("Failed to get type name: {}", e))?,
+            kind: TYPE_KIND_DOMAIN.to_string(),
+            definition,
+            enum_labels: Vec::new(),
+            depends_on: domain_row.try_get("depends_on").ok().flatten().into_iter().collect(),
+        });
     }
-    vec![]
+
+    types.sort_by(|a, b| a.type_name.cmp(&b.type_name));
+    Ok(types)
 }
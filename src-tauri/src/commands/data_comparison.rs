@@ -0,0 +1,258 @@
+use super::connection::connect_options;
+use crate::models::ConnectionConfig;
+use crate::utils::data_diff::{
+    chunk_boundaries_query, chunk_hash_query, chunk_rows_query, diff_chunk_rows,
+    generate_data_sync_sql, key_range_condition, DataColumn, DataCompareOptions,
+    DataTableLayout, RowDifference, RowValues, TableDataComparison,
+};
+use crate::utils::schema_diff::DiffStatus;
+use crate::utils::sql::qualified_name;
+use sqlx::postgres::PgConnection;
+use sqlx::{ConnectOptions, Connection, Executor, Row};
+
+/// Diff the rows of one table between two connections, matched by primary
+/// key. Both sides are split into the same key ranges and hashed per range;
+/// only ranges whose hashes differ are fetched and compared row by row.
+#[tauri::command]
+pub async fn compare_table_data(
+    source: ConnectionConfig,
+    target: ConnectionConfig,
+    table: String,
+    schema: Option<String>,
+    options: Option<DataCompareOptions>,
+) -> Result<TableDataComparison, String> {
+    let schema_name = schema.unwrap_or_else(|| "public".to_string());
+    let options = options.unwrap_or_default();
+
+    let (mut source_conn, mut target_conn) =
+        tokio::try_join!(open_snapshot(&source), open_snapshot(&target))?;
+
+    let result = compare_rows(
+        &mut source_conn,
+        &mut target_conn,
+        (&source.name, &target.name),
+        &schema_name,
+        &table,
+        &options,
+    )
+    .await;
+
+    // Closing ends the read-only snapshot transactions
+    let _ = source_conn.close().await;
+    let _ = target_conn.close().await;
+
+    let mut outcome = result?;
+
+    let sync_sql =
+        generate_data_sync_sql(&outcome.layout, &outcome.differences, &source.name, &target.name);
+    let differences = &outcome.differences;
+    let rows_added = differences.iter().filter(|d| matches!(d.status, DiffStatus::Added)).count();
+    let rows_removed = differences.iter().filter(|d| matches!(d.status, DiffStatus::Removed)).count();
+    let rows_changed = differences.iter().filter(|d| matches!(d.status, DiffStatus::Modified)).count();
+
+    let truncated = outcome.differences.len() > options.max_row_differences;
+    outcome.differences.truncate(options.max_row_differences);
+
+    let layout = outcome.layout;
+    let primary_key = layout
+        .key_columns
+        .iter()
+        .map(|&i| layout.columns[i].clone())
+        .collect();
+
+    Ok(TableDataComparison {
+        source_connection: source.name,
+        target_connection: target.name,
+        schema_name,
+        table_name: table,
+        primary_key,
+        columns: layout.columns,
+        ignored_columns: layout.ignored_columns,
+        source_row_count: outcome.source_row_count,
+        target_row_count: outcome.target_row_count,
+        chunks_total: outcome.chunks_total,
+        chunks_different: outcome.chunks_different,
+        rows_added,
+        rows_removed,
+        rows_changed,
+        row_differences: outcome.differences,
+        truncated,
+        sync_sql,
+    })
+}
+
+struct RowsOutcome {
+    layout: DataTableLayout,
+    differences: Vec<RowDifference>,
+    source_row_count: i64,
+    target_row_count: i64,
+    chunks_total: usize,
+    chunks_different: usize,
+}
+
+async fn compare_rows(
+    source_conn: &mut PgConnection,
+    target_conn: &mut PgConnection,
+    (source_name, target_name): (&str, &str),
+    schema_name: &str,
+    table: &str,
+    options: &DataCompareOptions,
+) -> Result<RowsOutcome, String> {
+    let (source_columns, target_columns) = tokio::try_join!(
+        fetch_columns(source_conn, schema_name, table, source_name),
+        fetch_columns(target_conn, schema_name, table, target_name)
+    )?;
+    let layout = DataTableLayout::new(schema_name, table, &source_columns, &target_columns)?;
+
+    // Chunks are (previous boundary, boundary], plus an open-ended last one
+    // that also catches target rows past the source's last key
+    let boundaries = fetch_boundaries(source_conn, &layout, options.chunk_size).await?;
+    let upper_bounds = boundaries.into_iter().map(Some).chain(std::iter::once(None));
+
+    let mut outcome = RowsOutcome {
+        layout,
+        differences: Vec::new(),
+        source_row_count: 0,
+        target_row_count: 0,
+        chunks_total: 0,
+        chunks_different: 0,
+    };
+    let mut lower: Option<Vec<String>> = None;
+
+    for upper in upper_bounds {
+        let condition = key_range_condition(&outcome.layout, lower.as_deref(), upper.as_deref());
+        let hash_query = chunk_hash_query(&outcome.layout, &condition);
+        let (source_hash, target_hash) = tokio::try_join!(
+            fetch_chunk_hash(source_conn, &hash_query),
+            fetch_chunk_hash(target_conn, &hash_query)
+        )?;
+
+        outcome.chunks_total += 1;
+        outcome.source_row_count += source_hash.0;
+        outcome.target_row_count += target_hash.0;
+
+        if source_hash != target_hash {
+            outcome.chunks_different += 1;
+            let rows_query = chunk_rows_query(&outcome.layout, &condition);
+            let column_count = outcome.layout.columns.len();
+            let (source_rows, target_rows) = tokio::try_join!(
+                fetch_rows(source_conn, &rows_query, column_count),
+                fetch_rows(target_conn, &rows_query, column_count)
+            )?;
+            outcome
+                .differences
+                .extend(diff_chunk_rows(&outcome.layout, source_rows, target_rows));
+        }
+
+        lower = upper;
+    }
+
+    Ok(outcome)
+}
+
+// A connection inside a repeatable-read, read-only transaction, so every
+// chunk sees the same snapshot. Output settings are pinned so both servers
+// render values (timestamps, floats, intervals) the same way.
+async fn open_snapshot(config: &ConnectionConfig) -> Result<PgConnection, String> {
+    let mut conn = connect_options(config)
+        .connect()
+        .await
+        .map_err(|e| format!("Error connecting to {}: {}", config.name, e))?;
+
+    conn.execute(sqlx::raw_sql(
+        "SET TIME ZONE 'UTC'; SET DateStyle = 'ISO, MDY'; SET IntervalStyle = 'postgres'; \
+         SET extra_float_digits = 1; BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY",
+    ))
+    .await
+    .map_err(|e| format!("Failed to start snapshot on {}: {}", config.name, e))?;
+
+    Ok(conn)
+}
+
+async fn fetch_columns(
+    conn: &mut PgConnection,
+    schema_name: &str,
+    table: &str,
+    side: &str,
+) -> Result<Vec<DataColumn>, String> {
+    let rows = sqlx::query(
+        "SELECT a.attname::text,
+                format_type(a.atttypid, a.atttypmod),
+                array_position(i.indkey::int2[], a.attnum),
+                a.attidentity = 'a',
+                a.attgenerated <> ''
+         FROM pg_attribute a
+         LEFT JOIN pg_index i ON i.indrelid = a.attrelid AND i.indisprimary
+         WHERE a.attrelid = to_regclass($1) AND a.attnum > 0 AND NOT a.attisdropped
+         ORDER BY a.attnum",
+    )
+    .bind(qualified_name(schema_name, table))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to fetch columns on {}: {}", side, e))?;
+
+    if rows.is_empty() {
+        return Err(format!("Table {}.{} not found on {}", schema_name, table, side));
+    }
+
+    Ok(rows
+        .iter()
+        .map(|row| DataColumn {
+            name: row.get(0),
+            data_type: row.get(1),
+            key_position: row.get::<Option<i32>, _>(2).map(|p| p as usize),
+            identity_always: row.get(3),
+            generated: row.get(4),
+        })
+        .collect())
+}
+
+async fn fetch_boundaries(
+    conn: &mut PgConnection,
+    layout: &DataTableLayout,
+    chunk_size: usize,
+) -> Result<Vec<Vec<String>>, String> {
+    let rows = sqlx::query(&chunk_boundaries_query(layout, chunk_size))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to split table into chunks: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            (0..layout.key_columns.len())
+                .map(|i| row.try_get::<String, _>(i))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to read chunk boundary: {}", e))
+        })
+        .collect()
+}
+
+async fn fetch_chunk_hash(
+    conn: &mut PgConnection,
+    query: &str,
+) -> Result<(i64, Option<String>), String> {
+    sqlx::query_as::<_, (i64, Option<String>)>(query)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to hash chunk: {}", e))
+}
+
+async fn fetch_rows(
+    conn: &mut PgConnection,
+    query: &str,
+    column_count: usize,
+) -> Result<Vec<RowValues>, String> {
+    let rows = sqlx::query(query)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to fetch rows: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            (0..column_count)
+                .map(|i| row.try_get::<Option<String>, _>(i))
+                .collect::<Result<RowValues, _>>()
+                .map_err(|e| format!("Failed to read row: {}", e))
+        })
+        .collect()
+}
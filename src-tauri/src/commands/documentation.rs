@@ -0,0 +1,38 @@
+use crate::constants::SCHEMA_DOCS_DIR_NAME;
+use crate::models::ConnectionConfig;
+use crate::utils::{get_app_dir, render_schema_docs, sanitize_file_name, DocsFormat};
+use std::fs;
+
+#[tauri::command]
+pub async fn generate_schema_docs(
+    config: ConnectionConfig,
+    schema: Option<String>,
+    format: String,
+) -> Result<String, String> {
+    let format = DocsFormat::parse(&format)?;
+    let schema_name = schema.unwrap_or_else(|| "public".to_string());
+
+    let enhanced_schema =
+        crate::commands::get_enhanced_database_schema(config.clone(), Some(schema_name.clone()))
+            .await?;
+
+    let title = format!("{} / {}", config.name, schema_name);
+    let document = render_schema_docs(&enhanced_schema, &title, format);
+
+    let docs_dir = get_app_dir()?.join(SCHEMA_DOCS_DIR_NAME);
+    fs::create_dir_all(&docs_dir)
+        .map_err(|e| format!("Could not create docs directory: {}", e))?;
+
+    let file_name = format!(
+        "{}.{}.{}",
+        sanitize_file_name(&config.name),
+        sanitize_file_name(&schema_name),
+        format.extension()
+    );
+    let docs_file = docs_dir.join(file_name);
+
+    fs::write(&docs_file, document)
+        .map_err(|e| format!("Could not write schema docs: {}", e))?;
+
+    Ok(docs_file.to_string_lossy().to_string())
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::process::Command;
 use crate::utils::get_app_dir;
 
@@ -254,3 +255,50 @@ pub fn git_pull() -> Result<String, String> {
         Ok("Pulled from remote successfully".to_string())
     }
 }
+
+/// "Name <email>" from the git config that applies in `project_path`, or
+/// whichever half is set. None when git is missing or neither is configured.
+pub(crate) async fn git_author(project_path: &Path) -> Option<String> {
+    match (
+        git_config(project_path, "user.name").await,
+        git_config(project_path, "user.email").await,
+    ) {
+        (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+        (name, email) => name.or(email),
+    }
+}
+
+/// The git author of a batch of writes, looked up on first use, so a batch
+/// that writes nothing doesn't run git at all
+pub(crate) struct LazyGitAuthor<'a> {
+    project_path: &'a Path,
+    author: Option<Option<String>>,
+}
+
+impl<'a> LazyGitAuthor<'a> {
+    pub fn new(project_path: &'a Path) -> Self {
+        Self {
+            project_path,
+            author: None,
+        }
+    }
+
+    pub async fn get(&mut self) -> Option<String> {
+        if self.author.is_none() {
+            self.author = Some(git_author(self.project_path).await);
+        }
+        self.author.clone().flatten()
+    }
+}
+
+async fn git_config(project_path: &Path, key: &str) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .arg("config")
+        .arg(key)
+        .current_dir(project_path)
+        .output()
+        .await
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
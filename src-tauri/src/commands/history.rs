@@ -1,84 +1,571 @@
-use crate::models::QueryHistoryEntry;
-use crate::storage::get_history_db;
-use crate::utils::get_app_dir;
+use crate::constants::{HISTORY_DELETE_BATCH_SIZE, HISTORY_STATUS_SUCCESS};
+use crate::models::{
+    HistoryEntryDetails, HistoryRetention, HistorySearch, HistorySearchPage, HistorySearchResult,
+    MigrationRun, QueryHistoryEntry,
+};
+use crate::storage::ProjectDb;
+use crate::utils::{
+    get_app_dir, get_current_project_path_internal, get_history_retention_internal,
+    set_history_retention_internal,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::sqlite::{Sqlite, SqlitePool, SqliteRow};
+use sqlx::{QueryBuilder, Row};
+use tauri::State;
 
+// Columns of a QueryHistoryEntry, on rows aliased as h
+const HISTORY_ENTRY_COLUMNS: &str = "h.id, h.query, h.connection_name, h.execution_time_ms, h.row_count, \
+     h.executed_at, h.status, h.error_message, h.sqlstate, h.database_name, h.schema_name, \
+     h.project_path, h.affected_rows";
+
+/// Record a query run. `details` carries the failure and context fields;
+/// when omitted the run is recorded as successful.
 #[tauri::command]
 pub async fn save_query_to_history(
+    db: State<'_, ProjectDb>,
     query: String,
     connection_name: String,
     execution_time_ms: i64,
     row_count: i64,
+    details: Option<HistoryEntryDetails>,
 ) -> Result<(), String> {
-    let app_dir = get_app_dir()?;
-    let pool = get_history_db(app_dir).await?;
+    record_query_run(
+        &db,
+        &query,
+        &connection_name,
+        execution_time_ms,
+        row_count,
+        details.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Shared with `execute_query`, which records failed runs itself since only
+/// it sees the SQLSTATE. Retention limits are applied at start-up and then
+/// every HISTORY_PRUNE_INTERVAL runs, so history can briefly run past them.
+pub(crate) async fn record_query_run(
+    db: &ProjectDb,
+    query: &str,
+    connection_name: &str,
+    execution_time_ms: i64,
+    row_count: i64,
+    details: HistoryEntryDetails,
+) -> Result<(), String> {
+    let project_path = get_current_project_path_internal()?;
+    let pool = db.pool(get_app_dir()?).await?;
 
     let now = chrono::Utc::now().to_rfc3339();
+    let status = details
+        .status
+        .unwrap_or_else(|| HISTORY_STATUS_SUCCESS.to_string());
 
     sqlx::query(
-        "INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at) VALUES (?, ?, ?, ?, ?)"
+        "INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at, status, error_message, sqlstate, database_name, schema_name, project_path, affected_rows) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
-    .bind(&query)
-    .bind(&connection_name)
+    .bind(query)
+    .bind(connection_name)
     .bind(execution_time_ms)
     .bind(row_count)
     .bind(&now)
+    .bind(&status)
+    .bind(&details.error_message)
+    .bind(&details.sqlstate)
+    .bind(&details.database_name)
+    .bind(&details.schema_name)
+    .bind(&project_path)
+    .bind(details.affected_rows)
     .execute(&pool)
     .await
     .map_err(|e| format!("Failed to save query: {}", e))?;
 
-    pool.close().await;
+    // The run is saved at this point; failing maintenance (say the file is
+    // busy) mustn't make it look lost, and is simply retried next time
+    if db.count_run_for_pruning() {
+        if let Err(e) = apply_retention(&pool).await {
+            eprintln!("History maintenance failed: {}", e);
+        }
+    }
+
+    Ok(())
+}
 
+// Pruning and VACUUM rewrite the file, too slow to do on every run
+async fn apply_retention(pool: &SqlitePool) -> Result<(), String> {
+    let deleted = prune_history(pool, &get_history_retention_internal()?).await?;
+    if deleted > 0 {
+        vacuum(pool).await?;
+    }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_query_history(limit: i64) -> Result<Vec<QueryHistoryEntry>, String> {
-    let app_dir = get_app_dir()?;
-    let pool = get_history_db(app_dir).await?;
-
-    let rows = sqlx::query_as::<_, (i64, String, String, i64, i64, String)>(
-        "SELECT id, query, connection_name, execution_time_ms, row_count, executed_at
-         FROM query_history
-         ORDER BY executed_at DESC
-         LIMIT ?",
-    )
+pub async fn get_query_history(
+    db: State<'_, ProjectDb>,
+    limit: i64,
+) -> Result<Vec<QueryHistoryEntry>, String> {
+    let pool = db.pool(get_app_dir()?).await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM query_history h ORDER BY h.executed_at DESC LIMIT ?",
+        HISTORY_ENTRY_COLUMNS
+    ))
     .bind(limit)
     .fetch_all(&pool)
     .await
     .map_err(|e| format!("Failed to fetch history: {}", e))?;
 
-    pool.close().await;
-
-    let history = rows
-        .into_iter()
-        .map(
-            |(id, query, connection_name, execution_time_ms, row_count, executed_at)| {
-                QueryHistoryEntry {
-                    id,
-                    query,
-                    connection_name,
-                    execution_time_ms,
-                    row_count,
-                    executed_at,
-                }
-            },
-        )
+    Ok(rows.iter().map(history_entry_from_row).collect())
+}
+
+/// Filtered, paged history, newest first. With `distinct`, identical
+/// statements collapse into their latest run and a run count.
+#[tauri::command]
+pub async fn search_query_history(
+    db: State<'_, ProjectDb>,
+    search: HistorySearch,
+) -> Result<HistorySearchPage, String> {
+    let executed_after = search.executed_after.as_deref().map(normalize_timestamp).transpose()?;
+    let executed_before = search.executed_before.as_deref().map(normalize_timestamp).transpose()?;
+    let filters = HistoryFilters {
+        search: &search,
+        match_expression: search.text.as_deref().and_then(fts_match_expression),
+        executed_after,
+        executed_before,
+    };
+
+    let pool = db.pool(get_app_dir()?).await?;
+
+    let mut count_query = QueryBuilder::<Sqlite>::new(if search.distinct {
+        "SELECT count(DISTINCT h.query) FROM query_history h"
+    } else {
+        "SELECT count(*) FROM query_history h"
+    });
+    filters.push_where(&mut count_query);
+
+    let total: i64 = count_query
+        .build_query_scalar()
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("Failed to search history: {}", e))?;
+
+    let mut page_query = QueryBuilder::<Sqlite>::new(format!("SELECT {}, ", HISTORY_ENTRY_COLUMNS));
+    if search.distinct {
+        // ids grow with time, so the highest id per statement is its latest run
+        page_query.push(
+            "g.run_count FROM query_history h JOIN (SELECT max(h.id) AS id, count(*) AS run_count FROM query_history h",
+        );
+        filters.push_where(&mut page_query);
+        page_query.push(" GROUP BY h.query) g ON g.id = h.id");
+    } else {
+        page_query.push("1 AS run_count FROM query_history h");
+        filters.push_where(&mut page_query);
+    }
+    page_query.push(" ORDER BY h.executed_at DESC, h.id DESC LIMIT ");
+    page_query.push_bind(search.limit.max(0));
+    page_query.push(" OFFSET ");
+    page_query.push_bind(search.offset.max(0));
+
+    let rows = page_query
+        .build()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to search history: {}", e))?;
+
+    let results = rows
+        .iter()
+        .map(|row| HistorySearchResult {
+            entry: history_entry_from_row(row),
+            run_count: row.get("run_count"),
+        })
         .collect();
 
-    Ok(history)
+    Ok(HistorySearchPage { results, total })
 }
 
 #[tauri::command]
-pub async fn clear_query_history() -> Result<(), String> {
-    let app_dir = get_app_dir()?;
-    let pool = get_history_db(app_dir).await?;
+pub async fn clear_query_history(db: State<'_, ProjectDb>) -> Result<(), String> {
+    let pool = db.pool(get_app_dir()?).await?;
 
     sqlx::query("DELETE FROM query_history")
         .execute(&pool)
         .await
         .map_err(|e| format!("Failed to clear history: {}", e))?;
 
-    pool.close().await;
+    vacuum(&pool).await
+}
+
+/// Remove selected entries; returns how many were deleted
+#[tauri::command]
+pub async fn delete_history_entries(
+    db: State<'_, ProjectDb>,
+    ids: Vec<i64>,
+) -> Result<u64, String> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let pool = db.pool(get_app_dir()?).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to delete history entries: {}", e))?;
+
+    let mut deleted = 0;
+    for batch in ids.chunks(HISTORY_DELETE_BATCH_SIZE) {
+        let mut query = QueryBuilder::<Sqlite>::new("DELETE FROM query_history WHERE id IN (");
+        let mut separated = query.separated(", ");
+        for id in batch {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        deleted += query
+            .build()
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to delete history entries: {}", e))?
+            .rows_affected();
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete history entries: {}", e))?;
+
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub fn get_history_retention() -> Result<HistoryRetention, String> {
+    get_history_retention_internal()
+}
+
+/// Save the retention limits and apply them straight away, compacting the
+/// file afterwards. Returns how many entries were removed.
+#[tauri::command]
+pub async fn set_history_retention(
+    db: State<'_, ProjectDb>,
+    retention: HistoryRetention,
+) -> Result<u64, String> {
+    let limits = [
+        retention.max_entries,
+        retention.max_age_days,
+        retention.max_entries_per_connection,
+    ];
+    if limits.iter().flatten().any(|&limit| limit < 1) {
+        return Err("History retention limits must be at least 1".to_string());
+    }
+    if let Some(days) = retention.max_age_days {
+        age_cutoff(days)?;
+    }
+
+    set_history_retention_internal(&retention)?;
+
+    let pool = db.pool(get_app_dir()?).await?;
+    let deleted = prune_history(&pool, &retention).await?;
+    if deleted > 0 {
+        vacuum(&pool).await?;
+    }
+
+    Ok(deleted)
+}
+
+/// Scripts run with `apply_migration`, newest first
+#[tauri::command]
+pub async fn get_migration_runs(
+    db: State<'_, ProjectDb>,
+    limit: i64,
+) -> Result<Vec<MigrationRun>, String> {
+    let pool = db.pool(get_app_dir()?).await?;
+
+    let rows = sqlx::query(
+        "SELECT id, connection_name, database_name, script, status, error, statements_total,
+                statements_committed, started_at, duration_ms
+         FROM migration_runs
+         ORDER BY started_at DESC
+         LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to fetch migration runs: {}", e))?;
+
+    let runs = rows
+        .iter()
+        .map(|row| MigrationRun {
+            id: row.get("id"),
+            connection_name: row.get("connection_name"),
+            database: row.get("database_name"),
+            script: row.get("script"),
+            status: row.get("status"),
+            error: row.get("error"),
+            statements_total: row.get("statements_total"),
+            statements_committed: row.get("statements_committed"),
+            started_at: row.get("started_at"),
+            duration_ms: row.get("duration_ms"),
+        })
+        .collect();
+
+    Ok(runs)
+}
+
+// Newest entries are kept, by insertion order
+async fn prune_history(pool: &SqlitePool, retention: &HistoryRetention) -> Result<u64, String> {
+    let mut deleted = 0;
+
+    if let Some(days) = retention.max_age_days {
+        deleted += sqlx::query("DELETE FROM query_history WHERE executed_at < ?")
+            .bind(age_cutoff(days)?.to_rfc3339())
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to prune history: {}", e))?
+            .rows_affected();
+    }
+
+    if let Some(max_entries) = retention.max_entries_per_connection {
+        deleted += sqlx::query(
+            "DELETE FROM query_history WHERE id IN (
+                 SELECT id FROM (
+                     SELECT id, row_number() OVER (PARTITION BY connection_name ORDER BY id DESC) AS position
+                     FROM query_history
+                 )
+                 WHERE position > ?
+             )",
+        )
+        .bind(max_entries)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to prune history: {}", e))?
+        .rows_affected();
+    }
+
+    if let Some(max_entries) = retention.max_entries {
+        deleted += sqlx::query(
+            "DELETE FROM query_history WHERE id NOT IN (
+                 SELECT id FROM query_history ORDER BY id DESC LIMIT ?
+             )",
+        )
+        .bind(max_entries)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to prune history: {}", e))?
+        .rows_affected();
+    }
+
+    Ok(deleted)
+}
+
+// Entries older than this are past `days`; an age that reaches back before
+// the earliest representable date is an error rather than a panic
+fn age_cutoff(days: i64) -> Result<DateTime<Utc>, String> {
+    TimeDelta::try_days(days)
+        .and_then(|age| Utc::now().checked_sub_signed(age))
+        .ok_or_else(|| format!("History retention of {} days is too long", days))
+}
+
+// Give the space freed by deletes back to the file system
+async fn vacuum(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query("VACUUM")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to compact history: {}", e))?;
 
     Ok(())
 }
+
+fn history_entry_from_row(row: &SqliteRow) -> QueryHistoryEntry {
+    QueryHistoryEntry {
+        id: row.get("id"),
+        query: row.get("query"),
+        connection_name: row.get("connection_name"),
+        execution_time_ms: row.get("execution_time_ms"),
+        row_count: row.get("row_count"),
+        executed_at: row.get("executed_at"),
+        status: row.get("status"),
+        error_message: row.get("error_message"),
+        sqlstate: row.get("sqlstate"),
+        database_name: row.get("database_name"),
+        schema_name: row.get("schema_name"),
+        project_path: row.get("project_path"),
+        affected_rows: row.get("affected_rows"),
+    }
+}
+
+struct HistoryFilters<'a> {
+    search: &'a HistorySearch,
+    match_expression: Option<String>,
+    executed_after: Option<String>,
+    executed_before: Option<String>,
+}
+
+impl HistoryFilters<'_> {
+    // Appends " WHERE ..." for the set filters, on rows aliased as h
+    fn push_where(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+        query.push(" WHERE 1 = 1");
+        if let Some(expression) = &self.match_expression {
+            query.push(" AND h.id IN (SELECT rowid FROM query_history_fts WHERE query_history_fts MATCH ");
+            query.push_bind(expression.clone());
+            query.push(")");
+        }
+        if let Some(connection_name) = &self.search.connection_name {
+            query.push(" AND h.connection_name = ");
+            query.push_bind(connection_name.clone());
+        }
+        if let Some(executed_after) = &self.executed_after {
+            query.push(" AND h.executed_at >= ");
+            query.push_bind(executed_after.clone());
+        }
+        if let Some(executed_before) = &self.executed_before {
+            query.push(" AND h.executed_at < ");
+            query.push_bind(executed_before.clone());
+        }
+        if let Some(min_execution_time_ms) = self.search.min_execution_time_ms {
+            query.push(" AND h.execution_time_ms >= ");
+            query.push_bind(min_execution_time_ms);
+        }
+        if let Some(status) = &self.search.status {
+            query.push(" AND h.status = ");
+            query.push_bind(status.clone());
+        }
+    }
+}
+
+// Each word becomes a quoted prefix term, so FTS5 operators and punctuation
+// in the search box are taken literally: `user_id = 5` -> `"user_id"* "5"*`.
+// Words without a letter or digit are dropped; the tokenizer keeps nothing
+// of them, and the empty term left behind would match no entry at all.
+fn fts_match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+// Stored timestamps are UTC RFC 3339, so bounds are converted to match
+// before comparing them as strings
+fn normalize_timestamp(value: &str) -> Result<String, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc).to_rfc3339())
+        .map_err(|e| format!("Invalid timestamp {}: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_expression_quotes_each_word_as_a_prefix() {
+        assert_eq!(
+            fts_match_expression("user_id \"x\" OR"),
+            Some("\"user_id\"* \"\"\"x\"\"\"* \"OR\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn match_expression_drops_words_without_letters_or_digits() {
+        assert_eq!(
+            fts_match_expression("user_id = 5"),
+            Some("\"user_id\"* \"5\"*".to_string())
+        );
+        assert_eq!(fts_match_expression("= * ()"), None);
+        assert_eq!(fts_match_expression("   "), None);
+    }
+
+    // Runs 1..=6, oldest first: dev gets the odd ones, prod the even ones.
+    // Run n is 10 - n days old.
+    async fn history_fixture(name: &str) -> (std::path::PathBuf, SqlitePool) {
+        let dir = std::env::temp_dir().join(format!("query-history-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = ProjectDb::default().pool(dir.clone()).await.unwrap();
+
+        for run in 1..=6 {
+            let executed_at = Utc::now() - TimeDelta::days(10 - run) - TimeDelta::minutes(1);
+            sqlx::query(
+                "INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at)
+                 VALUES (?, ?, 1, 1, ?)",
+            )
+            .bind(format!("SELECT {}", run))
+            .bind(if run % 2 == 1 { "dev" } else { "prod" })
+            .bind(executed_at.to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        (dir, pool)
+    }
+
+    async fn kept(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar("SELECT query FROM query_history ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn prune_applies_each_retention_limit() {
+        let limits = [
+            (
+                HistoryRetention { max_age_days: Some(6), ..Default::default() },
+                vec!["SELECT 5", "SELECT 6"],
+            ),
+            (
+                HistoryRetention { max_entries_per_connection: Some(1), ..Default::default() },
+                vec!["SELECT 5", "SELECT 6"],
+            ),
+            (
+                HistoryRetention { max_entries_per_connection: Some(2), ..Default::default() },
+                vec!["SELECT 3", "SELECT 4", "SELECT 5", "SELECT 6"],
+            ),
+            (
+                HistoryRetention { max_entries: Some(3), ..Default::default() },
+                vec!["SELECT 4", "SELECT 5", "SELECT 6"],
+            ),
+            (
+                HistoryRetention {
+                    max_entries: Some(1),
+                    max_age_days: Some(6),
+                    max_entries_per_connection: Some(2),
+                },
+                vec!["SELECT 6"],
+            ),
+            (
+                HistoryRetention::default(),
+                vec!["SELECT 1", "SELECT 2", "SELECT 3", "SELECT 4", "SELECT 5", "SELECT 6"],
+            ),
+        ];
+
+        for (index, (retention, expected)) in limits.into_iter().enumerate() {
+            let (dir, pool) = history_fixture(&format!("prune-{}", index)).await;
+
+            let deleted = prune_history(&pool, &retention).await.unwrap();
+
+            assert_eq!(kept(&pool).await, expected, "{:?}", retention);
+            assert_eq!(deleted as usize, 6 - expected.len());
+
+            pool.close().await;
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_rejects_an_age_beyond_any_date() {
+        let (dir, pool) = history_fixture("too-old").await;
+        let retention = HistoryRetention {
+            max_age_days: Some(100_000_000),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            prune_history(&pool, &retention).await,
+            Err("History retention of 100000000 days is too long".to_string())
+        );
+        assert_eq!(kept(&pool).await.len(), 6);
+
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
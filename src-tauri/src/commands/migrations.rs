@@ -0,0 +1,809 @@
+use super::connection::connect_options;
+use crate::constants::{
+    MIGRATIONS_TABLE_NAME, MIGRATION_ADVISORY_LOCK_KEY, MIGRATION_PROGRESS_EVENT,
+    MIGRATION_RUN_PARTIALLY_APPLIED, MIGRATION_RUN_ROLLED_BACK, MIGRATION_RUN_SUCCEEDED,
+};
+use crate::models::{
+    AppliedMigration, ConnectionConfig, MigrationFile, MigrationProgress, MigrationRun,
+    MigrationState, MigrationStatus, StatementStatus,
+};
+use crate::storage::{
+    list_migration_files, read_migration_file, write_migration_files, ProjectDb,
+};
+use crate::utils::migration::build_migration_plan;
+use crate::utils::sql::{
+    is_transaction_control, quote_ident, quote_literal, runs_outside_transaction,
+    split_statements,
+};
+use crate::utils::{get_app_dir, MigrationScriptOptions, SchemaComparison};
+use sqlx::postgres::PgConnection;
+use sqlx::{ConnectOptions, Connection, Executor, Row};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, State};
+
+/// Save the migration and rollback scripts for a comparison as the next
+/// numbered up/down pair in the project's migrations directory
+#[tauri::command]
+pub fn create_migration(
+    name: String,
+    comparison: SchemaComparison,
+    options: Option<MigrationScriptOptions>,
+) -> Result<MigrationFile, String> {
+    if name.trim().is_empty() {
+        return Err("Migration name cannot be empty".to_string());
+    }
+
+    let options = options.unwrap_or_default();
+    if build_migration_plan(&comparison, &options).steps.is_empty() {
+        return Err("No changes to migrate".to_string());
+    }
+
+    let up_sql = crate::utils::generate_migration_script(&comparison, &options);
+    let down_sql = crate::utils::generate_rollback_script(&comparison, &options);
+
+    write_migration_files(&name, &up_sql, Some(&down_sql), get_app_dir()?)
+}
+
+#[tauri::command]
+pub async fn migration_status(config: ConnectionConfig) -> Result<Vec<MigrationStatus>, String> {
+    let files = list_migration_files(get_app_dir()?)?;
+
+    let mut conn = connect(&config).await?;
+    let applied = fetch_applied_migrations(&mut conn).await;
+    let _ = conn.close().await;
+
+    Ok(migration_statuses(&files, &applied?))
+}
+
+/// Apply pending migrations in version order, up to and including
+/// `target_version` when given. Each file runs in the same transaction
+/// batches as `apply_migration`, and is recorded as applied in the same
+/// transaction as its last batch.
+#[tauri::command]
+pub async fn migrate_up(
+    config: ConnectionConfig,
+    target_version: Option<i64>,
+) -> Result<Vec<MigrationStatus>, String> {
+    if config.read_only {
+        return Err("Read-only mode: Migrations cannot be applied to this connection".to_string());
+    }
+
+    let app_dir = get_app_dir()?;
+    let files = list_migration_files(app_dir.clone())?;
+
+    let mut conn = connect(&config).await?;
+    acquire_migration_lock(&mut conn).await?;
+
+    let result = apply_pending(&mut conn, &files, target_version, &app_dir).await;
+
+    release_migration_lock(&mut conn).await;
+    let _ = conn.close().await;
+
+    result
+}
+
+/// Revert the most recently applied migrations, newest first
+#[tauri::command]
+pub async fn migrate_down(
+    config: ConnectionConfig,
+    steps: Option<u32>,
+) -> Result<Vec<MigrationStatus>, String> {
+    if config.read_only {
+        return Err("Read-only mode: Migrations cannot be reverted on this connection".to_string());
+    }
+
+    let app_dir = get_app_dir()?;
+    let files = list_migration_files(app_dir.clone())?;
+
+    let mut conn = connect(&config).await?;
+    acquire_migration_lock(&mut conn).await?;
+
+    let result = revert_applied(&mut conn, &files, steps.unwrap_or(1), &app_dir).await;
+
+    release_migration_lock(&mut conn).await;
+    let _ = conn.close().await;
+
+    result
+}
+
+/// Run a migration script against the target one statement at a time,
+/// emitting `migration-progress` as each statement starts and finishes.
+///
+/// Consecutive statements share a transaction, so a script is all-or-nothing
+/// unless it contains statements PostgreSQL won't run inside one (CREATE INDEX
+/// CONCURRENTLY, VACUUM, ...); those run on their own between transactions.
+/// BEGIN/COMMIT in the script are skipped in favour of this grouping. The run
+/// stops at the first error, rolling back the open transaction, and is
+/// recorded in the project db whatever the outcome.
+#[tauri::command]
+pub async fn apply_migration(
+    app: AppHandle,
+    db: State<'_, ProjectDb>,
+    config: ConnectionConfig,
+    script: String,
+) -> Result<MigrationRun, String> {
+    if config.read_only {
+        return Err("Read-only mode: Migrations cannot be applied to this connection".to_string());
+    }
+
+    let statements: Vec<String> = split_statements(&script)
+        .into_iter()
+        .filter(|statement| !is_transaction_control(statement))
+        .collect();
+    if statements.is_empty() {
+        return Err("The migration script has no statements to run".to_string());
+    }
+
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let start = Instant::now();
+
+    let mut conn = connect(&config).await?;
+    acquire_migration_lock(&mut conn).await?;
+
+    let outcome = run_statements(&app, &mut conn, &statements).await;
+
+    release_migration_lock(&mut conn).await;
+    let _ = conn.close().await;
+
+    let status = match (&outcome.error, outcome.committed) {
+        (None, _) => MIGRATION_RUN_SUCCEEDED,
+        (Some(_), 0) => MIGRATION_RUN_ROLLED_BACK,
+        (Some(_), _) => MIGRATION_RUN_PARTIALLY_APPLIED,
+    };
+
+    let mut run = MigrationRun {
+        id: 0,
+        connection_name: config.name.clone(),
+        database: config.database.clone(),
+        script,
+        status: status.to_string(),
+        error: outcome.error,
+        statements_total: statements.len() as i64,
+        statements_committed: outcome.committed as i64,
+        started_at,
+        duration_ms: start.elapsed().as_millis() as i64,
+    };
+    run.id = record_migration_run(&db, &run)
+        .await
+        .map_err(|e| format!("Migration {} but could not be recorded: {}", run.status, e))?;
+
+    Ok(run)
+}
+
+async fn apply_pending(
+    conn: &mut PgConnection,
+    files: &[MigrationFile],
+    target_version: Option<i64>,
+    app_dir: &std::path::Path,
+) -> Result<Vec<MigrationStatus>, String> {
+    ensure_migrations_table(conn).await?;
+    let applied = fetch_applied_migrations(conn).await?;
+
+    // Refuse to run on top of history that no longer matches the files
+    let modified: Vec<&str> = migration_statuses(files, &applied)
+        .iter()
+        .filter(|s| s.state == MigrationState::Modified)
+        .filter_map(|s| files.iter().find(|f| f.version == s.version))
+        .map(|f| f.up_file.as_str())
+        .collect();
+    if !modified.is_empty() {
+        return Err(format!(
+            "Migration files were edited after being applied: {}. Restore them or create a new migration instead.",
+            modified.join(", ")
+        ));
+    }
+
+    let applied_versions: Vec<i64> = applied.iter().map(|m| m.version).collect();
+    let pending = files
+        .iter()
+        .filter(|f| !applied_versions.contains(&f.version))
+        .filter(|f| target_version.is_none_or(|target| f.version <= target));
+
+    let mut done = Vec::new();
+    for file in pending {
+        let up_sql = read_migration_file(&file.up_file, app_dir.to_path_buf())?;
+
+        run_migration_file(conn, &file.up_file, &up_sql, |execution_time_ms| {
+            record_applied_sql(file, execution_time_ms)
+        })
+        .await?;
+
+        done.push(MigrationStatus {
+            version: file.version,
+            name: file.name.clone(),
+            state: MigrationState::Applied,
+            applied_at: None,
+            applied_by: None,
+            can_revert: file.down_file.is_some(),
+        });
+    }
+
+    Ok(done)
+}
+
+async fn revert_applied(
+    conn: &mut PgConnection,
+    files: &[MigrationFile],
+    steps: u32,
+    app_dir: &std::path::Path,
+) -> Result<Vec<MigrationStatus>, String> {
+    ensure_migrations_table(conn).await?;
+    let applied = fetch_applied_migrations(conn).await?;
+
+    let mut done = Vec::new();
+    for migration in applied.iter().rev().take(steps as usize) {
+        let Some((file, down_file)) = files
+            .iter()
+            .find(|f| f.version == migration.version)
+            .and_then(|f| f.down_file.as_ref().map(|down_file| (f, down_file)))
+        else {
+            return Err(format!(
+                "Migration {} ({}) has no down file and can't be reverted",
+                migration.version, migration.name
+            ));
+        };
+        // Rows recorded before down files were checksummed have nothing to compare
+        if migration
+            .down_checksum
+            .as_ref()
+            .is_some_and(|recorded| file.down_checksum.as_ref() != Some(recorded))
+        {
+            return Err(format!(
+                "{} was edited after migration {} was applied. Restore it or revert by hand.",
+                down_file, migration.version
+            ));
+        }
+        let down_sql = read_migration_file(down_file, app_dir.to_path_buf())?;
+
+        run_migration_file(conn, down_file, &down_sql, |_| {
+            record_reverted_sql(migration.version)
+        })
+        .await?;
+
+        done.push(MigrationStatus {
+            version: migration.version,
+            name: migration.name.clone(),
+            state: MigrationState::Pending,
+            applied_at: None,
+            applied_by: None,
+            can_revert: false,
+        });
+    }
+
+    Ok(done)
+}
+
+// Run a migration file statement by statement, batched like apply_migration,
+// then write its `_query_migrations` change. `record_sql` gets the file's run
+// time and runs inside the transaction of the file's last batch, so the file
+// and its record commit together. When the file can't be that atomic (it
+// ends with a statement that runs outside a transaction, or fails after an
+// earlier batch committed) the error says what state the database is in and
+// gives the statement to record it by hand. Settings the file made
+// (timeouts, search_path) are reset either way.
+async fn run_migration_file(
+    conn: &mut PgConnection,
+    file_name: &str,
+    sql: &str,
+    record_sql: impl Fn(i64) -> String,
+) -> Result<(), String> {
+    let statements: Vec<String> = split_statements(sql)
+        .into_iter()
+        .filter(|statement| !is_transaction_control(statement))
+        .collect();
+    let batches = transaction_batches(&statements);
+
+    let start = Instant::now();
+    let mut committed = 0;
+    let mut recorded = false;
+    let mut error = None;
+
+    for (position, (batch, transactional)) in batches.iter().enumerate() {
+        let record = *transactional && position == batches.len() - 1;
+        if let Err(e) = run_batch(conn, &statements[batch.clone()], *transactional, || {
+            record.then(|| record_sql(start.elapsed().as_millis() as i64))
+        })
+        .await
+        {
+            error = Some(e);
+            break;
+        }
+        committed += batch.len();
+        recorded = record;
+    }
+
+    if error.is_none() && !recorded {
+        let record = record_sql(start.elapsed().as_millis() as i64);
+        if let Err(e) = conn.execute(sqlx::raw_sql(&record)).await {
+            error = Some(format!("Failed to record {}: {}", file_name, e));
+        }
+    }
+    let _ = conn.execute(sqlx::raw_sql("RESET ALL")).await;
+
+    let Some(error) = error else {
+        return Ok(());
+    };
+    if committed == 0 {
+        return Err(format!("{} failed and was rolled back: {}", file_name, error));
+    }
+    let record = record_sql(start.elapsed().as_millis() as i64);
+    if committed == statements.len() {
+        Err(format!(
+            "{} ran but is not recorded as run: {}. Record it by hand with: {}",
+            file_name, error, record
+        ))
+    } else {
+        Err(format!(
+            "{} failed after {} of {} statements were committed: {}. The database is partially migrated; finish the remaining statements by hand, then record it with: {}",
+            file_name,
+            committed,
+            statements.len(),
+            error,
+            record
+        ))
+    }
+}
+
+// One batch of a migration file, with `extra` run last inside the
+// transaction when it returns a statement. A failed transaction is rolled back.
+async fn run_batch(
+    conn: &mut PgConnection,
+    statements: &[String],
+    transactional: bool,
+    extra: impl FnOnce() -> Option<String>,
+) -> Result<(), String> {
+    if transactional {
+        conn.execute(sqlx::raw_sql("BEGIN"))
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    }
+
+    let mut result = Ok(());
+    for statement in statements {
+        if let Err(e) = conn.execute(sqlx::raw_sql(statement)).await {
+            result = Err(e.to_string());
+            break;
+        }
+    }
+    if result.is_ok() {
+        if let Some(extra) = extra() {
+            result = conn.execute(sqlx::raw_sql(&extra))
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("Failed to record migration: {}", e));
+        }
+    }
+    if transactional && result.is_ok() {
+        // A deferred constraint can still fail here, which aborts the transaction
+        result = conn.execute(sqlx::raw_sql("COMMIT"))
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Failed to commit transaction: {}", e));
+    }
+
+    if transactional && result.is_err() {
+        let _ = conn.execute(sqlx::raw_sql("ROLLBACK")).await;
+    }
+    result
+}
+
+fn record_applied_sql(file: &MigrationFile, execution_time_ms: i64) -> String {
+    format!(
+        "INSERT INTO {} (version, name, checksum, down_checksum, execution_time_ms) VALUES ({}, {}, {}, {}, {})",
+        quote_ident(MIGRATIONS_TABLE_NAME),
+        file.version,
+        quote_literal(&file.name),
+        quote_literal(&file.checksum),
+        file.down_checksum
+            .as_deref()
+            .map(quote_literal)
+            .unwrap_or_else(|| "NULL".to_string()),
+        execution_time_ms
+    )
+}
+
+fn record_reverted_sql(version: i64) -> String {
+    format!(
+        "DELETE FROM {} WHERE version = {}",
+        quote_ident(MIGRATIONS_TABLE_NAME),
+        version
+    )
+}
+
+struct RunOutcome {
+    committed: usize, // statements whose transaction committed
+    error: Option<String>,
+}
+
+async fn run_statements(
+    app: &AppHandle,
+    conn: &mut PgConnection,
+    statements: &[String],
+) -> RunOutcome {
+    let progress = Progress { app, statements };
+    let mut committed = 0;
+
+    for (batch, transactional) in transaction_batches(statements) {
+        if transactional {
+            if let Err(e) = conn.execute(sqlx::raw_sql("BEGIN")).await {
+                return RunOutcome {
+                    committed,
+                    error: Some(format!("Failed to start transaction: {}", e)),
+                };
+            }
+        }
+
+        for index in batch.clone() {
+            progress.emit(index, StatementStatus::Running, 0, None);
+
+            let start = Instant::now();
+            let result = conn.execute(sqlx::raw_sql(&statements[index])).await;
+            let elapsed_ms = start.elapsed().as_millis() as i64;
+
+            if let Err(e) = result {
+                let error = e.to_string();
+                progress.emit(index, StatementStatus::Failed, elapsed_ms, Some(error.clone()));
+                if transactional {
+                    let _ = conn.execute(sqlx::raw_sql("ROLLBACK")).await;
+                    for undone in batch.start..index {
+                        progress.emit(undone, StatementStatus::RolledBack, 0, None);
+                    }
+                }
+                return RunOutcome {
+                    committed,
+                    error: Some(format!("Statement {} failed: {}", index + 1, error)),
+                };
+            }
+
+            progress.emit(index, StatementStatus::Succeeded, elapsed_ms, None);
+        }
+
+        if transactional {
+            // A deferred constraint can still fail here, which aborts the transaction
+            if let Err(e) = conn.execute(sqlx::raw_sql("COMMIT")).await {
+                let _ = conn.execute(sqlx::raw_sql("ROLLBACK")).await;
+                for undone in batch {
+                    progress.emit(undone, StatementStatus::RolledBack, 0, None);
+                }
+                return RunOutcome {
+                    committed,
+                    error: Some(format!("Failed to commit transaction: {}", e)),
+                };
+            }
+        }
+
+        committed += batch.len();
+    }
+
+    RunOutcome {
+        committed,
+        error: None,
+    }
+}
+
+// Runs of consecutive statements that can share a transaction, each flagged
+// true; statements that must run outside a transaction get a batch to
+// themselves, flagged false
+fn transaction_batches(statements: &[String]) -> Vec<(Range<usize>, bool)> {
+    let mut batches: Vec<(Range<usize>, bool)> = Vec::new();
+
+    for (index, statement) in statements.iter().enumerate() {
+        let transactional = !runs_outside_transaction(statement);
+        match batches.last_mut() {
+            Some((batch, true)) if transactional => batch.end = index + 1,
+            _ => batches.push((index..index + 1, transactional)),
+        }
+    }
+
+    batches
+}
+
+struct Progress<'a> {
+    app: &'a AppHandle,
+    statements: &'a [String],
+}
+
+impl Progress<'_> {
+    fn emit(&self, index: usize, status: StatementStatus, elapsed_ms: i64, error: Option<String>) {
+        let _ = self.app.emit(
+            MIGRATION_PROGRESS_EVENT,
+            MigrationProgress {
+                index,
+                total: self.statements.len(),
+                statement: self.statements[index].clone(),
+                status,
+                elapsed_ms,
+                error,
+            },
+        );
+    }
+}
+
+async fn record_migration_run(db: &ProjectDb, run: &MigrationRun) -> Result<i64, String> {
+    let pool = db.pool(get_app_dir()?).await?;
+
+    let result = sqlx::query(
+        "INSERT INTO migration_runs (connection_name, database_name, script, status, error, statements_total, statements_committed, started_at, duration_ms) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&run.connection_name)
+    .bind(&run.database)
+    .bind(&run.script)
+    .bind(&run.status)
+    .bind(&run.error)
+    .bind(run.statements_total)
+    .bind(run.statements_committed)
+    .bind(&run.started_at)
+    .bind(run.duration_ms)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to save migration run: {}", e))?;
+
+    Ok(result.last_insert_rowid())
+}
+
+// Join files and applied rows into one list, ordered by version
+fn migration_statuses(files: &[MigrationFile], applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    let applied_by_version: HashMap<i64, &AppliedMigration> =
+        applied.iter().map(|m| (m.version, m)).collect();
+
+    let mut statuses: Vec<MigrationStatus> = files
+        .iter()
+        .map(|file| {
+            let applied = applied_by_version.get(&file.version);
+            let state = match applied {
+                None => MigrationState::Pending,
+                Some(m) if m.checksum != file.checksum => MigrationState::Modified,
+                Some(m) if m.down_checksum.is_some() && m.down_checksum != file.down_checksum => {
+                    MigrationState::Modified
+                }
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: file.version,
+                name: file.name.clone(),
+                state,
+                applied_at: applied.map(|m| m.applied_at.clone()),
+                applied_by: applied.map(|m| m.applied_by.clone()),
+                can_revert: applied.is_some() && file.down_file.is_some(),
+            }
+        })
+        .collect();
+
+    for migration in applied {
+        if !files.iter().any(|f| f.version == migration.version) {
+            statuses.push(MigrationStatus {
+                version: migration.version,
+                name: migration.name.clone(),
+                state: MigrationState::Missing,
+                applied_at: Some(migration.applied_at.clone()),
+                applied_by: Some(migration.applied_by.clone()),
+                can_revert: false,
+            });
+        }
+    }
+
+    statuses.sort_by_key(|s| s.version);
+    statuses
+}
+
+async fn connect(config: &ConnectionConfig) -> Result<PgConnection, String> {
+    connect_options(config)
+        .connect()
+        .await
+        .map_err(|e| format!("Error connecting to database: {}", e))
+}
+
+// Session-level advisory lock, held on the same connection that runs the
+// migrations and released when it closes
+async fn acquire_migration_lock(conn: &mut PgConnection) -> Result<(), String> {
+    let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(MIGRATION_ADVISORY_LOCK_KEY)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to acquire migration lock: {}", e))?;
+
+    if !acquired {
+        return Err("Another migration is running against this database".to_string());
+    }
+
+    Ok(())
+}
+
+async fn release_migration_lock(conn: &mut PgConnection) {
+    let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_ADVISORY_LOCK_KEY)
+        .execute(&mut *conn)
+        .await;
+}
+
+async fn ensure_migrations_table(conn: &mut PgConnection) -> Result<(), String> {
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            version bigint PRIMARY KEY,
+            name text NOT NULL,
+            checksum text NOT NULL,
+            down_checksum text,
+            applied_at timestamptz NOT NULL DEFAULT now(),
+            applied_by text NOT NULL DEFAULT current_user,
+            execution_time_ms bigint NOT NULL
+        )",
+        quote_ident(MIGRATIONS_TABLE_NAME)
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to create migrations table: {}", e))?;
+
+    // Tables created before down files were checksummed
+    sqlx::query(&format!(
+        "ALTER TABLE {} ADD COLUMN IF NOT EXISTS down_checksum text",
+        quote_ident(MIGRATIONS_TABLE_NAME)
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to upgrade migrations table: {}", e))?;
+
+    Ok(())
+}
+
+// Applied migrations in version order; none when the table doesn't exist yet
+async fn fetch_applied_migrations(conn: &mut PgConnection) -> Result<Vec<AppliedMigration>, String> {
+    let table_exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(quote_ident(MIGRATIONS_TABLE_NAME))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to check migrations table: {}", e))?;
+
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    // down_checksum is read through to_jsonb so tables that predate the
+    // column can still be listed
+    let rows = sqlx::query(&format!(
+        "SELECT version, name, checksum, to_jsonb(m) ->> 'down_checksum' AS down_checksum,
+                applied_at::text AS applied_at, applied_by, execution_time_ms
+         FROM {} m
+         ORDER BY version",
+        quote_ident(MIGRATIONS_TABLE_NAME)
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to fetch applied migrations: {}", e))?;
+
+    let mut applied = Vec::with_capacity(rows.len());
+    for row in rows {
+        applied.push(AppliedMigration {
+            version: row
+                .try_get("version")
+                .map_err(|e| format!("Failed to get migration version: {}", e))?,
+            name: row
+                .try_get("name")
+                .map_err(|e| format!("Failed to get migration name: {}", e))?,
+            checksum: row
+                .try_get("checksum")
+                .map_err(|e| format!("Failed to get migration checksum: {}", e))?,
+            down_checksum: row
+                .try_get("down_checksum")
+                .map_err(|e| format!("Failed to get migration down checksum: {}", e))?,
+            applied_at: row
+                .try_get("applied_at")
+                .map_err(|e| format!("Failed to get applied_at: {}", e))?,
+            applied_by: row
+                .try_get("applied_by")
+                .map_err(|e| format!("Failed to get applied_by: {}", e))?,
+            execution_time_ms: row
+                .try_get("execution_time_ms")
+                .map_err(|e| format!("Failed to get execution time: {}", e))?,
+        });
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(version: i64, down_checksum: Option<&str>) -> MigrationFile {
+        MigrationFile {
+            version,
+            name: format!("step_{}", version),
+            up_file: format!("{:04}_step_{}.up.sql", version, version),
+            down_file: down_checksum.map(|_| format!("{:04}_step_{}.down.sql", version, version)),
+            checksum: "up".to_string(),
+            down_checksum: down_checksum.map(str::to_string),
+        }
+    }
+
+    fn applied(version: i64, down_checksum: Option<&str>) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            name: format!("step_{}", version),
+            checksum: "up".to_string(),
+            down_checksum: down_checksum.map(str::to_string),
+            applied_at: "2024-01-01".to_string(),
+            applied_by: "postgres".to_string(),
+            execution_time_ms: 1,
+        }
+    }
+
+    fn states(files: &[MigrationFile], applied: &[AppliedMigration]) -> Vec<MigrationState> {
+        migration_statuses(files, applied)
+            .iter()
+            .map(|s| s.state)
+            .collect()
+    }
+
+    #[test]
+    fn edited_down_file_marks_the_migration_modified() {
+        let files = [
+            file(1, Some("down")),
+            file(2, Some("edited")),
+            file(3, None),
+        ];
+        let rows = [
+            applied(1, Some("down")),
+            applied(2, Some("down")),
+            applied(3, None),
+        ];
+        assert_eq!(
+            states(&files, &rows),
+            [
+                MigrationState::Applied,
+                MigrationState::Modified,
+                MigrationState::Applied
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_without_a_down_checksum_only_compare_the_up_file() {
+        let files = [file(1, Some("down")), file(2, None)];
+        let rows = [applied(1, None)];
+        assert_eq!(
+            states(&files, &rows),
+            [MigrationState::Applied, MigrationState::Pending]
+        );
+
+        let deleted_down = [file(1, None)];
+        assert_eq!(
+            states(&deleted_down, &[applied(1, Some("down"))]),
+            [MigrationState::Modified]
+        );
+    }
+
+    #[test]
+    fn record_statements_quote_their_values() {
+        let mut migration = file(7, Some("abc"));
+        migration.name = "it's".to_string();
+        assert_eq!(
+            record_applied_sql(&migration, 12),
+            "INSERT INTO _query_migrations (version, name, checksum, down_checksum, execution_time_ms) VALUES (7, 'it''s', 'up', 'abc', 12)"
+        );
+        assert!(record_applied_sql(&file(7, None), 0).contains("'up', NULL, 0)"));
+        assert_eq!(
+            record_reverted_sql(7),
+            "DELETE FROM _query_migrations WHERE version = 7"
+        );
+    }
+
+    #[test]
+    fn batches_split_around_statements_that_need_no_transaction() {
+        let statements: Vec<String> = [
+            "ALTER TABLE t ADD COLUMN a int",
+            "ALTER TABLE t ADD COLUMN b int",
+            "CREATE INDEX CONCURRENTLY t_a ON t (a)",
+            "ALTER TABLE t ADD COLUMN c int",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            transaction_batches(&statements),
+            [(0..2, true), (2..3, false), (3..4, true)]
+        );
+        assert!(transaction_batches(&[]).is_empty());
+    }
+}
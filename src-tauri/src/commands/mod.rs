@@ -1,15 +1,35 @@
 mod comparison;
 mod connection;
+mod data_comparison;
+mod documentation;
 mod git;
 mod history;
+mod migrations;
+mod query_files;
+mod reports;
 mod saved_queries;
 mod settings;
+mod snapshots;
 
-pub use comparison::{compare_schemas, generate_migration_sql};
+pub use comparison::{check_resolved_warnings, compare_schemas, generate_migration_sql, generate_rollback_sql, preflight_migration};
 pub use connection::{execute_query, get_database_schema, get_database_schemas, test_postgres_connection, get_enhanced_database_schema};
+pub use data_comparison::compare_table_data;
+pub use documentation::generate_schema_docs;
 pub use git::{check_git_repo, get_git_log, get_git_status, git_commit, git_init, git_pull, git_push};
-pub use history::{clear_query_history, get_query_history, save_query_to_history};
-pub use saved_queries::{delete_saved_query, get_saved_queries, save_query, toggle_pin_query};
+pub use history::{
+    clear_query_history, delete_history_entries, get_history_retention, get_migration_runs,
+    get_query_history, save_query_to_history, search_query_history, set_history_retention,
+};
+pub use migrations::{apply_migration, create_migration, migrate_down, migrate_up, migration_status};
+pub use query_files::{
+    get_saved_query_files_enabled, set_saved_query_files_enabled, watch_saved_query_files,
+};
+pub use reports::export_comparison_report;
+pub use saved_queries::{
+    delete_saved_query, diff_saved_query_revisions, get_saved_queries,
+    list_saved_query_revisions, restore_saved_query_revision, save_query, search_saved_queries,
+    toggle_pin_query, update_saved_query,
+};
 pub use settings::{
     delete_connection_password, get_app_dir, get_auto_connect_enabled, get_connection_password,
     get_current_project_path, get_last_connection, load_connections, load_project_settings,
@@ -17,3 +37,4 @@ pub use settings::{
     set_project_path, get_recent_projects, remove_recent_project, get_vim_mode_enabled,
     set_vim_mode_enabled,
 };
+pub use snapshots::{list_schema_snapshots, snapshot_schema};
@@ -0,0 +1,504 @@
+use super::git::LazyGitAuthor;
+use super::saved_queries::{
+    fetch_saved_query, input_of, insert_saved_query, normalize_input, saved_query_from_row,
+    write_saved_query, SAVED_QUERY_COLUMNS,
+};
+use crate::constants::{
+    SAVED_QUERIES_CHANGED_EVENT, SAVED_QUERIES_DIR_NAME, SAVED_QUERY_FILES_DEBOUNCE_MS,
+};
+use crate::models::{SavedQuery, SavedQueryInput};
+use crate::storage::{
+    list_query_files, query_file_candidates, query_file_exists, remove_query_file,
+    write_query_file, ProjectDb,
+};
+use crate::utils::{
+    get_app_dir, get_saved_query_files_enabled_internal, sanitize_file_name,
+    set_saved_query_files_enabled_internal,
+};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::{Mutex, Notify};
+
+// Held across every change that touches both saved_queries and queries/, so
+// a sync never reads a file half way through the app writing it
+pub(super) static QUERY_FILES_LOCK: Mutex<()> = Mutex::const_new(());
+
+// Wakes the watcher to work out again what it should watch
+static QUERY_FILES_REWATCH: Notify = Notify::const_new();
+
+/// Tell the watcher the project or the saved query files setting changed
+pub(super) fn rewatch_saved_query_files() {
+    QUERY_FILES_REWATCH.notify_one();
+}
+
+#[tauri::command]
+pub fn get_saved_query_files_enabled() -> Result<bool, String> {
+    get_saved_query_files_enabled_internal()
+}
+
+/// Switch saved queries between project.db and .sql files under queries/.
+/// Turning files on writes a file for each query, except where a file with
+/// that name already exists, which is taken as the query's content instead.
+/// Turning them off keeps the files on disk but stops reading them, and
+/// brings back queries hidden because their file went missing.
+#[tauri::command]
+pub async fn set_saved_query_files_enabled(
+    db: State<'_, ProjectDb>,
+    enabled: bool,
+) -> Result<(), String> {
+    let app_dir = get_app_dir()?;
+    let pool = db.pool(app_dir.clone()).await?;
+
+    let _files = QUERY_FILES_LOCK.lock().await;
+
+    if !enabled {
+        detach_saved_query_files(&pool).await?;
+        set_saved_query_files_enabled_internal(false)?;
+        rewatch_saved_query_files();
+        return Ok(());
+    }
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM saved_queries q WHERE q.file_path IS NULL AND q.missing_since IS NULL ORDER BY q.id",
+        SAVED_QUERY_COLUMNS
+    ))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to fetch saved queries: {}", e))?;
+
+    for row in &rows {
+        let query = saved_query_from_row(row)?;
+        let path = query_file_candidates(query.folder.as_deref(), &query.name)
+            .next()
+            .unwrap_or_default();
+
+        if query_file_exists(&app_dir, &path) && !is_indexed(&pool, &path).await? {
+            set_file_path(&pool, query.id, &path).await?;
+        } else {
+            export_saved_query(&pool, &app_dir, query.id).await?;
+        }
+    }
+
+    set_saved_query_files_enabled_internal(true)?;
+    rewatch_saved_query_files();
+    sync_saved_query_files(&pool, &app_dir).await?;
+
+    Ok(())
+}
+
+// Missing is only tracked while files are on; left set, a query would stay
+// hidden, and turning files back on would skip it
+async fn detach_saved_query_files(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query("UPDATE saved_queries SET file_path = NULL, missing_since = NULL")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update saved queries: {}", e))?;
+
+    Ok(())
+}
+
+/// Write a saved query to its file, moving the file when the query's name or
+/// folder no longer match where it is
+pub(super) async fn export_saved_query(
+    pool: &SqlitePool,
+    app_dir: &Path,
+    id: i64,
+) -> Result<(), String> {
+    let query = fetch_saved_query(pool, id).await?;
+
+    let taken: Vec<String> = sqlx::query_scalar(
+        "SELECT file_path FROM saved_queries WHERE file_path IS NOT NULL AND id != ?",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch saved queries: {}", e))?;
+
+    let path = query_file_candidates(query.folder.as_deref(), &query.name)
+        .find(|path| {
+            query.file_path.as_ref() == Some(path)
+                || (!taken.contains(path) && !query_file_exists(app_dir, path))
+        })
+        .ok_or_else(|| format!("Could not pick a file name for {}", query.name))?;
+
+    write_query_file(app_dir, &path, &input_of(&query), query.is_pinned)?;
+
+    if query.file_path.as_ref() != Some(&path) {
+        set_file_path(pool, id, &path).await?;
+        if let Some(old_path) = &query.file_path {
+            remove_query_file(app_dir, old_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Bring saved_queries in line with the files under queries/: changed files
+/// update their query (adding a revision), new files add one, and queries
+/// whose file is gone are flagged missing and hidden, keeping their
+/// revisions. A new file carrying the name and body of a missing query is
+/// taken as that file moved (or restored). Returns whether anything changed.
+pub(super) async fn sync_saved_query_files(
+    pool: &SqlitePool,
+    app_dir: &Path,
+) -> Result<bool, String> {
+    let files = list_query_files(app_dir)?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {}, q.missing_since IS NOT NULL AS is_missing FROM saved_queries q
+         WHERE q.file_path IS NOT NULL OR q.missing_since IS NOT NULL",
+        SAVED_QUERY_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch saved queries: {}", e))?;
+
+    // Queries by file, and those already missing from an earlier sync
+    let mut indexed: HashMap<String, SavedQuery> = HashMap::new();
+    let mut missing: HashSet<i64> = HashSet::new();
+    let mut vanished: Vec<SavedQuery> = Vec::new();
+    for row in &rows {
+        let query = saved_query_from_row(row)?;
+        if row.get::<bool, _>("is_missing") {
+            missing.insert(query.id);
+        }
+        match query.file_path.clone() {
+            Some(path) => {
+                indexed.insert(path, query);
+            }
+            None => vanished.push(query),
+        }
+    }
+
+    let mut author = LazyGitAuthor::new(app_dir);
+    let mut changed = false;
+    let mut new_files = Vec::new();
+
+    for file in files {
+        // Files that don't make a valid query (a blank name, an unusable
+        // folder) are left alone until they do
+        let Ok(input) = normalize_input(file.input) else {
+            continue;
+        };
+
+        match indexed.remove(&file.path) {
+            Some(query) => {
+                if missing.contains(&query.id) {
+                    set_missing(pool, query.id, false).await?;
+                    changed = true;
+                }
+                changed |= apply_query_file(pool, &query, input, file.is_pinned, &mut author).await?;
+            }
+            None => new_files.push((file.path, input, file.is_pinned)),
+        }
+    }
+
+    // Whatever is left in the index lost its file
+    vanished.extend(indexed.into_values());
+
+    for (path, input, is_pinned) in new_files {
+        let moved = vanished
+            .iter()
+            .position(|query| query.name == input.name && query.query == input.query);
+
+        match moved {
+            Some(index) => {
+                let query = vanished.swap_remove(index);
+                set_file_path(pool, query.id, &path).await?;
+                if missing.contains(&query.id) {
+                    set_missing(pool, query.id, false).await?;
+                }
+                apply_query_file(pool, &query, input, is_pinned, &mut author).await?;
+            }
+            None => {
+                let author = author.get().await;
+                insert_saved_query(pool, &input, is_pinned, Some(&path), author.as_deref()).await?;
+            }
+        }
+        changed = true;
+    }
+
+    // Never deleted here: a file can vanish for a while (a branch switch, a
+    // half-done pull) and deleting the row would take its revisions with it
+    for query in vanished {
+        if !missing.contains(&query.id) {
+            set_missing(pool, query.id, true).await?;
+            changed = true;
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Watch the project's queries/ for outside edits (a text editor, a git
+/// pull) while saved query files are on, emitting `saved-queries-changed`
+/// when a sync finds any. A burst of file events, such as a checkout touching
+/// many files, is debounced into one sync.
+pub async fn watch_saved_query_files(app: AppHandle) {
+    let (events_tx, mut events) = unbounded_channel();
+    let mut watching: Option<(PathBuf, Debouncer<RecommendedWatcher>)> = None;
+
+    loop {
+        let wanted = match get_saved_query_files_enabled_internal() {
+            Ok(true) => get_app_dir().ok(),
+            _ => None,
+        };
+        if watching.as_ref().map(|(dir, _)| dir) != wanted.as_ref() {
+            // Dropping the debouncer stops the previous watch. A project
+            // that can't be watched is still synced whenever its saved
+            // queries are listed.
+            watching = None;
+            if let Some(app_dir) = wanted {
+                if let Ok(debouncer) = watch_queries_dir(&app_dir, events_tx.clone()) {
+                    watching = Some((app_dir, debouncer));
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = QUERY_FILES_REWATCH.notified() => continue,
+            Some(()) = events.recv() => {}
+        }
+
+        let Some((app_dir, _)) = &watching else {
+            continue;
+        };
+        let Ok(pool) = app.state::<ProjectDb>().pool(app_dir.clone()).await else {
+            continue;
+        };
+
+        let _files = QUERY_FILES_LOCK.lock().await;
+        if let Ok(true) = sync_saved_query_files(&pool, app_dir).await {
+            let _ = app.emit(SAVED_QUERIES_CHANGED_EVENT, ());
+        }
+    }
+}
+
+fn watch_queries_dir(
+    app_dir: &Path,
+    events: UnboundedSender<()>,
+) -> Result<Debouncer<RecommendedWatcher>, String> {
+    let queries_dir = app_dir.join(SAVED_QUERIES_DIR_NAME);
+    fs::create_dir_all(&queries_dir)
+        .map_err(|e| format!("Could not create {}: {}", queries_dir.display(), e))?;
+
+    // Watch errors (an overflowing event queue, say) may hide changes, so
+    // they trigger a sync too
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(SAVED_QUERY_FILES_DEBOUNCE_MS),
+        move |_: DebounceEventResult| {
+            let _ = events.send(());
+        },
+    )
+    .map_err(|e| format!("Could not watch {}: {}", queries_dir.display(), e))?;
+
+    debouncer
+        .watcher()
+        .watch(&queries_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Could not watch {}: {}", queries_dir.display(), e))?;
+
+    Ok(debouncer)
+}
+
+// Update a query from its file. Returns whether anything changed.
+async fn apply_query_file(
+    pool: &SqlitePool,
+    query: &SavedQuery,
+    mut input: SavedQueryInput,
+    is_pinned: bool,
+    author: &mut LazyGitAuthor<'_>,
+) -> Result<bool, String> {
+    let current = input_of(query);
+
+    // Folder names are sanitized on the way to disk, so a folder read back
+    // from a path only counts as a move when it differs from that
+    if input.folder == current.folder.as_deref().map(sanitize_folder) {
+        input.folder = current.folder.clone();
+    }
+
+    let mut changed = false;
+
+    if input != current {
+        let author = author.get().await;
+        write_saved_query(pool, query.id, &input, author.as_deref()).await?;
+        changed = true;
+    }
+
+    if is_pinned != query.is_pinned {
+        sqlx::query("UPDATE saved_queries SET is_pinned = ? WHERE id = ?")
+            .bind(is_pinned)
+            .bind(query.id)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to update pin status: {}", e))?;
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+async fn is_indexed(pool: &SqlitePool, path: &str) -> Result<bool, String> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM saved_queries WHERE file_path = ?)")
+        .bind(path)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to fetch saved queries: {}", e))
+}
+
+async fn set_file_path(pool: &SqlitePool, id: i64, path: &str) -> Result<(), String> {
+    sqlx::query("UPDATE saved_queries SET file_path = ? WHERE id = ?")
+        .bind(path)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update saved query: {}", e))?;
+
+    Ok(())
+}
+
+async fn set_missing(pool: &SqlitePool, id: i64, missing: bool) -> Result<(), String> {
+    let missing_since = missing.then(|| chrono::Utc::now().to_rfc3339());
+    sqlx::query("UPDATE saved_queries SET missing_since = ? WHERE id = ?")
+        .bind(missing_since)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update saved query: {}", e))?;
+
+    Ok(())
+}
+
+fn sanitize_folder(folder: &str) -> String {
+    folder
+        .split('/')
+        .map(sanitize_file_name)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn project_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("query-files-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn state(pool: &SqlitePool, id: i64) -> (Option<String>, bool, i64) {
+        sqlx::query_as(
+            "SELECT file_path, missing_since IS NOT NULL,
+                    (SELECT count(*) FROM saved_query_revisions WHERE query_id = id)
+             FROM saved_queries WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn missing_files_keep_their_query_and_revisions() {
+        let dir = project_dir("missing");
+        let pool = ProjectDb::default().pool(dir.clone()).await.unwrap();
+        let input = SavedQueryInput {
+            name: "Monthly".to_string(),
+            query: "SELECT 1".to_string(),
+            ..Default::default()
+        };
+
+        let id = insert_saved_query(&pool, &input, false, None, None)
+            .await
+            .unwrap();
+        let edited = SavedQueryInput {
+            query: "SELECT 2".to_string(),
+            ..input.clone()
+        };
+        write_saved_query(&pool, id, &edited, None).await.unwrap();
+        export_saved_query(&pool, &dir, id).await.unwrap();
+        let path = state(&pool, id).await.0.unwrap();
+
+        remove_query_file(&dir, &path).unwrap();
+        assert!(sync_saved_query_files(&pool, &dir).await.unwrap());
+        assert_eq!(state(&pool, id).await, (Some(path.clone()), true, 2));
+        // Still missing is not a change
+        assert!(!sync_saved_query_files(&pool, &dir).await.unwrap());
+
+        // Coming back restores the same query, with its history
+        write_query_file(&dir, &path, &edited, false).unwrap();
+        assert!(sync_saved_query_files(&pool, &dir).await.unwrap());
+        assert_eq!(state(&pool, id).await, (Some(path.clone()), false, 2));
+
+        // So does coming back under another folder, the move being an edit
+        remove_query_file(&dir, &path).unwrap();
+        sync_saved_query_files(&pool, &dir).await.unwrap();
+        let moved = format!("archive/{}", path);
+        write_query_file(&dir, &moved, &edited, false).unwrap();
+        assert!(sync_saved_query_files(&pool, &dir).await.unwrap());
+        assert_eq!(state(&pool, id).await, (Some(moved), false, 3));
+
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn turning_files_off_brings_back_missing_queries() {
+        let dir = project_dir("off");
+        let pool = ProjectDb::default().pool(dir.clone()).await.unwrap();
+        let input = SavedQueryInput {
+            name: "Monthly".to_string(),
+            query: "SELECT 1".to_string(),
+            ..Default::default()
+        };
+
+        let id = insert_saved_query(&pool, &input, false, None, None)
+            .await
+            .unwrap();
+        export_saved_query(&pool, &dir, id).await.unwrap();
+        let path = state(&pool, id).await.0.unwrap();
+        remove_query_file(&dir, &path).unwrap();
+        sync_saved_query_files(&pool, &dir).await.unwrap();
+        assert_eq!(state(&pool, id).await, (Some(path), true, 1));
+
+        detach_saved_query_files(&pool).await.unwrap();
+
+        assert_eq!(state(&pool, id).await, (None, false, 1));
+
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn unreadable_files_are_skipped() {
+        let dir = project_dir("unreadable");
+        let pool = ProjectDb::default().pool(dir.clone()).await.unwrap();
+        let input = SavedQueryInput {
+            name: "Monthly".to_string(),
+            query: "SELECT 1".to_string(),
+            ..Default::default()
+        };
+        write_query_file(&dir, "monthly.sql", &input, false).unwrap();
+        std::fs::write(dir.join(SAVED_QUERIES_DIR_NAME).join("latin1.sql"), b"SELECT '\xe9'").unwrap();
+
+        assert!(sync_saved_query_files(&pool, &dir).await.unwrap());
+        let names: Vec<String> = sqlx::query_scalar("SELECT name FROM saved_queries")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["Monthly"]);
+
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::constants::REPORTS_DIR_NAME;
+use crate::utils::{
+    generate_migration_script, get_app_dir, render_comparison_report, sanitize_file_name,
+    MigrationScriptOptions, ReportFormat, SchemaComparison,
+};
+use std::fs;
+
+/// Write a comparison, with the migration script for it, as a report in the
+/// project's reports directory. The file name only depends on the compared
+/// sides, so re-exporting overwrites the previous report and the change shows
+/// up as a diff in git.
+#[tauri::command]
+pub fn export_comparison_report(
+    comparison: SchemaComparison,
+    format: String,
+    options: Option<MigrationScriptOptions>,
+) -> Result<String, String> {
+    let format = ReportFormat::parse(&format)?;
+
+    let migration_script = generate_migration_script(&comparison, &options.unwrap_or_default());
+    let report = render_comparison_report(&comparison, &migration_script, format)?;
+
+    let reports_dir = get_app_dir()?.join(REPORTS_DIR_NAME);
+    fs::create_dir_all(&reports_dir)
+        .map_err(|e| format!("Could not create reports directory: {}", e))?;
+
+    let file_name = format!(
+        "{}_vs_{}.{}.{}",
+        sanitize_file_name(&comparison.source_connection),
+        sanitize_file_name(&comparison.target_connection),
+        sanitize_file_name(&comparison.schema_name),
+        format.extension()
+    );
+    let report_file = reports_dir.join(file_name);
+
+    fs::write(&report_file, report)
+        .map_err(|e| format!("Could not write comparison report: {}", e))?;
+
+    Ok(report_file.to_string_lossy().to_string())
+}
@@ -1,100 +1,251 @@
-use crate::models::SavedQuery;
-use crate::storage::get_saved_queries_db;
-use crate::utils::get_app_dir;
+use super::git::git_author;
+use super::query_files::{export_saved_query, sync_saved_query_files, QUERY_FILES_LOCK};
+use crate::models::{
+    SavedQuery, SavedQueryInput, SavedQueryRevision, SavedQueryRevisionDiff, SavedQuerySearch,
+};
+use crate::storage::{remove_query_file, ProjectDb};
+use crate::utils::text_diff::diff_lines;
+use crate::utils::{get_app_dir, get_saved_query_files_enabled_internal};
+use sqlx::sqlite::{Sqlite, SqlitePool, SqliteRow};
+use sqlx::{QueryBuilder, Row};
+use tauri::State;
 
+// Columns of a SavedQuery, on rows aliased as q. Tags come back as a JSON array.
+pub(super) const SAVED_QUERY_COLUMNS: &str = "q.id, q.name, q.query, q.description, q.is_pinned, q.folder, \
+     q.default_connection, q.parameters, q.file_path, q.created_at, q.updated_at, \
+     (SELECT json_group_array(tag) FROM (SELECT tag FROM saved_query_tags t WHERE t.query_id = q.id ORDER BY tag)) AS tags";
+
+/// Save a new query. With saved query files on, this and every other write
+/// below also writes the query's file, under the files lock so the watcher
+/// never sees the row and the file disagree.
 #[tauri::command]
 pub async fn save_query(
-    name: String,
-    query: String,
-    description: Option<String>,
+    db: State<'_, ProjectDb>,
+    input: SavedQueryInput,
 ) -> Result<SavedQuery, String> {
+    let input = normalize_input(input)?;
     let app_dir = get_app_dir()?;
-    let pool = get_saved_queries_db(app_dir).await?;
+    let author = git_author(&app_dir).await;
+    let pool = db.pool(app_dir.clone()).await?;
 
-    let now = chrono::Utc::now().to_rfc3339();
+    let _files = QUERY_FILES_LOCK.lock().await;
+    let id = insert_saved_query(&pool, &input, false, None, author.as_deref()).await?;
+    if get_saved_query_files_enabled_internal()? {
+        export_saved_query(&pool, &app_dir, id).await?;
+    }
 
-    let result = sqlx::query(
-        "INSERT INTO saved_queries (name, query, description, is_pinned, created_at, updated_at) VALUES (?, ?, ?, 0, ?, ?)"
+    fetch_saved_query(&pool, id).await
+}
+
+/// Replace the editable fields of a saved query, keeping its id, pin and
+/// created_at. The previous state stays available as a revision.
+#[tauri::command]
+pub async fn update_saved_query(
+    db: State<'_, ProjectDb>,
+    id: i64,
+    input: SavedQueryInput,
+) -> Result<SavedQuery, String> {
+    let input = normalize_input(input)?;
+    let app_dir = get_app_dir()?;
+    let author = git_author(&app_dir).await;
+    let pool = db.pool(app_dir.clone()).await?;
+
+    let _files = QUERY_FILES_LOCK.lock().await;
+    write_saved_query(&pool, id, &input, author.as_deref()).await?;
+    if get_saved_query_files_enabled_internal()? {
+        export_saved_query(&pool, &app_dir, id).await?;
+    }
+
+    fetch_saved_query(&pool, id).await
+}
+
+/// Revisions of a saved query, newest first
+#[tauri::command]
+pub async fn list_saved_query_revisions(
+    db: State<'_, ProjectDb>,
+    id: i64,
+) -> Result<Vec<SavedQueryRevision>, String> {
+    let pool = db.pool(get_app_dir()?).await?;
+
+    let rows = sqlx::query(
+        "SELECT query_id, revision, name, query, description, folder, tags, default_connection, parameters, author, created_at
+         FROM saved_query_revisions
+         WHERE query_id = ?
+         ORDER BY revision DESC",
     )
-    .bind(&name)
-    .bind(&query)
-    .bind(&description)
-    .bind(&now)
-    .bind(&now)
-    .execute(&pool)
+    .bind(id)
+    .fetch_all(&pool)
     .await
-    .map_err(|e| format!("Failed to save query: {}", e))?;
+    .map_err(|e| format!("Failed to fetch revisions: {}", e))?;
 
-    let id = result.last_insert_rowid();
+    rows.iter().map(revision_from_row).collect()
+}
 
-    pool.close().await;
+/// Line diff of the query body between two revisions of a saved query
+#[tauri::command]
+pub async fn diff_saved_query_revisions(
+    db: State<'_, ProjectDb>,
+    id: i64,
+    from_revision: i64,
+    to_revision: i64,
+) -> Result<SavedQueryRevisionDiff, String> {
+    let pool = db.pool(get_app_dir()?).await?;
 
-    Ok(SavedQuery {
-        id,
-        name,
-        query,
-        description,
-        is_pinned: false,
-        created_at: now.clone(),
-        updated_at: now,
-    })
+    let from = fetch_revision(&pool, id, from_revision).await?;
+    let to = fetch_revision(&pool, id, to_revision).await?;
+    let lines = diff_lines(&from.query, &to.query);
+
+    Ok(SavedQueryRevisionDiff { from, to, lines })
 }
 
+/// Make an earlier revision current again. Restoring is itself an edit, so
+/// it adds a revision and the ones after the restored one are kept.
 #[tauri::command]
-pub async fn get_saved_queries() -> Result<Vec<SavedQuery>, String> {
+pub async fn restore_saved_query_revision(
+    db: State<'_, ProjectDb>,
+    id: i64,
+    revision: i64,
+) -> Result<SavedQuery, String> {
     let app_dir = get_app_dir()?;
-    let pool = get_saved_queries_db(app_dir).await?;
+    let author = git_author(&app_dir).await;
+    let pool = db.pool(app_dir.clone()).await?;
 
-    let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, bool, String, String)>(
-        "SELECT id, name, query, description, is_pinned, created_at, updated_at
-         FROM saved_queries
-         ORDER BY is_pinned DESC, name ASC",
-    )
+    let restored = fetch_revision(&pool, id, revision).await?;
+    let input = SavedQueryInput {
+        name: restored.name,
+        query: restored.query,
+        description: restored.description,
+        folder: restored.folder,
+        tags: restored.tags,
+        default_connection: restored.default_connection,
+        parameters: restored.parameters,
+    };
+
+    let _files = QUERY_FILES_LOCK.lock().await;
+    write_saved_query(&pool, id, &input, author.as_deref()).await?;
+    if get_saved_query_files_enabled_internal()? {
+        export_saved_query(&pool, &app_dir, id).await?;
+    }
+
+    fetch_saved_query(&pool, id).await
+}
+
+#[tauri::command]
+pub async fn get_saved_queries(db: State<'_, ProjectDb>) -> Result<Vec<SavedQuery>, String> {
+    let app_dir = get_app_dir()?;
+    let pool = db.pool(app_dir.clone()).await?;
+
+    // Don't wait for the watcher to show edits made while the app was closed
+    if get_saved_query_files_enabled_internal()? {
+        let _files = QUERY_FILES_LOCK.lock().await;
+        sync_saved_query_files(&pool, &app_dir).await?;
+    }
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM saved_queries q WHERE q.missing_since IS NULL ORDER BY q.is_pinned DESC, q.name ASC",
+        SAVED_QUERY_COLUMNS
+    ))
     .fetch_all(&pool)
     .await
     .map_err(|e| format!("Failed to fetch saved queries: {}", e))?;
 
-    pool.close().await;
-
-    let queries = rows
-        .into_iter()
-        .map(
-            |(id, name, query, description, is_pinned, created_at, updated_at)| SavedQuery {
-                id,
-                name,
-                query,
-                description,
-                is_pinned,
-                created_at,
-                updated_at,
-            },
-        )
-        .collect();
+    rows.iter().map(saved_query_from_row).collect()
+}
 
-    Ok(queries)
+/// Saved queries whose name, description, tags or body contain `text`,
+/// optionally limited to a folder (and its subfolders) or a tag
+#[tauri::command]
+pub async fn search_saved_queries(
+    db: State<'_, ProjectDb>,
+    search: SavedQuerySearch,
+) -> Result<Vec<SavedQuery>, String> {
+    let pool = db.pool(get_app_dir()?).await?;
+    find_saved_queries(&pool, search).await
+}
+
+async fn find_saved_queries(
+    pool: &SqlitePool,
+    search: SavedQuerySearch,
+) -> Result<Vec<SavedQuery>, String> {
+    let folder = normalize_folder(search.folder)?;
+
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {} FROM saved_queries q WHERE q.missing_since IS NULL",
+        SAVED_QUERY_COLUMNS
+    ));
+
+    if let Some(text) = search.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let pattern = format!("%{}%", escape_like(text));
+        query.push(" AND (q.name LIKE ");
+        query.push_bind(pattern.clone());
+        query.push(" ESCAPE '\\' OR q.description LIKE ");
+        query.push_bind(pattern.clone());
+        query.push(" ESCAPE '\\' OR q.query LIKE ");
+        query.push_bind(pattern.clone());
+        query.push(" ESCAPE '\\' OR EXISTS (SELECT 1 FROM saved_query_tags t WHERE t.query_id = q.id AND t.tag LIKE ");
+        query.push_bind(pattern);
+        query.push(" ESCAPE '\\'))");
+    }
+
+    if let Some(folder) = folder {
+        query.push(" AND (q.folder = ");
+        query.push_bind(folder.clone());
+        query.push(" OR q.folder LIKE ");
+        query.push_bind(format!("{}/%", escape_like(&folder)));
+        query.push(" ESCAPE '\\')");
+    }
+
+    if let Some(tag) = search.tag.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        query.push(" AND EXISTS (SELECT 1 FROM saved_query_tags t WHERE t.query_id = q.id AND t.tag = ");
+        query.push_bind(tag.to_string());
+        query.push(")");
+    }
+
+    query.push(" ORDER BY q.is_pinned DESC, q.name ASC");
+
+    let rows = query
+        .build()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to search saved queries: {}", e))?;
+
+    rows.iter().map(saved_query_from_row).collect()
 }
 
 #[tauri::command]
-pub async fn delete_saved_query(id: i64) -> Result<(), String> {
+pub async fn delete_saved_query(db: State<'_, ProjectDb>, id: i64) -> Result<(), String> {
     let app_dir = get_app_dir()?;
-    let pool = get_saved_queries_db(app_dir).await?;
+    let pool = db.pool(app_dir.clone()).await?;
+
+    let _files = QUERY_FILES_LOCK.lock().await;
+    let file_path: Option<String> =
+        sqlx::query_scalar("SELECT file_path FROM saved_queries WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("Failed to fetch query: {}", e))?
+            .flatten();
 
+    // Tags and revisions go with it (ON DELETE CASCADE)
     sqlx::query("DELETE FROM saved_queries WHERE id = ?")
         .bind(id)
         .execute(&pool)
         .await
         .map_err(|e| format!("Failed to delete query: {}", e))?;
 
-    pool.close().await;
+    if let Some(file_path) = file_path {
+        remove_query_file(&app_dir, &file_path)?;
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn toggle_pin_query(id: i64) -> Result<bool, String> {
+pub async fn toggle_pin_query(db: State<'_, ProjectDb>, id: i64) -> Result<bool, String> {
     let app_dir = get_app_dir()?;
-    let pool = get_saved_queries_db(app_dir).await?;
+    let pool = db.pool(app_dir.clone()).await?;
 
+    let _files = QUERY_FILES_LOCK.lock().await;
     let row = sqlx::query_as::<_, (bool,)>("SELECT is_pinned FROM saved_queries WHERE id = ?")
         .bind(id)
         .fetch_one(&pool)
@@ -110,7 +261,501 @@ pub async fn toggle_pin_query(id: i64) -> Result<bool, String> {
         .await
         .map_err(|e| format!("Failed to update pin status: {}", e))?;
 
-    pool.close().await;
+    if get_saved_query_files_enabled_internal()? {
+        export_saved_query(&pool, &app_dir, id).await?;
+    }
 
     Ok(new_pin_status)
 }
+
+/// Insert a saved query and its first revision
+pub(super) async fn insert_saved_query(
+    pool: &SqlitePool,
+    input: &SavedQueryInput,
+    is_pinned: bool,
+    file_path: Option<&str>,
+    author: Option<&str>,
+) -> Result<i64, String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let parameters = to_json_list(&input.parameters)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to save query: {}", e))?;
+
+    let result = sqlx::query(
+        "INSERT INTO saved_queries (name, query, description, is_pinned, folder, default_connection, parameters, file_path, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&input.name)
+    .bind(&input.query)
+    .bind(&input.description)
+    .bind(is_pinned)
+    .bind(&input.folder)
+    .bind(&input.default_connection)
+    .bind(parameters)
+    .bind(file_path)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save query: {}", e))?;
+
+    let id = result.last_insert_rowid();
+    replace_tags(&mut tx, id, &input.tags).await?;
+    record_revision(&mut tx, id, input, author, &now).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save query: {}", e))?;
+
+    Ok(id)
+}
+
+/// Overwrite a saved query's editable fields, recording a revision. Saving
+/// the fields it already has changes nothing, not even updated_at.
+pub(super) async fn write_saved_query(
+    pool: &SqlitePool,
+    id: i64,
+    input: &SavedQueryInput,
+    author: Option<&str>,
+) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let parameters = to_json_list(&input.parameters)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to update query: {}", e))?;
+
+    let current = sqlx::query(&format!(
+        "SELECT {} FROM saved_queries q WHERE q.id = ?",
+        SAVED_QUERY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to fetch query: {}", e))?
+    .ok_or_else(|| format!("Saved query {} not found", id))?;
+    if input_of(&saved_query_from_row(&current)?) == *input {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "UPDATE saved_queries SET name = ?, query = ?, description = ?, folder = ?, default_connection = ?, parameters = ?, updated_at = ? WHERE id = ?"
+    )
+    .bind(&input.name)
+    .bind(&input.query)
+    .bind(&input.description)
+    .bind(&input.folder)
+    .bind(&input.default_connection)
+    .bind(parameters)
+    .bind(&now)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update query: {}", e))?;
+
+    replace_tags(&mut tx, id, &input.tags).await?;
+    record_revision(&mut tx, id, input, author, &now).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update query: {}", e))
+}
+
+pub(super) async fn fetch_saved_query(pool: &SqlitePool, id: i64) -> Result<SavedQuery, String> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM saved_queries q WHERE q.id = ?",
+        SAVED_QUERY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch query: {}", e))?
+    .ok_or_else(|| format!("Saved query {} not found", id))?;
+
+    saved_query_from_row(&row)
+}
+
+async fn replace_tags(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    id: i64,
+    tags: &[String],
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM saved_query_tags WHERE query_id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to update tags: {}", e))?;
+
+    for tag in tags {
+        sqlx::query("INSERT INTO saved_query_tags (query_id, tag) VALUES (?, ?)")
+            .bind(id)
+            .bind(tag)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update tags: {}", e))?;
+    }
+
+    Ok(())
+}
+
+async fn record_revision(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    id: i64,
+    input: &SavedQueryInput,
+    author: Option<&str>,
+    now: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO saved_query_revisions (query_id, revision, name, query, description, folder, tags, default_connection, parameters, author, created_at)
+         SELECT ?, COALESCE(MAX(revision), 0) + 1, ?, ?, ?, ?, ?, ?, ?, ?, ?
+         FROM saved_query_revisions WHERE query_id = ?",
+    )
+    .bind(id)
+    .bind(&input.name)
+    .bind(&input.query)
+    .bind(&input.description)
+    .bind(&input.folder)
+    .bind(to_json_list(&input.tags)?)
+    .bind(&input.default_connection)
+    .bind(to_json_list(&input.parameters)?)
+    .bind(author)
+    .bind(now)
+    .bind(id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to record revision: {}", e))?;
+
+    Ok(())
+}
+
+async fn fetch_revision(
+    pool: &SqlitePool,
+    id: i64,
+    revision: i64,
+) -> Result<SavedQueryRevision, String> {
+    let row = sqlx::query(
+        "SELECT query_id, revision, name, query, description, folder, tags, default_connection, parameters, author, created_at
+         FROM saved_query_revisions
+         WHERE query_id = ? AND revision = ?",
+    )
+    .bind(id)
+    .bind(revision)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to fetch revision: {}", e))?
+    .ok_or_else(|| format!("Revision {} of saved query {} not found", revision, id))?;
+
+    revision_from_row(&row)
+}
+
+fn revision_from_row(row: &SqliteRow) -> Result<SavedQueryRevision, String> {
+    Ok(SavedQueryRevision {
+        query_id: row.get("query_id"),
+        revision: row.get("revision"),
+        name: row.get("name"),
+        query: row.get("query"),
+        description: row.get("description"),
+        folder: row.get("folder"),
+        tags: from_json_list(row.get("tags"))?,
+        default_connection: row.get("default_connection"),
+        parameters: from_json_list(row.get("parameters"))?,
+        author: row.get("author"),
+        created_at: row.get("created_at"),
+    })
+}
+
+pub(super) fn input_of(query: &SavedQuery) -> SavedQueryInput {
+    SavedQueryInput {
+        name: query.name.clone(),
+        query: query.query.clone(),
+        description: query.description.clone(),
+        folder: query.folder.clone(),
+        tags: query.tags.clone(),
+        default_connection: query.default_connection.clone(),
+        parameters: query.parameters.clone(),
+    }
+}
+
+pub(super) fn saved_query_from_row(row: &SqliteRow) -> Result<SavedQuery, String> {
+    Ok(SavedQuery {
+        id: row.get("id"),
+        name: row.get("name"),
+        query: row.get("query"),
+        description: row.get("description"),
+        is_pinned: row.get("is_pinned"),
+        folder: row.get("folder"),
+        tags: from_json_list(row.get("tags"))?,
+        default_connection: row.get("default_connection"),
+        parameters: from_json_list(row.get("parameters"))?,
+        file_path: row.get("file_path"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+// Trim everything, drop blank optionals and duplicate tags, and canonicalise
+// the folder path. Tags and parameters are comma-separated in query files,
+// so they can't contain commas themselves.
+pub(super) fn normalize_input(input: SavedQueryInput) -> Result<SavedQueryInput, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Saved query name cannot be empty".to_string());
+    }
+
+    let mut tags: Vec<String> = input
+        .tags
+        .iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    if let Some(tag) = tags.iter().find(|tag| tag.contains(',')) {
+        return Err(format!("Tags cannot contain commas: {}", tag));
+    }
+
+    // Order matters for parameters, so only repeats are dropped
+    let mut parameters: Vec<String> = Vec::new();
+    for parameter in input.parameters.iter().map(|p| p.trim()) {
+        if !parameter.is_empty() && !parameters.iter().any(|p| p == parameter) {
+            parameters.push(parameter.to_string());
+        }
+    }
+    if let Some(parameter) = parameters.iter().find(|p| p.contains(',')) {
+        return Err(format!("Parameters cannot contain commas: {}", parameter));
+    }
+
+    Ok(SavedQueryInput {
+        name,
+        // Edges are trimmed so the body survives a round trip through a file
+        query: input
+            .query
+            .trim_end()
+            .trim_start_matches(['\r', '\n'])
+            .to_string(),
+        description: non_blank(input.description),
+        folder: normalize_folder(input.folder)?,
+        tags,
+        default_connection: non_blank(input.default_connection),
+        parameters,
+    })
+}
+
+// Folders nest with '/', e.g. "reports/monthly". Blank means the top level.
+fn normalize_folder(folder: Option<String>) -> Result<Option<String>, String> {
+    let Some(folder) = folder else {
+        return Ok(None);
+    };
+
+    let segments: Vec<&str> = folder
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    if segments.iter().any(|segment| *segment == "." || *segment == ".." || segment.contains('\\')) {
+        return Err(format!("Invalid folder name: {}", folder));
+    }
+
+    Ok(if segments.is_empty() { None } else { Some(segments.join("/")) })
+}
+
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn to_json_list(items: &[String]) -> Result<String, String> {
+    serde_json::to_string(items).map_err(|e| format!("Failed to serialize list: {}", e))
+}
+
+fn from_json_list(json: String) -> Result<Vec<String>, String> {
+    serde_json::from_str(&json).map_err(|e| format!("Failed to read list: {}", e))
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn project_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("query-saved-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn revision_count(pool: &SqlitePool, id: i64) -> i64 {
+        sqlx::query_scalar("SELECT count(*) FROM saved_query_revisions WHERE query_id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn save(pool: &SqlitePool, name: &str, folder: Option<&str>, tags: &[&str]) -> i64 {
+        let input = SavedQueryInput {
+            name: name.to_string(),
+            query: format!("SELECT '{}'", name),
+            folder: folder.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        insert_saved_query(pool, &normalize_input(input).unwrap(), false, None, None)
+            .await
+            .unwrap()
+    }
+
+    async fn found(pool: &SqlitePool, text: &str, folder: &str, tag: &str) -> Vec<String> {
+        let search = SavedQuerySearch {
+            text: Some(text.to_string()),
+            folder: Some(folder.to_string()),
+            tag: Some(tag.to_string()),
+        };
+        find_saved_queries(pool, search)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|q| q.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn search_matches_text_literally_within_folder_and_tag() {
+        let dir = project_dir("search");
+        let pool = ProjectDb::default().pool(dir.clone()).await.unwrap();
+        save(&pool, "100% done", Some("reports"), &["billing"]).await;
+        save(&pool, "1000 done", Some("reports/monthly"), &["billing", "slow"]).await;
+        save(&pool, "user_ids", Some("reportsarchive"), &[]).await;
+        save(&pool, "userXids", None, &["slow"]).await;
+
+        // % and _ match only themselves
+        assert_eq!(found(&pool, "0%", "", "").await, vec!["100% done"]);
+        assert_eq!(found(&pool, "user_", "", "").await, vec!["user_ids"]);
+        // Text also matches tags and bodies
+        assert_eq!(found(&pool, "SLOW", "", "").await, vec!["1000 done", "userXids"]);
+        assert_eq!(found(&pool, "'userX", "", "").await, vec!["userXids"]);
+
+        // A folder includes its subfolders, not folders sharing its prefix
+        assert_eq!(found(&pool, "", " reports/ ", "").await, vec!["100% done", "1000 done"]);
+        assert_eq!(found(&pool, "", "reports/monthly", "").await, vec!["1000 done"]);
+
+        // Tags match whole
+        assert_eq!(found(&pool, "", "", "slow").await, vec!["1000 done", "userXids"]);
+        assert!(found(&pool, "", "", "slo").await.is_empty());
+        assert_eq!(found(&pool, "done", "reports", "slow").await, vec!["1000 done"]);
+
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn folders_are_canonicalised_and_must_stay_inside_queries() {
+        let folder = |f: &str| normalize_folder(Some(f.to_string()));
+
+        assert_eq!(folder(" reports / monthly/ "), Ok(Some("reports/monthly".to_string())));
+        assert_eq!(folder("a//b"), Ok(Some("a/b".to_string())));
+        assert_eq!(folder(" / "), Ok(None));
+        assert_eq!(normalize_folder(None), Ok(None));
+        for invalid in ["..", "reports/../secrets", "./reports", "reports\\monthly"] {
+            assert!(folder(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn input_is_trimmed_and_deduplicated() {
+        let input = normalize_input(SavedQueryInput {
+            name: "  Monthly ".to_string(),
+            query: "\n\nSELECT 1  \n".to_string(),
+            description: Some("  ".to_string()),
+            folder: Some("reports/".to_string()),
+            tags: vec!["b".to_string(), " a".to_string(), "b ".to_string(), "".to_string()],
+            default_connection: Some(" prod ".to_string()),
+            parameters: vec!["to".to_string(), "from".to_string(), " to".to_string()],
+        })
+        .unwrap();
+
+        assert_eq!(
+            input,
+            SavedQueryInput {
+                name: "Monthly".to_string(),
+                query: "SELECT 1".to_string(),
+                description: None,
+                folder: Some("reports".to_string()),
+                tags: vec!["a".to_string(), "b".to_string()],
+                default_connection: Some("prod".to_string()),
+                parameters: vec!["to".to_string(), "from".to_string()],
+            }
+        );
+        assert!(normalize_input(SavedQueryInput {
+            name: " ".to_string(),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn commas_are_rejected_in_tags_and_parameters() {
+        let input = SavedQueryInput {
+            name: "Monthly".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            normalize_input(SavedQueryInput {
+                tags: vec!["a,b".to_string()],
+                ..input.clone()
+            }),
+            Err("Tags cannot contain commas: a,b".to_string())
+        );
+        assert_eq!(
+            normalize_input(SavedQueryInput {
+                parameters: vec!["from,to".to_string()],
+                ..input
+            }),
+            Err("Parameters cannot contain commas: from,to".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn saving_unchanged_fields_records_no_revision() {
+        let dir = project_dir("unchanged");
+        let pool = ProjectDb::default().pool(dir.clone()).await.unwrap();
+        let input = SavedQueryInput {
+            name: "Monthly".to_string(),
+            query: "SELECT 1".to_string(),
+            tags: vec!["billing".to_string()],
+            ..Default::default()
+        };
+
+        let id = insert_saved_query(&pool, &input, false, None, None)
+            .await
+            .unwrap();
+        let saved = fetch_saved_query(&pool, id).await.unwrap();
+        write_saved_query(&pool, id, &input, None).await.unwrap();
+
+        assert_eq!(revision_count(&pool, id).await, 1);
+        assert_eq!(
+            fetch_saved_query(&pool, id).await.unwrap().updated_at,
+            saved.updated_at
+        );
+        assert_eq!(
+            write_saved_query(&pool, id + 1, &input, None).await,
+            Err(format!("Saved query {} not found", id + 1))
+        );
+
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
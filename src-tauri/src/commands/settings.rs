@@ -1,6 +1,7 @@
 use crate::models::ConnectionConfig;
 use crate::storage::{
     delete_password_from_keychain, get_password_from_keychain, save_password_to_keychain,
+    ProjectDb,
 };
 use crate::utils::{
     get_auto_connect_enabled_internal, get_current_project_path_internal, get_last_connection_internal,
@@ -8,15 +9,21 @@ use crate::utils::{
     set_project_path_internal, get_recent_projects_internal, remove_recent_project_internal, RecentProject,
     get_vim_mode_enabled_internal, set_vim_mode_enabled_internal,
 };
+use tauri::State;
 
 #[tauri::command]
 pub fn get_app_dir() -> Result<std::path::PathBuf, String> {
     crate::utils::get_app_dir()
 }
 
+/// Switches the project and opens its project.db right away, so a file that
+/// can't be opened or upgraded is reported here rather than on first use
 #[tauri::command]
-pub fn set_project_path(path: String) -> Result<(), String> {
-    set_project_path_internal(path)
+pub async fn set_project_path(db: State<'_, ProjectDb>, path: String) -> Result<(), String> {
+    set_project_path_internal(path)?;
+    db.pool(crate::utils::get_app_dir()?).await?;
+    super::query_files::rewatch_saved_query_files();
+    Ok(())
 }
 
 #[tauri::command]
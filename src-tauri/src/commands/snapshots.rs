@@ -0,0 +1,38 @@
+use crate::constants::SNAPSHOT_FORMAT_VERSION;
+use crate::models::{ConnectionConfig, SchemaSnapshot, SchemaSnapshotInfo};
+use crate::storage::{list_snapshots, save_snapshot};
+use crate::utils::get_app_dir;
+use chrono::{SecondsFormat, Utc};
+
+#[tauri::command]
+pub async fn snapshot_schema(
+    config: ConnectionConfig,
+    schema: Option<String>,
+) -> Result<String, String> {
+    let schema_name = schema.unwrap_or_else(|| "public".to_string());
+
+    let enhanced_schema =
+        crate::commands::get_enhanced_database_schema(config.clone(), Some(schema_name.clone()))
+            .await?;
+
+    // Connection details only; the password is never written to the project
+    let snapshot = SchemaSnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        connection_name: config.name,
+        host: config.host,
+        port: config.port,
+        database: config.database,
+        schema_name,
+        schema: enhanced_schema,
+    };
+
+    let snapshot_file = save_snapshot(&snapshot, get_app_dir()?)?;
+
+    Ok(snapshot_file.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn list_schema_snapshots() -> Result<Vec<SchemaSnapshotInfo>, String> {
+    list_snapshots(get_app_dir()?)
+}
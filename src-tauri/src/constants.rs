@@ -1,6 +1,8 @@
 // Application constants
 
 // File names for data storage
+pub const PROJECT_DB_FILENAME: &str = "project.db";
+// Stores merged into project.db, kept for the one-time import
 pub const HISTORY_DB_FILENAME: &str = "history.db";
 pub const SAVED_QUERIES_DB_FILENAME: &str = "saved_queries.db";
 pub const CONNECTIONS_FILENAME: &str = "connections.json";
@@ -8,6 +10,49 @@ pub const SETTINGS_FILENAME: &str = "settings.json";
 
 // Directory names
 pub const APP_DIR_NAME: &str = ".query";
+pub const SCHEMA_DOCS_DIR_NAME: &str = "docs";
+pub const SNAPSHOTS_DIR_NAME: &str = "snapshots";
+pub const MIGRATIONS_DIR_NAME: &str = "migrations";
+pub const REPORTS_DIR_NAME: &str = "reports";
+pub const SAVED_QUERIES_DIR_NAME: &str = "queries";
+
+// Bumped when the snapshot file layout changes incompatibly
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+// Prefix of the throwaway databases DDL directories are applied to
+pub const SCRATCH_DATABASE_PREFIX: &str = "query_scratch_";
+
+// Applied-migration tracking on target databases
+pub const MIGRATIONS_TABLE_NAME: &str = "_query_migrations";
+// Arbitrary pg_advisory_lock key shared by every app instance, so only one
+// migration runs against a database at a time
+pub const MIGRATION_ADVISORY_LOCK_KEY: i64 = 0x5155_4552_594d_4947;
+
+// Event emitted per statement while apply_migration runs
+pub const MIGRATION_PROGRESS_EVENT: &str = "migration-progress";
+// Event emitted when edits to saved query files were picked up
+pub const SAVED_QUERIES_CHANGED_EVENT: &str = "saved-queries-changed";
+// Quiet period after a change under queries/ before the files are synced
+pub const SAVED_QUERY_FILES_DEBOUNCE_MS: u64 = 500;
+
+// Largest changed section (old lines × new lines) a revision diff aligns line
+// by line; bigger ones show as all removed, then all added
+pub const TEXT_DIFF_MAX_CELLS: usize = 4_000_000;
+
+// Outcomes of a recorded migration run
+pub const MIGRATION_RUN_SUCCEEDED: &str = "succeeded";
+pub const MIGRATION_RUN_ROLLED_BACK: &str = "rolled_back"; // failed, nothing was committed
+pub const MIGRATION_RUN_PARTIALLY_APPLIED: &str = "partially_applied"; // failed after a commit
+
+// Query history entry statuses
+pub const HISTORY_STATUS_SUCCESS: &str = "success";
+pub const HISTORY_STATUS_ERROR: &str = "error";
+
+// Retention limits are applied (and the file compacted) on the first recorded run and once every this many after
+pub const HISTORY_PRUNE_INTERVAL: i64 = 100;
+
+// Most ids bound in one DELETE; SQLite builds before 3.32 allow 999 parameters per statement
+pub const HISTORY_DELETE_BATCH_SIZE: usize = 999;
 
 // Keychain configuration
 pub const KEYCHAIN_SERVICE_NAME: &str = "Query";
@@ -15,6 +60,18 @@ pub const KEYCHAIN_SERVICE_NAME: &str = "Query";
 // SQL constants
 pub const SQL_NULLABLE_YES: &str = "YES";
 
+// Kinds of user-defined types
+pub const TYPE_KIND_ENUM: &str = "enum";
+pub const TYPE_KIND_COMPOSITE: &str = "composite";
+pub const TYPE_KIND_DOMAIN: &str = "domain";
+
 // Warning types for schema comparison
 pub const WARNING_TYPE_DATA_LOSS: &str = "data_loss";
 pub const WARNING_TYPE_BREAKING_CHANGE: &str = "breaking_change";
+pub const WARNING_TYPE_LOCKING: &str = "locking";
+
+// Upper bound for each live data check behind a comparison warning
+pub const WARNING_CHECK_STATEMENT_TIMEOUT: &str = "30s";
+
+// Tables above this size get a medium-risk pre-flight finding for locking steps
+pub const PREFLIGHT_LARGE_TABLE_BYTES: i64 = 1024 * 1024 * 1024;
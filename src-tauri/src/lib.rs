@@ -63,8 +63,12 @@ pub fn run() {
                 }
             });
 
+            // Pick up outside edits to saved query files
+            tauri::async_runtime::spawn(watch_saved_query_files(app.handle().clone()));
+
             Ok(())
         })
+        .manage(storage::ProjectDb::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
@@ -78,16 +82,44 @@ pub fn run() {
             get_enhanced_database_schema,
             // Comparison commands
             compare_schemas,
+            check_resolved_warnings,
             generate_migration_sql,
+            generate_rollback_sql,
+            preflight_migration,
+            compare_table_data,
+            // Snapshot commands
+            snapshot_schema,
+            list_schema_snapshots,
+            // Migration commands
+            create_migration,
+            migration_status,
+            migrate_up,
+            migrate_down,
+            apply_migration,
+            // Documentation commands
+            generate_schema_docs,
+            export_comparison_report,
             // History commands
             save_query_to_history,
             get_query_history,
+            search_query_history,
             clear_query_history,
+            delete_history_entries,
+            get_history_retention,
+            set_history_retention,
+            get_migration_runs,
             // Saved queries commands
             save_query,
+            update_saved_query,
             get_saved_queries,
+            search_saved_queries,
             delete_saved_query,
             toggle_pin_query,
+            list_saved_query_revisions,
+            diff_saved_query_revisions,
+            restore_saved_query_revision,
+            get_saved_query_files_enabled,
+            set_saved_query_files_enabled,
             // Settings commands
             set_project_path,
             get_current_project_path,
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+/// A numbered migration in the project's migrations directory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationFile {
+    pub version: i64,
+    pub name: String,
+    pub up_file: String,
+    pub down_file: Option<String>, // None when the migration can't be reverted
+    pub checksum: String,          // SHA-256 of the up file
+    pub down_checksum: Option<String>,
+}
+
+/// A row of the `_query_migrations` table on a target database
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub down_checksum: Option<String>, // None for rows recorded without a down file
+    pub applied_at: String,
+    pub applied_by: String,
+    pub execution_time_ms: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Pending,
+    Applied,
+    Modified, // applied, but the up or down file changed since
+    Missing,  // applied, but the file is gone from the project
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub applied_at: Option<String>,
+    pub applied_by: Option<String>,
+    pub can_revert: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementStatus {
+    Running,
+    Succeeded,
+    Failed,
+    RolledBack, // succeeded, then undone when a later statement in its transaction failed
+}
+
+/// Payload of the `migration-progress` event, sent as each statement of
+/// `apply_migration` starts and finishes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationProgress {
+    pub index: usize, // 0-based position in the script
+    pub total: usize,
+    pub statement: String,
+    pub status: StatementStatus,
+    pub elapsed_ms: i64,
+    pub error: Option<String>,
+}
+
+/// A script applied with `apply_migration`, as recorded in the project db
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationRun {
+    pub id: i64,
+    pub connection_name: String,
+    pub database: String,
+    pub script: String,
+    pub status: String, // 'succeeded', 'rolled_back' or 'partially_applied'
+    pub error: Option<String>,
+    pub statements_total: i64,
+    pub statements_committed: i64,
+    pub started_at: String, // ISO timestamp
+    pub duration_ms: i64,
+}
@@ -1,10 +1,20 @@
 mod connection;
+mod migration;
 mod query;
 mod schema;
 
 pub use connection::ConnectionConfig;
-pub use query::{QueryHistoryEntry, QueryResult, SavedQuery};
+pub use migration::{
+    AppliedMigration, MigrationFile, MigrationProgress, MigrationRun, MigrationState,
+    MigrationStatus, StatementStatus,
+};
+pub use query::{
+    HistoryEntryDetails, HistoryRetention, HistorySearch, HistorySearchPage, HistorySearchResult,
+    QueryHistoryEntry, QueryResult, SavedQuery, SavedQueryInput, SavedQueryRevision,
+    SavedQueryRevisionDiff, SavedQuerySearch,
+};
 pub use schema::{
-    ColumnInfo, DatabaseSchema, ForeignKeyInfo, TableInfo,
-    EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, IndexInfo, RoutineInfo, ViewInfo,
+    CheckConstraintInfo, ColumnInfo, DatabaseSchema, ForeignKeyInfo, TableInfo,
+    EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, ExtensionInfo, IndexInfo,
+    IndexKeyColumn, RoutineInfo, SchemaSnapshot, SchemaSnapshotInfo, SchemaSource, TypeInfo, ViewInfo,
 };
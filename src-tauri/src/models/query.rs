@@ -1,3 +1,4 @@
+use crate::utils::text_diff::DiffLine;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -6,6 +7,7 @@ pub struct QueryResult {
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
     pub execution_time_ms: u128,
+    pub affected_rows: Option<u64>, // rows changed, when the statement returned none
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -16,6 +18,79 @@ pub struct QueryHistoryEntry {
     pub execution_time_ms: i64,
     pub row_count: i64,
     pub executed_at: String, // ISO timestamp
+    pub status: String,      // 'success' or 'error'
+    pub error_message: Option<String>,
+    pub sqlstate: Option<String>,
+    pub database_name: Option<String>,
+    pub schema_name: Option<String>,
+    pub project_path: Option<String>,
+    pub affected_rows: Option<i64>,
+}
+
+/// Optional fields for `save_query_to_history` beyond the original four
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct HistoryEntryDetails {
+    pub status: Option<String>, // defaults to 'success'
+    pub error_message: Option<String>,
+    pub sqlstate: Option<String>,
+    pub database_name: Option<String>,
+    pub schema_name: Option<String>,
+    pub affected_rows: Option<i64>,
+}
+
+/// Filters and paging for `search_query_history`; every filter is optional
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct HistorySearch {
+    pub text: Option<String>, // full-text terms, each matched as a prefix
+    pub connection_name: Option<String>,
+    pub executed_after: Option<String>,  // ISO timestamp, inclusive
+    pub executed_before: Option<String>, // ISO timestamp, exclusive
+    pub min_execution_time_ms: Option<i64>,
+    pub status: Option<String>, // 'success' or 'error'
+    pub distinct: bool,         // one entry per statement, with its run count
+    pub offset: i64,
+    pub limit: i64,
+}
+
+impl Default for HistorySearch {
+    fn default() -> Self {
+        Self {
+            text: None,
+            connection_name: None,
+            executed_after: None,
+            executed_before: None,
+            min_execution_time_ms: None,
+            status: None,
+            distinct: false,
+            offset: 0,
+            limit: 50,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistorySearchResult {
+    #[serde(flatten)]
+    pub entry: QueryHistoryEntry, // the latest run when deduplicating
+    pub run_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistorySearchPage {
+    pub results: Vec<HistorySearchResult>,
+    pub total: i64, // matches across all pages
+}
+
+/// Limits enforced on query_history when they change and every
+/// HISTORY_PRUNE_INTERVAL recorded runs; None is unlimited
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct HistoryRetention {
+    pub max_entries: Option<i64>,
+    pub max_age_days: Option<i64>,
+    pub max_entries_per_connection: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,6 +100,55 @@ pub struct SavedQuery {
     pub query: String,
     pub description: Option<String>,
     pub is_pinned: bool,
-    pub created_at: String, // ISO timestamp
-    pub updated_at: String, // ISO timestamp
+    pub folder: Option<String>, // '/'-separated path, None at the top level
+    pub tags: Vec<String>,
+    pub default_connection: Option<String>,
+    pub parameters: Vec<String>,
+    pub file_path: Option<String>, // under queries/, when saved queries are kept as files
+    pub created_at: String,        // ISO timestamp
+    pub updated_at: String,        // ISO timestamp
+}
+
+/// The editable fields of a saved query
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SavedQueryInput {
+    pub name: String,
+    pub query: String,
+    pub description: Option<String>,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub default_connection: Option<String>,
+    pub parameters: Vec<String>, // names of the placeholders the query expects, in order
+}
+
+/// A saved query as it was after one save or edit
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedQueryRevision {
+    pub query_id: i64,
+    pub revision: i64, // 1 is the query as first saved
+    pub name: String,
+    pub query: String,
+    pub description: Option<String>,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub default_connection: Option<String>,
+    pub parameters: Vec<String>,
+    pub author: Option<String>, // "Name <email>" from git config
+    pub created_at: String,     // ISO timestamp
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedQueryRevisionDiff {
+    pub from: SavedQueryRevision,
+    pub to: SavedQueryRevision,
+    pub lines: Vec<DiffLine>, // query body, from -> to
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SavedQuerySearch {
+    pub text: Option<String>,   // matched against name, description, tags and body
+    pub folder: Option<String>, // includes its subfolders
+    pub tag: Option<String>,
 }
@@ -1,3 +1,4 @@
+use crate::models::ConnectionConfig;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -31,28 +32,88 @@ pub struct DatabaseSchema {
 
 // Enhanced schema types for schema comparison feature
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IndexKeyColumn {
+    pub expression: String, // column name, or expression such as lower(email)
+    pub is_expression: bool,
+    pub descending: bool,
+    pub nulls_first: bool,
+    pub opclass: Option<String>, // None when the default operator class is used
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct IndexInfo {
     pub index_name: String,
     pub table_name: String,
-    pub columns: Vec<String>,
+    pub columns: Vec<String>, // key columns/expressions, in index order
     pub is_unique: bool,
     pub is_primary: bool,
     pub definition: String,
+    #[serde(default)]
+    pub access_method: String, // 'btree', 'gin', 'gist', ...
+    #[serde(default)]
+    pub key_columns: Vec<IndexKeyColumn>,
+    #[serde(default)]
+    pub include_columns: Vec<String>,
+    pub predicate: Option<String>, // WHERE clause of a partial index
+    #[serde(default = "default_true")]
+    pub is_valid: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckConstraintInfo {
+    pub constraint_name: String,
+    pub definition: String, // e.g. "CHECK (price > 0::numeric)", without NOT VALID
+    #[serde(default)]
+    pub columns: Vec<String>, // columns the expression reads
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ViewInfo {
     pub view_name: String,
     pub definition: String,
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>, // tables/views the view reads from
+    #[serde(default)]
+    pub column_dependencies: Vec<String>, // "table.column" entries the view reads
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RoutineInfo {
     pub routine_name: String,
     pub routine_type: String, // 'FUNCTION' or 'PROCEDURE'
+    /// CREATE OR REPLACE statement from pg_get_functiondef, with the schema
+    /// left off the name. Older snapshots hold only the body.
     pub definition: Option<String>,
     pub return_type: Option<String>,
+    pub comment: Option<String>,
+    /// Argument types that identify the routine among overloads, e.g. "integer, text"
+    #[serde(default)]
+    pub identity_arguments: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExtensionInfo {
+    pub extension_name: String,
+    pub version: String,
+    pub schema_name: String,
+}
+
+/// An enum, composite type or domain defined in the schema
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TypeInfo {
+    pub type_name: String,
+    pub kind: String, // 'enum', 'composite' or 'domain'
+    pub definition: String, // what follows the name in CREATE TYPE / CREATE DOMAIN
+    #[serde(default)]
+    pub enum_labels: Vec<String>, // in sort order; enums only
+    #[serde(default)]
+    pub depends_on: Vec<String>, // other types of the schema it is built from
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -66,6 +127,10 @@ pub struct EnhancedColumnInfo {
     pub numeric_precision: Option<i32>,
     pub numeric_scale: Option<i32>,
     pub ordinal_position: i32,
+    pub comment: Option<String>,
+    pub collation_name: Option<String>, // None when the column uses the default collation
+    pub udt_name: Option<String>, // underlying type name, e.g. 'int4', '_text', an enum or a domain
+    pub udt_schema: Option<String>, // schema of udt_name, e.g. 'pg_catalog'
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -74,6 +139,9 @@ pub struct EnhancedTableInfo {
     pub columns: Vec<EnhancedColumnInfo>,
     pub foreign_keys: Vec<ForeignKeyInfo>,
     pub indexes: Vec<IndexInfo>,
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub check_constraints: Vec<CheckConstraintInfo>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -81,4 +149,49 @@ pub struct EnhancedDatabaseSchema {
     pub tables: Vec<EnhancedTableInfo>,
     pub views: Vec<ViewInfo>,
     pub routines: Vec<RoutineInfo>,
+    #[serde(default)]
+    pub extensions: Vec<ExtensionInfo>,
+    #[serde(default)]
+    pub types: Vec<TypeInfo>,
+    /// server_version_num of the database the schema was read from, e.g. 160002
+    #[serde(default)]
+    pub server_version_num: Option<i32>,
+}
+
+/// A schema saved to the project directory so it can be versioned with git
+/// and compared later without a live connection
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SchemaSnapshot {
+    pub format_version: u32,
+    pub created_at: String, // ISO 8601 timestamp
+    pub connection_name: String,
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    pub schema_name: String,
+    pub schema: EnhancedDatabaseSchema,
+}
+
+/// Snapshot metadata for listing, without the schema itself
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SchemaSnapshotInfo {
+    pub file_name: String,
+    pub created_at: String,
+    pub connection_name: String,
+    pub database: String,
+    pub schema_name: String,
+}
+
+/// One side of a schema comparison
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SchemaSource {
+    Connection { config: ConnectionConfig },
+    Snapshot { file_name: String }, // a file in the project's snapshots directory
+    /// A directory of .sql files, applied to a throwaway database on the
+    /// `scratch` server and introspected from there
+    DdlDirectory {
+        path: String, // absolute, or relative to the project directory
+        scratch: ConnectionConfig,
+    },
 }
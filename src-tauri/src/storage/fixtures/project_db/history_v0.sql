@@ -0,0 +1,14 @@
+-- history.db from before its schema was versioned: no status column and no
+-- search index. Frozen: never edit.
+
+CREATE TABLE query_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    query TEXT NOT NULL,
+    connection_name TEXT NOT NULL,
+    execution_time_ms INTEGER NOT NULL,
+    row_count INTEGER NOT NULL,
+    executed_at TEXT NOT NULL
+);
+
+INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at)
+VALUES ('SELECT * FROM legacy_orders', 'dev', 5, 1, '2025-01-01T00:00:00+00:00');
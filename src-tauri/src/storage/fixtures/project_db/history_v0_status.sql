@@ -0,0 +1,20 @@
+-- history.db from before its schema was versioned, after the status column
+-- and the search index had been added. Frozen: never edit.
+
+CREATE TABLE query_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    query TEXT NOT NULL,
+    connection_name TEXT NOT NULL,
+    execution_time_ms INTEGER NOT NULL,
+    row_count INTEGER NOT NULL,
+    executed_at TEXT NOT NULL
+, status TEXT NOT NULL DEFAULT 'success');
+
+CREATE VIRTUAL TABLE query_history_fts USING fts5(query, content = 'query_history', content_rowid = 'id');
+
+CREATE TRIGGER query_history_fts_insert AFTER INSERT ON query_history BEGIN
+    INSERT INTO query_history_fts (rowid, query) VALUES (new.id, new.query);
+END;
+
+INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at)
+VALUES ('SELECT * FROM legacy_orders', 'dev', 5, 1, '2025-01-01T00:00:00+00:00');
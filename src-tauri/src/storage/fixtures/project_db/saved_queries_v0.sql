@@ -0,0 +1,15 @@
+-- saved_queries.db, the separate store saved queries had before project.db.
+-- Frozen: never edit.
+
+CREATE TABLE saved_queries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    description TEXT,
+    is_pinned BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+INSERT INTO saved_queries (id, name, query, is_pinned, created_at, updated_at)
+VALUES (7, 'orders', 'SELECT * FROM orders', 1, 'then', 'then');
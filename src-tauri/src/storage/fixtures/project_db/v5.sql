@@ -0,0 +1,76 @@
+-- project.db at user_version 5 (saved query folders, tags and default connection), as released.
+-- Frozen: never edit, and never regenerate it from PROJECT_MIGRATIONS.
+
+CREATE TABLE query_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    query TEXT NOT NULL,
+    connection_name TEXT NOT NULL,
+    execution_time_ms INTEGER NOT NULL,
+    row_count INTEGER NOT NULL,
+    executed_at TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'success'
+, error_message TEXT, sqlstate TEXT, database_name TEXT, schema_name TEXT, project_path TEXT, affected_rows INTEGER);
+
+CREATE TABLE migration_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    connection_name TEXT NOT NULL,
+    database_name TEXT NOT NULL,
+    script TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    statements_total INTEGER NOT NULL,
+    statements_committed INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL
+);
+
+CREATE VIRTUAL TABLE query_history_fts USING fts5(
+    query,
+    content = 'query_history',
+    content_rowid = 'id'
+);
+
+CREATE TRIGGER query_history_fts_insert AFTER INSERT ON query_history BEGIN
+    INSERT INTO query_history_fts (rowid, query) VALUES (new.id, new.query);
+END;
+
+CREATE TRIGGER query_history_fts_delete AFTER DELETE ON query_history BEGIN
+    INSERT INTO query_history_fts (query_history_fts, rowid, query)
+    VALUES ('delete', old.id, old.query);
+END;
+
+CREATE TRIGGER query_history_fts_update AFTER UPDATE OF query ON query_history BEGIN
+    INSERT INTO query_history_fts (query_history_fts, rowid, query)
+    VALUES ('delete', old.id, old.query);
+    INSERT INTO query_history_fts (rowid, query) VALUES (new.id, new.query);
+END;
+
+CREATE TABLE saved_queries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    description TEXT,
+    is_pinned BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+, folder TEXT, default_connection TEXT);
+
+CREATE INDEX idx_query_history_executed_at ON query_history (executed_at);
+
+CREATE INDEX idx_query_history_connection ON query_history (connection_name, id);
+
+CREATE TABLE saved_query_tags (
+    query_id INTEGER NOT NULL REFERENCES saved_queries (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (query_id, tag)
+);
+
+CREATE INDEX idx_saved_query_tags_tag ON saved_query_tags (tag);
+
+INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at)
+VALUES ('SELECT * FROM legacy_orders', 'dev', 5, 1, '2025-01-01T00:00:00+00:00');
+
+INSERT INTO saved_queries (id, name, query, created_at, updated_at)
+VALUES (3, 'kept', 'SELECT 1', 'then', 'then');
+
+PRAGMA user_version = 5;
@@ -0,0 +1,132 @@
+use crate::constants::MIGRATIONS_DIR_NAME;
+use crate::models::MigrationFile;
+use crate::utils::sanitize_file_name;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const UP_SUFFIX: &str = ".up.sql";
+const DOWN_SUFFIX: &str = ".down.sql";
+
+/// Migrations in the project directory, ordered by version. Files are named
+/// `<version>_<name>.up.sql` and `<version>_<name>.down.sql`.
+pub fn list_migration_files(app_dir: PathBuf) -> Result<Vec<MigrationFile>, String> {
+    let migrations_dir = app_dir.join(MIGRATIONS_DIR_NAME);
+
+    if !migrations_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&migrations_dir)
+        .map_err(|e| format!("Failed to read migrations directory: {}", e))?;
+
+    let mut up_files: BTreeMap<i64, (String, String)> = BTreeMap::new();
+    let mut down_files: BTreeMap<i64, String> = BTreeMap::new();
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(stem) = file_name.strip_suffix(UP_SUFFIX) {
+            let Some((version, name)) = parse_stem(stem) else {
+                continue;
+            };
+            if let Some((existing, _)) = up_files.get(&version) {
+                return Err(format!(
+                    "Duplicate migration version {}: {} and {}",
+                    version, existing, file_name
+                ));
+            }
+            up_files.insert(version, (file_name, name));
+        } else if let Some(stem) = file_name.strip_suffix(DOWN_SUFFIX) {
+            if let Some((version, _)) = parse_stem(stem) {
+                down_files.insert(version, file_name);
+            }
+        }
+    }
+
+    let mut migrations = Vec::with_capacity(up_files.len());
+    for (version, (up_file, name)) in up_files {
+        let contents = fs::read_to_string(migrations_dir.join(&up_file))
+            .map_err(|e| format!("Failed to read {}: {}", up_file, e))?;
+        let down_file = down_files.remove(&version);
+        let down_checksum = match &down_file {
+            Some(down_file) => Some(checksum(
+                &fs::read_to_string(migrations_dir.join(down_file))
+                    .map_err(|e| format!("Failed to read {}: {}", down_file, e))?,
+            )),
+            None => None,
+        };
+        migrations.push(MigrationFile {
+            version,
+            name,
+            up_file,
+            down_file,
+            checksum: checksum(&contents),
+            down_checksum,
+        });
+    }
+
+    Ok(migrations)
+}
+
+/// Write a new migration with the next free version number
+pub fn write_migration_files(
+    name: &str,
+    up_sql: &str,
+    down_sql: Option<&str>,
+    app_dir: PathBuf,
+) -> Result<MigrationFile, String> {
+    let existing = list_migration_files(app_dir.clone())?;
+    let version = existing.last().map(|m| m.version + 1).unwrap_or(1);
+
+    let migrations_dir = app_dir.join(MIGRATIONS_DIR_NAME);
+    fs::create_dir_all(&migrations_dir)
+        .map_err(|e| format!("Could not create migrations directory: {}", e))?;
+
+    let name = sanitize_file_name(name.trim());
+    let stem = format!("{:04}_{}", version, name);
+    let up_file = format!("{}{}", stem, UP_SUFFIX);
+    fs::write(migrations_dir.join(&up_file), up_sql)
+        .map_err(|e| format!("Could not write migration file: {}", e))?;
+
+    let down_file = match down_sql {
+        Some(down_sql) => {
+            let down_file = format!("{}{}", stem, DOWN_SUFFIX);
+            fs::write(migrations_dir.join(&down_file), down_sql)
+                .map_err(|e| format!("Could not write migration file: {}", e))?;
+            Some(down_file)
+        }
+        None => None,
+    };
+
+    Ok(MigrationFile {
+        version,
+        name,
+        up_file,
+        down_file,
+        checksum: checksum(up_sql),
+        down_checksum: down_sql.map(checksum),
+    })
+}
+
+pub fn read_migration_file(file_name: &str, app_dir: PathBuf) -> Result<String, String> {
+    fs::read_to_string(app_dir.join(MIGRATIONS_DIR_NAME).join(file_name))
+        .map_err(|e| format!("Failed to read {}: {}", file_name, e))
+}
+
+/// SHA-256 of a migration, as lowercase hex
+pub fn checksum(contents: &str) -> String {
+    Sha256::digest(contents.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// "0003_add_users" -> (3, "add_users")
+fn parse_stem(stem: &str) -> Option<(i64, String)> {
+    let (version, name) = stem.split_once('_').unwrap_or((stem, ""));
+    if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((version.parse().ok()?, name.to_string()))
+}
@@ -1,11 +1,19 @@
 mod connections;
-mod history_db;
 mod keychain;
-mod saved_queries_db;
+mod migration_files;
+mod project_db;
+mod query_files;
+mod snapshots;
+mod sqlite_migrations;
 
 pub use connections::{load_connections, save_connections};
-pub use history_db::get_history_db;
 pub use keychain::{
     delete_password_from_keychain, get_password_from_keychain, save_password_to_keychain,
 };
-pub use saved_queries_db::get_saved_queries_db;
+pub use migration_files::{list_migration_files, read_migration_file, write_migration_files};
+pub use project_db::ProjectDb;
+pub use query_files::{
+    list_query_files, query_file_candidates, query_file_exists, remove_query_file,
+    write_query_file,
+};
+pub use snapshots::{list_snapshots, load_snapshot, save_snapshot};
@@ -0,0 +1,596 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool};
+use sqlx::Connection;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::sync::Mutex;
+use crate::constants::{
+    HISTORY_DB_FILENAME, HISTORY_PRUNE_INTERVAL, PROJECT_DB_FILENAME, SAVED_QUERIES_DB_FILENAME,
+};
+use crate::storage::sqlite_migrations::{run_sqlite_migrations, SqliteMigration};
+
+// Append new steps; never edit a released one, and add the file each release
+// leaves behind to fixtures/project_db. Versions 1 and 2 are the old
+// history.db's, which is why a history.db can become project.db as is.
+const PROJECT_MIGRATIONS: &[SqliteMigration] = &[
+    SqliteMigration {
+        version: 1,
+        description: "query history, search index and migration runs",
+        // Files from before versioning already have some of this, so every
+        // statement tolerates existing objects
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS query_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                query TEXT NOT NULL,
+                connection_name TEXT NOT NULL,
+                execution_time_ms INTEGER NOT NULL,
+                row_count INTEGER NOT NULL,
+                executed_at TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'success'
+            );
+
+            CREATE TABLE IF NOT EXISTS migration_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                connection_name TEXT NOT NULL,
+                database_name TEXT NOT NULL,
+                script TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT,
+                statements_total INTEGER NOT NULL,
+                statements_committed INTEGER NOT NULL,
+                started_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL
+            );
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS query_history_fts USING fts5(
+                query,
+                content = 'query_history',
+                content_rowid = 'id'
+            );
+
+            CREATE TRIGGER IF NOT EXISTS query_history_fts_insert AFTER INSERT ON query_history BEGIN
+                INSERT INTO query_history_fts (rowid, query) VALUES (new.id, new.query);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS query_history_fts_delete AFTER DELETE ON query_history BEGIN
+                INSERT INTO query_history_fts (query_history_fts, rowid, query)
+                VALUES ('delete', old.id, old.query);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS query_history_fts_update AFTER UPDATE OF query ON query_history BEGIN
+                INSERT INTO query_history_fts (query_history_fts, rowid, query)
+                VALUES ('delete', old.id, old.query);
+                INSERT INTO query_history_fts (rowid, query) VALUES (new.id, new.query);
+            END;
+
+            -- Index rows recorded before the search index existed
+            INSERT INTO query_history_fts (query_history_fts) VALUES ('rebuild');
+        "#,
+    },
+    SqliteMigration {
+        version: 2,
+        description: "failed runs and query context",
+        sql: r#"
+            ALTER TABLE query_history ADD COLUMN error_message TEXT;
+            ALTER TABLE query_history ADD COLUMN sqlstate TEXT;
+            ALTER TABLE query_history ADD COLUMN database_name TEXT;
+            ALTER TABLE query_history ADD COLUMN schema_name TEXT;
+            ALTER TABLE query_history ADD COLUMN project_path TEXT;
+            ALTER TABLE query_history ADD COLUMN affected_rows INTEGER;
+        "#,
+    },
+    SqliteMigration {
+        version: 3,
+        description: "saved queries",
+        sql: r#"
+            CREATE TABLE saved_queries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                description TEXT,
+                is_pinned BOOLEAN NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+        "#,
+    },
+    SqliteMigration {
+        version: 4,
+        description: "history retention indexes",
+        sql: r#"
+            CREATE INDEX idx_query_history_executed_at ON query_history (executed_at);
+            CREATE INDEX idx_query_history_connection ON query_history (connection_name, id);
+        "#,
+    },
+    SqliteMigration {
+        version: 5,
+        description: "saved query folders, tags and default connection",
+        sql: r#"
+            ALTER TABLE saved_queries ADD COLUMN folder TEXT;
+            ALTER TABLE saved_queries ADD COLUMN default_connection TEXT;
+
+            CREATE TABLE saved_query_tags (
+                query_id INTEGER NOT NULL REFERENCES saved_queries (id) ON DELETE CASCADE,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (query_id, tag)
+            );
+
+            CREATE INDEX idx_saved_query_tags_tag ON saved_query_tags (tag);
+        "#,
+    },
+    SqliteMigration {
+        version: 6,
+        description: "saved query revisions",
+        sql: r#"
+            CREATE TABLE saved_query_revisions (
+                query_id INTEGER NOT NULL REFERENCES saved_queries (id) ON DELETE CASCADE,
+                revision INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                description TEXT,
+                folder TEXT,
+                tags TEXT NOT NULL, -- JSON array
+                default_connection TEXT,
+                author TEXT,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (query_id, revision)
+            );
+        "#,
+    },
+    SqliteMigration {
+        version: 7,
+        description: "saved query parameters and files",
+        sql: r#"
+            ALTER TABLE saved_queries ADD COLUMN parameters TEXT NOT NULL DEFAULT '[]'; -- JSON array
+            ALTER TABLE saved_queries ADD COLUMN file_path TEXT;
+            ALTER TABLE saved_query_revisions ADD COLUMN parameters TEXT NOT NULL DEFAULT '[]';
+
+            CREATE UNIQUE INDEX idx_saved_queries_file_path ON saved_queries (file_path);
+        "#,
+    },
+    SqliteMigration {
+        version: 8,
+        description: "saved queries whose file is missing",
+        sql: r#"
+            -- Set while a query's file is gone from queries/; the query and its
+            -- revisions are kept so the file can come back
+            ALTER TABLE saved_queries ADD COLUMN missing_since TEXT;
+        "#,
+    },
+];
+
+/// The open project.db, held in Tauri state. It's opened on first use and
+/// reopened whenever the app dir changes, so a project switch can't leave
+/// commands writing to the previous project's file.
+#[derive(Default)]
+pub struct ProjectDb {
+    current: Mutex<Option<(PathBuf, SqlitePool)>>,
+    recorded_runs: AtomicI64, // query runs recorded since start-up
+}
+
+impl ProjectDb {
+    pub async fn pool(&self, app_dir: PathBuf) -> Result<SqlitePool, String> {
+        let mut current = self.current.lock().await;
+
+        if let Some((dir, pool)) = current.as_ref() {
+            if *dir == app_dir {
+                return Ok(pool.clone());
+            }
+        }
+
+        let pool = open_project_db(&app_dir).await?;
+        if let Some((_, previous)) = current.replace((app_dir, pool.clone())) {
+            previous.close().await;
+        }
+
+        Ok(pool)
+    }
+
+    /// Count a recorded query run. True when history retention is due: on
+    /// the first run after start-up and every HISTORY_PRUNE_INTERVAL runs after.
+    pub fn count_run_for_pruning(&self) -> bool {
+        self.recorded_runs.fetch_add(1, Ordering::Relaxed) % HISTORY_PRUNE_INTERVAL == 0
+    }
+}
+
+async fn open_project_db(app_dir: &Path) -> Result<SqlitePool, String> {
+    let db_path = app_dir.join(PROJECT_DB_FILENAME);
+
+    let legacy_history = app_dir.join(HISTORY_DB_FILENAME);
+    if !db_path.exists() && legacy_history.exists() {
+        settle_legacy_db(&legacy_history).await?;
+        // Nothing should be left beside the file now, but a sidecar that is
+        // still there has to stay with it
+        for suffix in ["-journal", "-wal", ""] {
+            let from = sidecar_path(&legacy_history, suffix);
+            if from.exists() {
+                fs::rename(&from, sidecar_path(&db_path, suffix)).map_err(|e| {
+                    format!("Could not move {} to {}: {}", HISTORY_DB_FILENAME, PROJECT_DB_FILENAME, e)
+                })?;
+            }
+        }
+        let _ = fs::remove_file(sidecar_path(&legacy_history, "-shm"));
+    }
+
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", db_path.display()))
+        .map_err(|e| format!("Failed to create options: {}", e))?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+
+    let pool = SqlitePool::connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to connect to project db: {}", e))?;
+
+    add_unversioned_status_column(&pool).await?;
+    run_sqlite_migrations(&pool, PROJECT_DB_FILENAME, PROJECT_MIGRATIONS).await?;
+    import_legacy_saved_queries(&pool, app_dir).await?;
+    add_baseline_revisions(&pool).await?;
+
+    Ok(pool)
+}
+
+// The old stores used SQLite's default rollback journal, so a crash can leave
+// a hot history.db-journal that only means anything next to history.db.
+// Opening the file once rolls it back (and checkpoints a WAL, should there be
+// one) so the file can be moved on its own.
+async fn settle_legacy_db(path: &Path) -> Result<(), String> {
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+        .map_err(|e| format!("Failed to create options: {}", e))?
+        .journal_mode(SqliteJournalMode::Delete);
+
+    let mut conn = SqliteConnection::connect_with(&options)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let recovered = sqlx::query("SELECT count(*) FROM sqlite_master")
+        .execute(&mut conn)
+        .await;
+    let _ = conn.close().await;
+
+    recovered
+        .map(|_| ())
+        .map_err(|e| format!("Failed to recover {}: {}", path.display(), e))
+}
+
+// Copy saved_queries.db in and delete it. Ids are kept, so if the delete
+// never happened the next open copies nothing twice.
+async fn import_legacy_saved_queries(pool: &SqlitePool, app_dir: &Path) -> Result<(), String> {
+    let legacy_path = app_dir.join(SAVED_QUERIES_DB_FILENAME);
+    if !legacy_path.exists() {
+        return Ok(());
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to connect to project db: {}", e))?;
+
+    sqlx::query("ATTACH DATABASE ? AS legacy")
+        .bind(legacy_path.to_string_lossy().to_string())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", SAVED_QUERIES_DB_FILENAME, e))?;
+
+    // main is spelled out: this connection may not have seen saved_queries
+    // created yet, and would then resolve the name to the legacy table
+    let copied = sqlx::query(
+        "INSERT OR IGNORE INTO main.saved_queries (id, name, query, description, is_pinned, created_at, updated_at)
+         SELECT id, name, query, description, is_pinned, created_at, updated_at FROM legacy.saved_queries",
+    )
+    .execute(&mut *conn)
+    .await;
+    let _ = sqlx::query("DETACH DATABASE legacy").execute(&mut *conn).await;
+
+    copied.map_err(|e| format!("Failed to import {}: {}", SAVED_QUERIES_DB_FILENAME, e))?;
+
+    fs::remove_file(&legacy_path)
+        .map_err(|e| format!("Could not remove {}: {}", SAVED_QUERIES_DB_FILENAME, e))?;
+    for suffix in ["-journal", "-wal", "-shm"] {
+        let _ = fs::remove_file(sidecar_path(&legacy_path, suffix));
+    }
+
+    Ok(())
+}
+
+// Queries saved before revisions existed, or imported from saved_queries.db,
+// start their history with their current state as revision 1
+async fn add_baseline_revisions(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO saved_query_revisions (query_id, revision, name, query, description, folder, tags, default_connection, parameters, created_at)
+         SELECT q.id, 1, q.name, q.query, q.description, q.folder,
+                (SELECT json_group_array(tag) FROM (SELECT tag FROM saved_query_tags t WHERE t.query_id = q.id ORDER BY tag)),
+                q.default_connection, q.parameters, q.updated_at
+         FROM saved_queries q
+         WHERE NOT EXISTS (SELECT 1 FROM saved_query_revisions r WHERE r.query_id = q.id)",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record saved query revisions: {}", e))?;
+
+    Ok(())
+}
+
+fn sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+// The one part of version 1 that CREATE ... IF NOT EXISTS can't cover: files
+// from before versioning may have query_history without its status column
+async fn add_unversioned_status_column(pool: &SqlitePool) -> Result<(), String> {
+    let needs_column: bool = sqlx::query_scalar(
+        "SELECT (SELECT user_version FROM pragma_user_version) = 0
+            AND EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'query_history')
+            AND NOT EXISTS (SELECT 1 FROM pragma_table_info('query_history') WHERE name = 'status')",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to inspect project db: {}", e))?;
+
+    if needs_column {
+        sqlx::query("ALTER TABLE query_history ADD COLUMN status TEXT NOT NULL DEFAULT 'success'")
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to add query_history.status: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Files as earlier releases left them, checked in so that editing a
+    // released step in PROJECT_MIGRATIONS can't quietly change them too
+    const V0_HISTORY: &str = include_str!("fixtures/project_db/history_v0.sql");
+    const V0_HISTORY_WITH_STATUS: &str =
+        include_str!("fixtures/project_db/history_v0_status.sql");
+    const V0_SAVED_QUERIES: &str = include_str!("fixtures/project_db/saved_queries_v0.sql");
+    const RELEASED_VERSIONS: &[(i64, &str, &str)] = &[
+        (1, HISTORY_DB_FILENAME, include_str!("fixtures/project_db/v1.sql")),
+        (2, HISTORY_DB_FILENAME, include_str!("fixtures/project_db/v2.sql")),
+        (3, PROJECT_DB_FILENAME, include_str!("fixtures/project_db/v3.sql")),
+        (4, PROJECT_DB_FILENAME, include_str!("fixtures/project_db/v4.sql")),
+        (5, PROJECT_DB_FILENAME, include_str!("fixtures/project_db/v5.sql")),
+        (6, PROJECT_DB_FILENAME, include_str!("fixtures/project_db/v6.sql")),
+        (7, PROJECT_DB_FILENAME, include_str!("fixtures/project_db/v7.sql")),
+    ];
+
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("query-project-db-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn fixture_db(path: &Path, sql: &str) -> SqlitePool {
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::raw_sql(sql).execute(&pool).await.unwrap();
+        pool
+    }
+
+    // Tables, indexes and triggers with their columns, in a stable order
+    async fn schema(pool: &SqlitePool) -> Vec<String> {
+        let mut schema: Vec<String> = sqlx::query_scalar(
+            "SELECT m.type || ' ' || m.name || COALESCE('.' || c.name, '')
+             FROM sqlite_master m
+             LEFT JOIN pragma_table_info(m.name) c ON m.type = 'table'
+             WHERE m.name NOT LIKE 'sqlite_%'",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        schema.sort();
+        schema
+    }
+
+    async fn user_version(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn latest_schema() -> Vec<String> {
+        let dir = fixture_dir("latest");
+        let pool = open_project_db(&dir).await.unwrap();
+        schema(&pool).await
+    }
+
+    // Open a fixture the way the app does and check it ends up identical to
+    // a new project.db, with the legacy query still searchable
+    async fn check_upgraded(dir: &Path) -> SqlitePool {
+        let pool = open_project_db(dir).await.unwrap();
+
+        assert_eq!(
+            user_version(&pool).await,
+            PROJECT_MIGRATIONS.last().unwrap().version
+        );
+        assert_eq!(schema(&pool).await, latest_schema().await);
+
+        let found: Vec<(String, String)> = sqlx::query_as(
+            "SELECT h.query, h.status FROM query_history_fts f
+             JOIN query_history h ON h.id = f.rowid
+             WHERE query_history_fts MATCH 'legacy_orders'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            found,
+            [(
+                "SELECT * FROM legacy_orders".to_string(),
+                "success".to_string()
+            )]
+        );
+
+        // A second open finds nothing to do
+        pool.close().await;
+        let pool = open_project_db(dir).await.unwrap();
+        assert_eq!(schema(&pool).await, latest_schema().await);
+        pool
+    }
+
+    #[tokio::test]
+    async fn upgrades_unversioned_history_without_status() {
+        let dir = fixture_dir("v0");
+        fixture_db(&dir.join(HISTORY_DB_FILENAME), V0_HISTORY)
+            .await
+            .close()
+            .await;
+
+        check_upgraded(&dir).await;
+        assert!(!dir.join(HISTORY_DB_FILENAME).exists());
+    }
+
+    #[tokio::test]
+    async fn upgrades_unversioned_history_with_status() {
+        let dir = fixture_dir("v0-status");
+        fixture_db(&dir.join(HISTORY_DB_FILENAME), V0_HISTORY_WITH_STATUS)
+            .await
+            .close()
+            .await;
+
+        check_upgraded(&dir).await;
+    }
+
+    // Releasing a version means freezing the file it leaves behind here
+    #[test]
+    fn every_earlier_version_has_a_fixture() {
+        let released: Vec<i64> = RELEASED_VERSIONS.iter().map(|(version, ..)| *version).collect();
+        let expected: Vec<i64> = PROJECT_MIGRATIONS
+            .iter()
+            .map(|m| m.version)
+            .filter(|v| *v < PROJECT_MIGRATIONS.last().unwrap().version)
+            .collect();
+
+        assert_eq!(released, expected);
+    }
+
+    #[tokio::test]
+    async fn upgrades_from_every_version() {
+        for (version, file, sql) in RELEASED_VERSIONS {
+            let dir = fixture_dir(&format!("v{}", version));
+            let pool = fixture_db(&dir.join(file), sql).await;
+            assert_eq!(user_version(&pool).await, *version);
+            pool.close().await;
+
+            let pool = check_upgraded(&dir).await;
+            if *version >= 3 {
+                let revisions: i64 = sqlx::query_scalar(
+                    "SELECT count(*) FROM saved_query_revisions WHERE query_id = 3",
+                )
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                assert_eq!(revisions, 1, "upgrading from version {}", version);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn merges_legacy_history_and_saved_queries() {
+        let dir = fixture_dir("merge");
+        fixture_db(&dir.join(HISTORY_DB_FILENAME), V0_HISTORY)
+            .await
+            .close()
+            .await;
+        fixture_db(&dir.join(SAVED_QUERIES_DB_FILENAME), V0_SAVED_QUERIES)
+        .await
+        .close()
+        .await;
+
+        let pool = check_upgraded(&dir).await;
+
+        assert!(!dir.join(HISTORY_DB_FILENAME).exists());
+        assert!(!dir.join(SAVED_QUERIES_DB_FILENAME).exists());
+        let saved: (i64, String, bool) =
+            sqlx::query_as("SELECT id, name, is_pinned FROM saved_queries")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(saved, (7, "orders".to_string(), true));
+        let revision: (i64, String) =
+            sqlx::query_as("SELECT revision, tags FROM saved_query_revisions WHERE query_id = 7")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(revision, (1, "[]".to_string()));
+    }
+
+    // A crash in the middle of a write to the old history.db leaves pages of
+    // the unfinished transaction in the file and the originals in its journal
+    #[tokio::test]
+    async fn rolls_back_a_hot_legacy_journal_before_moving_history() {
+        let crashed = fixture_dir("hot-journal-writer");
+        let legacy = crashed.join(HISTORY_DB_FILENAME);
+        fixture_db(&legacy, V0_HISTORY).await.close().await;
+
+        let mut writer = SqliteConnection::connect_with(
+            &SqliteConnectOptions::from_str(&format!("sqlite:{}", legacy.display())).unwrap(),
+        )
+        .await
+        .unwrap();
+        // A tiny cache makes SQLite spill the transaction's pages to the file
+        sqlx::raw_sql(
+            "PRAGMA cache_size = 1;
+             BEGIN;
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000)
+             INSERT INTO query_history (query, connection_name, execution_time_ms, row_count, executed_at)
+             SELECT hex(randomblob(200)), 'dev', 1, 1, 'now' FROM n;",
+        )
+        .execute(&mut writer)
+        .await
+        .unwrap();
+
+        let dir = fixture_dir("hot-journal");
+        for suffix in ["", "-journal"] {
+            fs::copy(sidecar_path(&legacy, suffix), sidecar_path(&dir.join(HISTORY_DB_FILENAME), suffix)).unwrap();
+        }
+        let _ = writer.close().await;
+
+        let pool = check_upgraded(&dir).await;
+
+        let rows: i64 = sqlx::query_scalar("SELECT count(*) FROM query_history")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
+        let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(integrity, "ok");
+        for suffix in ["", "-journal", "-wal"] {
+            assert!(!sidecar_path(&dir.join(HISTORY_DB_FILENAME), suffix).exists(), "{}", suffix);
+        }
+    }
+
+    #[test]
+    fn pruning_is_due_at_start_up_and_every_interval() {
+        let db = ProjectDb::default();
+        let due: Vec<i64> = (0..HISTORY_PRUNE_INTERVAL * 2 + 1)
+            .filter(|_| db.count_run_for_pruning())
+            .collect();
+
+        assert_eq!(due, [0, HISTORY_PRUNE_INTERVAL, HISTORY_PRUNE_INTERVAL * 2]);
+    }
+
+    #[tokio::test]
+    async fn refuses_a_newer_schema() {
+        let dir = fixture_dir("newer");
+        fixture_db(&dir.join(PROJECT_DB_FILENAME), "PRAGMA user_version = 999;")
+            .await
+            .close()
+            .await;
+
+        let err = open_project_db(&dir).await.unwrap_err();
+        assert!(err.contains("newer version"), "{}", err);
+    }
+}
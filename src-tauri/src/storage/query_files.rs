@@ -0,0 +1,278 @@
+use crate::constants::SAVED_QUERIES_DIR_NAME;
+use crate::models::SavedQueryInput;
+use crate::utils::sanitize_file_name;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const QUERY_FILE_EXTENSION: &str = "sql";
+const FRONT_MATTER_FENCE: &str = "-- ---";
+
+/// A saved query read from `queries/<folder>/<file>.sql`. The folder comes
+/// from where the file sits; everything else from its front matter, with the
+/// file name standing in for a missing name.
+pub struct QueryFile {
+    pub path: String, // relative to the queries directory, '/'-separated
+    pub input: SavedQueryInput,
+    pub is_pinned: bool,
+}
+
+/// Every readable .sql file under the queries directory, in path order.
+/// Files that can't be read as UTF-8 text are skipped rather than failing
+/// the whole listing.
+pub fn list_query_files(app_dir: &Path) -> Result<Vec<QueryFile>, String> {
+    let queries_dir = app_dir.join(SAVED_QUERIES_DIR_NAME);
+
+    if !queries_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    collect_sql_files(&queries_dir, &queries_dir, &mut paths)?;
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let contents = fs::read_to_string(queries_dir.join(&path)).ok()?;
+            Some(parse_query_file(&path, &contents))
+        })
+        .collect())
+}
+
+pub fn write_query_file(
+    app_dir: &Path,
+    path: &str,
+    input: &SavedQueryInput,
+    is_pinned: bool,
+) -> Result<(), String> {
+    let file = app_dir.join(SAVED_QUERIES_DIR_NAME).join(path);
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create queries directory: {}", e))?;
+    }
+
+    fs::write(&file, render_query_file(input, is_pinned))
+        .map_err(|e| format!("Could not write {}: {}", path, e))
+}
+
+/// Delete a query file, then any folders it leaves empty
+pub fn remove_query_file(app_dir: &Path, path: &str) -> Result<(), String> {
+    let queries_dir = app_dir.join(SAVED_QUERIES_DIR_NAME);
+    let file = queries_dir.join(path);
+
+    if file.exists() {
+        fs::remove_file(&file).map_err(|e| format!("Could not remove {}: {}", path, e))?;
+    }
+
+    // remove_dir refuses non-empty folders, which ends the walk
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        if current == queries_dir || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+
+    Ok(())
+}
+
+pub fn query_file_exists(app_dir: &Path, path: &str) -> bool {
+    app_dir.join(SAVED_QUERIES_DIR_NAME).join(path).exists()
+}
+
+/// File paths a query may be written to, best first: `<folder>/<name>.sql`,
+/// then `<name>-2.sql`, `<name>-3.sql`, ... for queries sharing a name
+pub fn query_file_candidates(folder: Option<&str>, name: &str) -> impl Iterator<Item = String> {
+    let prefix = folder
+        .map(|folder| {
+            folder
+                .split('/')
+                .map(sanitize_file_name)
+                .map(|segment| segment + "/")
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    let stem = sanitize_file_name(name);
+
+    (1..).map(move |n| match n {
+        1 => format!("{}{}.{}", prefix, stem, QUERY_FILE_EXTENSION),
+        n => format!("{}{}-{}.{}", prefix, stem, n, QUERY_FILE_EXTENSION),
+    })
+}
+
+// The header is a block of `-- key: value` lines between `-- ---` fences, so
+// the file stays runnable SQL. Lists are comma-separated, which is why tags
+// and parameters can't contain commas; a multi-line description repeats its
+// key once per line.
+fn render_query_file(input: &SavedQueryInput, is_pinned: bool) -> String {
+    let mut header = vec![
+        FRONT_MATTER_FENCE.to_string(),
+        format!("-- name: {}", input.name),
+    ];
+
+    if let Some(description) = &input.description {
+        header.extend(
+            description
+                .lines()
+                .map(|line| format!("-- description: {}", line)),
+        );
+    }
+    if is_pinned {
+        header.push("-- pinned: true".to_string());
+    }
+    if !input.tags.is_empty() {
+        header.push(format!("-- tags: {}", input.tags.join(", ")));
+    }
+    if let Some(connection) = &input.default_connection {
+        header.push(format!("-- default_connection: {}", connection));
+    }
+    if !input.parameters.is_empty() {
+        header.push(format!("-- parameters: {}", input.parameters.join(", ")));
+    }
+    header.push(FRONT_MATTER_FENCE.to_string());
+
+    format!("{}\n\n{}\n", header.join("\n"), input.query)
+}
+
+fn parse_query_file(path: &str, contents: &str) -> QueryFile {
+    let (folder, file_name) = match path.rsplit_once('/') {
+        Some((folder, file_name)) => (Some(folder.to_string()), file_name),
+        None => (None, path),
+    };
+    let stem = file_name
+        .strip_suffix(&format!(".{}", QUERY_FILE_EXTENSION))
+        .unwrap_or(file_name);
+
+    let mut input = SavedQueryInput {
+        name: stem.to_string(),
+        folder,
+        ..Default::default()
+    };
+    let mut is_pinned = false;
+    let mut description: Vec<&str> = Vec::new();
+
+    let mut lines = contents.lines();
+    let body = if contents.lines().next().map(str::trim_end) == Some(FRONT_MATTER_FENCE) {
+        lines.next();
+        for line in lines.by_ref() {
+            let line = line.trim_end();
+            if line == FRONT_MATTER_FENCE {
+                break;
+            }
+            let Some((key, value)) = line
+                .strip_prefix("--")
+                .and_then(|entry| entry.split_once(':'))
+            else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "name" if !value.is_empty() => input.name = value.to_string(),
+                "description" => description.push(value),
+                "pinned" => is_pinned = value.eq_ignore_ascii_case("true"),
+                "tags" => input.tags = split_list(value),
+                "default_connection" if !value.is_empty() => {
+                    input.default_connection = Some(value.to_string())
+                }
+                "parameters" => input.parameters = split_list(value),
+                _ => {}
+            }
+        }
+        lines.collect::<Vec<_>>().join("\n")
+    } else {
+        contents.to_string()
+    };
+
+    if !description.is_empty() {
+        input.description = Some(description.join("\n"));
+    }
+    input.query = body;
+
+    QueryFile {
+        path: path.to_string(),
+        input,
+        is_pinned,
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn collect_sql_files(root: &Path, dir: &Path, paths: &mut Vec<String>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read queries directory: {}", e))?;
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path: PathBuf = entry.path();
+        if path.is_dir() {
+            collect_sql_files(root, &path, paths)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some(QUERY_FILE_EXTENSION) {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let segments: Vec<String> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect();
+                paths.push(segments.join("/"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_file_parses_back_to_the_same_query() {
+        let input = SavedQueryInput {
+            name: "Monthly revenue".to_string(),
+            query: "-- by month\nSELECT date_trunc('month', paid_at), sum(total)\nFROM payments\nGROUP BY 1".to_string(),
+            description: Some("Revenue per month.\nExcludes refunds.".to_string()),
+            folder: Some("reports/finance".to_string()),
+            tags: vec!["billing".to_string(), "monthly".to_string()],
+            default_connection: Some("prod".to_string()),
+            parameters: vec!["from".to_string(), "to".to_string()],
+        };
+
+        let contents = render_query_file(&input, true);
+        let mut parsed = parse_query_file("reports/finance/Monthly revenue.sql", &contents);
+        // The blank line after the header is trimmed on save, like any leading newline
+        parsed.input.query = parsed.input.query.trim_start_matches('\n').to_string();
+
+        assert_eq!(parsed.input, input);
+        assert!(parsed.is_pinned);
+        assert_eq!(parsed.path, "reports/finance/Monthly revenue.sql");
+    }
+
+    #[test]
+    fn file_without_front_matter_is_named_after_the_file() {
+        let parsed = parse_query_file("users.sql", "-- name: not a header\nSELECT * FROM users;\n");
+
+        assert_eq!(parsed.input.name, "users");
+        assert_eq!(parsed.input.folder, None);
+        assert_eq!(
+            parsed.input.query,
+            "-- name: not a header\nSELECT * FROM users;\n"
+        );
+        assert!(!parsed.is_pinned);
+    }
+
+    #[test]
+    fn front_matter_ignores_unknown_keys_and_blank_values() {
+        let contents = "-- ---\n-- name:\n-- owner: someone\n-- tags: a, , b ,\n-- pinned: no\n-- ---\nSELECT 1";
+        let parsed = parse_query_file("misc/stats.sql", contents);
+
+        assert_eq!(parsed.input.name, "stats");
+        assert_eq!(parsed.input.folder.as_deref(), Some("misc"));
+        assert_eq!(parsed.input.tags, vec!["a", "b"]);
+        assert_eq!(parsed.input.query, "SELECT 1");
+        assert!(!parsed.is_pinned);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::constants::{SNAPSHOTS_DIR_NAME, SNAPSHOT_FORMAT_VERSION};
+use crate::models::{SchemaSnapshot, SchemaSnapshotInfo};
+use crate::utils::sanitize_file_name;
+use crate::utils::sql::unquote_ident;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_EXTENSION: &str = "json";
+
+/// Write a snapshot as pretty-printed JSON so diffs stay readable in git.
+/// Returns the path of the new file.
+pub fn save_snapshot(snapshot: &SchemaSnapshot, app_dir: PathBuf) -> Result<PathBuf, String> {
+    let snapshots_dir = app_dir.join(SNAPSHOTS_DIR_NAME);
+    fs::create_dir_all(&snapshots_dir)
+        .map_err(|e| format!("Could not create snapshots directory: {}", e))?;
+
+    // Compact UTC timestamp so files sort chronologically by name
+    let timestamp = snapshot
+        .created_at
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .take(14)
+        .collect::<String>();
+    let file_name = format!(
+        "{}.{}.{}.{}",
+        sanitize_file_name(&snapshot.connection_name),
+        sanitize_file_name(&snapshot.schema_name),
+        timestamp,
+        SNAPSHOT_EXTENSION
+    );
+    let snapshot_file = snapshots_dir.join(file_name);
+
+    let json = serde_json::to_string_pretty(snapshot)
+        .map_err(|e| format!("Could not serialize snapshot: {}", e))?;
+
+    fs::write(&snapshot_file, json).map_err(|e| format!("Could not write snapshot file: {}", e))?;
+
+    Ok(snapshot_file)
+}
+
+pub fn load_snapshot(file_name: &str, app_dir: PathBuf) -> Result<SchemaSnapshot, String> {
+    let snapshot_file = snapshot_path(file_name, &app_dir)?;
+
+    let data = fs::read_to_string(&snapshot_file)
+        .map_err(|e| format!("Failed to read snapshot {}: {}", file_name, e))?;
+
+    parse_snapshot(&data).map_err(|e| format!("Failed to parse snapshot {}: {}", file_name, e))
+}
+
+/// Snapshots in the project directory, newest first. Files that aren't
+/// readable snapshots are skipped.
+pub fn list_snapshots(app_dir: PathBuf) -> Result<Vec<SchemaSnapshotInfo>, String> {
+    let snapshots_dir = app_dir.join(SNAPSHOTS_DIR_NAME);
+
+    if !snapshots_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&snapshots_dir)
+        .map_err(|e| format!("Failed to read snapshots directory: {}", e))?;
+
+    let mut snapshots: Vec<SchemaSnapshotInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(SNAPSHOT_EXTENSION))
+        .filter_map(|path| {
+            let data = fs::read_to_string(&path).ok()?;
+            let snapshot = parse_snapshot(&data).ok()?;
+            Some(SchemaSnapshotInfo {
+                file_name: path.file_name()?.to_string_lossy().to_string(),
+                created_at: snapshot.created_at,
+                connection_name: snapshot.connection_name,
+                database: snapshot.database,
+                schema_name: snapshot.schema_name,
+            })
+        })
+        .collect();
+
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    Ok(snapshots)
+}
+
+fn parse_snapshot(data: &str) -> Result<SchemaSnapshot, String> {
+    let value: serde_json::Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+
+    // Check the version before the layout, so a newer file gets a clear error
+    let version = value
+        .get("format_version")
+        .and_then(|v| v.as_u64())
+        .ok_or("missing format_version")?;
+    if version > SNAPSHOT_FORMAT_VERSION as u64 {
+        return Err(format!(
+            "format version {} is newer than this app supports ({})",
+            version, SNAPSHOT_FORMAT_VERSION
+        ));
+    }
+
+    let mut snapshot: SchemaSnapshot = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    if version < 2 {
+        unquote_index_columns(&mut snapshot);
+    }
+
+    Ok(snapshot)
+}
+
+// Version 1 kept index columns as pg_get_indexdef printed them, quoted where
+// needed; later versions keep bare names
+fn unquote_index_columns(snapshot: &mut SchemaSnapshot) {
+    for idx in snapshot.schema.tables.iter_mut().flat_map(|t| t.indexes.iter_mut()) {
+        for key in idx.key_columns.iter_mut().filter(|k| !k.is_expression) {
+            key.expression = unquote_ident(&key.expression);
+        }
+        idx.columns = if idx.key_columns.is_empty() {
+            idx.columns.iter().map(|c| unquote_ident(c)).collect()
+        } else {
+            idx.key_columns.iter().map(|k| k.expression.clone()).collect()
+        };
+        idx.include_columns = idx.include_columns.iter().map(|c| unquote_ident(c)).collect();
+    }
+}
+
+// Snapshots are addressed by file name only, so a comparison can't read
+// arbitrary files outside the snapshots directory
+fn snapshot_path(file_name: &str, app_dir: &Path) -> Result<PathBuf, String> {
+    let is_plain_name = Path::new(file_name).file_name().and_then(|n| n.to_str()) == Some(file_name);
+    if !is_plain_name || file_name.starts_with('.') {
+        return Err(format!("Invalid snapshot file name: {}", file_name));
+    }
+
+    Ok(app_dir.join(SNAPSHOTS_DIR_NAME).join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_index_columns_are_unquoted() {
+        let index = serde_json::json!({
+            "index_name": "by_name",
+            "table_name": "users",
+            "columns": ["\"Last Name\"", "lower(email)"],
+            "is_unique": false,
+            "is_primary": false,
+            "definition": "",
+            "key_columns": [
+                { "expression": "\"Last Name\"", "is_expression": false, "descending": false, "nulls_first": false, "opclass": null },
+                { "expression": "lower(email)", "is_expression": true, "descending": false, "nulls_first": false, "opclass": null }
+            ],
+            "include_columns": ["\"Say \"\"hi\"\"\""],
+            "predicate": null
+        });
+        let snapshot = serde_json::json!({
+            "format_version": 1,
+            "created_at": "2026-01-01T00:00:00Z",
+            "connection_name": "dev",
+            "host": "localhost",
+            "port": 5432,
+            "database": "app",
+            "schema_name": "public",
+            "schema": {
+                "tables": [{ "table_name": "users", "columns": [], "foreign_keys": [], "indexes": [index], "comment": null }],
+                "views": [],
+                "routines": []
+            }
+        });
+
+        let parsed = parse_snapshot(&snapshot.to_string()).unwrap();
+        let idx = &parsed.schema.tables[0].indexes[0];
+        assert_eq!(idx.columns, ["Last Name", "lower(email)"]);
+        assert_eq!(idx.key_columns[0].expression, "Last Name");
+        assert_eq!(idx.include_columns, ["Say \"hi\""]);
+
+        // Current snapshots are read as written
+        let mut current = snapshot;
+        current["format_version"] = SNAPSHOT_FORMAT_VERSION.into();
+        let parsed = parse_snapshot(&current.to_string()).unwrap();
+        assert_eq!(
+            parsed.schema.tables[0].indexes[0].columns[0],
+            "\"Last Name\""
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use sqlx::sqlite::SqlitePool;
+use sqlx::Executor;
+
+/// One schema change for an app SQLite store. Steps are numbered from 1 and
+/// never edited once released; `PRAGMA user_version` holds the number of
+/// the last step applied.
+pub struct SqliteMigration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// Apply the steps newer than the store's user_version. Everything runs in
+/// one `BEGIN IMMEDIATE` transaction, so a concurrent open waits for the
+/// write lock and then finds nothing left to do, and a failing step leaves
+/// the file as it was.
+pub async fn run_sqlite_migrations(
+    pool: &SqlitePool,
+    store: &str,
+    migrations: &[SqliteMigration],
+) -> Result<(), String> {
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", store, e))?;
+
+    conn.execute(sqlx::raw_sql("BEGIN IMMEDIATE"))
+        .await
+        .map_err(|e| format!("Failed to lock {} for upgrade: {}", store, e))?;
+
+    let result = async {
+        let version = sqlite_user_version(&mut conn).await?;
+        if version > latest {
+            return Err(format!(
+                "{} was created by a newer version of the app (schema {}, expected {})",
+                store, version, latest
+            ));
+        }
+
+        for migration in migrations.iter().filter(|m| m.version > version) {
+            conn.execute(sqlx::raw_sql(migration.sql))
+                .await
+                .map_err(|e| {
+                    format!(
+                        "Failed to upgrade {} to version {} ({}): {}",
+                        store, migration.version, migration.description, e
+                    )
+                })?;
+            sqlx::query(&format!("PRAGMA user_version = {}", migration.version))
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to update {} version: {}", store, e))?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    let end = if result.is_ok() { "COMMIT" } else { "ROLLBACK" };
+    let finished = conn.execute(sqlx::raw_sql(end)).await;
+
+    result?;
+    finished.map_err(|e| format!("Failed to upgrade {}: {}", store, e))?;
+
+    Ok(())
+}
+
+async fn sqlite_user_version(conn: &mut sqlx::SqliteConnection) -> Result<i64, String> {
+    sqlx::query_scalar("PRAGMA user_version")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read schema version: {}", e))
+}
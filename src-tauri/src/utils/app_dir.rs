@@ -4,6 +4,7 @@ use std::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use crate::constants::{APP_DIR_NAME, SETTINGS_FILENAME};
+use crate::models::HistoryRetention;
 
 // Global state for current project path
 pub static PROJECT_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
@@ -34,6 +35,19 @@ pub fn get_app_dir() -> Result<PathBuf, String> {
     Ok(app_dir)
 }
 
+// Keep generated file names portable across platforms
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 /// Validates that a path is safe to use as a project directory
 /// Prevents path traversal attacks and ensures the path is absolute
 fn validate_project_path(path: &str) -> Result<PathBuf, String> {
@@ -229,6 +243,51 @@ pub fn get_vim_mode_enabled_internal() -> Result<bool, String> {
     Ok(settings.get("vim_mode_enabled").and_then(|v| v.as_bool()).unwrap_or(false))
 }
 
+pub fn set_saved_query_files_enabled_internal(enabled: bool) -> Result<(), String> {
+    let settings_file = get_settings_file()?;
+    let mut settings = load_settings_json(&settings_file)?;
+    settings["saved_query_files_enabled"] = serde_json::json!(enabled);
+
+    let json_str = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(settings_file, json_str)
+        .map_err(|e| format!("Could not write settings: {}", e))?;
+
+    Ok(())
+}
+
+pub fn get_saved_query_files_enabled_internal() -> Result<bool, String> {
+    let settings_file = get_settings_file()?;
+    let settings = load_settings_json(&settings_file)?;
+    Ok(settings
+        .get("saved_query_files_enabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false))
+}
+
+pub fn set_history_retention_internal(retention: &HistoryRetention) -> Result<(), String> {
+    let settings_file = get_settings_file()?;
+    let mut settings = load_settings_json(&settings_file)?;
+    settings["history_retention"] = serde_json::to_value(retention)
+        .map_err(|e| format!("Failed to serialize history retention: {}", e))?;
+
+    let json_str = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(settings_file, json_str)
+        .map_err(|e| format!("Could not write settings: {}", e))?;
+
+    Ok(())
+}
+
+pub fn get_history_retention_internal() -> Result<HistoryRetention, String> {
+    let settings_file = get_settings_file()?;
+    let settings = load_settings_json(&settings_file)?;
+    Ok(settings
+        .get("history_retention")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default())
+}
+
 // Recent projects management
 
 const MAX_RECENT_PROJECTS: usize = 10;
@@ -0,0 +1,584 @@
+use crate::models::ForeignKeyInfo;
+use crate::utils::schema_diff::{ComparisonWarning, DiffStatus, SchemaComparison, WarningSeverity};
+use crate::utils::schema_docs::{escape_html, markdown_cell};
+
+/// Output formats supported by the comparison report generator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            "json" => Ok(ReportFormat::Json),
+            other => Err(format!("Unsupported report format: {}", other)),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+/// Render a comparison and the migration script generated from it into a
+/// self-contained report
+pub fn render_comparison_report(
+    comparison: &SchemaComparison,
+    migration_script: &str,
+    format: ReportFormat,
+) -> Result<String, String> {
+    match format {
+        ReportFormat::Markdown => Ok(render_markdown(comparison, migration_script)),
+        ReportFormat::Html => Ok(render_html(comparison, migration_script)),
+        ReportFormat::Json => render_json(comparison, migration_script),
+    }
+}
+
+// One changed object inside a table, view list, etc.
+struct ChangeRow {
+    kind: &'static str,
+    name: String,
+    status: &'static str,
+    details: String,
+}
+
+fn render_markdown(comparison: &SchemaComparison, migration_script: &str) -> String {
+    let mut doc = String::new();
+
+    // No generation time, so reports of the same comparison are identical
+    doc.push_str(&format!("# {}\n\n", report_title(comparison)));
+    doc.push_str(&format!("_Schema: `{}`_\n\n", comparison.schema_name));
+
+    doc.push_str("## Summary\n\n| Change | Count |\n|---|---|\n");
+    for (label, count) in summary_rows(comparison) {
+        doc.push_str(&format!("| {} | {} |\n", label, count));
+    }
+    doc.push('\n');
+
+    if !comparison.warnings.is_empty() {
+        doc.push_str(&format!("## Warnings ({})\n\n", comparison.warnings.len()));
+        doc.push_str("| Severity | Object | Warning | Details |\n|---|---|---|---|\n");
+        for warning in &comparison.warnings {
+            doc.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                severity_label(&warning.severity),
+                markdown_cell(&warning.affected_object),
+                markdown_cell(&warning.message),
+                markdown_cell(warning.details.as_deref().unwrap_or("")),
+            ));
+        }
+        doc.push('\n');
+    }
+
+    let sections = change_sections(comparison);
+    if sections.is_empty() {
+        doc.push_str("No differences found.\n\n");
+    }
+    for (heading, rows) in &sections {
+        doc.push_str(&format!("## {}\n\n", heading));
+        doc.push_str("| Object | Name | Change | Details |\n|---|---|---|---|\n");
+        for row in rows {
+            doc.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                row.kind,
+                markdown_cell(&row.name),
+                row.status,
+                markdown_cell(&row.details),
+            ));
+        }
+        doc.push('\n');
+    }
+
+    if !migration_script.trim().is_empty() {
+        // A fence longer than any backtick run in the script can't be closed early
+        let fence = "`".repeat(longest_backtick_run(migration_script).max(2) + 1);
+        doc.push_str("## Migration script\n\n");
+        doc.push_str(&format!("{}sql\n{}\n{}\n", fence, migration_script.trim_end(), fence));
+    }
+
+    doc
+}
+
+fn render_html(comparison: &SchemaComparison, migration_script: &str) -> String {
+    let mut doc = String::new();
+    let title = report_title(comparison);
+
+    doc.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    doc.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+    doc.push_str(
+        "<style>\n\
+         body { font-family: -apple-system, BlinkMacSystemFont, sans-serif; margin: 2rem; color: #1f2933; }\n\
+         table { border-collapse: collapse; margin-bottom: 1rem; }\n\
+         th, td { border: 1px solid #d9e2ec; padding: 0.25rem 0.5rem; text-align: left; vertical-align: top; }\n\
+         th { background: #f0f4f8; }\n\
+         code, pre { background: #f0f4f8; padding: 0.1rem 0.25rem; }\n\
+         pre { padding: 0.5rem; overflow-x: auto; }\n\
+         .high { color: #b42318; font-weight: 600; }\n\
+         .medium { color: #b54708; }\n\
+         .low { color: #52606d; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+
+    doc.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+    doc.push_str(&format!(
+        "<p><em>Schema: <code>{}</code></em></p>\n",
+        escape_html(&comparison.schema_name)
+    ));
+
+    doc.push_str("<h2>Summary</h2>\n<table>\n<tr><th>Change</th><th>Count</th></tr>\n");
+    for (label, count) in summary_rows(comparison) {
+        doc.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", label, count));
+    }
+    doc.push_str("</table>\n");
+
+    if !comparison.warnings.is_empty() {
+        doc.push_str(&format!("<h2>Warnings ({})</h2>\n", comparison.warnings.len()));
+        doc.push_str(
+            "<table>\n<tr><th>Severity</th><th>Object</th><th>Warning</th><th>Details</th></tr>\n",
+        );
+        for warning in &comparison.warnings {
+            doc.push_str(&warning_html_row(warning));
+        }
+        doc.push_str("</table>\n");
+    }
+
+    let sections = change_sections(comparison);
+    if sections.is_empty() {
+        doc.push_str("<p>No differences found.</p>\n");
+    }
+    for (heading, rows) in &sections {
+        doc.push_str(&format!("<h2>{}</h2>\n", escape_html(heading)));
+        doc.push_str(
+            "<table>\n<tr><th>Object</th><th>Name</th><th>Change</th><th>Details</th></tr>\n",
+        );
+        for row in rows {
+            doc.push_str(&format!(
+                "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+                row.kind,
+                escape_html(&row.name),
+                row.status,
+                escape_html(&row.details),
+            ));
+        }
+        doc.push_str("</table>\n");
+    }
+
+    if !migration_script.trim().is_empty() {
+        doc.push_str("<h2>Migration script</h2>\n");
+        doc.push_str(&format!("<pre>{}</pre>\n", escape_html(migration_script.trim_end())));
+    }
+
+    doc.push_str("</body>\n</html>\n");
+    doc
+}
+
+fn render_json(comparison: &SchemaComparison, migration_script: &str) -> Result<String, String> {
+    let report = serde_json::json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "comparison": comparison,
+        "migration_script": migration_script,
+    });
+
+    serde_json::to_string_pretty(&report).map_err(|e| format!("Failed to serialize report: {}", e))
+}
+
+fn report_title(comparison: &SchemaComparison) -> String {
+    format!(
+        "Schema comparison: {} → {}",
+        comparison.source_connection, comparison.target_connection
+    )
+}
+
+fn summary_rows(comparison: &SchemaComparison) -> Vec<(&'static str, usize)> {
+    let summary = &comparison.summary;
+    vec![
+        ("Tables added", summary.tables_added),
+        ("Tables removed", summary.tables_removed),
+        ("Tables modified", summary.tables_modified),
+        ("Indexes missing", summary.indexes_missing),
+        ("Views changed", summary.views_changed),
+        ("Functions/procedures changed", summary.routines_changed),
+        ("Extensions changed", summary.extensions_changed),
+        ("Types changed", summary.types_changed),
+        ("Warnings", comparison.warnings.len()),
+    ]
+}
+
+// Every non-identical difference, grouped by heading; tables get a section each
+fn change_sections(comparison: &SchemaComparison) -> Vec<(String, Vec<ChangeRow>)> {
+    let mut sections = Vec::new();
+
+    for table in &comparison.table_differences {
+        if is_identical(&table.status) {
+            continue;
+        }
+
+        let mut rows = Vec::new();
+        let mut table_details = Vec::new();
+        if let Some(old_name) = &table.renamed_from {
+            table_details.push(format!("renamed from {}", old_name));
+        }
+        if table.source_comment != table.target_comment {
+            table_details.push("comment changed".to_string());
+        }
+        if !matches!(table.status, DiffStatus::Modified) || !table_details.is_empty() {
+            rows.push(ChangeRow {
+                kind: "Table",
+                name: table.table_name.clone(),
+                status: status_label(&table.status),
+                details: table_details.join("; "),
+            });
+        }
+
+        for column in &table.column_changes {
+            if is_identical(&column.status) {
+                continue;
+            }
+            let definition = column.source_definition.as_ref().or(column.target_definition.as_ref());
+            let details = if column.changes.is_empty() {
+                definition.map(|c| c.data_type.clone()).unwrap_or_default()
+            } else {
+                column.changes.join("; ")
+            };
+            rows.push(ChangeRow {
+                kind: "Column",
+                name: column.column_name.clone(),
+                status: status_label(&column.status),
+                details,
+            });
+        }
+
+        for index in &table.index_changes {
+            if is_identical(&index.status) {
+                continue;
+            }
+            rows.push(ChangeRow {
+                kind: "Index",
+                name: index.index_name.clone(),
+                status: status_label(&index.status),
+                details: index
+                    .source_definition
+                    .as_ref()
+                    .or(index.target_definition.as_ref())
+                    .map(|i| i.definition.clone())
+                    .unwrap_or_default(),
+            });
+        }
+
+        for fk in &table.fk_changes {
+            if is_identical(&fk.status) {
+                continue;
+            }
+            rows.push(ChangeRow {
+                kind: "Foreign key",
+                name: fk.constraint_name.clone(),
+                status: status_label(&fk.status),
+                details: fk
+                    .source_definition
+                    .as_ref()
+                    .or(fk.target_definition.as_ref())
+                    .map(describe_foreign_key)
+                    .unwrap_or_default(),
+            });
+        }
+
+        for check in &table.check_changes {
+            if is_identical(&check.status) {
+                continue;
+            }
+            rows.push(ChangeRow {
+                kind: "Check constraint",
+                name: check.constraint_name.clone(),
+                status: status_label(&check.status),
+                details: check
+                    .source_definition
+                    .as_ref()
+                    .or(check.target_definition.as_ref())
+                    .map(|c| c.definition.clone())
+                    .unwrap_or_default(),
+            });
+        }
+
+        sections.push((format!("Table {}", table.table_name), rows));
+    }
+
+    let views: Vec<ChangeRow> = comparison
+        .view_differences
+        .iter()
+        .filter(|view| !is_identical(&view.status))
+        .map(|view| {
+            let mut details = Vec::new();
+            if view.definition_changed {
+                details.push("definition changed".to_string());
+            }
+            if view.source_comment != view.target_comment {
+                details.push("comment changed".to_string());
+            }
+            ChangeRow {
+                kind: "View",
+                name: view.view_name.clone(),
+                status: status_label(&view.status),
+                details: details.join("; "),
+            }
+        })
+        .collect();
+    if !views.is_empty() {
+        sections.push(("Views".to_string(), views));
+    }
+
+    let routines: Vec<ChangeRow> = comparison
+        .routine_differences
+        .iter()
+        .filter(|routine| !is_identical(&routine.status))
+        .map(|routine| {
+            let mut details = Vec::new();
+            if routine.definition_changed {
+                details.push("definition changed");
+            }
+            if routine.comment_changed {
+                details.push("comment changed");
+            }
+            ChangeRow {
+                kind: "Routine",
+                name: routine.routine_name.clone(),
+                status: status_label(&routine.status),
+                details: details.join("; "),
+            }
+        })
+        .collect();
+    if !routines.is_empty() {
+        sections.push(("Functions/Procedures".to_string(), routines));
+    }
+
+    let extensions: Vec<ChangeRow> = comparison
+        .extension_differences
+        .iter()
+        .filter(|extension| !is_identical(&extension.status))
+        .map(|extension| {
+            let source_version = extension.source_definition.as_ref().map(|e| e.version.as_str());
+            let target_version = extension.target_definition.as_ref().map(|e| e.version.as_str());
+            let details = match (source_version, target_version) {
+                (Some(source), Some(target)) => format!("version {} → {}", target, source),
+                (Some(version), None) | (None, Some(version)) => format!("version {}", version),
+                (None, None) => String::new(),
+            };
+            ChangeRow {
+                kind: "Extension",
+                name: extension.extension_name.clone(),
+                status: status_label(&extension.status),
+                details,
+            }
+        })
+        .collect();
+    if !extensions.is_empty() {
+        sections.push(("Extensions".to_string(), extensions));
+    }
+
+    let types: Vec<ChangeRow> = comparison
+        .type_differences
+        .iter()
+        .filter(|type_change| !is_identical(&type_change.status))
+        .map(|type_change| {
+            let source = type_change.source_definition.as_ref();
+            let target = type_change.target_definition.as_ref();
+            let details = match (source, target) {
+                (Some(source), Some(target)) => {
+                    format!("{} → {}", target.definition, source.definition)
+                }
+                (Some(type_info), None) | (None, Some(type_info)) => {
+                    format!("{} {}", type_info.kind, type_info.definition)
+                }
+                (None, None) => String::new(),
+            };
+            ChangeRow {
+                kind: "Type",
+                name: type_change.type_name.clone(),
+                status: status_label(&type_change.status),
+                details,
+            }
+        })
+        .collect();
+    if !types.is_empty() {
+        sections.push(("Types".to_string(), types));
+    }
+
+    sections
+}
+
+fn warning_html_row(warning: &ComparisonWarning) -> String {
+    let severity = severity_label(&warning.severity);
+    format!(
+        "<tr><td class=\"{}\">{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+        severity,
+        severity,
+        escape_html(&warning.affected_object),
+        escape_html(&warning.message),
+        escape_html(warning.details.as_deref().unwrap_or("")),
+    )
+}
+
+fn describe_foreign_key(fk: &ForeignKeyInfo) -> String {
+    format!(
+        "{} → {}.{}",
+        fk.column_name, fk.foreign_table_name, fk.foreign_column_name
+    )
+}
+
+fn is_identical(status: &DiffStatus) -> bool {
+    matches!(status, DiffStatus::Identical)
+}
+
+fn status_label(status: &DiffStatus) -> &'static str {
+    match status {
+        DiffStatus::Identical => "identical",
+        DiffStatus::Modified => "modified",
+        DiffStatus::Added => "added",
+        DiffStatus::Removed => "removed",
+    }
+}
+
+fn severity_label(severity: &WarningSeverity) -> &'static str {
+    match severity {
+        WarningSeverity::High => "high",
+        WarningSeverity::Medium => "medium",
+        WarningSeverity::Low => "low",
+    }
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EnhancedColumnInfo;
+    use crate::utils::schema_diff::{comparison_summary, ColumnChange, TableDifference};
+
+    fn comparison() -> SchemaComparison {
+        let column = EnhancedColumnInfo {
+            column_name: "a|b".to_string(),
+            data_type: "text".to_string(),
+            is_nullable: "YES".to_string(),
+            is_primary_key: false,
+            column_default: None,
+            character_maximum_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: 1,
+            comment: None,
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        };
+        let tables = vec![TableDifference {
+            table_name: "<orders>".to_string(),
+            status: DiffStatus::Added,
+            column_changes: vec![ColumnChange {
+                column_name: column.column_name.clone(),
+                status: DiffStatus::Added,
+                source_definition: Some(column),
+                target_definition: None,
+                changes: Vec::new(),
+            }],
+            index_changes: Vec::new(),
+            fk_changes: Vec::new(),
+            check_changes: Vec::new(),
+            source_comment: None,
+            target_comment: None,
+            renamed_from: None,
+        }];
+
+        SchemaComparison {
+            source_connection: "dev".to_string(),
+            target_connection: "R&D".to_string(),
+            schema_name: "public".to_string(),
+            summary: comparison_summary(&tables, &[], &[], &[], &[]),
+            table_differences: tables,
+            view_differences: Vec::new(),
+            routine_differences: Vec::new(),
+            extension_differences: Vec::new(),
+            type_differences: Vec::new(),
+            rename_candidates: Vec::new(),
+            warnings: vec![ComparisonWarning {
+                severity: WarningSeverity::High,
+                warning_type: "data_loss".to_string(),
+                message: "Drop \"<orders>\" | everything".to_string(),
+                affected_object: "<orders>".to_string(),
+                details: Some("a & b".to_string()),
+                check_query: None,
+            }],
+            target_server_version: None,
+        }
+    }
+
+    const SCRIPT: &str = "CREATE TABLE \"<orders>\" (\"a|b\" text);\n-- ```quoted``` & <done>\n";
+
+    #[test]
+    fn html_report_escapes_names_and_script() {
+        let html = render_comparison_report(&comparison(), SCRIPT, ReportFormat::Html).unwrap();
+
+        assert!(html.contains("<title>Schema comparison: dev → R&amp;D</title>"));
+        assert!(html.contains(
+            "<tr><td class=\"high\">high</td><td><code>&lt;orders&gt;</code></td>\
+             <td>Drop &quot;&lt;orders&gt;&quot; | everything</td><td>a &amp; b</td></tr>"
+        ));
+        assert!(html.contains("<h2>Table &lt;orders&gt;</h2>"));
+        assert!(html.contains(
+            "<pre>CREATE TABLE &quot;&lt;orders&gt;&quot; (&quot;a|b&quot; text);\n\
+             -- ```quoted``` &amp; &lt;done&gt;</pre>"
+        ));
+        assert!(!html.contains("<orders>"));
+    }
+
+    #[test]
+    fn markdown_report_keeps_cells_and_script_intact() {
+        let markdown = render_comparison_report(&comparison(), SCRIPT, ReportFormat::Markdown).unwrap();
+
+        assert!(markdown.starts_with("# Schema comparison: dev → R&D\n\n_Schema: `public`_\n\n"));
+        assert!(markdown.contains("| Tables added | 1 |\n"));
+        assert!(markdown.contains("| high | <orders> | Drop \"<orders>\" \\| everything | a & b |\n"));
+        assert!(markdown.contains("| Column | a\\|b | added | text |\n"));
+        // The fence is longer than the backtick run inside the script
+        assert!(markdown.ends_with(&format!("````sql\n{}\n````\n", SCRIPT.trim_end())));
+
+        let empty = SchemaComparison {
+            table_differences: Vec::new(),
+            warnings: Vec::new(),
+            ..comparison()
+        };
+        let markdown = render_comparison_report(&empty, "", ReportFormat::Markdown).unwrap();
+        assert!(markdown.contains("No differences found."));
+        assert!(!markdown.contains("## Migration script"));
+    }
+
+    #[test]
+    fn json_report_carries_the_comparison_and_script() {
+        let json = render_comparison_report(&comparison(), SCRIPT, ReportFormat::Json).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(report["migration_script"], SCRIPT);
+        assert_eq!(report["comparison"]["target_connection"], "R&D");
+        assert_eq!(report["comparison"]["warnings"][0]["severity"], "high");
+        assert!(report["generated_at"].as_str().unwrap().ends_with("+00:00"));
+    }
+
+    #[test]
+    fn rendered_reports_carry_no_generation_time() {
+        for format in [ReportFormat::Markdown, ReportFormat::Html] {
+            let report = render_comparison_report(&comparison(), SCRIPT, format).unwrap();
+            assert!(!report.contains("Generated"), "{:?}", format);
+        }
+        assert_eq!(ReportFormat::parse("MD"), Ok(ReportFormat::Markdown));
+        assert!(ReportFormat::parse("pdf").is_err());
+    }
+}
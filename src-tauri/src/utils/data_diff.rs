@@ -0,0 +1,574 @@
+use crate::utils::schema_diff::DiffStatus;
+use crate::utils::sql::{qualified_name, quote_ident, quote_literal};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Column values of one row as PostgreSQL's text output, in layout column order
+pub type RowValues = Vec<Option<String>>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DataCompareOptions {
+    /// Rows per hashed chunk; only chunks whose hashes differ are fetched
+    pub chunk_size: usize,
+    /// Cap on `row_differences`; counts and the sync script always cover every row
+    pub max_row_differences: usize,
+}
+
+impl Default for DataCompareOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1000,
+            max_row_differences: 1000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ColumnValueChange {
+    pub column_name: String,
+    pub source_value: Option<String>,
+    pub target_value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RowDifference {
+    pub status: DiffStatus, // added: only in source, removed: only in target
+    pub key: Vec<String>,   // primary key values, in primary key order
+    pub source_values: Option<RowValues>,
+    pub target_values: Option<RowValues>,
+    pub column_changes: Vec<ColumnValueChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableDataComparison {
+    pub source_connection: String,
+    pub target_connection: String,
+    pub schema_name: String,
+    pub table_name: String,
+    pub primary_key: Vec<String>,
+    pub columns: Vec<String>,
+    pub ignored_columns: Vec<String>, // only on one side, or generated
+    pub source_row_count: i64,
+    pub target_row_count: i64,
+    pub chunks_total: usize,
+    pub chunks_different: usize,
+    pub rows_added: usize,
+    pub rows_removed: usize,
+    pub rows_changed: usize,
+    pub row_differences: Vec<RowDifference>,
+    pub truncated: bool,
+    /// Makes the target's rows match the source's
+    pub sync_sql: String,
+}
+
+/// A column as read from pg_attribute on one side
+#[derive(Debug, Clone)]
+pub struct DataColumn {
+    pub name: String,
+    pub data_type: String,
+    pub key_position: Option<usize>, // 1-based position in the primary key
+    pub identity_always: bool,
+    pub generated: bool,
+}
+
+/// Columns compared on both sides
+#[derive(Debug, Clone)]
+pub struct DataTableLayout {
+    pub schema_name: String,
+    pub table_name: String,
+    pub columns: Vec<String>,
+    pub key_columns: Vec<usize>, // indexes into `columns`, in primary key order
+    pub key_types: Vec<String>,
+    pub ignored_columns: Vec<String>,
+    pub identity_always: bool, // inserts need OVERRIDING SYSTEM VALUE
+}
+
+impl DataTableLayout {
+    /// Match up both sides' columns. Rows are paired by primary key, so both
+    /// sides need the same one.
+    pub fn new(
+        schema_name: &str,
+        table_name: &str,
+        source: &[DataColumn],
+        target: &[DataColumn],
+    ) -> Result<Self, String> {
+        let source_key = primary_key(source);
+        if source_key.is_empty() {
+            return Err(format!(
+                "Table {} has no primary key, so rows can't be matched",
+                table_name
+            ));
+        }
+        if source_key != primary_key(target) {
+            return Err(format!(
+                "Table {} has a different primary key on source and target",
+                table_name
+            ));
+        }
+
+        let target_by_name: HashMap<&str, &DataColumn> =
+            target.iter().map(|c| (c.name.as_str(), c)).collect();
+
+        let mut columns = Vec::new();
+        let mut ignored_columns = Vec::new();
+        let mut identity_always = false;
+        for column in source {
+            match target_by_name.get(column.name.as_str()) {
+                Some(target_column) if !column.generated && !target_column.generated => {
+                    identity_always |= target_column.identity_always;
+                    columns.push(column.name.clone());
+                }
+                _ => ignored_columns.push(column.name.clone()),
+            }
+        }
+        for column in target {
+            if !source.iter().any(|c| c.name == column.name) {
+                ignored_columns.push(column.name.clone());
+            }
+        }
+
+        let key_columns = source_key
+            .iter()
+            .map(|key| columns.iter().position(|c| c == key))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(|| format!("Primary key of {} includes a generated column", table_name))?;
+        let key_types = source_key
+            .iter()
+            .filter_map(|key| source.iter().find(|c| &c.name == key))
+            .map(|c| c.data_type.clone())
+            .collect();
+
+        Ok(Self {
+            schema_name: schema_name.to_string(),
+            table_name: table_name.to_string(),
+            columns,
+            key_columns,
+            key_types,
+            ignored_columns,
+            identity_always,
+        })
+    }
+
+    fn table(&self) -> String {
+        qualified_name(&self.schema_name, &self.table_name)
+    }
+
+    fn key_list(&self) -> String {
+        self.key_columns
+            .iter()
+            .map(|&i| quote_ident(&self.columns[i]))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn key_of(&self, row: &RowValues) -> Vec<String> {
+        self.key_columns
+            .iter()
+            .map(|&i| row[i].clone().unwrap_or_default())
+            .collect()
+    }
+}
+
+// Primary key column names in key order
+fn primary_key(columns: &[DataColumn]) -> Vec<String> {
+    let mut keyed: Vec<&DataColumn> = columns.iter().filter(|c| c.key_position.is_some()).collect();
+    keyed.sort_by_key(|c| c.key_position);
+    keyed.into_iter().map(|c| c.name.clone()).collect()
+}
+
+/// Every `chunk_size`-th primary key of a side, used as chunk upper bounds
+pub fn chunk_boundaries_query(layout: &DataTableLayout, chunk_size: usize) -> String {
+    let keys = layout.key_list();
+    let key_text = layout
+        .key_columns
+        .iter()
+        .map(|&i| format!("{}::text", quote_ident(&layout.columns[i])))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT {} FROM (SELECT {}, row_number() OVER (ORDER BY {}) AS rn FROM {}) numbered \
+         WHERE rn % {} = 0 ORDER BY rn",
+        key_text,
+        keys,
+        keys,
+        layout.table(),
+        chunk_size.max(1)
+    )
+}
+
+/// Row count and an order-sensitive hash of every row in a key range
+pub fn chunk_hash_query(layout: &DataTableLayout, condition: &str) -> String {
+    let columns = layout
+        .columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT count(*), md5(string_agg(md5(ROW({})::text), '' ORDER BY {})) FROM {} WHERE {}",
+        columns,
+        layout.key_list(),
+        layout.table(),
+        condition
+    )
+}
+
+/// Every compared column, as text, of the rows in a key range
+pub fn chunk_rows_query(layout: &DataTableLayout, condition: &str) -> String {
+    let columns = layout
+        .columns
+        .iter()
+        .map(|c| format!("{}::text", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT {} FROM {} WHERE {} ORDER BY {}",
+        columns,
+        layout.table(),
+        condition,
+        layout.key_list()
+    )
+}
+
+/// WHERE condition for keys in (lower, upper]; an open end is unbounded
+pub fn key_range_condition(
+    layout: &DataTableLayout,
+    lower: Option<&[String]>,
+    upper: Option<&[String]>,
+) -> String {
+    let keys = format!("({})", layout.key_list());
+    let bound = |values: &[String]| {
+        let literals: Vec<String> = values
+            .iter()
+            .zip(&layout.key_types)
+            .map(|(value, data_type)| format!("{}::{}", quote_literal(value), data_type))
+            .collect();
+        format!("({})", literals.join(", "))
+    };
+
+    let mut parts = Vec::new();
+    if let Some(lower) = lower {
+        parts.push(format!("{} > {}", keys, bound(lower)));
+    }
+    if let Some(upper) = upper {
+        parts.push(format!("{} <= {}", keys, bound(upper)));
+    }
+    if parts.is_empty() {
+        "TRUE".to_string()
+    } else {
+        parts.join(" AND ")
+    }
+}
+
+/// Pair up the rows of one chunk by primary key. Rows only in the source
+/// come first, in source order, then rows only in the target.
+pub fn diff_chunk_rows(
+    layout: &DataTableLayout,
+    source_rows: Vec<RowValues>,
+    target_rows: Vec<RowValues>,
+) -> Vec<RowDifference> {
+    let mut target_by_key: HashMap<Vec<String>, RowValues> = HashMap::new();
+    let mut target_order = Vec::new();
+    for row in target_rows {
+        let key = layout.key_of(&row);
+        target_order.push(key.clone());
+        target_by_key.insert(key, row);
+    }
+
+    let mut differences = Vec::new();
+    for source_row in source_rows {
+        let key = layout.key_of(&source_row);
+        match target_by_key.remove(&key) {
+            None => differences.push(RowDifference {
+                status: DiffStatus::Added,
+                key,
+                source_values: Some(source_row),
+                target_values: None,
+                column_changes: Vec::new(),
+            }),
+            Some(target_row) => {
+                let column_changes: Vec<ColumnValueChange> = layout
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| source_row[*i] != target_row[*i])
+                    .map(|(i, column)| ColumnValueChange {
+                        column_name: column.clone(),
+                        source_value: source_row[i].clone(),
+                        target_value: target_row[i].clone(),
+                    })
+                    .collect();
+                if !column_changes.is_empty() {
+                    differences.push(RowDifference {
+                        status: DiffStatus::Modified,
+                        key,
+                        source_values: Some(source_row),
+                        target_values: Some(target_row),
+                        column_changes,
+                    });
+                }
+            }
+        }
+    }
+
+    for key in target_order {
+        if let Some(target_row) = target_by_key.remove(&key) {
+            differences.push(RowDifference {
+                status: DiffStatus::Removed,
+                key,
+                source_values: None,
+                target_values: Some(target_row),
+                column_changes: Vec::new(),
+            });
+        }
+    }
+
+    differences
+}
+
+/// Script that makes the target's rows match the source's: deletes first,
+/// so unique values freed by a removed row can be reused, then updates,
+/// then inserts
+pub fn generate_data_sync_sql(
+    layout: &DataTableLayout,
+    differences: &[RowDifference],
+    source_name: &str,
+    target_name: &str,
+) -> String {
+    let mut script = String::new();
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+
+    script.push_str(&format!("-- Data sync for {}\n", layout.table()));
+    script.push_str(&format!("-- Source: {}\n", source_name));
+    script.push_str(&format!("-- Target: {}\n", target_name));
+    script.push_str(&format!("-- Generated: {}\n\n", timestamp));
+
+    if differences.is_empty() {
+        script.push_str("-- No differences found\n");
+        return script;
+    }
+
+    script.push_str("BEGIN;\n\n");
+
+    for difference in differences {
+        if let (DiffStatus::Removed, Some(row)) = (&difference.status, &difference.target_values) {
+            script.push_str(&format!(
+                "DELETE FROM {} WHERE {};\n",
+                layout.table(),
+                key_match(layout, row)
+            ));
+        }
+    }
+
+    for difference in differences {
+        if let (DiffStatus::Modified, Some(row)) = (&difference.status, &difference.target_values) {
+            let assignments: Vec<String> = difference
+                .column_changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} = {}",
+                        quote_ident(&change.column_name),
+                        sql_value(&change.source_value)
+                    )
+                })
+                .collect();
+            script.push_str(&format!(
+                "UPDATE {} SET {} WHERE {};\n",
+                layout.table(),
+                assignments.join(", "),
+                key_match(layout, row)
+            ));
+        }
+    }
+
+    let columns = layout
+        .columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let overriding = if layout.identity_always {
+        " OVERRIDING SYSTEM VALUE"
+    } else {
+        ""
+    };
+    for difference in differences {
+        if let (DiffStatus::Added, Some(row)) = (&difference.status, &difference.source_values) {
+            let values: Vec<String> = row.iter().map(sql_value).collect();
+            script.push_str(&format!(
+                "INSERT INTO {} ({}){} VALUES ({});\n",
+                layout.table(),
+                columns,
+                overriding,
+                values.join(", ")
+            ));
+        }
+    }
+
+    script.push_str("\nCOMMIT;\n");
+    script
+}
+
+// "id = '5'": untyped literals take the column's type
+fn key_match(layout: &DataTableLayout, row: &RowValues) -> String {
+    layout
+        .key_columns
+        .iter()
+        .map(|&i| format!("{} = {}", quote_ident(&layout.columns[i]), sql_value(&row[i])))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn sql_value(value: &Option<String>) -> String {
+    match value {
+        Some(value) => quote_literal(value),
+        None => "NULL".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, key_position: Option<usize>) -> DataColumn {
+        DataColumn {
+            name: name.to_string(),
+            data_type: "integer".to_string(),
+            key_position,
+            identity_always: false,
+            generated: false,
+        }
+    }
+
+    // Keyed on (tenant, id), with one payload column
+    fn layout() -> DataTableLayout {
+        let columns = [
+            column("name", None),
+            column("id", Some(2)),
+            column("tenant", Some(1)),
+        ];
+        DataTableLayout::new("public", "items", &columns, &columns).unwrap()
+    }
+
+    // Values in layout order: name, id, tenant
+    fn row(name: Option<&str>, id: &str, tenant: &str) -> RowValues {
+        vec![
+            name.map(str::to_string),
+            Some(id.to_string()),
+            Some(tenant.to_string()),
+        ]
+    }
+
+    #[test]
+    fn layout_keeps_shared_columns_and_orders_the_key() {
+        let mut generated = column("total", None);
+        generated.generated = true;
+        let source = [
+            column("id", Some(1)),
+            column("name", None),
+            column("new_col", None),
+            generated.clone(),
+        ];
+        let target = [
+            column("id", Some(1)),
+            column("name", None),
+            generated,
+            column("old_col", None),
+        ];
+
+        let layout = DataTableLayout::new("public", "items", &source, &target).unwrap();
+
+        assert_eq!(layout.columns, vec!["id", "name"]);
+        assert_eq!(layout.ignored_columns, vec!["new_col", "total", "old_col"]);
+        assert_eq!(self::layout().key_columns, vec![2, 1]);
+    }
+
+    #[test]
+    fn layout_needs_the_same_primary_key_on_both_sides() {
+        let keyed = [column("id", Some(1)), column("name", None)];
+        let unkeyed = [column("id", None), column("name", None)];
+        let other_key = [column("id", None), column("name", Some(1))];
+
+        assert!(DataTableLayout::new("public", "items", &unkeyed, &unkeyed)
+            .unwrap_err()
+            .contains("no primary key"));
+        assert!(DataTableLayout::new("public", "items", &keyed, &other_key)
+            .unwrap_err()
+            .contains("different primary key"));
+    }
+
+    #[test]
+    fn rows_are_matched_by_their_whole_key() {
+        let layout = layout();
+        let source = vec![
+            row(Some("same"), "1", "a"),
+            row(Some("new name"), "2", "a"),
+            row(Some("only source"), "1", "b"),
+            row(None, "3", "a"),
+        ];
+        let target = vec![
+            row(Some("only target"), "9", "a"),
+            row(Some("same"), "1", "a"),
+            row(Some("old name"), "2", "a"),
+            row(Some("was set"), "3", "a"),
+        ];
+
+        let differences = diff_chunk_rows(&layout, source, target);
+        let summary: Vec<(&DiffStatus, Vec<String>, Vec<&str>)> = differences
+            .iter()
+            .map(|d| {
+                (
+                    &d.status,
+                    d.key.clone(),
+                    d.column_changes
+                        .iter()
+                        .map(|c| c.column_name.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+
+        assert!(matches!(
+            summary.as_slice(),
+            [
+                (DiffStatus::Modified, k1, c1),
+                (DiffStatus::Added, k2, c2),
+                (DiffStatus::Modified, k3, c3),
+                (DiffStatus::Removed, k4, c4),
+            ] if k1 == &["a", "2"] && c1 == &["name"]
+                && k2 == &["b", "1"] && c2.is_empty()
+                && k3 == &["a", "3"] && c3 == &["name"]
+                && k4 == &["a", "9"] && c4.is_empty()
+        ));
+        assert_eq!(differences[2].column_changes[0].source_value, None);
+    }
+
+    #[test]
+    fn sync_script_deletes_then_updates_then_inserts() {
+        let layout = layout();
+        let differences = diff_chunk_rows(
+            &layout,
+            vec![row(Some("it's"), "2", "a"), row(None, "5", "a")],
+            vec![row(Some("old"), "2", "a"), row(Some("gone"), "7", "a")],
+        );
+
+        let script = generate_data_sync_sql(&layout, &differences, "dev", "prod");
+        let statements: Vec<&str> = script
+            .lines()
+            .filter(|line| !line.starts_with("--") && !line.is_empty())
+            .collect();
+
+        assert_eq!(
+            statements,
+            vec![
+                "BEGIN;",
+                "DELETE FROM public.items WHERE tenant = 'a' AND id = '7';",
+                "UPDATE public.items SET name = 'it''s' WHERE tenant = 'a' AND id = '2';",
+                "INSERT INTO public.items (name, id, tenant) VALUES (NULL, '5', 'a');",
+                "COMMIT;",
+            ]
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::utils::get_app_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A .sql file from a DDL directory
+#[derive(Debug, Clone)]
+pub struct DdlFile {
+    pub relative_path: String,
+    pub contents: String,
+}
+
+/// Resolve a DDL directory given as an absolute path or relative to the
+/// project directory
+pub fn resolve_ddl_directory(path: &str) -> Result<PathBuf, String> {
+    if path.contains("..") {
+        return Err("Invalid path: parent directory references (..) are not allowed".to_string());
+    }
+
+    let directory = PathBuf::from(path);
+    let directory = if directory.is_absolute() {
+        directory
+    } else {
+        get_app_dir()?.join(directory)
+    };
+
+    if !directory.is_dir() {
+        return Err(format!("DDL directory does not exist: {}", directory.display()));
+    }
+
+    Ok(directory)
+}
+
+/// All .sql files under `directory`, including subdirectories, ordered by
+/// relative path. Files are applied in this order, so dependencies can be
+/// expressed with prefixes such as `01_types.sql`, `02_tables.sql`.
+pub fn read_ddl_files(directory: &Path) -> Result<Vec<DdlFile>, String> {
+    let mut paths = Vec::new();
+    collect_sql_files(directory, &mut paths)?;
+
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let relative_path = path
+            .strip_prefix(directory)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        files.push(DdlFile {
+            relative_path,
+            contents,
+        });
+    }
+
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(files)
+}
+
+fn collect_sql_files(directory: &Path, paths: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(directory)
+        .map_err(|e| format!("Failed to read directory {}: {}", directory.display(), e))?;
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        // Skip hidden files and directories such as .git
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_sql_files(&path, paths)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some("sql") {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Directed graph of "must run before" edges between numbered nodes.
+///
+/// Each node carries a priority; among nodes whose dependencies are already
+/// satisfied, the one with the lowest priority (then the lowest index) comes
+/// first, so the output is deterministic and follows the natural phase order
+/// wherever the edges allow it.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    priorities: Vec<usize>,
+    dependents: Vec<Vec<usize>>,
+}
+
+/// Result of a topological sort
+#[derive(Debug)]
+pub struct TopologicalOrder {
+    pub order: Vec<usize>,
+    /// Nodes that were emitted before all their dependencies to break a cycle
+    pub cycle_breaks: Vec<usize>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, priority: usize) -> usize {
+        self.priorities.push(priority);
+        self.dependents.push(Vec::new());
+        self.priorities.len() - 1
+    }
+
+    /// Record that `before` must come before `after`
+    pub fn add_edge(&mut self, before: usize, after: usize) {
+        if before != after && !self.dependents[before].contains(&after) {
+            self.dependents[before].push(after);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.priorities.len()
+    }
+
+    /// Kahn's algorithm with a priority queue. Cycles are broken by emitting
+    /// the lowest-priority remaining node that lies on one, and reporting it in
+    /// `cycle_breaks`; nodes merely waiting on a cycle keep their order.
+    pub fn sort(&self) -> TopologicalOrder {
+        let node_count = self.len();
+        let mut in_degree = vec![0usize; node_count];
+        for dependents in &self.dependents {
+            for &after in dependents {
+                in_degree[after] += 1;
+            }
+        }
+
+        let mut ready: BinaryHeap<Reverse<(usize, usize)>> = (0..node_count)
+            .filter(|&n| in_degree[n] == 0)
+            .map(|n| Reverse((self.priorities[n], n)))
+            .collect();
+
+        let mut emitted = vec![false; node_count];
+        let mut order = Vec::with_capacity(node_count);
+        let mut cycle_breaks = Vec::new();
+
+        while order.len() < node_count {
+            let node = match ready.pop() {
+                Some(Reverse((_, node))) => node,
+                None => {
+                    // Everything left is part of or blocked by a cycle
+                    let mut remaining: Vec<usize> = (0..node_count).filter(|&n| !emitted[n]).collect();
+                    remaining.sort_by_key(|&n| (self.priorities[n], n));
+                    let node = remaining
+                        .into_iter()
+                        .find(|&n| self.on_cycle(n, &emitted))
+                        .expect("unsorted nodes remain on a cycle");
+                    cycle_breaks.push(node);
+                    node
+                }
+            };
+
+            if emitted[node] {
+                continue;
+            }
+            emitted[node] = true;
+            order.push(node);
+
+            for &after in &self.dependents[node] {
+                in_degree[after] = in_degree[after].saturating_sub(1);
+                if in_degree[after] == 0 && !emitted[after] {
+                    ready.push(Reverse((self.priorities[after], after)));
+                }
+            }
+        }
+
+        TopologicalOrder {
+            order,
+            cycle_breaks,
+        }
+    }
+
+    // Whether `node` can reach itself through nodes not emitted yet
+    fn on_cycle(&self, node: usize, emitted: &[bool]) -> bool {
+        let mut visited = vec![false; self.len()];
+        let mut stack = vec![node];
+        while let Some(current) = stack.pop() {
+            for &after in &self.dependents[current] {
+                if after == node {
+                    return true;
+                }
+                if !emitted[after] && !visited[after] {
+                    visited[after] = true;
+                    stack.push(after);
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(priorities: &[usize], edges: &[(usize, usize)]) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for &priority in priorities {
+            graph.add_node(priority);
+        }
+        for &(before, after) in edges {
+            graph.add_edge(before, after);
+        }
+        graph
+    }
+
+    #[test]
+    fn empty_graph_sorts_to_nothing() {
+        let sorted = DependencyGraph::new().sort();
+        assert!(sorted.order.is_empty());
+        assert!(sorted.cycle_breaks.is_empty());
+    }
+
+    #[test]
+    fn unconstrained_nodes_follow_priority_then_index() {
+        let sorted = graph(&[2, 0, 1, 0], &[]).sort();
+        assert_eq!(sorted.order, vec![1, 3, 2, 0]);
+        assert!(sorted.cycle_breaks.is_empty());
+    }
+
+    #[test]
+    fn edges_override_priority() {
+        // 0 would come first by priority but depends on 2, which depends on 1
+        let sorted = graph(&[0, 5, 3], &[(2, 0), (1, 2), (1, 2), (0, 0)]).sort();
+        assert_eq!(sorted.order, vec![1, 2, 0]);
+        assert!(sorted.cycle_breaks.is_empty());
+    }
+
+    #[test]
+    fn cycles_are_broken_at_the_lowest_priority_node() {
+        // 1 -> 2 -> 3 -> 1, and 0 waits on the cycle
+        let sorted = graph(&[0, 4, 2, 3], &[(1, 2), (2, 3), (3, 1), (3, 0)]).sort();
+        assert_eq!(sorted.order, vec![2, 3, 0, 1]);
+        assert_eq!(sorted.cycle_breaks, vec![2]);
+    }
+}
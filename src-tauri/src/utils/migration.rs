@@ -0,0 +1,2963 @@
+use crate::constants::{SQL_NULLABLE_YES, TYPE_KIND_DOMAIN, TYPE_KIND_ENUM};
+use crate::models::{
+    CheckConstraintInfo, EnhancedColumnInfo, ForeignKeyInfo, IndexInfo, IndexKeyColumn, RoutineInfo, TypeInfo,
+};
+use crate::utils::dependency_graph::DependencyGraph;
+use crate::utils::renames::apply_accepted_renames;
+use crate::utils::selection::{apply_selection, MigrationSelection};
+use crate::utils::schema_diff::{
+    ColumnChange, DiffStatus, RoutineChange, SchemaComparison, TableDifference, TypeChange, ViewChange,
+};
+use crate::utils::sql::{qualified_name, quote_ident, quote_literal};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Kind of operation a migration step performs. The declaration order is the
+/// default phase order used when dependencies don't force anything else:
+/// extensions first, then drops (dependents before dependencies), then creates.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    CreateExtension,
+    UpdateExtension,
+    DropView,
+    DropRoutine,
+    DropForeignKey,
+    DropCheckConstraint,
+    DropIndex,
+    DropTable,
+    DropColumn,
+    DropType,
+    RenameTable,
+    RenameColumn,
+    CreateType,
+    AlterType,
+    CreateTable,
+    AddColumn,
+    AlterColumn,
+    CreateIndex,
+    AddForeignKey,
+    AddCheckConstraint,
+    ValidateConstraint,
+    SetNotNull,
+    CreateRoutine,
+    CreateView,
+    Comment,
+    DropExtension,
+}
+
+impl StepKind {
+    fn section_title(&self) -> &'static str {
+        match self {
+            StepKind::CreateExtension | StepKind::UpdateExtension => "EXTENSIONS",
+            StepKind::DropView => "DROPPED VIEWS",
+            StepKind::DropRoutine => "DROPPED FUNCTIONS/PROCEDURES",
+            StepKind::DropForeignKey => "DROPPED FOREIGN KEYS",
+            StepKind::DropCheckConstraint => "DROPPED CHECK CONSTRAINTS",
+            StepKind::DropIndex => "DROPPED INDEXES",
+            StepKind::DropTable => "DROPPED TABLES",
+            StepKind::DropColumn => "DROPPED COLUMNS",
+            StepKind::DropType => "DROPPED TYPES",
+            StepKind::RenameTable | StepKind::RenameColumn => "RENAMES",
+            StepKind::CreateType | StepKind::AlterType => "TYPES",
+            StepKind::CreateTable => "NEW TABLES",
+            StepKind::AddColumn => "NEW COLUMNS",
+            StepKind::AlterColumn => "MODIFIED COLUMNS",
+            StepKind::CreateIndex => "NEW INDEXES",
+            StepKind::AddForeignKey => "NEW FOREIGN KEYS",
+            StepKind::AddCheckConstraint => "NEW CHECK CONSTRAINTS",
+            StepKind::ValidateConstraint => "CONSTRAINT VALIDATION",
+            StepKind::SetNotNull => "NOT NULL CONSTRAINTS",
+            StepKind::CreateRoutine => "FUNCTIONS/PROCEDURES",
+            StepKind::CreateView => "VIEWS",
+            StepKind::Comment => "COMMENTS",
+            StepKind::DropExtension => "DROPPED EXTENSIONS",
+        }
+    }
+}
+
+/// A single operation in a migration, e.g. creating one table
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationStep {
+    pub kind: StepKind,
+    pub object_name: String, // "users", "users.email", "users.users_org_fk", ...
+    pub description: String,
+    pub statements: Vec<String>, // without trailing semicolons
+    pub warning: Option<String>,
+    /// Must run outside a transaction block (e.g. CREATE INDEX CONCURRENTLY)
+    #[serde(default)]
+    pub outside_transaction: bool,
+}
+
+/// Migration steps in execution order
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationPlan {
+    pub steps: Vec<MigrationStep>,
+    pub notes: Vec<String>,
+}
+
+/// How the migration script should run against a live database
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct MigrationScriptOptions {
+    /// Wrap the script in BEGIN/COMMIT, leaving out steps that can't run in a transaction
+    pub transactional: bool,
+    /// e.g. "5s"; how long a statement may wait for a lock before giving up
+    pub lock_timeout: Option<String>,
+    /// e.g. "15min"; how long a single statement may run
+    pub statement_timeout: Option<String>,
+    /// Build and drop indexes on existing tables with CONCURRENTLY
+    pub concurrent_indexes: bool,
+    /// Add foreign keys and check constraints on existing tables as NOT VALID,
+    /// then validate them separately. On PostgreSQL 12+ targets, SET NOT NULL
+    /// is preceded by a validated IS NOT NULL check so it doesn't scan the table.
+    pub not_valid_constraints: bool,
+    /// Migrate only these differences; None migrates all of them
+    pub selection: Option<MigrationSelection>,
+}
+
+/// Generate PostgreSQL migration script from schema comparison
+pub fn generate_migration_script(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+) -> String {
+    let (comparison, notes) = resolve_changes(comparison, options);
+    let plan = plan_resolved_changes(&comparison, options, notes);
+    render_script(&comparison, &plan, options, "Schema Migration Script")
+}
+
+/// Generate the script that undoes `generate_migration_script`, taking the
+/// target back to the state it was in before the migration ran
+pub fn generate_rollback_script(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+) -> String {
+    let (comparison, notes) = resolve_changes(comparison, options);
+    let plan = plan_resolved_rollback(&comparison, options, notes);
+    render_script(&comparison, &plan, options, "Schema Rollback Script")
+}
+
+// Migration steps that reverse the forward plan. Steps that can bring back
+// the structure but not the data (re-creating dropped tables and columns,
+// type conversions) are flagged as irreversible.
+fn plan_resolved_rollback(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+    notes: Vec<String>,
+) -> MigrationPlan {
+    let mut plan = plan_resolved_changes(&reverse_comparison(comparison), options, notes);
+
+    for step in &mut plan.steps {
+        let irreversible = match step.kind {
+            StepKind::CreateTable => Some("table was dropped by the migration, its rows are not restored"),
+            StepKind::AddColumn => Some("column was dropped by the migration, its values are not restored"),
+            StepKind::AlterColumn if step.statements.iter().any(|s| s.contains(" TYPE ")) => {
+                Some("values converted by the migration may not convert back exactly")
+            }
+            _ => None,
+        };
+        if let Some(reason) = irreversible {
+            step.warning = Some(format!("IRREVERSIBLE: {}", reason));
+        }
+    }
+
+    // Dropped objects without a known definition can't be recreated at all
+    for view_change in &comparison.view_differences {
+        if matches!(view_change.status, DiffStatus::Removed) && view_change.target_definition.is_none() {
+            plan.notes.push(format!(
+                "IRREVERSIBLE: view {} can't be recreated, its definition is unknown",
+                view_change.view_name
+            ));
+        }
+    }
+    for routine_change in &comparison.routine_differences {
+        let dropped = matches!(routine_change.status, DiffStatus::Removed)
+            || (matches!(routine_change.status, DiffStatus::Modified) && routine_change.definition_changed);
+        let definition = routine_change
+            .target_definition
+            .as_ref()
+            .and_then(|r| create_routine_statement(&comparison.schema_name, r));
+        if dropped && definition.is_none() {
+            plan.notes.push(format!(
+                "IRREVERSIBLE: {} can't be recreated, its definition is unknown",
+                routine_change.routine_name
+            ));
+        }
+    }
+
+    plan
+}
+
+// The comparison seen from the other side: what the target had becomes the
+// desired state, so planning it yields the inverse of the forward migration
+fn reverse_comparison(comparison: &SchemaComparison) -> SchemaComparison {
+    let mut reversed = comparison.clone();
+    std::mem::swap(&mut reversed.source_connection, &mut reversed.target_connection);
+
+    for table_diff in &mut reversed.table_differences {
+        table_diff.status = reverse_status(&table_diff.status);
+        if let Some(old_name) = table_diff.renamed_from.take() {
+            table_diff.renamed_from = Some(std::mem::replace(&mut table_diff.table_name, old_name));
+        }
+        std::mem::swap(&mut table_diff.source_comment, &mut table_diff.target_comment);
+
+        for col_change in &mut table_diff.column_changes {
+            col_change.status = reverse_status(&col_change.status);
+            std::mem::swap(&mut col_change.source_definition, &mut col_change.target_definition);
+            // A renamed column is known by its name in the desired state
+            if let Some(source_def) = &col_change.source_definition {
+                col_change.column_name = source_def.column_name.clone();
+            }
+            for change in &mut col_change.changes {
+                *change = reverse_change_detail(change);
+            }
+        }
+        for idx_change in &mut table_diff.index_changes {
+            idx_change.status = reverse_status(&idx_change.status);
+            std::mem::swap(&mut idx_change.source_definition, &mut idx_change.target_definition);
+        }
+        for fk_change in &mut table_diff.fk_changes {
+            fk_change.status = reverse_status(&fk_change.status);
+            std::mem::swap(&mut fk_change.source_definition, &mut fk_change.target_definition);
+        }
+        for check_change in &mut table_diff.check_changes {
+            check_change.status = reverse_status(&check_change.status);
+            std::mem::swap(&mut check_change.source_definition, &mut check_change.target_definition);
+        }
+    }
+
+    for view_change in &mut reversed.view_differences {
+        view_change.status = reverse_status(&view_change.status);
+        std::mem::swap(&mut view_change.source_definition, &mut view_change.target_definition);
+        std::mem::swap(&mut view_change.source_comment, &mut view_change.target_comment);
+        std::mem::swap(&mut view_change.source_depends_on, &mut view_change.target_depends_on);
+        std::mem::swap(
+            &mut view_change.source_column_dependencies,
+            &mut view_change.target_column_dependencies,
+        );
+    }
+
+    for routine_change in &mut reversed.routine_differences {
+        routine_change.status = reverse_status(&routine_change.status);
+        std::mem::swap(&mut routine_change.source_definition, &mut routine_change.target_definition);
+    }
+
+    for extension_change in &mut reversed.extension_differences {
+        extension_change.status = reverse_status(&extension_change.status);
+        std::mem::swap(&mut extension_change.source_definition, &mut extension_change.target_definition);
+    }
+
+    for type_change in &mut reversed.type_differences {
+        type_change.status = reverse_status(&type_change.status);
+        std::mem::swap(&mut type_change.source_definition, &mut type_change.target_definition);
+    }
+
+    std::mem::swap(&mut reversed.summary.tables_added, &mut reversed.summary.tables_removed);
+
+    reversed
+}
+
+fn reverse_status(status: &DiffStatus) -> DiffStatus {
+    match status {
+        DiffStatus::Added => DiffStatus::Removed,
+        DiffStatus::Removed => DiffStatus::Added,
+        other => other.clone(),
+    }
+}
+
+// "type: integer → bigint" becomes "type: bigint → integer"
+fn reverse_change_detail(change: &str) -> String {
+    match change.split_once(": ") {
+        Some((prefix, values)) => match values.split_once(" → ") {
+            Some((from, to)) => format!("{}: {} → {}", prefix, to, from),
+            None => change.to_string(),
+        },
+        None => change.to_string(),
+    }
+}
+
+fn render_script(
+    comparison: &SchemaComparison,
+    plan: &MigrationPlan,
+    options: &MigrationScriptOptions,
+    title: &str,
+) -> String {
+    let mut script = String::new();
+
+    // Header. No timestamp: saved scripts are committed and checksummed, so
+    // the same comparison has to render the same file
+    script.push_str(&format!(
+        "-- ============================================\n\
+         -- {}\n\
+         -- Source: {}\n\
+         -- Target: {}\n\
+         -- ============================================\n\n",
+        title, comparison.source_connection, comparison.target_connection
+    ));
+
+    script.push_str(
+        "-- WARNING: This script will make changes to the target database!\n\
+         -- Review carefully before executing.\n\n",
+    );
+
+    for note in &plan.notes {
+        script.push_str(&format!("-- NOTE: {}\n", note));
+    }
+    if !plan.notes.is_empty() {
+        script.push('\n');
+    }
+
+    // Session settings come first so they also cover steps outside the transaction
+    if let Some(timeout) = &options.lock_timeout {
+        script.push_str(&format!("SET lock_timeout = {};\n", quote_literal(timeout)));
+    }
+    if let Some(timeout) = &options.statement_timeout {
+        script.push_str(&format!("SET statement_timeout = {};\n", quote_literal(timeout)));
+    }
+    if options.lock_timeout.is_some() || options.statement_timeout.is_some() {
+        script.push('\n');
+    }
+
+    let mut in_transaction = false;
+    let mut current_section = None;
+    for step in &plan.steps {
+        if options.transactional && in_transaction == step.outside_transaction {
+            if in_transaction {
+                script.push_str("COMMIT;\n\n");
+            } else {
+                script.push_str("BEGIN;\n\n");
+            }
+            in_transaction = !in_transaction;
+        }
+
+        let section = step.kind.section_title();
+        if current_section != Some(section) {
+            script.push_str(&format!(
+                "-- ============================================\n\
+                 -- {}\n\
+                 -- ============================================\n\n",
+                section
+            ));
+            current_section = Some(section);
+        }
+        script.push_str(&render_step(step));
+        script.push('\n');
+    }
+
+    if in_transaction {
+        script.push_str("COMMIT;\n\n");
+    }
+
+    // Footer
+    script.push_str("-- ============================================\n");
+    script.push_str("-- END OF MIGRATION SCRIPT\n");
+
+    if plan.steps.is_empty() {
+        script.push_str("-- No changes detected.\n");
+    } else {
+        let total_changes = comparison.summary.tables_added
+            + comparison.summary.tables_removed
+            + comparison.summary.tables_modified
+            + comparison.summary.views_changed
+            + comparison.summary.routines_changed
+            + comparison.summary.extensions_changed
+            + comparison.summary.types_changed;
+
+        script.push_str(&format!("-- Total affected objects: {}\n", total_changes));
+
+        if !comparison.warnings.is_empty() {
+            script.push_str(&format!("-- Warnings: {}\n", comparison.warnings.len()));
+        }
+    }
+
+    script.push_str("-- ============================================\n");
+
+    script
+}
+
+// Render one step as commented SQL
+fn render_step(step: &MigrationStep) -> String {
+    let mut sql = format!("-- {}\n", step.description);
+    if let Some(warning) = &step.warning {
+        sql.push_str(&format!("-- WARNING: {}\n", warning));
+    }
+    for statement in &step.statements {
+        sql.push_str(statement);
+        sql.push_str(";\n");
+    }
+    sql
+}
+
+/// Turn a comparison into dependency-ordered migration steps
+pub fn build_migration_plan(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+) -> MigrationPlan {
+    let (comparison, notes) = resolve_changes(comparison, options);
+    plan_resolved_changes(&comparison, options, notes)
+}
+
+// Accepted renames and the object selection are applied before planning.
+// Returns the notes for objects the selection had to pull in.
+fn resolve_changes(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+) -> (SchemaComparison, Vec<String>) {
+    let comparison = apply_accepted_renames(comparison);
+    match &options.selection {
+        Some(selection) => apply_selection(&comparison, selection),
+        None => (comparison, Vec::new()),
+    }
+}
+
+fn plan_resolved_changes(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+    notes: Vec<String>,
+) -> MigrationPlan {
+    let mut builder = PlanBuilder {
+        options: options.clone(),
+        server_version: comparison.target_server_version,
+        ..Default::default()
+    };
+
+    let schema = comparison.schema_name.as_str();
+
+    add_extension_steps(&mut builder, comparison);
+    for type_change in &comparison.type_differences {
+        add_type_steps(&mut builder, schema, type_change);
+    }
+    for table_diff in &comparison.table_differences {
+        add_table_steps(&mut builder, schema, table_diff);
+    }
+    add_view_steps(&mut builder, schema, comparison);
+    for routine_change in &comparison.routine_differences {
+        add_routine_steps(&mut builder, schema, routine_change);
+    }
+
+    let mut plan = builder.into_plan(comparison);
+    plan.notes.splice(0..0, notes);
+    plan
+}
+
+#[derive(Default)]
+struct PlanBuilder {
+    options: MigrationScriptOptions,
+    server_version: Option<i32>, // of the target; None when unknown
+    steps: Vec<MigrationStep>,
+    lookup: HashMap<(StepKind, String), usize>,
+    notes: Vec<String>, // changes that can't be planned as steps
+}
+
+impl PlanBuilder {
+    fn push(
+        &mut self,
+        kind: StepKind,
+        object_name: String,
+        description: String,
+        statements: Vec<String>,
+        warning: Option<String>,
+    ) {
+        self.lookup.insert((kind, object_name.clone()), self.steps.len());
+        self.steps.push(MigrationStep {
+            kind,
+            object_name,
+            description,
+            statements,
+            warning,
+            outside_transaction: false,
+        });
+    }
+
+    // Flag the step that was pushed last as unable to run inside a transaction
+    fn mark_outside_transaction(&mut self) {
+        if let Some(step) = self.steps.last_mut() {
+            step.outside_transaction = true;
+        }
+    }
+
+    fn find(&self, kind: StepKind, object_name: &str) -> Option<usize> {
+        self.lookup.get(&(kind, object_name.to_string())).copied()
+    }
+
+    // Steps of the given kind whose object is "<table>.<something>"
+    fn find_in_table(&self, kind: StepKind, table_name: &str) -> Vec<usize> {
+        let prefix = format!("{}.", table_name);
+        self.steps
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.kind == kind && s.object_name.starts_with(&prefix))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn into_plan(self, comparison: &SchemaComparison) -> MigrationPlan {
+        let mut graph = DependencyGraph::new();
+        for step in &self.steps {
+            graph.add_node(step.kind as usize);
+        }
+
+        let mut edge = |before: Option<usize>, after: Option<usize>| {
+            if let (Some(before), Some(after)) = (before, after) {
+                graph.add_edge(before, after);
+            }
+        };
+
+        for table_diff in &comparison.table_differences {
+            let table = &table_diff.table_name;
+
+            // Everything else on a renamed table refers to it by its new name
+            if let Some(old_name) = &table_diff.renamed_from {
+                let rename_table = self.find(StepKind::RenameTable, table);
+                let prefix = format!("{}.", table);
+                for (i, step) in self.steps.iter().enumerate() {
+                    if step.kind != StepKind::RenameTable && step.object_name.starts_with(&prefix) {
+                        edge(rename_table, Some(i));
+                    }
+                }
+                edge(rename_table, self.find(StepKind::CreateTable, old_name));
+            }
+
+            for col_change in &table_diff.column_changes {
+                let column_object = format!("{}.{}", table, col_change.column_name);
+                edge(
+                    self.find(StepKind::RenameColumn, &column_object),
+                    self.find(StepKind::AlterColumn, &column_object),
+                );
+            }
+
+            for fk_change in &table_diff.fk_changes {
+                let fk_object = format!("{}.{}", table, fk_change.constraint_name);
+
+                // New foreign keys need both tables and both columns in place
+                if let Some(fk) = &fk_change.source_definition {
+                    let add_fk = self.find(StepKind::AddForeignKey, &fk_object);
+                    let referenced_column =
+                        format!("{}.{}", fk.foreign_table_name, fk.foreign_column_name);
+                    let local_column = format!("{}.{}", table, fk.column_name);
+
+                    edge(self.find(StepKind::CreateTable, table), add_fk);
+                    edge(self.find(StepKind::CreateTable, &fk.foreign_table_name), add_fk);
+                    edge(self.find(StepKind::RenameTable, &fk.foreign_table_name), add_fk);
+                    edge(self.find(StepKind::RenameColumn, &local_column), add_fk);
+                    edge(self.find(StepKind::RenameColumn, &referenced_column), add_fk);
+                    edge(self.find(StepKind::AddColumn, &local_column), add_fk);
+                    edge(self.find(StepKind::AlterColumn, &local_column), add_fk);
+                    edge(self.find(StepKind::AddColumn, &referenced_column), add_fk);
+                    edge(self.find(StepKind::AlterColumn, &referenced_column), add_fk);
+                    edge(self.find(StepKind::DropForeignKey, &fk_object), add_fk);
+                    edge(add_fk, self.find(StepKind::ValidateConstraint, &fk_object));
+                    for create_index in self.find_in_table(StepKind::CreateIndex, &fk.foreign_table_name) {
+                        edge(Some(create_index), add_fk);
+                    }
+                }
+
+                // Foreign keys must go before the tables they reference are dropped,
+                // and referencing tables are dropped before referenced ones
+                if let Some(fk) = &fk_change.target_definition {
+                    let drop_referenced = self.find(StepKind::DropTable, &fk.foreign_table_name);
+                    edge(self.find(StepKind::DropForeignKey, &fk_object), drop_referenced);
+                    if matches!(table_diff.status, DiffStatus::Removed) {
+                        edge(self.find(StepKind::DropTable, table), drop_referenced);
+                    }
+                }
+            }
+
+            for check_change in &table_diff.check_changes {
+                let check_object = format!("{}.{}", table, check_change.constraint_name);
+
+                // New checks go after the columns they read
+                if let Some(check) = &check_change.source_definition {
+                    let add_check = self.find(StepKind::AddCheckConstraint, &check_object);
+                    edge(self.find(StepKind::DropCheckConstraint, &check_object), add_check);
+                    edge(add_check, self.find(StepKind::ValidateConstraint, &check_object));
+                    for column in &check.columns {
+                        let column_object = format!("{}.{}", table, column);
+                        for column_step in [StepKind::RenameColumn, StepKind::AddColumn, StepKind::AlterColumn] {
+                            edge(self.find(column_step, &column_object), add_check);
+                        }
+                    }
+                }
+
+                // Old checks go before the columns they read are changed or dropped
+                if let Some(check) = &check_change.target_definition {
+                    let drop_check = self.find(StepKind::DropCheckConstraint, &check_object);
+                    for column in &check.columns {
+                        let column_object = format!("{}.{}", table, column);
+                        for column_step in [StepKind::DropColumn, StepKind::AlterColumn] {
+                            edge(drop_check, self.find(column_step, &column_object));
+                        }
+                    }
+                }
+            }
+
+            // SET NOT NULL relies on its helper check having been validated
+            for col_change in &table_diff.column_changes {
+                let column_object = format!("{}.{}", table, col_change.column_name);
+                let validate = self.find(
+                    StepKind::ValidateConstraint,
+                    &format!("{}.{}", table, not_null_check_name(table, &col_change.column_name)),
+                );
+                edge(self.find(StepKind::AlterColumn, &column_object), validate);
+                edge(validate, self.find(StepKind::SetNotNull, &column_object));
+            }
+
+            // Recreated indexes and columns come after their drops
+            for idx_change in &table_diff.index_changes {
+                let idx_object = format!("{}.{}", table, idx_change.index_name);
+                edge(
+                    self.find(StepKind::DropIndex, &idx_object),
+                    self.find(StepKind::CreateIndex, &idx_object),
+                );
+                for column_step in [StepKind::AddColumn, StepKind::AlterColumn] {
+                    for column in self.find_in_table(column_step, table) {
+                        edge(Some(column), self.find(StepKind::CreateIndex, &idx_object));
+                    }
+                }
+                edge(
+                    self.find(StepKind::CreateTable, table),
+                    self.find(StepKind::CreateIndex, &idx_object),
+                );
+            }
+        }
+
+        // Types are created before the columns and types built on them, and
+        // dropped after them
+        let create_type = |name: &str| {
+            self.find(StepKind::CreateType, name)
+                .or_else(|| self.find(StepKind::AlterType, name))
+        };
+        for type_change in &comparison.type_differences {
+            let name = &type_change.type_name;
+            if let Some(source_type) = &type_change.source_definition {
+                for dependency in &source_type.depends_on {
+                    edge(create_type(dependency), create_type(name));
+                }
+            }
+            if let Some(target_type) = &type_change.target_definition {
+                for dependency in &target_type.depends_on {
+                    edge(
+                        self.find(StepKind::DropType, name),
+                        self.find(StepKind::DropType, dependency),
+                    );
+                }
+            }
+        }
+        for table_diff in &comparison.table_differences {
+            let table = &table_diff.table_name;
+            for col_change in &table_diff.column_changes {
+                let column_object = format!("{}.{}", table, col_change.column_name);
+                if let Some(type_name) = col_change.source_definition.as_ref().and_then(column_type_name) {
+                    let create_type = create_type(type_name);
+                    edge(create_type, self.find(StepKind::CreateTable, table));
+                    edge(create_type, self.find(StepKind::AddColumn, &column_object));
+                    edge(create_type, self.find(StepKind::AlterColumn, &column_object));
+                }
+                if let Some(type_name) = col_change.target_definition.as_ref().and_then(column_type_name) {
+                    let drop_type = self.find(StepKind::DropType, type_name);
+                    edge(self.find(StepKind::DropTable, table), drop_type);
+                    edge(self.find(StepKind::DropColumn, &column_object), drop_type);
+                    edge(self.find(StepKind::AlterColumn, &column_object), drop_type);
+                }
+            }
+        }
+
+        for view_change in &comparison.view_differences {
+            let view = &view_change.view_name;
+            let drop_view = self.find(StepKind::DropView, view);
+            let create_view = self.find(StepKind::CreateView, view);
+
+            edge(drop_view, create_view);
+
+            // Dropping: a view goes before anything it reads from or calls
+            for dependency in &view_change.target_depends_on {
+                edge(drop_view, self.find(StepKind::DropView, dependency));
+                edge(drop_view, self.find(StepKind::DropRoutine, dependency));
+                edge(drop_view, self.find(StepKind::DropTable, dependency));
+                for column_step in [StepKind::DropColumn, StepKind::AlterColumn] {
+                    for column in self.find_in_table(column_step, dependency) {
+                        edge(drop_view, Some(column));
+                    }
+                }
+            }
+
+            // Creating: a view goes after everything it reads from or calls
+            for dependency in &view_change.source_depends_on {
+                edge(self.find(StepKind::CreateView, dependency), create_view);
+                edge(self.find(StepKind::CreateRoutine, dependency), create_view);
+                edge(self.find(StepKind::CreateTable, dependency), create_view);
+                for column_step in [StepKind::AddColumn, StepKind::AlterColumn] {
+                    for column in self.find_in_table(column_step, dependency) {
+                        edge(Some(column), create_view);
+                    }
+                }
+            }
+        }
+
+        for routine_change in &comparison.routine_differences {
+            let routine = &routine_change.routine_name;
+            edge(
+                self.find(StepKind::DropRoutine, routine),
+                self.find(StepKind::CreateRoutine, routine),
+            );
+        }
+
+        let sorted = graph.sort();
+        let mut notes = self.notes;
+        notes.extend(sorted.cycle_breaks.iter().map(|&i| {
+            format!(
+                "Circular dependency involving '{}'; ordering was chosen by object type",
+                self.steps[i].object_name
+            )
+        }));
+
+        let mut steps: Vec<Option<MigrationStep>> = self.steps.into_iter().map(Some).collect();
+        let ordered = sorted
+            .order
+            .into_iter()
+            .filter_map(|i| steps[i].take())
+            .collect();
+
+        MigrationPlan {
+            steps: ordered,
+            notes,
+        }
+    }
+}
+
+fn add_extension_steps(builder: &mut PlanBuilder, comparison: &SchemaComparison) {
+    for extension_change in &comparison.extension_differences {
+        let name = &extension_change.extension_name;
+        match extension_change.status {
+            DiffStatus::Added => {
+                if let Some(source_ext) = &extension_change.source_definition {
+                    builder.push(
+                        StepKind::CreateExtension,
+                        name.clone(),
+                        format!("Create extension: {}", name),
+                        vec![format!(
+                            "CREATE EXTENSION IF NOT EXISTS {} WITH SCHEMA {} VERSION {}",
+                            quote_ident(name),
+                            quote_ident(&source_ext.schema_name),
+                            quote_literal(&source_ext.version)
+                        )],
+                        None,
+                    );
+                }
+            }
+            DiffStatus::Modified => {
+                if let Some(source_ext) = &extension_change.source_definition {
+                    builder.push(
+                        StepKind::UpdateExtension,
+                        name.clone(),
+                        format!("Update extension: {}", name),
+                        vec![format!(
+                            "ALTER EXTENSION {} UPDATE TO {}",
+                            quote_ident(name),
+                            quote_literal(&source_ext.version)
+                        )],
+                        None,
+                    );
+                }
+            }
+            DiffStatus::Removed => {
+                builder.push(
+                    StepKind::DropExtension,
+                    name.clone(),
+                    format!("Drop extension: {}", name),
+                    vec![format!("DROP EXTENSION IF EXISTS {}", quote_ident(name))],
+                    Some("Objects depending on this extension will fail to drop!".to_string()),
+                );
+            }
+            DiffStatus::Identical => {}
+        }
+    }
+}
+
+fn add_type_steps(builder: &mut PlanBuilder, schema: &str, type_change: &TypeChange) {
+    let name = &type_change.type_name;
+    let qualified_type = qualified_name(schema, name);
+
+    match type_change.status {
+        DiffStatus::Added => {
+            if let Some(source_type) = &type_change.source_definition {
+                builder.push(
+                    StepKind::CreateType,
+                    name.clone(),
+                    format!("Create type: {}", name),
+                    vec![format!(
+                        "CREATE {} {} {}",
+                        type_keyword(source_type),
+                        qualified_type,
+                        source_type.definition
+                    )],
+                    None,
+                );
+            }
+        }
+        DiffStatus::Removed => {
+            if let Some(target_type) = &type_change.target_definition {
+                builder.push(
+                    StepKind::DropType,
+                    name.clone(),
+                    format!("Drop type: {}", name),
+                    vec![format!("DROP {} IF EXISTS {}", type_keyword(target_type), qualified_type)],
+                    None,
+                );
+            }
+        }
+        DiffStatus::Modified => {
+            let (Some(source_type), Some(target_type)) =
+                (&type_change.source_definition, &type_change.target_definition)
+            else {
+                return;
+            };
+            match enum_label_additions(&qualified_type, source_type, target_type) {
+                // A new label can't be used in the transaction that adds it
+                Some(statements) => {
+                    builder.push(
+                        StepKind::AlterType,
+                        name.clone(),
+                        format!("Add enum values: {}", name),
+                        statements,
+                        None,
+                    );
+                    builder.mark_outside_transaction();
+                }
+                None => builder.notes.push(format!(
+                    "Type {} changed in a way that can't be migrated in place; columns using it have to be converted by hand",
+                    name
+                )),
+            }
+        }
+        DiffStatus::Identical => {}
+    }
+}
+
+// ADD VALUE statements that turn the target's enum into the source's. None
+// unless the change only adds labels, the one change ALTER TYPE makes in place.
+fn enum_label_additions(qualified_type: &str, source: &TypeInfo, target: &TypeInfo) -> Option<Vec<String>> {
+    if source.kind != TYPE_KIND_ENUM || target.kind != TYPE_KIND_ENUM {
+        return None;
+    }
+    let kept: Vec<&String> = source
+        .enum_labels
+        .iter()
+        .filter(|label| target.enum_labels.contains(label))
+        .collect();
+    if !kept.iter().copied().eq(target.enum_labels.iter()) {
+        return None;
+    }
+
+    let mut statements = Vec::new();
+    for (i, label) in source.enum_labels.iter().enumerate() {
+        if target.enum_labels.contains(label) {
+            continue;
+        }
+        // Labels are added in order, so the one before is always there by now
+        let position = match i.checked_sub(1) {
+            Some(previous) => format!(" AFTER {}", quote_literal(&source.enum_labels[previous])),
+            None => target
+                .enum_labels
+                .first()
+                .map(|first| format!(" BEFORE {}", quote_literal(first)))
+                .unwrap_or_default(),
+        };
+        statements.push(format!(
+            "ALTER TYPE {} ADD VALUE IF NOT EXISTS {}{}",
+            qualified_type,
+            quote_literal(label),
+            position
+        ));
+    }
+    Some(statements)
+}
+
+fn type_keyword(type_info: &TypeInfo) -> &'static str {
+    if type_info.kind == TYPE_KIND_DOMAIN {
+        "DOMAIN"
+    } else {
+        "TYPE"
+    }
+}
+
+fn add_table_steps(builder: &mut PlanBuilder, schema: &str, table_diff: &TableDifference) {
+    let table = &table_diff.table_name;
+    let qualified_table = qualified_name(schema, table);
+
+    match table_diff.status {
+        DiffStatus::Added => {
+            builder.push(
+                StepKind::CreateTable,
+                table.clone(),
+                format!("Create table: {}", table),
+                create_table_statements(schema, table_diff),
+                None,
+            );
+        }
+        DiffStatus::Removed => {
+            builder.push(
+                StepKind::DropTable,
+                table.clone(),
+                format!("Drop table: {}", table),
+                vec![format!("DROP TABLE IF EXISTS {}", qualified_table)],
+                Some("Dropping table will cause data loss!".to_string()),
+            );
+            return;
+        }
+        DiffStatus::Modified => {
+            if let Some(old_name) = &table_diff.renamed_from {
+                builder.push(
+                    StepKind::RenameTable,
+                    table.clone(),
+                    format!("Rename table: {} → {}", old_name, table),
+                    vec![format!(
+                        "ALTER TABLE {} RENAME TO {}",
+                        qualified_name(schema, old_name),
+                        quote_ident(table)
+                    )],
+                    None,
+                );
+            }
+            if table_diff.source_comment != table_diff.target_comment {
+                builder.push(
+                    StepKind::Comment,
+                    table.clone(),
+                    format!("Comment on table: {}", table),
+                    vec![format!(
+                        "COMMENT ON TABLE {} IS {}",
+                        qualified_table,
+                        comment_literal(table_diff.source_comment.as_deref())
+                    )],
+                    None,
+                );
+            }
+            for col_change in &table_diff.column_changes {
+                add_column_steps(builder, schema, table, col_change);
+            }
+        }
+        DiffStatus::Identical => return,
+    }
+
+    // Index changes (for new tables every index is "added")
+    for idx_change in &table_diff.index_changes {
+        let idx_object = format!("{}.{}", table, idx_change.index_name);
+        let is_new_table = matches!(table_diff.status, DiffStatus::Added);
+        // A new table is empty, so only existing tables benefit from CONCURRENTLY
+        let concurrent_indexes = builder.options.concurrent_indexes;
+        let concurrently = |idx: &IndexInfo| concurrent_indexes && !is_new_table && !idx.is_primary;
+
+        if matches!(idx_change.status, DiffStatus::Removed | DiffStatus::Modified) {
+            if let Some(idx) = &idx_change.target_definition {
+                let concurrent = concurrently(idx);
+                builder.push(
+                    StepKind::DropIndex,
+                    idx_object.clone(),
+                    format!("Drop index: {}", idx_change.index_name),
+                    vec![drop_index_statement(schema, table, idx, concurrent)],
+                    None,
+                );
+                if concurrent {
+                    builder.mark_outside_transaction();
+                }
+            }
+        }
+
+        if matches!(idx_change.status, DiffStatus::Added | DiffStatus::Modified) {
+            if let Some(idx) = &idx_change.source_definition {
+                // The primary key of a new table is part of CREATE TABLE
+                if is_new_table && idx.is_primary {
+                    continue;
+                }
+                let concurrent = concurrently(idx);
+                let warning = concurrent.then(|| {
+                    "If this fails it leaves an invalid index behind that must be dropped before retrying"
+                        .to_string()
+                });
+                builder.push(
+                    StepKind::CreateIndex,
+                    idx_object.clone(),
+                    format!("Create index: {}", idx_change.index_name),
+                    vec![create_index_statement(schema, table, idx, concurrent)],
+                    warning,
+                );
+                if concurrent {
+                    builder.mark_outside_transaction();
+                }
+            }
+        }
+    }
+
+    // Foreign key changes
+    for fk_change in &table_diff.fk_changes {
+        let fk_object = format!("{}.{}", table, fk_change.constraint_name);
+
+        if matches!(fk_change.status, DiffStatus::Removed | DiffStatus::Modified) {
+            builder.push(
+                StepKind::DropForeignKey,
+                fk_object.clone(),
+                format!("Drop foreign key: {}", fk_change.constraint_name),
+                vec![format!(
+                    "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {}",
+                    qualified_table,
+                    quote_ident(&fk_change.constraint_name)
+                )],
+                None,
+            );
+        }
+
+        if matches!(fk_change.status, DiffStatus::Added | DiffStatus::Modified) {
+            if let Some(fk) = &fk_change.source_definition {
+                let mut statement = add_foreign_key_statement(schema, table, &fk_change.constraint_name, fk);
+                // NOT VALID skips the scan of existing rows while holding the lock;
+                // VALIDATE then checks them in its own transaction under a lock
+                // that doesn't block writes
+                let not_valid =
+                    builder.options.not_valid_constraints && !matches!(table_diff.status, DiffStatus::Added);
+                if not_valid {
+                    statement.push_str(" NOT VALID");
+                }
+                builder.push(
+                    StepKind::AddForeignKey,
+                    fk_object.clone(),
+                    format!("Add foreign key: {}", fk_change.constraint_name),
+                    vec![statement],
+                    None,
+                );
+                if not_valid {
+                    builder.push(
+                        StepKind::ValidateConstraint,
+                        fk_object,
+                        format!("Validate foreign key: {}", fk_change.constraint_name),
+                        vec![format!(
+                            "ALTER TABLE {} VALIDATE CONSTRAINT {}",
+                            qualified_table,
+                            quote_ident(&fk_change.constraint_name)
+                        )],
+                        None,
+                    );
+                    builder.mark_outside_transaction();
+                }
+            }
+        }
+    }
+
+    // Check constraints of new tables are part of CREATE TABLE
+    if matches!(table_diff.status, DiffStatus::Added) {
+        return;
+    }
+    for check_change in &table_diff.check_changes {
+        let check_object = format!("{}.{}", table, check_change.constraint_name);
+
+        if matches!(check_change.status, DiffStatus::Removed | DiffStatus::Modified) {
+            builder.push(
+                StepKind::DropCheckConstraint,
+                check_object.clone(),
+                format!("Drop check constraint: {}", check_change.constraint_name),
+                vec![format!(
+                    "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {}",
+                    qualified_table,
+                    quote_ident(&check_change.constraint_name)
+                )],
+                None,
+            );
+        }
+
+        if matches!(check_change.status, DiffStatus::Added | DiffStatus::Modified) {
+            if let Some(check) = &check_change.source_definition {
+                let not_valid = builder.options.not_valid_constraints;
+                let mut statement = format!(
+                    "ALTER TABLE {} ADD {}",
+                    qualified_table,
+                    check_constraint_clause(check)
+                );
+                if not_valid {
+                    statement.push_str(" NOT VALID");
+                }
+                builder.push(
+                    StepKind::AddCheckConstraint,
+                    check_object.clone(),
+                    format!("Add check constraint: {}", check_change.constraint_name),
+                    vec![statement],
+                    None,
+                );
+                if not_valid {
+                    builder.push(
+                        StepKind::ValidateConstraint,
+                        check_object,
+                        format!("Validate check constraint: {}", check_change.constraint_name),
+                        vec![format!(
+                            "ALTER TABLE {} VALIDATE CONSTRAINT {}",
+                            qualified_table,
+                            quote_ident(&check_change.constraint_name)
+                        )],
+                        None,
+                    );
+                    builder.mark_outside_transaction();
+                }
+            }
+        }
+    }
+}
+
+fn add_column_steps(builder: &mut PlanBuilder, schema: &str, table: &str, col_change: &ColumnChange) {
+    let column = &col_change.column_name;
+    let column_object = format!("{}.{}", table, column);
+    let qualified_table = qualified_name(schema, table);
+
+    match col_change.status {
+        DiffStatus::Added => {
+            if let Some(source_def) = &col_change.source_definition {
+                let mut statements = vec![format!(
+                    "ALTER TABLE {} ADD COLUMN {}",
+                    qualified_table,
+                    column_definition(source_def)
+                )];
+                if let Some(comment) = &source_def.comment {
+                    statements.push(format!(
+                        "COMMENT ON COLUMN {}.{} IS {}",
+                        qualified_table,
+                        quote_ident(column),
+                        comment_literal(Some(comment))
+                    ));
+                }
+                builder.push(
+                    StepKind::AddColumn,
+                    column_object.clone(),
+                    format!("Add column: {}", column_object),
+                    statements,
+                    None,
+                );
+            }
+        }
+        DiffStatus::Removed => {
+            builder.push(
+                StepKind::DropColumn,
+                column_object.clone(),
+                format!("Drop column: {}", column_object),
+                vec![format!(
+                    "ALTER TABLE {} DROP COLUMN {}",
+                    qualified_table,
+                    quote_ident(column)
+                )],
+                Some("Dropping column will cause data loss!".to_string()),
+            );
+        }
+        DiffStatus::Modified => {
+            let renamed_from = col_change
+                .target_definition
+                .as_ref()
+                .map(|c| &c.column_name)
+                .filter(|old_name| *old_name != column);
+            if let Some(old_name) = renamed_from {
+                builder.push(
+                    StepKind::RenameColumn,
+                    column_object.clone(),
+                    format!("Rename column: {}.{} → {}", table, old_name, column),
+                    vec![format!(
+                        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                        qualified_table,
+                        quote_ident(old_name),
+                        quote_ident(column)
+                    )],
+                    None,
+                );
+            }
+            if let Some(source_def) = &col_change.source_definition {
+                // PostgreSQL 12+ skips SET NOT NULL's full scan when a valid
+                // CHECK (col IS NOT NULL) already proves it, and that check
+                // can be validated without blocking writes
+                let sets_not_null = source_def.is_nullable != SQL_NULLABLE_YES
+                    && col_change.changes.iter().any(|c| c.starts_with("nullable:"));
+                let not_null_check = (sets_not_null
+                    && builder.options.not_valid_constraints
+                    && builder.server_version.is_some_and(|v| v >= 120000))
+                .then(|| not_null_check_name(table, column));
+
+                let statements =
+                    alter_column_statements(&qualified_table, col_change, source_def, not_null_check.as_deref());
+                if !statements.is_empty() {
+                    builder.push(
+                        StepKind::AlterColumn,
+                        column_object.clone(),
+                        format!("Modify column: {}", column_object),
+                        statements,
+                        None,
+                    );
+                }
+
+                if let Some(check_name) = not_null_check {
+                    builder.push(
+                        StepKind::ValidateConstraint,
+                        format!("{}.{}", table, check_name),
+                        format!("Validate NOT NULL check: {}", column_object),
+                        vec![format!(
+                            "ALTER TABLE {} VALIDATE CONSTRAINT {}",
+                            qualified_table,
+                            quote_ident(&check_name)
+                        )],
+                        None,
+                    );
+                    builder.mark_outside_transaction();
+                    builder.push(
+                        StepKind::SetNotNull,
+                        column_object.clone(),
+                        format!("Set NOT NULL: {}", column_object),
+                        vec![
+                            format!(
+                                "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL",
+                                qualified_table,
+                                quote_ident(column)
+                            ),
+                            format!(
+                                "ALTER TABLE {} DROP CONSTRAINT {}",
+                                qualified_table,
+                                quote_ident(&check_name)
+                            ),
+                        ],
+                        None,
+                    );
+                }
+            }
+        }
+        DiffStatus::Identical => {}
+    }
+}
+
+// With `not_null_check`, NOT NULL is added as that NOT VALID check instead of
+// SET NOT NULL, leaving validation and SET NOT NULL to later steps
+fn alter_column_statements(
+    qualified_table: &str,
+    col_change: &ColumnChange,
+    source_def: &EnhancedColumnInfo,
+    not_null_check: Option<&str>,
+) -> Vec<String> {
+    let column = quote_ident(&col_change.column_name);
+    let has_change = |prefix: &str| col_change.changes.iter().any(|c| c.starts_with(prefix));
+    let mut statements = Vec::new();
+
+    // Type, length and collation changes are a single ALTER COLUMN ... TYPE
+    if has_change("type:") || has_change("max_length:") || has_change("collation:") {
+        statements.push(format!(
+            "ALTER TABLE {} ALTER COLUMN {} TYPE {}{}",
+            qualified_table,
+            column,
+            column_type_sql(source_def),
+            collation_clause(source_def)
+        ));
+    }
+
+    if let (true, Some(check_name)) = (has_change("nullable:"), not_null_check) {
+        statements.push(format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({} IS NOT NULL) NOT VALID",
+            qualified_table,
+            quote_ident(check_name),
+            column
+        ));
+    } else if has_change("nullable:") {
+        let nullable_clause = if source_def.is_nullable == SQL_NULLABLE_YES {
+            "DROP NOT NULL"
+        } else {
+            "SET NOT NULL"
+        };
+        statements.push(format!(
+            "ALTER TABLE {} ALTER COLUMN {} {}",
+            qualified_table, column, nullable_clause
+        ));
+    }
+
+    if has_change("default:") {
+        if let Some(default_val) = &source_def.column_default {
+            statements.push(format!(
+                "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}",
+                qualified_table, column, default_val
+            ));
+        } else {
+            statements.push(format!(
+                "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT",
+                qualified_table, column
+            ));
+        }
+    }
+
+    if has_change("comment:") {
+        statements.push(format!(
+            "COMMENT ON COLUMN {}.{} IS {}",
+            qualified_table,
+            column,
+            comment_literal(source_def.comment.as_deref())
+        ));
+    }
+
+    statements
+}
+
+fn add_view_steps(builder: &mut PlanBuilder, schema: &str, comparison: &SchemaComparison) {
+    // Columns whose type changes or that get dropped; views reading them
+    // have to be dropped first and recreated afterwards
+    let mut altered_columns: HashSet<String> = HashSet::new();
+    let mut altered_tables: HashSet<String> = HashSet::new();
+    for table_diff in &comparison.table_differences {
+        if !matches!(table_diff.status, DiffStatus::Modified) {
+            continue;
+        }
+        for col_change in &table_diff.column_changes {
+            let rewrites = matches!(col_change.status, DiffStatus::Removed)
+                || col_change.changes.iter().any(|c| {
+                    c.starts_with("type:") || c.starts_with("max_length:") || c.starts_with("collation:")
+                });
+            if rewrites {
+                altered_columns.insert(format!("{}.{}", table_diff.table_name, col_change.column_name));
+                altered_tables.insert(table_diff.table_name.clone());
+            }
+        }
+    }
+
+    // Views can't outlive a routine they call
+    let dropped_routines: HashSet<String> = comparison
+        .routine_differences
+        .iter()
+        .filter(|r| routine_is_dropped(r))
+        .map(|r| r.routine_name.clone())
+        .collect();
+
+    let mut dropped_views: HashSet<String> = comparison
+        .view_differences
+        .iter()
+        .filter(|v| {
+            matches!(v.status, DiffStatus::Removed)
+                || (matches!(v.status, DiffStatus::Modified) && v.definition_changed)
+        })
+        .map(|v| v.view_name.clone())
+        .collect();
+
+    // Unchanged views that must be rebuilt, including views built on top of them
+    let mut rebuilt_views: HashSet<String> = HashSet::new();
+    loop {
+        let mut changed = false;
+        for view_change in &comparison.view_differences {
+            if !matches!(view_change.status, DiffStatus::Identical | DiffStatus::Modified)
+                || dropped_views.contains(&view_change.view_name)
+            {
+                continue;
+            }
+            let reads_dropped_routine = view_change
+                .target_depends_on
+                .iter()
+                .any(|d| dropped_routines.contains(d));
+            if reads_dropped_routine
+                || view_needs_rebuild(view_change, &altered_columns, &altered_tables, &dropped_views)
+            {
+                dropped_views.insert(view_change.view_name.clone());
+                rebuilt_views.insert(view_change.view_name.clone());
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for view_change in &comparison.view_differences {
+        let view = &view_change.view_name;
+        let qualified_view = qualified_name(schema, view);
+        let rebuilt = rebuilt_views.contains(view);
+
+        if dropped_views.contains(view) {
+            builder.push(
+                StepKind::DropView,
+                view.clone(),
+                format!("Drop view: {}", view),
+                vec![format!("DROP VIEW IF EXISTS {}", qualified_view)],
+                None,
+            );
+        }
+
+        let recreate = rebuilt
+            || matches!(view_change.status, DiffStatus::Added)
+            || (matches!(view_change.status, DiffStatus::Modified) && view_change.definition_changed);
+
+        if recreate {
+            if let Some(def) = &view_change.source_definition {
+                let mut statements = vec![format!(
+                    "CREATE VIEW {} AS\n{}",
+                    qualified_view,
+                    trim_statement(def)
+                )];
+                if let Some(comment) = &view_change.source_comment {
+                    statements.push(format!(
+                        "COMMENT ON VIEW {} IS {}",
+                        qualified_view,
+                        comment_literal(Some(comment))
+                    ));
+                }
+                let description = if rebuilt {
+                    format!("Recreate view: {} (depends on altered objects)", view)
+                } else {
+                    format!("Create view: {}", view)
+                };
+                builder.push(StepKind::CreateView, view.clone(), description, statements, None);
+            }
+        } else if matches!(view_change.status, DiffStatus::Modified) {
+            // Only the comment changed
+            builder.push(
+                StepKind::Comment,
+                format!("view:{}", view),
+                format!("Comment on view: {}", view),
+                vec![format!(
+                    "COMMENT ON VIEW {} IS {}",
+                    qualified_view,
+                    comment_literal(view_change.source_comment.as_deref())
+                )],
+                None,
+            );
+        }
+    }
+}
+
+fn view_needs_rebuild(
+    view_change: &ViewChange,
+    altered_columns: &HashSet<String>,
+    altered_tables: &HashSet<String>,
+    dropped_views: &HashSet<String>,
+) -> bool {
+    let reads_dropped_view = view_change
+        .target_depends_on
+        .iter()
+        .any(|d| dropped_views.contains(d));
+
+    let reads_altered_column = if view_change.target_column_dependencies.is_empty() {
+        view_change
+            .target_depends_on
+            .iter()
+            .any(|d| altered_tables.contains(d))
+    } else {
+        view_change
+            .target_column_dependencies
+            .iter()
+            .any(|c| altered_columns.contains(c))
+    };
+
+    reads_dropped_view || reads_altered_column
+}
+
+fn add_routine_steps(builder: &mut PlanBuilder, schema: &str, routine_change: &RoutineChange) {
+    let routine = &routine_change.routine_name;
+    let keyword = routine_object_keyword(routine_change);
+
+    let create = matches!(routine_change.status, DiffStatus::Added)
+        || (matches!(routine_change.status, DiffStatus::Modified) && routine_change.definition_changed);
+
+    // No CASCADE: views calling the routine are dropped and recreated around
+    // it by the plan, anything else should stop the migration
+    if routine_is_dropped(routine_change) {
+        if let Some(target_info) = &routine_change.target_definition {
+            builder.push(
+                StepKind::DropRoutine,
+                routine.clone(),
+                format!("Drop {}: {}", keyword.to_lowercase(), routine),
+                vec![format!("DROP {} IF EXISTS {}", keyword, routine_reference(schema, target_info))],
+                None,
+            );
+        }
+    }
+
+    if create {
+        let Some(routine_info) = &routine_change.source_definition else {
+            return;
+        };
+        let Some(create_statement) = create_routine_statement(schema, routine_info) else {
+            builder.notes.push(format!(
+                "{} can't be created: its definition has no CREATE statement (snapshot taken by an older version?)",
+                routine
+            ));
+            return;
+        };
+        let mut statements = vec![create_statement];
+        if let Some(comment) = &routine_info.comment {
+            statements.push(format!(
+                "COMMENT ON {} {} IS {}",
+                keyword,
+                routine_reference(schema, routine_info),
+                comment_literal(Some(comment))
+            ));
+        }
+        builder.push(
+            StepKind::CreateRoutine,
+            routine.clone(),
+            format!("Create {}: {}", keyword.to_lowercase(), routine),
+            statements,
+            None,
+        );
+    } else if matches!(routine_change.status, DiffStatus::Modified) && routine_change.comment_changed {
+        let Some(routine_info) = routine_change
+            .source_definition
+            .as_ref()
+            .or(routine_change.target_definition.as_ref())
+        else {
+            return;
+        };
+        builder.push(
+            StepKind::Comment,
+            format!("routine:{}", routine),
+            format!("Comment on {}: {}", keyword.to_lowercase(), routine),
+            vec![format!(
+                "COMMENT ON {} {} IS {}",
+                keyword,
+                routine_reference(schema, routine_info),
+                comment_literal(routine_info.comment.as_deref())
+            )],
+            None,
+        );
+    }
+}
+
+// CREATE OR REPLACE can change a routine's body but not its result type,
+// kind, or parameter names and defaults; those changes drop it first
+fn routine_is_dropped(routine_change: &RoutineChange) -> bool {
+    match routine_change.status {
+        DiffStatus::Removed => true,
+        DiffStatus::Modified if routine_change.definition_changed => {
+            let (Some(source), Some(target)) = (&routine_change.source_definition, &routine_change.target_definition)
+            else {
+                return true;
+            };
+            source.routine_type != target.routine_type
+                || source.return_type != target.return_type
+                || routine_header(source) != routine_header(target)
+        }
+        _ => false,
+    }
+}
+
+// First line of pg_get_functiondef: the name with its parameters
+fn routine_header(routine: &RoutineInfo) -> Option<&str> {
+    routine.definition.as_deref()?.lines().next().map(str::trim_end)
+}
+
+// The stored definition leaves the schema off the routine's name; the
+// statement is run against the comparison's schema instead
+fn create_routine_statement(schema: &str, routine: &RoutineInfo) -> Option<String> {
+    let definition = trim_statement(routine.definition.as_deref()?);
+    ["CREATE OR REPLACE FUNCTION ", "CREATE OR REPLACE PROCEDURE "]
+        .iter()
+        .find_map(|prefix| {
+            definition
+                .strip_prefix(prefix)
+                .map(|rest| format!("{}{}.{}", prefix, quote_ident(schema), rest))
+        })
+}
+
+// DROP and COMMENT ON need the argument types to pick one of several overloads
+fn routine_reference(schema: &str, routine: &RoutineInfo) -> String {
+    let name = qualified_name(schema, &routine.routine_name);
+    match &routine.identity_arguments {
+        Some(arguments) => format!("{}({})", name, arguments),
+        None => name,
+    }
+}
+
+fn create_table_statements(schema: &str, table_diff: &TableDifference) -> Vec<String> {
+    let qualified_table = qualified_name(schema, &table_diff.table_name);
+
+    let mut columns: Vec<&EnhancedColumnInfo> = table_diff
+        .column_changes
+        .iter()
+        .filter_map(|c| c.source_definition.as_ref())
+        .collect();
+    columns.sort_by_key(|c| c.ordinal_position);
+
+    let mut lines: Vec<String> = columns
+        .iter()
+        .map(|c| format!("  {}", column_definition(c)))
+        .collect();
+
+    // Primary key as a table constraint so composite keys work
+    let primary_index = table_diff
+        .index_changes
+        .iter()
+        .filter_map(|i| i.source_definition.as_ref())
+        .find(|i| i.is_primary);
+    match primary_index {
+        Some(idx) => lines.push(format!(
+            "  CONSTRAINT {} PRIMARY KEY ({})",
+            quote_ident(&idx.index_name),
+            index_column_list(&idx.columns)
+        )),
+        None => {
+            let pk_columns: Vec<String> = columns
+                .iter()
+                .filter(|c| c.is_primary_key)
+                .map(|c| quote_ident(&c.column_name))
+                .collect();
+            if !pk_columns.is_empty() {
+                lines.push(format!("  PRIMARY KEY ({})", pk_columns.join(", ")));
+            }
+        }
+    }
+
+    for check in table_diff.check_changes.iter().filter_map(|c| c.source_definition.as_ref()) {
+        lines.push(format!("  {}", check_constraint_clause(check)));
+    }
+
+    let mut statements = vec![format!(
+        "CREATE TABLE {} (\n{}\n)",
+        qualified_table,
+        lines.join(",\n")
+    )];
+
+    if let Some(comment) = &table_diff.source_comment {
+        statements.push(format!(
+            "COMMENT ON TABLE {} IS {}",
+            qualified_table,
+            comment_literal(Some(comment))
+        ));
+    }
+    for column in &columns {
+        if let Some(comment) = &column.comment {
+            statements.push(format!(
+                "COMMENT ON COLUMN {}.{} IS {}",
+                qualified_table,
+                quote_ident(&column.column_name),
+                comment_literal(Some(comment))
+            ));
+        }
+    }
+
+    statements
+}
+
+// Built from the index's parts rather than pg_get_indexdef, whose text names
+// the table in the source schema
+fn create_index_statement(schema: &str, table: &str, idx: &IndexInfo, concurrently: bool) -> String {
+    if idx.is_primary {
+        return format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} PRIMARY KEY ({})",
+            qualified_name(schema, table),
+            quote_ident(&idx.index_name),
+            index_column_list(&idx.columns)
+        );
+    }
+
+    let mut statement = format!(
+        "CREATE {}INDEX {}{} ON {}",
+        if idx.is_unique { "UNIQUE " } else { "" },
+        if concurrently { "CONCURRENTLY " } else { "" },
+        quote_ident(&idx.index_name),
+        qualified_name(schema, table)
+    );
+    if !idx.access_method.is_empty() && idx.access_method != "btree" {
+        statement.push_str(&format!(" USING {}", idx.access_method));
+    }
+
+    // Snapshots from before key columns were modelled only have the names
+    let keys: Vec<String> = if idx.key_columns.is_empty() {
+        idx.columns.iter().map(|c| quote_ident(c)).collect()
+    } else {
+        idx.key_columns.iter().map(index_key).collect()
+    };
+    statement.push_str(&format!(" ({})", keys.join(", ")));
+
+    if !idx.include_columns.is_empty() {
+        statement.push_str(&format!(" INCLUDE ({})", index_column_list(&idx.include_columns)));
+    }
+    if let Some(predicate) = &idx.predicate {
+        statement.push_str(&format!(" WHERE {}", predicate));
+    }
+
+    statement
+}
+
+// One key of CREATE INDEX. Sort order and NULLS placement are only written
+// when they differ from the defaults (NULLS LAST ascending, FIRST descending).
+fn index_key(key: &IndexKeyColumn) -> String {
+    let mut sql = if key.is_expression {
+        format!("({})", key.expression)
+    } else {
+        quote_ident(&key.expression)
+    };
+    if let Some(opclass) = &key.opclass {
+        sql.push_str(&format!(" {}", quote_ident(opclass)));
+    }
+    if key.descending {
+        sql.push_str(" DESC");
+    }
+    match (key.descending, key.nulls_first) {
+        (false, true) => sql.push_str(" NULLS FIRST"),
+        (true, false) => sql.push_str(" NULLS LAST"),
+        _ => {}
+    }
+    sql
+}
+
+fn index_column_list(columns: &[String]) -> String {
+    columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>().join(", ")
+}
+
+fn drop_index_statement(schema: &str, table: &str, idx: &IndexInfo, concurrently: bool) -> String {
+    // Primary key indexes belong to their constraint and can't be dropped directly
+    if idx.is_primary {
+        format!(
+            "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {}",
+            qualified_name(schema, table),
+            quote_ident(&idx.index_name)
+        )
+    } else {
+        format!(
+            "DROP INDEX {}IF EXISTS {}",
+            if concurrently { "CONCURRENTLY " } else { "" },
+            qualified_name(schema, &idx.index_name)
+        )
+    }
+}
+
+fn add_foreign_key_statement(
+    schema: &str,
+    table: &str,
+    constraint_name: &str,
+    fk: &ForeignKeyInfo,
+) -> String {
+    format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+        qualified_name(schema, table),
+        quote_ident(constraint_name),
+        quote_ident(&fk.column_name),
+        qualified_name(schema, &fk.foreign_table_name),
+        quote_ident(&fk.foreign_column_name)
+    )
+}
+
+// "CONSTRAINT name CHECK (...)", as used in CREATE TABLE and ADD
+fn check_constraint_clause(check: &CheckConstraintInfo) -> String {
+    format!("CONSTRAINT {} {}", quote_ident(&check.constraint_name), check.definition)
+}
+
+// Temporary check that lets SET NOT NULL skip its scan
+fn not_null_check_name(table: &str, column: &str) -> String {
+    format!("{}_{}_not_null_check", table, column)
+}
+
+// Column definition as used in CREATE TABLE and ADD COLUMN
+fn column_definition(column: &EnhancedColumnInfo) -> String {
+    let nullable = if column.is_nullable == SQL_NULLABLE_YES {
+        "NULL"
+    } else {
+        "NOT NULL"
+    };
+    let default = column
+        .column_default
+        .as_ref()
+        .map(|d| format!(" DEFAULT {}", d))
+        .unwrap_or_default();
+
+    format!(
+        "{} {}{} {}{}",
+        quote_ident(&column.column_name),
+        column_type_sql(column),
+        collation_clause(column),
+        nullable,
+        default
+    )
+}
+
+fn collation_clause(column: &EnhancedColumnInfo) -> String {
+    column
+        .collation_name
+        .as_ref()
+        .map(|c| format!(" COLLATE {}", quote_ident(c)))
+        .unwrap_or_default()
+}
+
+/// Name of the user-defined type (or array element type) a column is
+/// declared with, e.g. an enum or domain
+pub(crate) fn column_type_name(column: &EnhancedColumnInfo) -> Option<&str> {
+    match column.data_type.as_str() {
+        "USER-DEFINED" => column.udt_name.as_deref(),
+        "ARRAY" => column.udt_name.as_deref().map(|udt| udt.trim_start_matches('_')),
+        _ => None,
+    }
+}
+
+/// SQL type for a column, including length/precision and array or user-defined types
+pub fn column_type_sql(column: &EnhancedColumnInfo) -> String {
+    match column.data_type.as_str() {
+        "USER-DEFINED" => match &column.udt_name {
+            Some(udt) => user_type_sql(column, udt),
+            None => column.data_type.clone(),
+        },
+        "ARRAY" => match &column.udt_name {
+            Some(udt) => format!("{}[]", user_type_sql(column, udt.trim_start_matches('_'))),
+            None => column.data_type.clone(),
+        },
+        "numeric" => match (column.numeric_precision, column.numeric_scale) {
+            (Some(precision), Some(scale)) => format!("numeric({},{})", precision, scale),
+            (Some(precision), None) => format!("numeric({})", precision),
+            _ => column.data_type.clone(),
+        },
+        data_type => match column.character_maximum_length {
+            Some(length) => format!("{}({})", data_type, length),
+            None => data_type.to_string(),
+        },
+    }
+}
+
+// The script doesn't set search_path, so types are qualified with their
+// schema. Built-in ones are always found and stay bare, as do columns from
+// snapshots taken before udt_schema was recorded.
+fn user_type_sql(column: &EnhancedColumnInfo, type_name: &str) -> String {
+    match column.udt_schema.as_deref() {
+        Some(schema) if schema != "pg_catalog" => qualified_name(schema, type_name),
+        _ => quote_ident(type_name),
+    }
+}
+
+// Render a comment as a SQL string literal, or NULL to remove it
+fn comment_literal(comment: Option<&str>) -> String {
+    match comment {
+        Some(text) => quote_literal(text),
+        None => "NULL".to_string(),
+    }
+}
+
+// COMMENT ON / DROP need to know whether the routine is a function or a procedure
+fn routine_object_keyword(routine_change: &RoutineChange) -> &'static str {
+    let routine_type = routine_change
+        .source_definition
+        .as_ref()
+        .or(routine_change.target_definition.as_ref())
+        .map(|r| r.routine_type.as_str());
+
+    if routine_type == Some("PROCEDURE") {
+        "PROCEDURE"
+    } else {
+        "FUNCTION"
+    }
+}
+
+// Definitions from the catalog may already end with a semicolon
+fn trim_statement(sql: &str) -> &str {
+    sql.trim().trim_end_matches(';').trim_end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ExtensionInfo;
+    use crate::utils::schema_diff::{CheckConstraintChange, ExtensionChange, ForeignKeyChange, IndexChange};
+
+    fn index(name: &str, columns: &[&str]) -> IndexInfo {
+        IndexInfo {
+            index_name: name.to_string(),
+            table_name: "Order Items".to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            is_unique: false,
+            is_primary: false,
+            definition: format!(
+                "CREATE INDEX {} ON source.\"Order Items\" USING btree (x)",
+                name
+            ),
+            access_method: "btree".to_string(),
+            key_columns: columns
+                .iter()
+                .map(|c| IndexKeyColumn {
+                    expression: c.to_string(),
+                    is_expression: false,
+                    descending: false,
+                    nulls_first: false,
+                    opclass: None,
+                })
+                .collect(),
+            include_columns: Vec::new(),
+            predicate: None,
+            is_valid: true,
+        }
+    }
+
+    fn column(name: &str, nullable: bool) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            column_name: name.to_string(),
+            data_type: "integer".to_string(),
+            is_nullable: if nullable { "YES" } else { "NO" }.to_string(),
+            is_primary_key: false,
+            column_default: None,
+            character_maximum_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: 1,
+            comment: None,
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        }
+    }
+
+    fn check(name: &str, definition: &str, columns: &[&str]) -> CheckConstraintInfo {
+        CheckConstraintInfo {
+            constraint_name: name.to_string(),
+            definition: definition.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn orders(status: DiffStatus) -> TableDifference {
+        TableDifference {
+            table_name: "orders".to_string(),
+            status,
+            column_changes: Vec::new(),
+            index_changes: Vec::new(),
+            fk_changes: Vec::new(),
+            check_changes: Vec::new(),
+            source_comment: None,
+            target_comment: None,
+            renamed_from: None,
+        }
+    }
+
+    fn comparison(table_diff: TableDifference, server_version: Option<i32>) -> SchemaComparison {
+        SchemaComparison {
+            source_connection: "dev".to_string(),
+            target_connection: "prod".to_string(),
+            schema_name: "public".to_string(),
+            summary: crate::utils::schema_diff::comparison_summary(
+                std::slice::from_ref(&table_diff),
+                &[],
+                &[],
+                &[],
+                &[],
+            ),
+            table_differences: vec![table_diff],
+            view_differences: Vec::new(),
+            routine_differences: Vec::new(),
+            extension_differences: Vec::new(),
+            type_differences: Vec::new(),
+            rename_candidates: Vec::new(),
+            warnings: Vec::new(),
+            target_server_version: server_version,
+        }
+    }
+
+    fn not_valid() -> MigrationScriptOptions {
+        MigrationScriptOptions {
+            not_valid_constraints: true,
+            ..Default::default()
+        }
+    }
+
+    fn plan_summary(plan: &MigrationPlan) -> Vec<(StepKind, bool, Vec<String>)> {
+        plan.steps
+            .iter()
+            .map(|s| (s.kind, s.outside_transaction, s.statements.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn check_on_an_existing_table_is_validated_separately() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "total".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(column("total", true)),
+            target_definition: None,
+            changes: Vec::new(),
+        });
+        table_diff.check_changes.push(CheckConstraintChange {
+            constraint_name: "orders_total_check".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(check(
+                "orders_total_check",
+                "CHECK (total >= 0)",
+                &["total"],
+            )),
+            target_definition: None,
+        });
+
+        let plan = build_migration_plan(&comparison(table_diff, None), &not_valid());
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![
+                (
+                    StepKind::AddColumn,
+                    false,
+                    vec!["ALTER TABLE public.orders ADD COLUMN total integer NULL".to_string()]
+                ),
+                (
+                    StepKind::AddCheckConstraint,
+                    false,
+                    vec![
+                        "ALTER TABLE public.orders ADD CONSTRAINT orders_total_check \
+                          CHECK (total >= 0) NOT VALID"
+                            .to_string()
+                    ]
+                ),
+                (
+                    StepKind::ValidateConstraint,
+                    true,
+                    vec![
+                        "ALTER TABLE public.orders VALIDATE CONSTRAINT orders_total_check"
+                            .to_string()
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn scripts_render_the_same_every_time() {
+        let comparison = comparison(orders(DiffStatus::Added), None);
+        let options = MigrationScriptOptions::default();
+
+        for render in [generate_migration_script, generate_rollback_script] {
+            let script = render(&comparison, &options);
+            assert!(!script.contains("Generated"), "{}", script);
+            assert_eq!(script, render(&comparison, &options));
+        }
+    }
+
+    #[test]
+    fn modified_check_is_dropped_before_its_column_changes() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "total".to_string(),
+            status: DiffStatus::Modified,
+            source_definition: Some(EnhancedColumnInfo {
+                data_type: "bigint".to_string(),
+                ..column("total", true)
+            }),
+            target_definition: Some(column("total", true)),
+            changes: vec!["type: integer → bigint".to_string()],
+        });
+        table_diff.check_changes.push(CheckConstraintChange {
+            constraint_name: "orders_total_check".to_string(),
+            status: DiffStatus::Modified,
+            source_definition: Some(check("orders_total_check", "CHECK (total > 0)", &["total"])),
+            target_definition: Some(check(
+                "orders_total_check",
+                "CHECK (total >= 0)",
+                &["total"],
+            )),
+        });
+
+        let plan = build_migration_plan(
+            &comparison(table_diff, None),
+            &MigrationScriptOptions::default(),
+        );
+        let kinds: Vec<StepKind> = plan.steps.iter().map(|s| s.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                StepKind::DropCheckConstraint,
+                StepKind::AlterColumn,
+                StepKind::AddCheckConstraint
+            ]
+        );
+        assert_eq!(
+            plan.steps[2].statements,
+            vec!["ALTER TABLE public.orders ADD CONSTRAINT orders_total_check CHECK (total > 0)"]
+        );
+    }
+
+    #[test]
+    fn new_table_declares_its_checks() {
+        let mut table_diff = orders(DiffStatus::Added);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "total".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(column("total", false)),
+            target_definition: None,
+            changes: Vec::new(),
+        });
+        table_diff.check_changes.push(CheckConstraintChange {
+            constraint_name: "Total Positive".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(check("Total Positive", "CHECK (total > 0)", &["total"])),
+            target_definition: None,
+        });
+
+        let plan = build_migration_plan(&comparison(table_diff, None), &not_valid());
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![(
+                StepKind::CreateTable,
+                false,
+                vec![
+                    "CREATE TABLE public.orders (\n  total integer NOT NULL,\n  \
+                      CONSTRAINT \"Total Positive\" CHECK (total > 0)\n)"
+                        .to_string()
+                ]
+            )]
+        );
+    }
+
+    fn set_not_null(server_version: Option<i32>) -> MigrationPlan {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "customer_id".to_string(),
+            status: DiffStatus::Modified,
+            source_definition: Some(column("customer_id", false)),
+            target_definition: Some(column("customer_id", true)),
+            changes: vec!["nullable: YES → NO".to_string()],
+        });
+        build_migration_plan(&comparison(table_diff, server_version), &not_valid())
+    }
+
+    #[test]
+    fn not_null_on_postgres_12_is_proved_by_a_validated_check() {
+        assert_eq!(
+            plan_summary(&set_not_null(Some(120000))),
+            vec![
+                (
+                    StepKind::AlterColumn,
+                    false,
+                    vec!["ALTER TABLE public.orders ADD CONSTRAINT orders_customer_id_not_null_check \
+                          CHECK (customer_id IS NOT NULL) NOT VALID"
+                        .to_string()]
+                ),
+                (
+                    StepKind::ValidateConstraint,
+                    true,
+                    vec!["ALTER TABLE public.orders VALIDATE CONSTRAINT orders_customer_id_not_null_check"
+                        .to_string()]
+                ),
+                (
+                    StepKind::SetNotNull,
+                    false,
+                    vec![
+                        "ALTER TABLE public.orders ALTER COLUMN customer_id SET NOT NULL".to_string(),
+                        "ALTER TABLE public.orders DROP CONSTRAINT orders_customer_id_not_null_check"
+                            .to_string(),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_null_before_postgres_12_or_when_unknown_is_set_directly() {
+        for server_version in [Some(110000), None] {
+            assert_eq!(
+                plan_summary(&set_not_null(server_version)),
+                vec![(
+                    StepKind::AlterColumn,
+                    false,
+                    vec![
+                        "ALTER TABLE public.orders ALTER COLUMN customer_id SET NOT NULL"
+                            .to_string()
+                    ]
+                )]
+            );
+        }
+    }
+
+    fn new_table(name: &str, references: &str) -> TableDifference {
+        let fk_name = format!("{}_{}_fk", name, references);
+        TableDifference {
+            table_name: name.to_string(),
+            column_changes: vec![ColumnChange {
+                column_name: "other_id".to_string(),
+                status: DiffStatus::Added,
+                source_definition: Some(column("other_id", true)),
+                target_definition: None,
+                changes: Vec::new(),
+            }],
+            fk_changes: vec![ForeignKeyChange {
+                constraint_name: fk_name.clone(),
+                status: DiffStatus::Added,
+                source_definition: Some(ForeignKeyInfo {
+                    constraint_name: fk_name,
+                    table_name: name.to_string(),
+                    column_name: "other_id".to_string(),
+                    foreign_table_name: references.to_string(),
+                    foreign_column_name: "other_id".to_string(),
+                }),
+                target_definition: None,
+            }],
+            ..orders(DiffStatus::Added)
+        }
+    }
+
+    #[test]
+    fn new_tables_referencing_each_other_are_not_a_cycle() {
+        let mut comparison = comparison(new_table("a", "b"), None);
+        comparison.table_differences.push(new_table("b", "a"));
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+        let steps: Vec<(StepKind, &str)> = plan
+            .steps
+            .iter()
+            .map(|s| (s.kind, s.object_name.as_str()))
+            .collect();
+
+        assert!(plan.notes.is_empty(), "{:?}", plan.notes);
+        assert_eq!(
+            steps,
+            vec![
+                (StepKind::CreateTable, "a"),
+                (StepKind::CreateTable, "b"),
+                (StepKind::AddForeignKey, "a.a_b_fk"),
+                (StepKind::AddForeignKey, "b.b_a_fk"),
+            ]
+        );
+    }
+
+    #[test]
+    fn added_objects_are_built_from_their_source_definition() {
+        let mut table_diff = new_table("orders", "customers");
+        table_diff.status = DiffStatus::Modified;
+        table_diff.index_changes.push(IndexChange {
+            index_name: "orders_other".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(index("orders_other", &["other_id"])),
+            target_definition: None,
+        });
+
+        let plan = build_migration_plan(
+            &comparison(table_diff, None),
+            &MigrationScriptOptions::default(),
+        );
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![
+                (
+                    StepKind::AddColumn,
+                    false,
+                    vec!["ALTER TABLE public.orders ADD COLUMN other_id integer NULL".to_string()]
+                ),
+                (
+                    StepKind::CreateIndex,
+                    false,
+                    vec!["CREATE INDEX orders_other ON public.orders (other_id)".to_string()]
+                ),
+                (
+                    StepKind::AddForeignKey,
+                    false,
+                    vec![
+                        "ALTER TABLE public.orders ADD CONSTRAINT orders_customers_fk \
+                          FOREIGN KEY (other_id) REFERENCES public.customers (other_id)"
+                            .to_string()
+                    ]
+                ),
+            ]
+        );
+    }
+
+    fn enum_type(name: &str, labels: &[&str]) -> TypeInfo {
+        TypeInfo {
+            type_name: name.to_string(),
+            kind: TYPE_KIND_ENUM.to_string(),
+            definition: format!(
+                "AS ENUM ({})",
+                labels
+                    .iter()
+                    .map(|l| quote_literal(l))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            enum_labels: labels.iter().map(|l| l.to_string()).collect(),
+            depends_on: Vec::new(),
+        }
+    }
+
+    fn type_change(
+        status: DiffStatus,
+        source_definition: Option<TypeInfo>,
+        target_definition: Option<TypeInfo>,
+    ) -> TypeChange {
+        TypeChange {
+            type_name: source_definition
+                .as_ref()
+                .or(target_definition.as_ref())
+                .map(|t| t.type_name.clone())
+                .unwrap_or_default(),
+            status,
+            source_definition,
+            target_definition,
+        }
+    }
+
+    fn status_column(status: DiffStatus) -> ColumnChange {
+        let definition = EnhancedColumnInfo {
+            data_type: "USER-DEFINED".to_string(),
+            udt_name: Some("order_status".to_string()),
+            udt_schema: Some("public".to_string()),
+            ..column("status", true)
+        };
+        let (source_definition, target_definition) = match status {
+            DiffStatus::Added => (Some(definition), None),
+            _ => (None, Some(definition)),
+        };
+        ColumnChange {
+            column_name: "status".to_string(),
+            status,
+            source_definition,
+            target_definition,
+            changes: Vec::new(),
+        }
+    }
+
+    fn step_names(plan: &MigrationPlan) -> Vec<(StepKind, &str)> {
+        plan.steps
+            .iter()
+            .map(|s| (s.kind, s.object_name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn new_column_using_a_new_enum_comes_after_the_type() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff
+            .column_changes
+            .push(status_column(DiffStatus::Added));
+        let mut comparison = comparison(table_diff, None);
+        // A domain over the enum, listed before the enum it is built on
+        let domain = TypeInfo {
+            type_name: "a_status".to_string(),
+            kind: TYPE_KIND_DOMAIN.to_string(),
+            definition: "AS public.order_status NOT NULL".to_string(),
+            enum_labels: Vec::new(),
+            depends_on: vec!["order_status".to_string()],
+        };
+        comparison.type_differences = vec![
+            type_change(DiffStatus::Added, Some(domain), None),
+            type_change(
+                DiffStatus::Added,
+                Some(enum_type("order_status", &["new", "paid"])),
+                None,
+            ),
+        ];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert!(plan.notes.is_empty(), "{:?}", plan.notes);
+        assert_eq!(
+            step_names(&plan),
+            vec![
+                (StepKind::CreateType, "order_status"),
+                (StepKind::CreateType, "a_status"),
+                (StepKind::AddColumn, "orders.status"),
+            ]
+        );
+        assert_eq!(
+            plan.steps[0].statements,
+            vec!["CREATE TYPE public.order_status AS ENUM ('new', 'paid')".to_string()]
+        );
+        assert_eq!(
+            plan.steps[1].statements,
+            vec!["CREATE DOMAIN public.a_status AS public.order_status NOT NULL".to_string()]
+        );
+    }
+
+    #[test]
+    fn column_types_are_qualified_with_their_schema() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        let mut status = status_column(DiffStatus::Added);
+        if let Some(definition) = status.source_definition.as_mut() {
+            definition.udt_schema = Some("Sales".to_string());
+        }
+        table_diff.column_changes.push(status);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "history".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(EnhancedColumnInfo {
+                data_type: "ARRAY".to_string(),
+                udt_name: Some("_order_status".to_string()),
+                udt_schema: Some("Sales".to_string()),
+                ..column("history", true)
+            }),
+            target_definition: None,
+            changes: Vec::new(),
+        });
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "tags".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(EnhancedColumnInfo {
+                data_type: "ARRAY".to_string(),
+                udt_name: Some("_text".to_string()),
+                udt_schema: Some("pg_catalog".to_string()),
+                ..column("tags", true)
+            }),
+            target_definition: None,
+            changes: Vec::new(),
+        });
+        let mut comparison = comparison(table_diff, None);
+        comparison.schema_name = "Sales".to_string();
+        comparison.type_differences = vec![type_change(
+            DiffStatus::Added,
+            Some(enum_type("order_status", &["new", "paid"])),
+            None,
+        )];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![
+                (
+                    StepKind::CreateType,
+                    false,
+                    vec!["CREATE TYPE \"Sales\".order_status AS ENUM ('new', 'paid')".to_string()]
+                ),
+                (
+                    StepKind::AddColumn,
+                    false,
+                    vec!["ALTER TABLE \"Sales\".orders ADD COLUMN status \"Sales\".order_status NULL"
+                        .to_string()]
+                ),
+                (
+                    StepKind::AddColumn,
+                    false,
+                    vec!["ALTER TABLE \"Sales\".orders ADD COLUMN history \"Sales\".order_status[] NULL"
+                        .to_string()]
+                ),
+                (
+                    StepKind::AddColumn,
+                    false,
+                    vec!["ALTER TABLE \"Sales\".orders ADD COLUMN tags text[] NULL".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_type_is_dropped_after_the_column_using_it() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff
+            .column_changes
+            .push(status_column(DiffStatus::Removed));
+        let mut comparison = comparison(table_diff, None);
+        comparison.type_differences = vec![type_change(
+            DiffStatus::Removed,
+            None,
+            Some(enum_type("order_status", &["new", "paid"])),
+        )];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert_eq!(
+            step_names(&plan),
+            vec![
+                (StepKind::DropColumn, "orders.status"),
+                (StepKind::DropType, "order_status")
+            ]
+        );
+        assert_eq!(
+            plan.steps[1].statements,
+            vec!["DROP TYPE IF EXISTS public.order_status".to_string()]
+        );
+    }
+
+    #[test]
+    fn enum_values_are_added_in_place_outside_the_transaction() {
+        let mut comparison = comparison(orders(DiffStatus::Identical), None);
+        comparison.type_differences = vec![type_change(
+            DiffStatus::Modified,
+            Some(enum_type(
+                "order_status",
+                &["draft", "new", "shipped", "paid"],
+            )),
+            Some(enum_type("order_status", &["new", "paid"])),
+        )];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert!(plan.notes.is_empty(), "{:?}", plan.notes);
+        assert_eq!(
+            plan_summary(&plan),
+            vec![(
+                StepKind::AlterType,
+                true,
+                vec![
+                    "ALTER TYPE public.order_status ADD VALUE IF NOT EXISTS 'draft' BEFORE 'new'"
+                        .to_string(),
+                    "ALTER TYPE public.order_status ADD VALUE IF NOT EXISTS 'shipped' AFTER 'new'"
+                        .to_string(),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn reordered_enum_values_are_left_as_a_note() {
+        let mut comparison = comparison(orders(DiffStatus::Identical), None);
+        comparison.type_differences = vec![type_change(
+            DiffStatus::Modified,
+            Some(enum_type("order_status", &["paid", "new"])),
+            Some(enum_type("order_status", &["new", "paid"])),
+        )];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert!(plan.steps.is_empty());
+        assert_eq!(plan.notes.len(), 1);
+        assert!(
+            plan.notes[0].starts_with("Type order_status changed"),
+            "{:?}",
+            plan.notes
+        );
+    }
+
+    #[test]
+    fn index_statement_targets_the_comparison_schema() {
+        let idx = index("items_by_order", &["Order Id", "line"]);
+
+        assert_eq!(
+            create_index_statement("target", "Order Items", &idx, false),
+            "CREATE INDEX items_by_order ON target.\"Order Items\" (\"Order Id\", line)"
+        );
+    }
+
+    #[test]
+    fn index_statement_keeps_every_part() {
+        let mut idx = index("Recent Emails", &["email"]);
+        idx.is_unique = true;
+        idx.access_method = "gist".to_string();
+        idx.key_columns[0].descending = true;
+        idx.key_columns[0].opclass = Some("text_pattern_ops".to_string());
+        idx.key_columns.push(IndexKeyColumn {
+            expression: "lower(name)".to_string(),
+            is_expression: true,
+            descending: false,
+            nulls_first: true,
+            opclass: None,
+        });
+        idx.include_columns = vec!["Created At".to_string()];
+        idx.predicate = Some("(deleted_at IS NULL)".to_string());
+
+        assert_eq!(
+            create_index_statement("app", "users", &idx, true),
+            "CREATE UNIQUE INDEX CONCURRENTLY \"Recent Emails\" ON app.users USING gist \
+             (email text_pattern_ops DESC NULLS LAST, (lower(name)) NULLS FIRST) \
+             INCLUDE (\"Created At\") WHERE (deleted_at IS NULL)"
+        );
+    }
+
+    #[test]
+    fn new_indexes_are_built_from_their_pg_index_parts() {
+        let expression = IndexInfo {
+            key_columns: vec![IndexKeyColumn {
+                expression: "lower((email)::text)".to_string(),
+                is_expression: true,
+                descending: false,
+                nulls_first: false,
+                opclass: None,
+            }],
+            ..index("orders_email_lower", &["lower((email)::text)"])
+        };
+        let partial = IndexInfo {
+            predicate: Some("(shipped_at IS NULL)".to_string()),
+            ..index("orders_unshipped", &["created_at"])
+        };
+        let include = IndexInfo {
+            include_columns: vec!["total".to_string(), "Customer Note".to_string()],
+            ..index("orders_covering", &["customer_id"])
+        };
+        let mut descending = index("orders_recent", &["created_at", "id"]);
+        descending.key_columns[0].descending = true;
+        descending.key_columns[0].nulls_first = true;
+        descending.key_columns[1].descending = true;
+
+        let cases = [
+            (expression, "CREATE INDEX orders_email_lower ON public.orders ((lower((email)::text)))"),
+            (partial, "CREATE INDEX orders_unshipped ON public.orders (created_at) WHERE (shipped_at IS NULL)"),
+            (
+                include,
+                "CREATE INDEX orders_covering ON public.orders (customer_id) INCLUDE (total, \"Customer Note\")",
+            ),
+            (descending, "CREATE INDEX orders_recent ON public.orders (created_at DESC, id DESC NULLS LAST)"),
+        ];
+        for (idx, expected) in cases {
+            let mut table_diff = orders(DiffStatus::Modified);
+            table_diff.index_changes.push(IndexChange {
+                index_name: idx.index_name.clone(),
+                status: DiffStatus::Added,
+                source_definition: Some(idx),
+                target_definition: None,
+            });
+
+            let plan = build_migration_plan(&comparison(table_diff, None), &MigrationScriptOptions::default());
+
+            assert_eq!(
+                plan_summary(&plan),
+                vec![(StepKind::CreateIndex, false, vec![expected.to_string()])]
+            );
+        }
+    }
+
+    #[test]
+    fn primary_key_columns_are_quoted() {
+        let mut idx = index("Order Items_pkey", &["Order Id", "line"]);
+        idx.is_primary = true;
+
+        assert_eq!(
+            create_index_statement("public", "Order Items", &idx, false),
+            "ALTER TABLE public.\"Order Items\" ADD CONSTRAINT \"Order Items_pkey\" \
+             PRIMARY KEY (\"Order Id\", line)"
+        );
+    }
+
+    fn extension_change(name: &str, source_version: Option<&str>, target_version: Option<&str>) -> ExtensionChange {
+        let info = |version: &str| ExtensionInfo {
+            extension_name: name.to_string(),
+            version: version.to_string(),
+            schema_name: "extensions".to_string(),
+        };
+        ExtensionChange {
+            extension_name: name.to_string(),
+            status: match (source_version, target_version) {
+                (Some(_), None) => DiffStatus::Added,
+                (None, Some(_)) => DiffStatus::Removed,
+                (Some(source), Some(target)) if source != target => DiffStatus::Modified,
+                _ => DiffStatus::Identical,
+            },
+            source_definition: source_version.map(info),
+            target_definition: target_version.map(info),
+        }
+    }
+
+    #[test]
+    fn extensions_are_created_or_updated_before_anything_else() {
+        let mut comparison = comparison(new_table("orders", "customers"), None);
+        comparison.extension_differences = vec![
+            extension_change("uuid-ossp", Some("1.1"), None),
+            extension_change("pg_trgm", Some("1.6"), Some("1.5")),
+            extension_change("plpgsql", Some("1.0"), Some("1.0")),
+            extension_change("hstore", None, Some("1.8")),
+        ];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+        let extension_steps: Vec<(usize, StepKind, &[String], Option<&str>)> = plan
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                matches!(
+                    s.kind,
+                    StepKind::CreateExtension | StepKind::UpdateExtension | StepKind::DropExtension
+                )
+            })
+            .map(|(i, s)| (i, s.kind, s.statements.as_slice(), s.warning.as_deref()))
+            .collect();
+
+        let last = plan.steps.len() - 1;
+        assert_eq!(
+            extension_steps,
+            vec![
+                (
+                    0,
+                    StepKind::CreateExtension,
+                    &["CREATE EXTENSION IF NOT EXISTS \"uuid-ossp\" WITH SCHEMA extensions VERSION '1.1'".to_string()]
+                        [..],
+                    None
+                ),
+                (
+                    1,
+                    StepKind::UpdateExtension,
+                    &["ALTER EXTENSION pg_trgm UPDATE TO '1.6'".to_string()][..],
+                    None
+                ),
+                (
+                    last,
+                    StepKind::DropExtension,
+                    &["DROP EXTENSION IF EXISTS hstore".to_string()][..],
+                    Some("Objects depending on this extension will fail to drop!")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn collation_change_alters_the_column_type() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        let source = EnhancedColumnInfo {
+            data_type: "text".to_string(),
+            collation_name: Some("C".to_string()),
+            ..column("Name", true)
+        };
+        let target = EnhancedColumnInfo {
+            collation_name: None,
+            ..source.clone()
+        };
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "Name".to_string(),
+            status: DiffStatus::Modified,
+            source_definition: Some(source),
+            target_definition: Some(target),
+            changes: vec!["collation: None → Some(\"C\")".to_string()],
+        });
+
+        let plan = build_migration_plan(&comparison(table_diff, None), &MigrationScriptOptions::default());
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![(
+                StepKind::AlterColumn,
+                false,
+                vec!["ALTER TABLE public.orders ALTER COLUMN \"Name\" TYPE text COLLATE \"C\"".to_string()]
+            )]
+        );
+    }
+
+    fn order_total(arguments: &str, returns: &str, body: &str) -> RoutineInfo {
+        RoutineInfo {
+            routine_name: "order_total".to_string(),
+            routine_type: "FUNCTION".to_string(),
+            definition: Some(format!(
+                "CREATE OR REPLACE FUNCTION order_total({})\n RETURNS {}\n LANGUAGE sql\nAS $function${}$function$\n",
+                arguments, returns, body
+            )),
+            return_type: Some(returns.to_string()),
+            comment: None,
+            identity_arguments: Some(arguments.split_whitespace().last().unwrap_or("").to_string()),
+        }
+    }
+
+    fn routine_change(source: Option<RoutineInfo>, target: Option<RoutineInfo>) -> RoutineChange {
+        let info = source.as_ref().or(target.as_ref()).unwrap();
+        RoutineChange {
+            routine_name: crate::utils::schema_diff::routine_key(info),
+            status: match (&source, &target) {
+                (Some(_), None) => DiffStatus::Added,
+                (None, Some(_)) => DiffStatus::Removed,
+                _ => DiffStatus::Modified,
+            },
+            definition_changed: source.is_some() && target.is_some(),
+            comment_changed: false,
+            source_definition: source,
+            target_definition: target,
+        }
+    }
+
+    fn order_totals_view() -> ViewChange {
+        ViewChange {
+            view_name: "order_totals".to_string(),
+            status: DiffStatus::Identical,
+            source_definition: Some("SELECT order_total(1) AS total".to_string()),
+            target_definition: Some("SELECT order_total(1) AS total".to_string()),
+            definition_changed: false,
+            source_comment: None,
+            target_comment: None,
+            source_depends_on: vec!["order_total(integer)".to_string()],
+            target_depends_on: vec!["order_total(integer)".to_string()],
+            source_column_dependencies: Vec::new(),
+            target_column_dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn changed_body_replaces_the_routine_in_the_comparison_schema() {
+        let mut comparison = comparison(orders(DiffStatus::Identical), None);
+        comparison.schema_name = "Sales".to_string();
+        comparison.view_differences = vec![order_totals_view()];
+        comparison.routine_differences = vec![routine_change(
+            Some(order_total("o integer", "numeric", "SELECT 2")),
+            Some(order_total("o integer", "numeric", "SELECT 1")),
+        )];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![(
+                StepKind::CreateRoutine,
+                false,
+                vec![
+                    "CREATE OR REPLACE FUNCTION \"Sales\".order_total(o integer)\n RETURNS numeric\n \
+                     LANGUAGE sql\nAS $function$SELECT 2$function$"
+                        .to_string()
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn changed_signature_drops_the_overload_and_the_views_calling_it() {
+        let mut comparison = comparison(orders(DiffStatus::Identical), None);
+        comparison.view_differences = vec![order_totals_view()];
+        comparison.routine_differences = vec![
+            routine_change(
+                Some(order_total("o integer", "bigint", "SELECT 2")),
+                Some(order_total("o integer", "numeric", "SELECT 1")),
+            ),
+            routine_change(None, Some(order_total("o text", "numeric", "SELECT 1"))),
+        ];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+        let statements: Vec<(StepKind, &str)> = plan
+            .steps
+            .iter()
+            .map(|s| (s.kind, s.statements[0].lines().next().unwrap()))
+            .collect();
+
+        assert_eq!(
+            statements,
+            vec![
+                (StepKind::DropView, "DROP VIEW IF EXISTS public.order_totals"),
+                (StepKind::DropRoutine, "DROP FUNCTION IF EXISTS public.order_total(integer)"),
+                (StepKind::DropRoutine, "DROP FUNCTION IF EXISTS public.order_total(text)"),
+                (StepKind::CreateRoutine, "CREATE OR REPLACE FUNCTION public.order_total(o integer)"),
+                (StepKind::CreateView, "CREATE VIEW public.order_totals AS"),
+            ]
+        );
+    }
+
+    #[test]
+    fn routine_without_a_create_statement_is_left_as_a_note() {
+        let mut comparison = comparison(orders(DiffStatus::Identical), None);
+        let body_only = RoutineInfo {
+            definition: Some("SELECT 1".to_string()),
+            identity_arguments: None,
+            ..order_total("o integer", "numeric", "")
+        };
+        comparison.routine_differences = vec![routine_change(Some(body_only), None)];
+
+        let plan = build_migration_plan(&comparison, &MigrationScriptOptions::default());
+
+        assert!(plan.steps.is_empty());
+        assert_eq!(plan.notes.len(), 1);
+        assert!(plan.notes[0].starts_with("order_total can't be created"), "{:?}", plan.notes);
+    }
+
+    fn rollback_plan(comparison: &SchemaComparison) -> MigrationPlan {
+        let options = MigrationScriptOptions::default();
+        let (comparison, notes) = resolve_changes(comparison, &options);
+        plan_resolved_rollback(&comparison, &options, notes)
+    }
+
+    #[test]
+    fn rollback_re_adds_a_dropped_column_as_it_was() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "Note".to_string(),
+            status: DiffStatus::Removed,
+            source_definition: None,
+            target_definition: Some(EnhancedColumnInfo {
+                data_type: "character varying".to_string(),
+                character_maximum_length: Some(40),
+                column_default: Some("'none'::character varying".to_string()),
+                ..column("Note", false)
+            }),
+            changes: Vec::new(),
+        });
+
+        let plan = rollback_plan(&comparison(table_diff, None));
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![(
+                StepKind::AddColumn,
+                false,
+                vec![
+                    "ALTER TABLE public.orders ADD COLUMN \"Note\" character varying(40) \
+                     NOT NULL DEFAULT 'none'::character varying"
+                        .to_string()
+                ]
+            )]
+        );
+        assert!(plan.steps[0]
+            .warning
+            .as_deref()
+            .is_some_and(|w| w.starts_with("IRREVERSIBLE:")));
+    }
+
+    #[test]
+    fn rollback_recreates_dropped_indexes_and_foreign_keys_from_the_target() {
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff.index_changes.push(IndexChange {
+            index_name: "orders_customer".to_string(),
+            status: DiffStatus::Removed,
+            source_definition: None,
+            target_definition: Some(index("orders_customer", &["customer_id"])),
+        });
+        table_diff.fk_changes.push(ForeignKeyChange {
+            constraint_name: "orders_customer_fk".to_string(),
+            status: DiffStatus::Removed,
+            source_definition: None,
+            target_definition: Some(ForeignKeyInfo {
+                constraint_name: "orders_customer_fk".to_string(),
+                table_name: "orders".to_string(),
+                column_name: "customer_id".to_string(),
+                foreign_table_name: "customers".to_string(),
+                foreign_column_name: "id".to_string(),
+            }),
+        });
+
+        let plan = rollback_plan(&comparison(table_diff, None));
+
+        assert_eq!(
+            plan_summary(&plan),
+            vec![
+                (
+                    StepKind::CreateIndex,
+                    false,
+                    vec!["CREATE INDEX orders_customer ON public.orders (customer_id)".to_string()]
+                ),
+                (
+                    StepKind::AddForeignKey,
+                    false,
+                    vec![
+                        "ALTER TABLE public.orders ADD CONSTRAINT orders_customer_fk \
+                          FOREIGN KEY (customer_id) REFERENCES public.customers (id)"
+                            .to_string()
+                    ]
+                ),
+            ]
+        );
+        assert!(plan.steps.iter().all(|s| s.warning.is_none()));
+    }
+
+    #[test]
+    fn rollback_flags_steps_that_cannot_restore_data() {
+        let mut dropped_table = new_table("archive", "orders");
+        dropped_table.status = DiffStatus::Removed;
+        for col_change in &mut dropped_table.column_changes {
+            col_change.status = DiffStatus::Removed;
+            col_change.target_definition = col_change.source_definition.take();
+        }
+        for fk_change in &mut dropped_table.fk_changes {
+            fk_change.status = DiffStatus::Removed;
+            fk_change.target_definition = fk_change.source_definition.take();
+        }
+        let mut table_diff = orders(DiffStatus::Modified);
+        table_diff.column_changes.push(ColumnChange {
+            column_name: "total".to_string(),
+            status: DiffStatus::Modified,
+            source_definition: Some(EnhancedColumnInfo {
+                data_type: "bigint".to_string(),
+                ..column("total", true)
+            }),
+            target_definition: Some(column("total", true)),
+            changes: vec!["type: integer → bigint".to_string()],
+        });
+        let mut comparison = comparison(table_diff, None);
+        comparison.table_differences.push(dropped_table);
+
+        let plan = rollback_plan(&comparison);
+        let flagged: Vec<(StepKind, bool)> = plan
+            .steps
+            .iter()
+            .map(|s| {
+                let irreversible = s
+                    .warning
+                    .as_deref()
+                    .is_some_and(|w| w.starts_with("IRREVERSIBLE:"));
+                (s.kind, irreversible)
+            })
+            .collect();
+
+        assert_eq!(
+            flagged,
+            vec![
+                (StepKind::CreateTable, true),
+                (StepKind::AlterColumn, true),
+                (StepKind::AddForeignKey, false),
+            ]
+        );
+        assert!(plan.steps[1]
+            .statements
+            .iter()
+            .any(|s| s.contains("TYPE integer")));
+    }
+}
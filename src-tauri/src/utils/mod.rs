@@ -1,15 +1,37 @@
 mod app_dir;
+mod comparison_report;
+pub mod data_diff;
+mod ddl_files;
+pub mod dependency_graph;
+pub mod migration;
+pub mod preflight;
+pub mod renames;
 pub mod schema_diff;
+mod schema_docs;
+pub mod selection;
+pub mod sql;
+pub mod text_diff;
+mod warning_rules;
 
 pub use app_dir::{
     get_app_dir, get_auto_connect_enabled_internal, get_current_project_path_internal,
     get_last_connection_internal, load_project_settings_internal, set_auto_connect_enabled_internal,
     set_last_connection_internal, set_project_path_internal, get_recent_projects_internal,
     remove_recent_project_internal, RecentProject, get_vim_mode_enabled_internal,
-    set_vim_mode_enabled_internal,
+    set_vim_mode_enabled_internal, sanitize_file_name, get_history_retention_internal,
+    set_history_retention_internal, get_saved_query_files_enabled_internal,
+    set_saved_query_files_enabled_internal,
 };
 
+pub use comparison_report::{render_comparison_report, ReportFormat};
+
+pub use ddl_files::{read_ddl_files, resolve_ddl_directory, DdlFile};
+
+pub use migration::{generate_migration_script, generate_rollback_script, MigrationScriptOptions};
+
 pub use schema_diff::{
-    compare_schemas, generate_migration_script,
+    compare_schemas,
     SchemaComparison,
 };
+
+pub use schema_docs::{render_schema_docs, DocsFormat};
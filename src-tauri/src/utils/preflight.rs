@@ -0,0 +1,565 @@
+use crate::constants::{PREFLIGHT_LARGE_TABLE_BYTES, SQL_NULLABLE_YES};
+use crate::utils::migration::{
+    build_migration_plan, column_type_sql, MigrationPlan, MigrationScriptOptions, StepKind,
+};
+use crate::utils::renames::apply_accepted_renames;
+use crate::utils::schema_diff::{
+    ColumnChange, DiffStatus, SchemaComparison, TableDifference, WarningSeverity,
+};
+use crate::utils::sql::{qualified_name, quote_ident, quote_literal};
+use crate::utils::warning_rules::{
+    check_violation_query, duplicate_check_query, existing_rows_query, null_check_query, orphan_check_query,
+    type_change_rewrites,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeKind {
+    NullValues,
+    InvalidCasts,
+    DuplicateKeys,
+    OrphanedRows,
+    CheckViolations,
+    ExistingRows,
+    TableSize,
+}
+
+/// A read-only query to run on the target before a migration step. Every
+/// probe query returns a single bigint: a row count, or bytes for `TableSize`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreflightProbe {
+    pub step_index: usize,
+    pub kind: ProbeKind,
+    pub object_name: String,
+    pub query: String,
+    /// Set when the target can't run the query; the probe is reported as not checked
+    #[serde(default)]
+    pub skipped_because: Option<String>,
+}
+
+/// Outcome of one probe, tied to the step it was run for
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreflightFinding {
+    pub step_index: usize,
+    pub step_description: String,
+    pub object_name: String,
+    pub kind: ProbeKind,
+    pub severity: WarningSeverity,
+    pub message: String,
+    pub value: Option<i64>, // rows, or bytes for table sizes; None if the probe failed
+    pub query: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreflightReport {
+    pub findings: Vec<PreflightFinding>,
+    /// Number of findings that will make a step fail
+    pub blocking_count: usize,
+}
+
+/// The migration plan plus the probes for its steps, in plan order. Probes
+/// use the names objects have on the target *before* the migration, so
+/// renamed tables and columns are looked up under their old names.
+pub fn build_preflight_probes(
+    comparison: &SchemaComparison,
+    options: &MigrationScriptOptions,
+) -> (MigrationPlan, Vec<PreflightProbe>) {
+    let comparison = apply_accepted_renames(comparison);
+    let plan = build_migration_plan(&comparison, options);
+    let schema = comparison.schema_name.as_str();
+    // Unknown versions are probed anyway; a failing query is reported as not checked
+    let can_check_casts = comparison.target_server_version.is_none_or(|v| v >= 160000);
+    let mut probes = Vec::new();
+
+    for (step_index, step) in plan.steps.iter().enumerate() {
+        let (table_name, member) = step.object_name.split_once('.').unwrap_or((&step.object_name, ""));
+        let Some(table_diff) = comparison
+            .table_differences
+            .iter()
+            .find(|t| t.table_name == table_name && matches!(t.status, DiffStatus::Modified))
+        else {
+            continue;
+        };
+        // Name of the table on the target as it is now
+        let table = table_diff.renamed_from.as_deref().unwrap_or(&table_diff.table_name);
+
+        let mut probe = |kind: ProbeKind, query: String| {
+            let skipped_because = (kind == ProbeKind::InvalidCasts && !can_check_casts)
+                .then(|| "checking conversions requires PostgreSQL 16 or later".to_string());
+            probes.push(PreflightProbe {
+                step_index,
+                kind,
+                object_name: step.object_name.clone(),
+                query,
+                skipped_because,
+            });
+        };
+
+        match step.kind {
+            StepKind::AlterColumn => {
+                let Some(col_change) = find_column(table_diff, member) else {
+                    continue;
+                };
+                let (Some(source), Some(target)) = (&col_change.source_definition, &col_change.target_definition)
+                else {
+                    continue;
+                };
+                let column = target.column_name.as_str();
+                let type_changed = has_change(col_change, "type:") || has_change(col_change, "max_length:");
+
+                if has_change(col_change, "nullable:") && source.is_nullable != SQL_NULLABLE_YES {
+                    probe(ProbeKind::NullValues, null_check_query(schema, table, column));
+                }
+                if type_changed {
+                    probe(
+                        ProbeKind::InvalidCasts,
+                        invalid_cast_query(schema, table, column, &column_type_sql(source)),
+                    );
+                }
+                if (type_changed && type_change_rewrites(source, target)) || has_change(col_change, "nullable:") {
+                    probe(ProbeKind::TableSize, table_size_query(schema, table));
+                }
+            }
+            StepKind::AddColumn => {
+                let Some(source) = find_column(table_diff, member).and_then(|c| c.source_definition.as_ref())
+                else {
+                    continue;
+                };
+                if source.is_nullable != SQL_NULLABLE_YES && source.column_default.is_none() {
+                    probe(ProbeKind::ExistingRows, existing_rows_query(schema, table));
+                }
+            }
+            StepKind::CreateIndex => {
+                let Some(idx) = table_diff
+                    .index_changes
+                    .iter()
+                    .find(|i| i.index_name == member)
+                    .and_then(|i| i.source_definition.as_ref())
+                else {
+                    continue;
+                };
+                if idx.is_unique {
+                    // Key columns may be renamed by an earlier step; compare against the old names
+                    let mut target_idx = idx.clone();
+                    for key in &mut target_idx.key_columns {
+                        key.expression = target_column_name(table_diff, &key.expression);
+                    }
+                    target_idx.columns = target_idx
+                        .columns
+                        .iter()
+                        .map(|c| target_column_name(table_diff, c))
+                        .collect();
+                    if let Some(query) = duplicate_check_query(schema, table, &target_idx) {
+                        probe(ProbeKind::DuplicateKeys, query);
+                    }
+                }
+                probe(ProbeKind::TableSize, table_size_query(schema, table));
+            }
+            StepKind::AddForeignKey => {
+                let Some(fk) = table_diff
+                    .fk_changes
+                    .iter()
+                    .find(|f| f.constraint_name == member)
+                    .and_then(|f| f.source_definition.as_ref())
+                else {
+                    continue;
+                };
+                let Some(referenced) = comparison
+                    .table_differences
+                    .iter()
+                    .find(|t| t.table_name == fk.foreign_table_name && !matches!(t.status, DiffStatus::Added))
+                else {
+                    continue;
+                };
+                let referenced_table = referenced.renamed_from.as_deref().unwrap_or(&referenced.table_name);
+                probe(
+                    ProbeKind::OrphanedRows,
+                    orphan_check_query(
+                        schema,
+                        table,
+                        &target_column_name(table_diff, &fk.column_name),
+                        referenced_table,
+                        &target_column_name(referenced, &fk.foreign_column_name),
+                    ),
+                );
+                probe(ProbeKind::TableSize, table_size_query(schema, table));
+            }
+            StepKind::AddCheckConstraint => {
+                let Some(check) = table_diff
+                    .check_changes
+                    .iter()
+                    .find(|c| c.constraint_name == member)
+                    .and_then(|c| c.source_definition.as_ref())
+                else {
+                    continue;
+                };
+                // The expression names columns as they are after the
+                // migration, so it can only be checked when none are new or renamed
+                let pending = check.columns.iter().any(|column| {
+                    find_column(table_diff, column).is_some_and(|c| {
+                        matches!(c.status, DiffStatus::Added) || target_column_name(table_diff, column) != *column
+                    })
+                });
+                if pending {
+                    continue;
+                }
+                if let Some(query) = check_violation_query(schema, table, check) {
+                    probe(ProbeKind::CheckViolations, query);
+                }
+                probe(ProbeKind::TableSize, table_size_query(schema, table));
+            }
+            _ => {}
+        }
+    }
+
+    (plan, probes)
+}
+
+/// Turn a probe result into a finding. A non-zero count means the step will
+/// fail, except for table sizes which only tell how long it holds its lock.
+pub fn preflight_finding(
+    probe: &PreflightProbe,
+    step_description: &str,
+    result: Result<i64, String>,
+) -> PreflightFinding {
+    let object = &probe.object_name;
+    let (severity, message, value) = match result {
+        Err(e) => (WarningSeverity::Low, format!("Could not check {}: {}", object, e), None),
+        Ok(bytes) if probe.kind == ProbeKind::TableSize => {
+            let severity = if bytes > PREFLIGHT_LARGE_TABLE_BYTES {
+                WarningSeverity::Medium
+            } else {
+                WarningSeverity::Low
+            };
+            let message = format!(
+                "Table is {}; this step scans or rewrites it while holding a lock",
+                format_size(bytes)
+            );
+            (severity, message, Some(bytes))
+        }
+        Ok(0) => {
+            let message = match probe.kind {
+                ProbeKind::NullValues => "No NULL values",
+                ProbeKind::InvalidCasts => "All values convert to the new type",
+                ProbeKind::DuplicateKeys => "No duplicate keys",
+                ProbeKind::OrphanedRows => "Every row has a matching referenced row",
+                ProbeKind::CheckViolations => "Every row passes the check",
+                ProbeKind::ExistingRows => "Table is empty",
+                ProbeKind::TableSize => unreachable!("handled above"),
+            };
+            (WarningSeverity::Low, message.to_string(), Some(0))
+        }
+        Ok(count) => {
+            let message = match probe.kind {
+                ProbeKind::NullValues => {
+                    format!("{} row(s) have NULL in {}; SET NOT NULL will fail", count, object)
+                }
+                ProbeKind::InvalidCasts => {
+                    format!("{} value(s) in {} can't be converted to the new type", count, object)
+                }
+                ProbeKind::DuplicateKeys => {
+                    format!("{} key value(s) are duplicated; the unique index will fail to build", count)
+                }
+                ProbeKind::OrphanedRows => {
+                    format!("{} row(s) reference rows that don't exist; the foreign key will fail", count)
+                }
+                ProbeKind::CheckViolations => {
+                    format!("{} row(s) violate {}; the check constraint will fail", count, object)
+                }
+                ProbeKind::ExistingRows => {
+                    "Table has rows; a NOT NULL column without a default can't be added".to_string()
+                }
+                ProbeKind::TableSize => unreachable!("handled above"),
+            };
+            (WarningSeverity::High, message, Some(count))
+        }
+    };
+
+    PreflightFinding {
+        step_index: probe.step_index,
+        step_description: step_description.to_string(),
+        object_name: probe.object_name.clone(),
+        kind: probe.kind,
+        severity,
+        message,
+        value,
+        query: probe.query.clone(),
+    }
+}
+
+fn format_size(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["bytes", "kB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn find_column<'a>(table_diff: &'a TableDifference, column_name: &str) -> Option<&'a ColumnChange> {
+    table_diff
+        .column_changes
+        .iter()
+        .find(|c| c.column_name == column_name)
+}
+
+fn has_change(col_change: &ColumnChange, prefix: &str) -> bool {
+    col_change.changes.iter().any(|c| c.starts_with(prefix))
+}
+
+// The current name of a column that may be renamed by the migration
+fn target_column_name(table_diff: &TableDifference, column_name: &str) -> String {
+    find_column(table_diff, column_name)
+        .and_then(|c| c.target_definition.as_ref())
+        .map(|c| c.column_name.clone())
+        .unwrap_or_else(|| column_name.to_string())
+}
+
+// pg_input_is_valid needs PostgreSQL 16 or later; older targets skip the probe
+fn invalid_cast_query(schema: &str, table: &str, column: &str, new_type: &str) -> String {
+    format!(
+        "SELECT count(*) FROM {} WHERE {} IS NOT NULL AND NOT pg_input_is_valid({}::text, {})",
+        qualified_name(schema, table),
+        quote_ident(column),
+        quote_ident(column),
+        quote_literal(new_type)
+    )
+}
+
+fn table_size_query(schema: &str, table: &str) -> String {
+    format!(
+        "SELECT coalesce(pg_total_relation_size(to_regclass({})), 0)::bigint",
+        quote_literal(&qualified_name(schema, table))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EnhancedColumnInfo, IndexInfo, IndexKeyColumn};
+    use crate::utils::renames::{RenameCandidate, RENAME_OBJECT_COLUMN, RENAME_OBJECT_TABLE};
+    use crate::utils::schema_diff::{comparison_summary, IndexChange};
+
+    fn column(name: &str, data_type: &str, nullable: bool, position: i32) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            column_name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: if nullable { "YES" } else { "NO" }.to_string(),
+            is_primary_key: false,
+            column_default: None,
+            character_maximum_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: position,
+            comment: None,
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        }
+    }
+
+    fn table(name: &str, status: DiffStatus, columns: Vec<EnhancedColumnInfo>) -> TableDifference {
+        let added = matches!(status, DiffStatus::Added);
+        TableDifference {
+            table_name: name.to_string(),
+            status: status.clone(),
+            column_changes: columns
+                .into_iter()
+                .map(|c| ColumnChange {
+                    column_name: c.column_name.clone(),
+                    status: status.clone(),
+                    source_definition: added.then(|| c.clone()),
+                    target_definition: (!added).then_some(c),
+                    changes: Vec::new(),
+                })
+                .collect(),
+            index_changes: Vec::new(),
+            fk_changes: Vec::new(),
+            check_changes: Vec::new(),
+            source_comment: None,
+            target_comment: None,
+            renamed_from: None,
+        }
+    }
+
+    fn rename(object_type: &str, old_name: &str, new_name: &str) -> RenameCandidate {
+        RenameCandidate {
+            object_type: object_type.to_string(),
+            table_name: "orders".to_string(),
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            reason: String::new(),
+            accepted: true,
+        }
+    }
+
+    // legacy_orders(id, cust integer NULL) on the target becomes
+    // orders(id, customer_id bigint NOT NULL UNIQUE) through accepted renames
+    fn renamed_orders(server_version: Option<i32>) -> SchemaComparison {
+        let mut orders = table(
+            "orders",
+            DiffStatus::Added,
+            vec![column("id", "integer", false, 1), column("customer_id", "bigint", false, 2)],
+        );
+        orders.index_changes.push(IndexChange {
+            index_name: "orders_customer_id_key".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(IndexInfo {
+                index_name: "orders_customer_id_key".to_string(),
+                table_name: "orders".to_string(),
+                columns: vec!["customer_id".to_string()],
+                is_unique: true,
+                is_primary: false,
+                definition: "CREATE UNIQUE INDEX orders_customer_id_key ON public.orders USING btree (customer_id)"
+                    .to_string(),
+                access_method: "btree".to_string(),
+                key_columns: vec![IndexKeyColumn {
+                    expression: "customer_id".to_string(),
+                    is_expression: false,
+                    descending: false,
+                    nulls_first: false,
+                    opclass: None,
+                }],
+                include_columns: Vec::new(),
+                predicate: None,
+                is_valid: true,
+            }),
+            target_definition: None,
+        });
+        let legacy = table(
+            "legacy_orders",
+            DiffStatus::Removed,
+            vec![column("id", "integer", false, 1), column("cust", "integer", true, 2)],
+        );
+        let tables = vec![orders, legacy];
+
+        SchemaComparison {
+            source_connection: "dev".to_string(),
+            target_connection: "prod".to_string(),
+            schema_name: "public".to_string(),
+            summary: comparison_summary(&tables, &[], &[], &[], &[]),
+            table_differences: tables,
+            view_differences: Vec::new(),
+            routine_differences: Vec::new(),
+            extension_differences: Vec::new(),
+            type_differences: Vec::new(),
+            rename_candidates: vec![
+                rename(RENAME_OBJECT_TABLE, "legacy_orders", "orders"),
+                rename(RENAME_OBJECT_COLUMN, "cust", "customer_id"),
+            ],
+            warnings: Vec::new(),
+            target_server_version: server_version,
+        }
+    }
+
+    fn probe(kind: ProbeKind) -> PreflightProbe {
+        PreflightProbe {
+            step_index: 3,
+            kind,
+            object_name: "orders.customer_id".to_string(),
+            query: "SELECT 1".to_string(),
+            skipped_because: None,
+        }
+    }
+
+    #[test]
+    fn probes_point_at_their_steps_and_use_target_names() {
+        let (plan, probes) = build_preflight_probes(&renamed_orders(Some(160000)), &MigrationScriptOptions::default());
+
+        let found: Vec<(StepKind, ProbeKind)> = probes
+            .iter()
+            .map(|p| {
+                let step = &plan.steps[p.step_index];
+                assert_eq!(step.object_name, p.object_name);
+                (step.kind, p.kind)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (StepKind::AlterColumn, ProbeKind::NullValues),
+                (StepKind::AlterColumn, ProbeKind::InvalidCasts),
+                (StepKind::AlterColumn, ProbeKind::TableSize),
+                (StepKind::CreateIndex, ProbeKind::DuplicateKeys),
+                (StepKind::CreateIndex, ProbeKind::TableSize),
+            ]
+        );
+
+        // Probes run before the renames, so they read the old table and column
+        let query = |kind: ProbeKind| probes.iter().find(|p| p.kind == kind).unwrap().query.as_str();
+        assert_eq!(
+            query(ProbeKind::NullValues),
+            "SELECT count(*) FROM public.legacy_orders WHERE cust IS NULL"
+        );
+        assert_eq!(
+            query(ProbeKind::InvalidCasts),
+            "SELECT count(*) FROM public.legacy_orders WHERE cust IS NOT NULL \
+             AND NOT pg_input_is_valid(cust::text, 'bigint')"
+        );
+        assert!(query(ProbeKind::DuplicateKeys).contains("FROM public.legacy_orders"));
+        assert!(query(ProbeKind::DuplicateKeys).contains("cust IS NOT NULL"));
+        assert!(query(ProbeKind::TableSize).contains("'public.legacy_orders'"));
+        assert!(probes.iter().all(|p| p.skipped_because.is_none()));
+    }
+
+    #[test]
+    fn cast_probe_is_skipped_before_postgres_16() {
+        for (server_version, skipped) in [(Some(150004), true), (Some(160000), false), (None, false)] {
+            let (_, probes) =
+                build_preflight_probes(&renamed_orders(server_version), &MigrationScriptOptions::default());
+            for p in &probes {
+                let expect_skip = skipped && p.kind == ProbeKind::InvalidCasts;
+                assert_eq!(p.skipped_because.is_some(), expect_skip, "{:?} on {:?}", p.kind, server_version);
+            }
+        }
+
+        let mut skipped = probe(ProbeKind::InvalidCasts);
+        skipped.skipped_because = Some("checking conversions requires PostgreSQL 16 or later".to_string());
+        let finding = preflight_finding(&skipped, "Alter column", Err(skipped.skipped_because.clone().unwrap()));
+        assert_eq!(finding.severity, WarningSeverity::Low);
+        assert_eq!(finding.value, None);
+        assert_eq!(
+            finding.message,
+            "Could not check orders.customer_id: checking conversions requires PostgreSQL 16 or later"
+        );
+    }
+
+    #[test]
+    fn findings_are_high_only_when_a_step_will_fail() {
+        let counted = [
+            ProbeKind::NullValues,
+            ProbeKind::InvalidCasts,
+            ProbeKind::DuplicateKeys,
+            ProbeKind::OrphanedRows,
+            ProbeKind::CheckViolations,
+            ProbeKind::ExistingRows,
+        ];
+        for kind in counted {
+            let clean = preflight_finding(&probe(kind), "step", Ok(0));
+            assert_eq!((clean.severity, clean.value), (WarningSeverity::Low, Some(0)), "{:?}", kind);
+            let failing = preflight_finding(&probe(kind), "step", Ok(3));
+            assert_eq!((failing.severity, failing.value), (WarningSeverity::High, Some(3)), "{:?}", kind);
+            let failed = preflight_finding(&probe(kind), "step", Err("timeout".to_string()));
+            assert_eq!((failed.severity, failed.value), (WarningSeverity::Low, None), "{:?}", kind);
+        }
+
+        // Sizes only say how long the lock is held
+        let small = preflight_finding(&probe(ProbeKind::TableSize), "step", Ok(8192));
+        assert_eq!(small.severity, WarningSeverity::Low);
+        assert_eq!(small.message, "Table is 8.0 kB; this step scans or rewrites it while holding a lock");
+        let large = preflight_finding(&probe(ProbeKind::TableSize), "step", Ok(PREFLIGHT_LARGE_TABLE_BYTES + 1));
+        assert_eq!(large.severity, WarningSeverity::Medium);
+
+        let finding = preflight_finding(&probe(ProbeKind::NullValues), "Alter orders.customer_id", Ok(2));
+        assert_eq!(finding.step_index, 3);
+        assert_eq!(finding.step_description, "Alter orders.customer_id");
+        assert_eq!(finding.query, "SELECT 1");
+    }
+}
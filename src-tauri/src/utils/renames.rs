@@ -0,0 +1,525 @@
+use crate::models::{CheckConstraintInfo, EnhancedColumnInfo, ForeignKeyInfo, IndexInfo};
+use crate::utils::schema_diff::{
+    compare_check_constraints, compare_columns, compare_foreign_keys, compare_indexes, ColumnChange,
+    ComparisonWarning, DiffStatus, SchemaComparison, TableDifference,
+};
+use crate::utils::sql::{qualified_name, quote_ident};
+use crate::utils::warning_rules::generate_warnings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const RENAME_OBJECT_TABLE: &str = "table";
+pub const RENAME_OBJECT_COLUMN: &str = "column";
+
+/// A dropped object and an added object that look like the same object
+/// under a new name. Nothing is renamed unless the user accepts it; otherwise
+/// the migration drops the old object and creates the new one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenameCandidate {
+    pub object_type: String, // 'table' or 'column'
+    pub table_name: String,  // the (new) table name; for tables the same as new_name
+    pub old_name: String,
+    pub new_name: String,
+    pub reason: String,
+    #[serde(default)]
+    pub accepted: bool,
+}
+
+// Heuristic: a removed and an added column with the same type, nullability and
+// default are likely a rename; the closest ordinal position wins
+pub(crate) fn detect_rename_candidates(table_diffs: &[TableDifference]) -> Vec<RenameCandidate> {
+    let mut candidates = detect_table_renames(table_diffs);
+
+    for table_diff in table_diffs {
+        if !matches!(table_diff.status, DiffStatus::Modified) {
+            continue;
+        }
+
+        let added: Vec<&EnhancedColumnInfo> = table_diff
+            .column_changes
+            .iter()
+            .filter(|c| matches!(c.status, DiffStatus::Added))
+            .filter_map(|c| c.source_definition.as_ref())
+            .collect();
+        let mut claimed: HashSet<&str> = HashSet::new();
+
+        let removed = table_diff
+            .column_changes
+            .iter()
+            .filter(|c| matches!(c.status, DiffStatus::Removed))
+            .filter_map(|c| c.target_definition.as_ref());
+
+        for old_column in removed {
+            let best_match = added
+                .iter()
+                .filter(|new_column| !claimed.contains(new_column.column_name.as_str()))
+                .filter(|new_column| same_column_shape(old_column, new_column))
+                .min_by_key(|new_column| (new_column.ordinal_position - old_column.ordinal_position).abs());
+
+            if let Some(new_column) = best_match {
+                claimed.insert(new_column.column_name.as_str());
+                let reason = if new_column.ordinal_position == old_column.ordinal_position {
+                    "Same type, nullability, default and position"
+                } else {
+                    "Same type, nullability and default"
+                };
+                candidates.push(RenameCandidate {
+                    object_type: RENAME_OBJECT_COLUMN.to_string(),
+                    table_name: table_diff.table_name.clone(),
+                    old_name: old_column.column_name.clone(),
+                    new_name: new_column.column_name.clone(),
+                    reason: reason.to_string(),
+                    accepted: false,
+                });
+            }
+        }
+    }
+
+    candidates
+}
+
+// A removed and an added table with exactly the same columns (names and types)
+fn detect_table_renames(table_diffs: &[TableDifference]) -> Vec<RenameCandidate> {
+    let mut candidates = Vec::new();
+    let mut claimed: HashSet<&str> = HashSet::new();
+
+    let added: Vec<&TableDifference> = table_diffs
+        .iter()
+        .filter(|t| matches!(t.status, DiffStatus::Added))
+        .collect();
+
+    for old_table in table_diffs.iter().filter(|t| matches!(t.status, DiffStatus::Removed)) {
+        let old_signature = column_signature(&table_columns(old_table, false));
+        let new_table = added.iter().find(|new_table| {
+            !claimed.contains(new_table.table_name.as_str())
+                && column_signature(&table_columns(new_table, true)) == old_signature
+        });
+
+        if let Some(new_table) = new_table {
+            claimed.insert(new_table.table_name.as_str());
+            candidates.push(RenameCandidate {
+                object_type: RENAME_OBJECT_TABLE.to_string(),
+                table_name: new_table.table_name.clone(),
+                old_name: old_table.table_name.clone(),
+                new_name: new_table.table_name.clone(),
+                reason: "Same column names and types".to_string(),
+                accepted: false,
+            });
+        }
+    }
+
+    candidates
+}
+
+/// Rewrite the comparison so that accepted renames become modifications of
+/// the renamed object instead of a drop plus a create
+pub fn apply_accepted_renames(comparison: &SchemaComparison) -> SchemaComparison {
+    let mut resolved = comparison.clone();
+    let accepted: Vec<RenameCandidate> = resolved
+        .rename_candidates
+        .drain(..)
+        .filter(|c| c.accepted)
+        .collect();
+
+    if accepted.is_empty() {
+        return resolved;
+    }
+
+    // Tables first, so column renames inside a renamed table find it by its new name
+    for candidate in accepted.iter().filter(|c| c.object_type == RENAME_OBJECT_TABLE) {
+        apply_table_rename(&mut resolved, candidate);
+    }
+    for candidate in accepted.iter().filter(|c| c.object_type == RENAME_OBJECT_COLUMN) {
+        if let Some(table_diff) = resolved
+            .table_differences
+            .iter_mut()
+            .find(|t| t.table_name == candidate.table_name)
+        {
+            apply_column_rename(table_diff, candidate);
+        }
+    }
+
+    resolved.warnings = comparison_warnings(&resolved);
+
+    resolved
+}
+
+/// The warnings for the comparison as it will be migrated, with accepted
+/// renames applied. They come straight from the rules, so any checks against
+/// the target's rows have to be run again.
+pub fn resolved_warnings(comparison: &SchemaComparison) -> Vec<ComparisonWarning> {
+    comparison_warnings(&apply_accepted_renames(comparison))
+}
+
+fn comparison_warnings(comparison: &SchemaComparison) -> Vec<ComparisonWarning> {
+    generate_warnings(
+        &comparison.schema_name,
+        &comparison.table_differences,
+        &comparison.view_differences,
+        &comparison.routine_differences,
+        &comparison.extension_differences,
+    )
+}
+
+fn apply_table_rename(comparison: &mut SchemaComparison, candidate: &RenameCandidate) {
+    let tables = &mut comparison.table_differences;
+    let Some(old_pos) = tables
+        .iter()
+        .position(|t| t.table_name == candidate.old_name && matches!(t.status, DiffStatus::Removed))
+    else {
+        return;
+    };
+    let old_table = tables.remove(old_pos);
+    let Some(new_table) = tables
+        .iter_mut()
+        .find(|t| t.table_name == candidate.new_name && matches!(t.status, DiffStatus::Added))
+    else {
+        tables.insert(old_pos, old_table);
+        return;
+    };
+
+    let schema = comparison.schema_name.as_str();
+    let source_columns = table_columns(new_table, true);
+    let target_columns = table_columns(&old_table, false);
+    let source_indexes: Vec<IndexInfo> = new_table
+        .index_changes
+        .iter()
+        .filter_map(|i| i.source_definition.clone())
+        .collect();
+    // Index definitions name the table, which would make every index look changed
+    let target_indexes: Vec<IndexInfo> = old_table
+        .index_changes
+        .iter()
+        .filter_map(|i| i.target_definition.clone())
+        .map(|mut idx| {
+            idx.table_name = candidate.new_name.clone();
+            for (old_ref, new_ref) in [
+                (qualified_name(schema, &candidate.old_name), qualified_name(schema, &candidate.new_name)),
+                (quote_ident(&candidate.old_name), quote_ident(&candidate.new_name)),
+            ] {
+                idx.definition = idx
+                    .definition
+                    .replace(&format!(" ON {} ", old_ref), &format!(" ON {} ", new_ref))
+                    .replace(&format!(" ON ONLY {} ", old_ref), &format!(" ON ONLY {} ", new_ref));
+            }
+            idx
+        })
+        .collect();
+    let source_fks: Vec<ForeignKeyInfo> = new_table
+        .fk_changes
+        .iter()
+        .filter_map(|f| f.source_definition.clone())
+        .collect();
+    let target_fks: Vec<ForeignKeyInfo> = old_table
+        .fk_changes
+        .iter()
+        .filter_map(|f| f.target_definition.clone())
+        .collect();
+
+    let source_checks: Vec<CheckConstraintInfo> = new_table
+        .check_changes
+        .iter()
+        .filter_map(|c| c.source_definition.clone())
+        .collect();
+    let target_checks: Vec<CheckConstraintInfo> = old_table
+        .check_changes
+        .iter()
+        .filter_map(|c| c.target_definition.clone())
+        .collect();
+
+    new_table.status = DiffStatus::Modified;
+    new_table.renamed_from = Some(candidate.old_name.clone());
+    new_table.column_changes = compare_columns(&source_columns, &target_columns);
+    new_table.index_changes = compare_indexes(&source_indexes, &target_indexes);
+    new_table.fk_changes = compare_foreign_keys(&source_fks, &target_fks);
+    new_table.check_changes = compare_check_constraints(&source_checks, &target_checks);
+    new_table.target_comment = old_table.target_comment;
+
+    comparison.summary.tables_added = comparison.summary.tables_added.saturating_sub(1);
+    comparison.summary.tables_removed = comparison.summary.tables_removed.saturating_sub(1);
+    comparison.summary.tables_modified += 1;
+}
+
+fn apply_column_rename(table_diff: &mut TableDifference, candidate: &RenameCandidate) {
+    let changes = &mut table_diff.column_changes;
+    let is_old = |c: &ColumnChange| {
+        c.column_name == candidate.old_name && matches!(c.status, DiffStatus::Removed)
+    };
+    let is_new = |c: &ColumnChange| {
+        c.column_name == candidate.new_name && matches!(c.status, DiffStatus::Added)
+    };
+    if !changes.iter().any(is_old) || !changes.iter().any(is_new) {
+        return;
+    }
+
+    let (pair, rest): (Vec<ColumnChange>, Vec<ColumnChange>) =
+        changes.drain(..).partition(|c| is_old(c) || is_new(c));
+    *changes = rest;
+    let old_column = pair.iter().find(|c| is_old(c)).cloned();
+    let new_column = pair.iter().find(|c| is_new(c)).cloned();
+
+    let (Some(source), Some(mut target)) = (
+        new_column.and_then(|c| c.source_definition),
+        old_column.and_then(|c| c.target_definition),
+    ) else {
+        return;
+    };
+
+    // Compare as if the old column already had the new name, then record the rename itself
+    target.column_name = source.column_name.clone();
+    let mut renamed = compare_columns(std::slice::from_ref(&source), std::slice::from_ref(&target))
+        .pop()
+        .expect("one column compared");
+    renamed.status = DiffStatus::Modified;
+    renamed
+        .changes
+        .insert(0, format!("name: {} → {}", candidate.old_name, candidate.new_name));
+    target.column_name = candidate.old_name.clone();
+    renamed.target_definition = Some(target);
+
+    changes.push(renamed);
+    changes.sort_by_key(|c| {
+        c.source_definition
+            .as_ref()
+            .or(c.target_definition.as_ref())
+            .map(|d| d.ordinal_position)
+    });
+}
+
+// Columns of an added (source) or removed (target) table, in ordinal order
+fn table_columns(table_diff: &TableDifference, from_source: bool) -> Vec<EnhancedColumnInfo> {
+    let mut columns: Vec<EnhancedColumnInfo> = table_diff
+        .column_changes
+        .iter()
+        .filter_map(|c| {
+            if from_source {
+                c.source_definition.clone()
+            } else {
+                c.target_definition.clone()
+            }
+        })
+        .collect();
+    columns.sort_by_key(|c| c.ordinal_position);
+    columns
+}
+
+fn column_signature(columns: &[EnhancedColumnInfo]) -> Vec<(String, String)> {
+    let mut signature: Vec<(String, String)> = columns
+        .iter()
+        .map(|c| (c.column_name.clone(), c.data_type.clone()))
+        .collect();
+    signature.sort();
+    signature
+}
+
+fn same_column_shape(a: &EnhancedColumnInfo, b: &EnhancedColumnInfo) -> bool {
+    a.data_type == b.data_type
+        && a.udt_name == b.udt_name
+        && a.character_maximum_length == b.character_maximum_length
+        && a.numeric_precision == b.numeric_precision
+        && a.numeric_scale == b.numeric_scale
+        && a.is_nullable == b.is_nullable
+        && a.column_default == b.column_default
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, position: i32) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            column_name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: "YES".to_string(),
+            is_primary_key: false,
+            column_default: None,
+            character_maximum_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: position,
+            comment: None,
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        }
+    }
+
+    fn change(status: DiffStatus, column: EnhancedColumnInfo) -> ColumnChange {
+        let (source_definition, target_definition) = match status {
+            DiffStatus::Removed => (None, Some(column.clone())),
+            _ => (Some(column.clone()), None),
+        };
+        ColumnChange {
+            column_name: column.column_name,
+            status,
+            source_definition,
+            target_definition,
+            changes: Vec::new(),
+        }
+    }
+
+    fn table(name: &str, status: DiffStatus, column_changes: Vec<ColumnChange>) -> TableDifference {
+        TableDifference {
+            table_name: name.to_string(),
+            status,
+            column_changes,
+            index_changes: Vec::new(),
+            fk_changes: Vec::new(),
+            check_changes: Vec::new(),
+            source_comment: None,
+            target_comment: None,
+            renamed_from: None,
+        }
+    }
+
+    fn renames(candidates: &[RenameCandidate]) -> Vec<(&str, &str, &str, &str)> {
+        candidates
+            .iter()
+            .map(|c| {
+                (
+                    c.object_type.as_str(),
+                    c.table_name.as_str(),
+                    c.old_name.as_str(),
+                    c.new_name.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn column_with_the_same_shape_is_a_rename_candidate() {
+        let users = table(
+            "users",
+            DiffStatus::Modified,
+            vec![
+                change(DiffStatus::Removed, column("email", "text", 2)),
+                change(DiffStatus::Added, column("email_address", "text", 2)),
+                change(DiffStatus::Removed, column("age", "integer", 3)),
+                change(DiffStatus::Added, column("born", "date", 3)),
+            ],
+        );
+
+        let candidates = detect_rename_candidates(&[users]);
+
+        assert_eq!(
+            renames(&candidates),
+            vec![("column", "users", "email", "email_address")]
+        );
+        assert_eq!(
+            candidates[0].reason,
+            "Same type, nullability, default and position"
+        );
+        assert!(!candidates[0].accepted);
+    }
+
+    #[test]
+    fn closest_position_wins_and_each_new_column_is_claimed_once() {
+        let users = table(
+            "users",
+            DiffStatus::Modified,
+            vec![
+                change(DiffStatus::Removed, column("a", "text", 5)),
+                change(DiffStatus::Removed, column("b", "text", 1)),
+                change(DiffStatus::Added, column("x", "text", 2)),
+                change(DiffStatus::Added, column("y", "text", 4)),
+            ],
+        );
+
+        let candidates = detect_rename_candidates(&[users]);
+
+        assert_eq!(
+            renames(&candidates),
+            vec![("column", "users", "a", "y"), ("column", "users", "b", "x")]
+        );
+        assert_eq!(candidates[0].reason, "Same type, nullability and default");
+    }
+
+    #[test]
+    fn table_with_the_same_columns_is_a_rename_candidate() {
+        let columns = || vec![column("id", "integer", 1), column("name", "text", 2)];
+        let old = table(
+            "users",
+            DiffStatus::Removed,
+            columns()
+                .into_iter()
+                .map(|c| change(DiffStatus::Removed, c))
+                .collect(),
+        );
+        let new = table(
+            "accounts",
+            DiffStatus::Added,
+            columns()
+                .into_iter()
+                .map(|c| change(DiffStatus::Added, c))
+                .collect(),
+        );
+        let other = table(
+            "people",
+            DiffStatus::Added,
+            vec![change(DiffStatus::Added, column("id", "bigint", 1))],
+        );
+
+        let candidates = detect_rename_candidates(&[old, other, new]);
+
+        assert_eq!(
+            renames(&candidates),
+            vec![("table", "accounts", "users", "accounts")]
+        );
+    }
+
+    #[test]
+    fn accepted_rename_drops_the_warnings_about_the_old_column() {
+        let users = table(
+            "users",
+            DiffStatus::Modified,
+            vec![
+                change(DiffStatus::Removed, column("email", "text", 2)),
+                change(DiffStatus::Added, column("email_address", "text", 2)),
+            ],
+        );
+        let mut comparison = SchemaComparison {
+            source_connection: "dev".to_string(),
+            target_connection: "prod".to_string(),
+            schema_name: "public".to_string(),
+            summary: crate::utils::schema_diff::comparison_summary(
+                std::slice::from_ref(&users),
+                &[],
+                &[],
+                &[],
+                &[],
+            ),
+            rename_candidates: detect_rename_candidates(std::slice::from_ref(&users)),
+            table_differences: vec![users],
+            view_differences: Vec::new(),
+            routine_differences: Vec::new(),
+            extension_differences: Vec::new(),
+            type_differences: Vec::new(),
+            warnings: Vec::new(),
+            target_server_version: None,
+        };
+        let about_email = |warnings: &[ComparisonWarning]| {
+            warnings
+                .iter()
+                .filter(|w| w.affected_object.contains("email"))
+                .count()
+        };
+
+        assert!(about_email(&resolved_warnings(&comparison)) > 0);
+
+        comparison.rename_candidates[0].accepted = true;
+        assert_eq!(about_email(&resolved_warnings(&comparison)), 0);
+    }
+
+    #[test]
+    fn unchanged_tables_yield_no_candidates() {
+        let users = table(
+            "users",
+            DiffStatus::Identical,
+            vec![
+                change(DiffStatus::Removed, column("a", "text", 1)),
+                change(DiffStatus::Added, column("b", "text", 1)),
+            ],
+        );
+        assert!(detect_rename_candidates(&[users]).is_empty());
+    }
+}
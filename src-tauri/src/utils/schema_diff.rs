@@ -1,11 +1,15 @@
 use crate::models::{
-    EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, ForeignKeyInfo, IndexInfo,
-    RoutineInfo, ViewInfo,
+    CheckConstraintInfo, EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, ExtensionInfo, ForeignKeyInfo,
+    IndexInfo, RoutineInfo, TypeInfo, ViewInfo,
 };
-use crate::constants::{WARNING_TYPE_DATA_LOSS, WARNING_TYPE_BREAKING_CHANGE, SQL_NULLABLE_YES};
+use crate::utils::renames::{detect_rename_candidates, RenameCandidate};
+use crate::utils::warning_rules::generate_warnings;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
+/// How an object on the source compares to the target. `Added` objects exist
+/// only on the source, so only their `source_definition` is set; `Removed`
+/// objects only have a `target_definition`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum DiffStatus {
@@ -40,6 +44,14 @@ pub struct ForeignKeyChange {
     pub target_definition: Option<ForeignKeyInfo>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckConstraintChange {
+    pub constraint_name: String,
+    pub status: DiffStatus,
+    pub source_definition: Option<CheckConstraintInfo>,
+    pub target_definition: Option<CheckConstraintInfo>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TableDifference {
     pub table_name: String,
@@ -47,6 +59,13 @@ pub struct TableDifference {
     pub column_changes: Vec<ColumnChange>,
     pub index_changes: Vec<IndexChange>,
     pub fk_changes: Vec<ForeignKeyChange>,
+    #[serde(default)]
+    pub check_changes: Vec<CheckConstraintChange>,
+    pub source_comment: Option<String>,
+    pub target_comment: Option<String>,
+    /// Previous name when the user accepted a table rename
+    #[serde(default)]
+    pub renamed_from: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -56,18 +75,45 @@ pub struct ViewChange {
     pub source_definition: Option<String>,
     pub target_definition: Option<String>,
     pub definition_changed: bool,
+    pub source_comment: Option<String>,
+    pub target_comment: Option<String>,
+    #[serde(default)]
+    pub source_depends_on: Vec<String>,
+    #[serde(default)]
+    pub target_depends_on: Vec<String>,
+    #[serde(default)]
+    pub source_column_dependencies: Vec<String>,
+    #[serde(default)]
+    pub target_column_dependencies: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RoutineChange {
-    pub routine_name: String,
+    pub routine_name: String, // with its argument types when known, e.g. "order_total(integer)"
     pub status: DiffStatus,
     pub source_definition: Option<RoutineInfo>,
     pub target_definition: Option<RoutineInfo>,
     pub definition_changed: bool,
+    pub comment_changed: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExtensionChange {
+    pub extension_name: String,
+    pub status: DiffStatus,
+    pub source_definition: Option<ExtensionInfo>,
+    pub target_definition: Option<ExtensionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TypeChange {
+    pub type_name: String,
+    pub status: DiffStatus,
+    pub source_definition: Option<TypeInfo>,
+    pub target_definition: Option<TypeInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum WarningSeverity {
     High,
@@ -82,6 +128,9 @@ pub struct ComparisonWarning {
     pub message: String,
     pub affected_object: String,
     pub details: Option<String>,
+    /// Read-only query counting the rows on the target that trigger this warning
+    #[serde(default)]
+    pub check_query: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -92,17 +141,36 @@ pub struct ComparisonSummary {
     pub indexes_missing: usize,
     pub views_changed: usize,
     pub routines_changed: usize,
+    #[serde(default)]
+    pub extensions_changed: usize,
+    #[serde(default)]
+    pub types_changed: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SchemaComparison {
     pub source_connection: String,
     pub target_connection: String,
+    #[serde(default = "default_schema_name")]
+    pub schema_name: String,
     pub summary: ComparisonSummary,
     pub table_differences: Vec<TableDifference>,
     pub view_differences: Vec<ViewChange>,
     pub routine_differences: Vec<RoutineChange>,
+    #[serde(default)]
+    pub extension_differences: Vec<ExtensionChange>,
+    #[serde(default)]
+    pub type_differences: Vec<TypeChange>,
+    #[serde(default)]
+    pub rename_candidates: Vec<RenameCandidate>,
     pub warnings: Vec<ComparisonWarning>,
+    /// server_version_num of the target, when it was read from a live database
+    #[serde(default)]
+    pub target_server_version: Option<i32>,
+}
+
+fn default_schema_name() -> String {
+    "public".to_string()
 }
 
 // Compare two enhanced database schemas
@@ -111,13 +179,54 @@ pub fn compare_schemas(
     target: &EnhancedDatabaseSchema,
     source_connection: String,
     target_connection: String,
+    schema_name: String,
 ) -> SchemaComparison {
     let table_differences = compare_tables(&source.tables, &target.tables);
     let view_differences = compare_views(&source.views, &target.views);
     let routine_differences = compare_routines(&source.routines, &target.routines);
-    let warnings = generate_warnings(&table_differences, &view_differences, &routine_differences);
+    let extension_differences = compare_extensions(&source.extensions, &target.extensions);
+    let type_differences = compare_types(&source.types, &target.types);
+    let rename_candidates = detect_rename_candidates(&table_differences);
+    let warnings = generate_warnings(
+        &schema_name,
+        &table_differences,
+        &view_differences,
+        &routine_differences,
+        &extension_differences,
+    );
+
+    let summary = comparison_summary(
+        &table_differences,
+        &view_differences,
+        &routine_differences,
+        &extension_differences,
+        &type_differences,
+    );
 
-    let summary = ComparisonSummary {
+    SchemaComparison {
+        source_connection,
+        target_connection,
+        schema_name,
+        summary,
+        table_differences,
+        view_differences,
+        routine_differences,
+        extension_differences,
+        type_differences,
+        rename_candidates,
+        warnings,
+        target_server_version: target.server_version_num,
+    }
+}
+
+pub(crate) fn comparison_summary(
+    table_differences: &[TableDifference],
+    view_differences: &[ViewChange],
+    routine_differences: &[RoutineChange],
+    extension_differences: &[ExtensionChange],
+    type_differences: &[TypeChange],
+) -> ComparisonSummary {
+    ComparisonSummary {
         tables_modified: table_differences
             .iter()
             .filter(|t| matches!(t.status, DiffStatus::Modified))
@@ -143,16 +252,14 @@ pub fn compare_schemas(
             .iter()
             .filter(|r| matches!(r.status, DiffStatus::Modified | DiffStatus::Added | DiffStatus::Removed))
             .count(),
-    };
-
-    SchemaComparison {
-        source_connection,
-        target_connection,
-        summary,
-        table_differences,
-        view_differences,
-        routine_differences,
-        warnings,
+        extensions_changed: extension_differences
+            .iter()
+            .filter(|e| matches!(e.status, DiffStatus::Modified | DiffStatus::Added | DiffStatus::Removed))
+            .count(),
+        types_changed: type_differences
+            .iter()
+            .filter(|t| matches!(t.status, DiffStatus::Modified | DiffStatus::Added | DiffStatus::Removed))
+            .count(),
     }
 }
 
@@ -183,14 +290,19 @@ fn compare_tables(
         let source_table = source_map.get(&table_name);
         let target_table = target_map.get(&table_name);
 
-        let (status, column_changes, index_changes, fk_changes) = match (source_table, target_table) {
+        let (status, column_changes, index_changes, fk_changes, check_changes) = match (source_table, target_table) {
             (Some(src), Some(tgt)) => {
                 // Table exists in both - check for modifications
                 let col_changes = compare_columns(&src.columns, &tgt.columns);
                 let idx_changes = compare_indexes(&src.indexes, &tgt.indexes);
                 let fk_changes = compare_foreign_keys(&src.foreign_keys, &tgt.foreign_keys);
+                let check_changes = compare_check_constraints(&src.check_constraints, &tgt.check_constraints);
 
-                let is_modified = !col_changes.is_empty() || !idx_changes.is_empty() || !fk_changes.is_empty();
+                let is_modified = col_changes.iter().any(|c| !matches!(c.status, DiffStatus::Identical))
+                    || idx_changes.iter().any(|c| !matches!(c.status, DiffStatus::Identical))
+                    || fk_changes.iter().any(|c| !matches!(c.status, DiffStatus::Identical))
+                    || check_changes.iter().any(|c| !matches!(c.status, DiffStatus::Identical))
+                    || src.comment != tgt.comment;
 
                 (
                     if is_modified {
@@ -201,15 +313,29 @@ fn compare_tables(
                     col_changes,
                     idx_changes,
                     fk_changes,
+                    check_changes,
                 )
             }
-            (Some(_), None) => {
-                // Table only in source (will be added to target)
-                (DiffStatus::Added, vec![], vec![], vec![])
+            (Some(src), None) => {
+                // Table only in source (will be added to target); keep its full
+                // definition so the migration can create it
+                (
+                    DiffStatus::Added,
+                    compare_columns(&src.columns, &[]),
+                    compare_indexes(&src.indexes, &[]),
+                    compare_foreign_keys(&src.foreign_keys, &[]),
+                    compare_check_constraints(&src.check_constraints, &[]),
+                )
             }
-            (None, Some(_)) => {
+            (None, Some(tgt)) => {
                 // Table only in target (will be removed from target)
-                (DiffStatus::Removed, vec![], vec![], vec![])
+                (
+                    DiffStatus::Removed,
+                    compare_columns(&[], &tgt.columns),
+                    compare_indexes(&[], &tgt.indexes),
+                    compare_foreign_keys(&[], &tgt.foreign_keys),
+                    compare_check_constraints(&[], &tgt.check_constraints),
+                )
             }
             (None, None) => unreachable!(),
         };
@@ -220,14 +346,20 @@ fn compare_tables(
             column_changes,
             index_changes,
             fk_changes,
+            check_changes,
+            source_comment: source_table.and_then(|t| t.comment.clone()),
+            target_comment: target_table.and_then(|t| t.comment.clone()),
+            renamed_from: None,
         });
     }
 
+    // Stable order so generated migrations are reproducible
+    differences.sort_by(|a, b| a.table_name.cmp(&b.table_name));
     differences
 }
 
 // Compare columns
-fn compare_columns(
+pub(crate) fn compare_columns(
     source_cols: &[EnhancedColumnInfo],
     target_cols: &[EnhancedColumnInfo],
 ) -> Vec<ColumnChange> {
@@ -277,6 +409,15 @@ fn compare_columns(
                         tgt.character_maximum_length, src.character_maximum_length
                     ));
                 }
+                if src.collation_name != tgt.collation_name {
+                    details.push(format!(
+                        "collation: {:?} → {:?}",
+                        tgt.collation_name, src.collation_name
+                    ));
+                }
+                if src.comment != tgt.comment {
+                    details.push(format!("comment: {:?} → {:?}", tgt.comment, src.comment));
+                }
 
                 let status = if details.is_empty() {
                     DiffStatus::Identical
@@ -300,11 +441,17 @@ fn compare_columns(
         });
     }
 
+    changes.sort_by_key(|c| {
+        c.source_definition
+            .as_ref()
+            .or(c.target_definition.as_ref())
+            .map(|d| d.ordinal_position)
+    });
     changes
 }
 
 // Compare indexes
-fn compare_indexes(source_indexes: &[IndexInfo], target_indexes: &[IndexInfo]) -> Vec<IndexChange> {
+pub(crate) fn compare_indexes(source_indexes: &[IndexInfo], target_indexes: &[IndexInfo]) -> Vec<IndexChange> {
     let mut changes = Vec::new();
 
     let source_map: HashMap<String, &IndexInfo> = source_indexes
@@ -328,9 +475,13 @@ fn compare_indexes(source_indexes: &[IndexInfo], target_indexes: &[IndexInfo]) -
         let status = match (source_idx, target_idx) {
             (Some(src), Some(tgt)) => {
                 // Check if index definition changed
+                // An invalid index (e.g. a failed CREATE INDEX CONCURRENTLY) needs rebuilding
                 if src.definition != tgt.definition
                     || src.columns != tgt.columns
                     || src.is_unique != tgt.is_unique
+                    || src.include_columns != tgt.include_columns
+                    || src.predicate != tgt.predicate
+                    || src.is_valid != tgt.is_valid
                 {
                     DiffStatus::Modified
                 } else {
@@ -350,11 +501,12 @@ fn compare_indexes(source_indexes: &[IndexInfo], target_indexes: &[IndexInfo]) -
         });
     }
 
+    changes.sort_by(|a, b| a.index_name.cmp(&b.index_name));
     changes
 }
 
 // Compare foreign keys
-fn compare_foreign_keys(
+pub(crate) fn compare_foreign_keys(
     source_fks: &[ForeignKeyInfo],
     target_fks: &[ForeignKeyInfo],
 ) -> Vec<ForeignKeyChange> {
@@ -402,6 +554,56 @@ fn compare_foreign_keys(
         });
     }
 
+    changes.sort_by(|a, b| a.constraint_name.cmp(&b.constraint_name));
+    changes
+}
+
+pub(crate) fn compare_check_constraints(
+    source_checks: &[CheckConstraintInfo],
+    target_checks: &[CheckConstraintInfo],
+) -> Vec<CheckConstraintChange> {
+    let mut changes = Vec::new();
+
+    let source_map: HashMap<String, &CheckConstraintInfo> = source_checks
+        .iter()
+        .map(|c| (c.constraint_name.clone(), c))
+        .collect();
+
+    let target_map: HashMap<String, &CheckConstraintInfo> = target_checks
+        .iter()
+        .map(|c| (c.constraint_name.clone(), c))
+        .collect();
+
+    let mut all_checks: HashSet<String> = HashSet::new();
+    all_checks.extend(source_map.keys().cloned());
+    all_checks.extend(target_map.keys().cloned());
+
+    for check_name in all_checks {
+        let source_check = source_map.get(&check_name);
+        let target_check = target_map.get(&check_name);
+
+        let status = match (source_check, target_check) {
+            (Some(src), Some(tgt)) => {
+                if src.definition != tgt.definition {
+                    DiffStatus::Modified
+                } else {
+                    DiffStatus::Identical
+                }
+            }
+            (Some(_), None) => DiffStatus::Added,
+            (None, Some(_)) => DiffStatus::Removed,
+            (None, None) => unreachable!(),
+        };
+
+        changes.push(CheckConstraintChange {
+            constraint_name: check_name,
+            status,
+            source_definition: source_check.cloned().cloned(),
+            target_definition: target_check.cloned().cloned(),
+        });
+    }
+
+    changes.sort_by(|a, b| a.constraint_name.cmp(&b.constraint_name));
     changes
 }
 
@@ -431,7 +633,7 @@ fn compare_views(source_views: &[ViewInfo], target_views: &[ViewInfo]) -> Vec<Vi
             (Some(src), Some(tgt)) => {
                 let changed = src.definition.trim() != tgt.definition.trim();
                 (
-                    if changed {
+                    if changed || src.comment != tgt.comment {
                         DiffStatus::Modified
                     } else {
                         DiffStatus::Identical
@@ -450,13 +652,32 @@ fn compare_views(source_views: &[ViewInfo], target_views: &[ViewInfo]) -> Vec<Vi
             source_definition: source_view.map(|v| v.definition.clone()),
             target_definition: target_view.map(|v| v.definition.clone()),
             definition_changed,
+            source_comment: source_view.and_then(|v| v.comment.clone()),
+            target_comment: target_view.and_then(|v| v.comment.clone()),
+            source_depends_on: source_view.map(|v| v.depends_on.clone()).unwrap_or_default(),
+            target_depends_on: target_view.map(|v| v.depends_on.clone()).unwrap_or_default(),
+            source_column_dependencies: source_view
+                .map(|v| v.column_dependencies.clone())
+                .unwrap_or_default(),
+            target_column_dependencies: target_view
+                .map(|v| v.column_dependencies.clone())
+                .unwrap_or_default(),
         });
     }
 
+    changes.sort_by(|a, b| a.view_name.cmp(&b.view_name));
     changes
 }
 
 // Compare routines
+// Overloads share a name, so routines are matched on their argument types too
+pub(crate) fn routine_key(routine: &RoutineInfo) -> String {
+    match &routine.identity_arguments {
+        Some(arguments) => format!("{}({})", routine.routine_name, arguments),
+        None => routine.routine_name.clone(),
+    }
+}
+
 fn compare_routines(
     source_routines: &[RoutineInfo],
     target_routines: &[RoutineInfo],
@@ -465,12 +686,12 @@ fn compare_routines(
 
     let source_map: HashMap<String, &RoutineInfo> = source_routines
         .iter()
-        .map(|r| (r.routine_name.clone(), r))
+        .map(|r| (routine_key(r), r))
         .collect();
 
     let target_map: HashMap<String, &RoutineInfo> = target_routines
         .iter()
-        .map(|r| (r.routine_name.clone(), r))
+        .map(|r| (routine_key(r), r))
         .collect();
 
     let mut all_routines: HashSet<String> = HashSet::new();
@@ -481,22 +702,24 @@ fn compare_routines(
         let source_routine = source_map.get(&routine_name);
         let target_routine = target_map.get(&routine_name);
 
-        let (status, definition_changed) = match (source_routine, target_routine) {
+        let (status, definition_changed, comment_changed) = match (source_routine, target_routine) {
             (Some(src), Some(tgt)) => {
                 let changed = src.definition != tgt.definition
                     || src.routine_type != tgt.routine_type
                     || src.return_type != tgt.return_type;
+                let comment_changed = src.comment != tgt.comment;
                 (
-                    if changed {
+                    if changed || comment_changed {
                         DiffStatus::Modified
                     } else {
                         DiffStatus::Identical
                     },
                     changed,
+                    comment_changed,
                 )
             }
-            (Some(_), None) => (DiffStatus::Added, false),
-            (None, Some(_)) => (DiffStatus::Removed, false),
+            (Some(_), None) => (DiffStatus::Added, false, false),
+            (None, Some(_)) => (DiffStatus::Removed, false, false),
             (None, None) => unreachable!(),
         };
 
@@ -506,456 +729,223 @@ fn compare_routines(
             source_definition: source_routine.cloned().cloned(),
             target_definition: target_routine.cloned().cloned(),
             definition_changed,
+            comment_changed,
         });
     }
 
+    changes.sort_by(|a, b| a.routine_name.cmp(&b.routine_name));
     changes
 }
 
-// Generate warnings based on detected changes
-fn generate_warnings(
-    table_diffs: &[TableDifference],
-    _view_diffs: &[ViewChange],
-    _routine_diffs: &[RoutineChange],
-) -> Vec<ComparisonWarning> {
-    let mut warnings = Vec::new();
-
-    for table_diff in table_diffs {
-        // Warn about dropped tables
-        if matches!(table_diff.status, DiffStatus::Removed) {
-            warnings.push(ComparisonWarning {
-                severity: WarningSeverity::High,
-                warning_type: WARNING_TYPE_DATA_LOSS.to_string(),
-                message: format!("Dropping table '{}' will result in data loss", table_diff.table_name),
-                affected_object: table_diff.table_name.clone(),
-                details: Some("Consider backing up data before proceeding".to_string()),
-            });
-        }
-
-        // Warn about dropped columns
-        for col_change in &table_diff.column_changes {
-            if matches!(col_change.status, DiffStatus::Removed) {
-                warnings.push(ComparisonWarning {
-                    severity: WarningSeverity::High,
-                    warning_type: WARNING_TYPE_DATA_LOSS.to_string(),
-                    message: format!(
-                        "Dropping column '{}.{}' will result in data loss",
-                        table_diff.table_name, col_change.column_name
-                    ),
-                    affected_object: format!("{}.{}", table_diff.table_name, col_change.column_name),
-                    details: Some("Consider backing up column data first".to_string()),
-                });
-            }
-
-            // Warn about type changes
-            if matches!(col_change.status, DiffStatus::Modified) {
-                if col_change.changes.iter().any(|c| c.starts_with("type:")) {
-                    warnings.push(ComparisonWarning {
-                        severity: WarningSeverity::Medium,
-                        warning_type: WARNING_TYPE_BREAKING_CHANGE.to_string(),
-                        message: format!(
-                            "Changing data type for column '{}.{}' may cause issues",
-                            table_diff.table_name, col_change.column_name
-                        ),
-                        affected_object: format!("{}.{}", table_diff.table_name, col_change.column_name),
-                        details: Some("Ensure data is compatible with new type".to_string()),
-                    });
-                }
-            }
-        }
-    }
-
-    warnings
-}
-
-/// Generate PostgreSQL migration script from schema comparison
-pub fn generate_migration_script(comparison: &SchemaComparison) -> String {
-    let mut script = String::new();
-    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-
-    // Header
-    script.push_str(&format!(
-        "-- ============================================\n\
-         -- Schema Migration Script\n\
-         -- Source: {}\n\
-         -- Target: {}\n\
-         -- Generated: {}\n\
-         -- ============================================\n\n",
-        comparison.source_connection, comparison.target_connection, timestamp
-    ));
-
-    script.push_str(
-        "-- WARNING: This script will make changes to the target database!\n\
-         -- Review carefully before executing.\n\n",
-    );
-
-    let mut has_changes = false;
+// Compare installed extensions
+fn compare_extensions(
+    source_extensions: &[ExtensionInfo],
+    target_extensions: &[ExtensionInfo],
+) -> Vec<ExtensionChange> {
+    let mut changes = Vec::new();
 
-    // Table modifications
-    let modified_tables: Vec<_> = comparison
-        .table_differences
+    let source_map: HashMap<String, &ExtensionInfo> = source_extensions
         .iter()
-        .filter(|t| matches!(t.status, DiffStatus::Modified))
+        .map(|e| (e.extension_name.clone(), e))
         .collect();
 
-    if !modified_tables.is_empty() {
-        has_changes = true;
-        script.push_str(
-            "-- ============================================\n\
-             -- TABLE MODIFICATIONS\n\
-             -- ============================================\n\n",
-        );
+    let target_map: HashMap<String, &ExtensionInfo> = target_extensions
+        .iter()
+        .map(|e| (e.extension_name.clone(), e))
+        .collect();
 
-        for table_diff in modified_tables {
-            script.push_str(&format!("-- Modify table: {}\n", table_diff.table_name));
-
-            // Column changes
-            for col_change in &table_diff.column_changes {
-                match col_change.status {
-                    DiffStatus::Added => {
-                        if let Some(target_def) = &col_change.target_definition {
-                            let nullable = if target_def.is_nullable == SQL_NULLABLE_YES {
-                                "NULL"
-                            } else {
-                                "NOT NULL"
-                            };
-                            let default = target_def
-                                .column_default
-                                .as_ref()
-                                .map(|d| format!(" DEFAULT {}", d))
-                                .unwrap_or_default();
-                            script.push_str(&format!(
-                                "ALTER TABLE {} ADD COLUMN {} {} {}{};\n",
-                                table_diff.table_name,
-                                col_change.column_name,
-                                target_def.data_type,
-                                nullable,
-                                default
-                            ));
-                        }
-                    }
-                    DiffStatus::Removed => {
-                        script.push_str(&format!(
-                            "-- WARNING: Dropping column will cause data loss!\n\
-                             ALTER TABLE {} DROP COLUMN {};\n",
-                            table_diff.table_name, col_change.column_name
-                        ));
-                    }
-                    DiffStatus::Modified => {
-                        if let Some(target_def) = &col_change.target_definition {
-                            // Type changes
-                            if col_change.changes.iter().any(|c| c.starts_with("type:")) {
-                                script.push_str(&format!(
-                                    "ALTER TABLE {} ALTER COLUMN {} TYPE {};\n",
-                                    table_diff.table_name, col_change.column_name, target_def.data_type
-                                ));
-                            }
-
-                            // Nullable changes
-                            if col_change
-                                .changes
-                                .iter()
-                                .any(|c| c.starts_with("nullable:"))
-                            {
-                                let nullable_clause = if target_def.is_nullable == SQL_NULLABLE_YES {
-                                    "DROP NOT NULL"
-                                } else {
-                                    "SET NOT NULL"
-                                };
-                                script.push_str(&format!(
-                                    "ALTER TABLE {} ALTER COLUMN {} {};\n",
-                                    table_diff.table_name, col_change.column_name, nullable_clause
-                                ));
-                            }
-
-                            // Default changes
-                            if col_change
-                                .changes
-                                .iter()
-                                .any(|c| c.starts_with("default:"))
-                            {
-                                if let Some(default_val) = &target_def.column_default {
-                                    script.push_str(&format!(
-                                        "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};\n",
-                                        table_diff.table_name, col_change.column_name, default_val
-                                    ));
-                                } else {
-                                    script.push_str(&format!(
-                                        "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;\n",
-                                        table_diff.table_name, col_change.column_name
-                                    ));
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
+    let mut all_extensions: HashSet<String> = HashSet::new();
+    all_extensions.extend(source_map.keys().cloned());
+    all_extensions.extend(target_map.keys().cloned());
 
-            // Index changes
-            for idx_change in &table_diff.index_changes {
-                match idx_change.status {
-                    DiffStatus::Added => {
-                        if let Some(idx_info) = &idx_change.target_definition {
-                            script.push_str(&format!("{};\n", idx_info.definition));
-                        }
-                    }
-                    DiffStatus::Removed => {
-                        script.push_str(&format!("DROP INDEX IF EXISTS {};\n", idx_change.index_name));
-                    }
-                    DiffStatus::Modified => {
-                        // Drop and recreate
-                        script.push_str(&format!("DROP INDEX IF EXISTS {};\n", idx_change.index_name));
-                        if let Some(idx_info) = &idx_change.target_definition {
-                            script.push_str(&format!("{};\n", idx_info.definition));
-                        }
-                    }
-                    _ => {}
-                }
-            }
+    for extension_name in all_extensions {
+        let source_ext = source_map.get(&extension_name);
+        let target_ext = target_map.get(&extension_name);
 
-            // Foreign key changes
-            for fk_change in &table_diff.fk_changes {
-                match fk_change.status {
-                    DiffStatus::Added => {
-                        if let Some(target_fk) = &fk_change.target_definition {
-                            script.push_str(&format!(
-                                "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({});\n",
-                                target_fk.table_name,
-                                fk_change.constraint_name,
-                                target_fk.column_name,
-                                target_fk.foreign_table_name,
-                                target_fk.foreign_column_name
-                            ));
-                        }
-                    }
-                    DiffStatus::Removed => {
-                        if let Some(source_fk) = &fk_change.source_definition {
-                            script.push_str(&format!(
-                                "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};\n",
-                                source_fk.table_name, fk_change.constraint_name
-                            ));
-                        }
-                    }
-                    DiffStatus::Modified => {
-                        // Drop and recreate
-                        if let Some(source_fk) = &fk_change.source_definition {
-                            script.push_str(&format!(
-                                "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};\n",
-                                source_fk.table_name, fk_change.constraint_name
-                            ));
-                        }
-                        if let Some(target_fk) = &fk_change.target_definition {
-                            script.push_str(&format!(
-                                "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({});\n",
-                                target_fk.table_name,
-                                fk_change.constraint_name,
-                                target_fk.column_name,
-                                target_fk.foreign_table_name,
-                                target_fk.foreign_column_name
-                            ));
-                        }
-                    }
-                    _ => {}
+        let status = match (source_ext, target_ext) {
+            (Some(src), Some(tgt)) => {
+                if src.version != tgt.version {
+                    DiffStatus::Modified
+                } else {
+                    DiffStatus::Identical
                 }
             }
+            (Some(_), None) => DiffStatus::Added,
+            (None, Some(_)) => DiffStatus::Removed,
+            (None, None) => unreachable!(),
+        };
 
-            script.push('\n');
-        }
+        changes.push(ExtensionChange {
+            extension_name,
+            status,
+            source_definition: source_ext.cloned().cloned(),
+            target_definition: target_ext.cloned().cloned(),
+        });
     }
 
-    // New tables
-    let new_tables: Vec<_> = comparison
-        .table_differences
+    changes.sort_by(|a, b| a.extension_name.cmp(&b.extension_name));
+    changes
+}
+
+// Compare user-defined types
+fn compare_types(source_types: &[TypeInfo], target_types: &[TypeInfo]) -> Vec<TypeChange> {
+    let mut changes = Vec::new();
+
+    let source_map: HashMap<String, &TypeInfo> = source_types
         .iter()
-        .filter(|t| matches!(t.status, DiffStatus::Added))
+        .map(|t| (t.type_name.clone(), t))
         .collect();
 
-    if !new_tables.is_empty() {
-        has_changes = true;
-        script.push_str(
-            "-- ============================================\n\
-             -- NEW TABLES\n\
-             -- ============================================\n\n",
-        );
+    let target_map: HashMap<String, &TypeInfo> = target_types
+        .iter()
+        .map(|t| (t.type_name.clone(), t))
+        .collect();
 
-        for table_diff in new_tables {
-            script.push_str(&format!("-- Create table: {}\n", table_diff.table_name));
-            script.push_str(&format!("CREATE TABLE {} (\n", table_diff.table_name));
-
-            let columns: Vec<String> = table_diff
-                .column_changes
-                .iter()
-                .filter_map(|col| {
-                    if let Some(target_def) = &col.target_definition {
-                        let nullable = if target_def.is_nullable == SQL_NULLABLE_YES {
-                            "NULL"
-                        } else {
-                            "NOT NULL"
-                        };
-                        let default = target_def
-                            .column_default
-                            .as_ref()
-                            .map(|d| format!(" DEFAULT {}", d))
-                            .unwrap_or_default();
-                        let pk = if target_def.is_primary_key {
-                            " PRIMARY KEY"
-                        } else {
-                            ""
-                        };
-                        Some(format!(
-                            "  {} {} {}{}{}",
-                            col.column_name, target_def.data_type, nullable, default, pk
-                        ))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            script.push_str(&columns.join(",\n"));
-            script.push_str("\n);\n\n");
-
-            // Indexes for new table
-            for idx_change in &table_diff.index_changes {
-                if let Some(idx_info) = &idx_change.target_definition {
-                    script.push_str(&format!("{};\n", idx_info.definition));
-                }
-            }
+    let mut all_types: HashSet<String> = HashSet::new();
+    all_types.extend(source_map.keys().cloned());
+    all_types.extend(target_map.keys().cloned());
 
-            // Foreign keys for new table
-            for fk_change in &table_diff.fk_changes {
-                if let Some(target_fk) = &fk_change.target_definition {
-                    script.push_str(&format!(
-                        "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({});\n",
-                        target_fk.table_name,
-                        fk_change.constraint_name,
-                        target_fk.column_name,
-                        target_fk.foreign_table_name,
-                        target_fk.foreign_column_name
-                    ));
+    for type_name in all_types {
+        let source_type = source_map.get(&type_name);
+        let target_type = target_map.get(&type_name);
+
+        let status = match (source_type, target_type) {
+            (Some(src), Some(tgt)) => {
+                if src.kind != tgt.kind || src.definition != tgt.definition {
+                    DiffStatus::Modified
+                } else {
+                    DiffStatus::Identical
                 }
             }
+            (Some(_), None) => DiffStatus::Added,
+            (None, Some(_)) => DiffStatus::Removed,
+            (None, None) => unreachable!(),
+        };
 
-            script.push('\n');
-        }
+        changes.push(TypeChange {
+            type_name,
+            status,
+            source_definition: source_type.cloned().cloned(),
+            target_definition: target_type.cloned().cloned(),
+        });
     }
 
-    // Dropped tables
-    let dropped_tables: Vec<_> = comparison
-        .table_differences
-        .iter()
-        .filter(|t| matches!(t.status, DiffStatus::Removed))
-        .collect();
-
-    if !dropped_tables.is_empty() {
-        has_changes = true;
-        script.push_str(
-            "-- ============================================\n\
-             -- DROPPED TABLES\n\
-             -- ============================================\n\n",
-        );
+    changes.sort_by(|a, b| a.type_name.cmp(&b.type_name));
+    changes
+}
 
-        for table_diff in dropped_tables {
-            script.push_str(&format!(
-                "-- WARNING: Dropping table will cause data loss!\n\
-                 DROP TABLE IF EXISTS {} CASCADE;\n\n",
-                table_diff.table_name
-            ));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orders() -> EnhancedTableInfo {
+        EnhancedTableInfo {
+            table_name: "orders".to_string(),
+            columns: vec![EnhancedColumnInfo {
+                column_name: "id".to_string(),
+                data_type: "integer".to_string(),
+                is_nullable: "NO".to_string(),
+                is_primary_key: true,
+                column_default: None,
+                character_maximum_length: None,
+                numeric_precision: None,
+                numeric_scale: None,
+                ordinal_position: 1,
+                comment: None,
+                collation_name: None,
+                udt_name: Some("int4".to_string()),
+                udt_schema: Some("pg_catalog".to_string()),
+            }],
+            foreign_keys: vec![ForeignKeyInfo {
+                constraint_name: "orders_customer_fk".to_string(),
+                table_name: "orders".to_string(),
+                column_name: "id".to_string(),
+                foreign_table_name: "customers".to_string(),
+                foreign_column_name: "id".to_string(),
+            }],
+            indexes: Vec::new(),
+            comment: None,
+            check_constraints: vec![CheckConstraintInfo {
+                constraint_name: "orders_id_check".to_string(),
+                definition: "CHECK (id > 0)".to_string(),
+                columns: vec!["id".to_string()],
+            }],
         }
     }
 
-    // View changes
-    let view_changes: Vec<_> = comparison
-        .view_differences
-        .iter()
-        .filter(|v| !matches!(v.status, DiffStatus::Identical))
-        .collect();
-
-    if !view_changes.is_empty() {
-        has_changes = true;
-        script.push_str(
-            "-- ============================================\n\
-             -- VIEWS\n\
-             -- ============================================\n\n",
-        );
+    #[test]
+    fn table_with_only_identical_parts_is_identical() {
+        let differences = compare_tables(&[orders()], &[orders()]);
 
-        for view_change in view_changes {
-            match view_change.status {
-                DiffStatus::Added | DiffStatus::Modified => {
-                    script.push_str(&format!("DROP VIEW IF EXISTS {};\n\n", view_change.view_name));
-                    if let Some(def) = &view_change.target_definition {
-                        script.push_str(&format!("CREATE VIEW {} AS\n{};\n\n", view_change.view_name, def));
-                    }
-                }
-                DiffStatus::Removed => {
-                    script.push_str(&format!("DROP VIEW IF EXISTS {};\n\n", view_change.view_name));
-                }
-                _ => {}
-            }
-        }
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(differences[0].status, DiffStatus::Identical));
+        assert!(!differences[0].column_changes.is_empty());
     }
 
-    // Routine changes
-    let routine_changes: Vec<_> = comparison
-        .routine_differences
-        .iter()
-        .filter(|r| !matches!(r.status, DiffStatus::Identical))
-        .collect();
+    #[test]
+    fn table_with_a_changed_column_is_modified() {
+        let mut target = orders();
+        target.columns[0].is_nullable = "YES".to_string();
 
-    if !routine_changes.is_empty() {
-        has_changes = true;
-        script.push_str(
-            "-- ============================================\n\
-             -- FUNCTIONS/PROCEDURES\n\
-             -- ============================================\n\n",
-        );
+        let differences = compare_tables(&[orders()], &[target]);
 
-        for routine_change in routine_changes {
-            match routine_change.status {
-                DiffStatus::Added | DiffStatus::Modified => {
-                    script.push_str(&format!(
-                        "DROP FUNCTION IF EXISTS {} CASCADE;\n\n",
-                        routine_change.routine_name
-                    ));
-                    if let Some(routine_info) = &routine_change.target_definition {
-                        if let Some(def) = &routine_info.definition {
-                            script.push_str(&format!("{};\n\n", def));
-                        }
-                    }
-                }
-                DiffStatus::Removed => {
-                    script.push_str(&format!(
-                        "DROP FUNCTION IF EXISTS {} CASCADE;\n\n",
-                        routine_change.routine_name
-                    ));
-                }
-                _ => {}
-            }
-        }
+        assert!(matches!(differences[0].status, DiffStatus::Modified));
     }
 
-    // Footer
-    script.push_str("-- ============================================\n");
-    script.push_str("-- END OF MIGRATION SCRIPT\n");
-
-    if !has_changes {
-        script.push_str("-- No changes detected.\n");
-    } else {
-        let total_changes = comparison.summary.tables_added
-            + comparison.summary.tables_removed
-            + comparison.summary.tables_modified
-            + comparison.summary.views_changed
-            + comparison.summary.routines_changed;
-
-        script.push_str(&format!("-- Total affected objects: {}\n", total_changes));
-
-        if !comparison.warnings.is_empty() {
-            script.push_str(&format!("-- Warnings: {}\n", comparison.warnings.len()));
+    #[test]
+    fn index_parts_outside_the_key_make_it_modified() {
+        let index = IndexInfo {
+            index_name: "orders_open".to_string(),
+            table_name: "orders".to_string(),
+            columns: vec!["id".to_string()],
+            is_unique: false,
+            is_primary: false,
+            definition: "CREATE INDEX orders_open ON public.orders USING btree (id)".to_string(),
+            access_method: "btree".to_string(),
+            key_columns: Vec::new(),
+            include_columns: Vec::new(),
+            predicate: None,
+            is_valid: true,
+        };
+        let changed: [fn(&mut IndexInfo); 3] = [
+            |idx| idx.include_columns = vec!["total".to_string()],
+            |idx| idx.predicate = Some("(closed_at IS NULL)".to_string()),
+            |idx| idx.is_valid = false,
+        ];
+
+        for change in changed {
+            let mut target = index.clone();
+            change(&mut target);
+            let changes = compare_indexes(std::slice::from_ref(&index), &[target]);
+            assert!(matches!(changes[0].status, DiffStatus::Modified), "{:?}", changes[0]);
         }
+        let unchanged = compare_indexes(std::slice::from_ref(&index), std::slice::from_ref(&index));
+        assert!(matches!(unchanged[0].status, DiffStatus::Identical));
     }
 
-    script.push_str("-- ============================================\n");
+    #[test]
+    fn overloaded_routines_are_matched_by_argument_types() {
+        let routine = |arguments: &str, body: &str| RoutineInfo {
+            routine_name: "order_total".to_string(),
+            routine_type: "FUNCTION".to_string(),
+            definition: Some(body.to_string()),
+            return_type: Some("numeric".to_string()),
+            comment: None,
+            identity_arguments: Some(arguments.to_string()),
+        };
+
+        let changes = compare_routines(
+            &[routine("integer", "SELECT 2"), routine("text", "SELECT 1")],
+            &[routine("integer", "SELECT 1"), routine("text", "SELECT 1"), routine("", "SELECT 0")],
+        );
+        let statuses: Vec<(&str, bool)> = changes
+            .iter()
+            .map(|c| (c.routine_name.as_str(), matches!(c.status, DiffStatus::Identical)))
+            .collect();
 
-    script
+        assert_eq!(
+            statuses,
+            vec![("order_total()", false), ("order_total(integer)", false), ("order_total(text)", true)]
+        );
+    }
 }
@@ -0,0 +1,502 @@
+use crate::constants::SQL_NULLABLE_YES;
+use crate::models::{EnhancedColumnInfo, EnhancedDatabaseSchema, EnhancedTableInfo, ForeignKeyInfo};
+
+/// Output formats supported by the data dictionary generator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocsFormat {
+    Markdown,
+    Html,
+}
+
+impl DocsFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(DocsFormat::Markdown),
+            "html" => Ok(DocsFormat::Html),
+            other => Err(format!("Unsupported documentation format: {}", other)),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocsFormat::Markdown => "md",
+            DocsFormat::Html => "html",
+        }
+    }
+}
+
+/// Render a schema into a data dictionary document
+pub fn render_schema_docs(
+    schema: &EnhancedDatabaseSchema,
+    title: &str,
+    format: DocsFormat,
+) -> String {
+    match format {
+        DocsFormat::Markdown => render_markdown(schema, title),
+        DocsFormat::Html => render_html(schema, title),
+    }
+}
+
+fn render_markdown(schema: &EnhancedDatabaseSchema, title: &str) -> String {
+    let mut doc = String::new();
+
+    // No generation time, so an unchanged schema renders an identical document
+    doc.push_str(&format!("# {}\n\n", title));
+
+    // Table of contents
+    if !schema.tables.is_empty() {
+        doc.push_str("## Tables\n\n");
+        for table in &schema.tables {
+            doc.push_str(&format!(
+                "- [{}](#{}){}\n",
+                table.table_name,
+                markdown_anchor(&table.table_name),
+                table
+                    .comment
+                    .as_ref()
+                    .map(|c| format!(" — {}", single_line(c)))
+                    .unwrap_or_default()
+            ));
+        }
+        doc.push('\n');
+    }
+
+    for table in &schema.tables {
+        doc.push_str(&format!("### {}\n\n", table.table_name));
+
+        if let Some(comment) = &table.comment {
+            doc.push_str(&format!("{}\n\n", comment));
+        }
+
+        doc.push_str("| Column | Type | Nullable | Default | Key | Comment |\n");
+        doc.push_str("|---|---|---|---|---|---|\n");
+        for column in &table.columns {
+            doc.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                markdown_cell(&column.column_name),
+                markdown_cell(&display_type(column)),
+                if column.is_nullable == SQL_NULLABLE_YES { "YES" } else { "NO" },
+                markdown_cell(column.column_default.as_deref().unwrap_or("")),
+                column_keys(table, column).join(", "),
+                markdown_cell(column.comment.as_deref().unwrap_or("")),
+            ));
+        }
+        doc.push('\n');
+
+        if !table.indexes.is_empty() {
+            doc.push_str("**Indexes**\n\n");
+            for index in &table.indexes {
+                doc.push_str(&format!("- `{}`: `{}`\n", index.index_name, index.definition));
+            }
+            doc.push('\n');
+        }
+
+        if !table.foreign_keys.is_empty() {
+            doc.push_str("**References**\n\n");
+            for fk in &table.foreign_keys {
+                doc.push_str(&format!(
+                    "- `{}` → [{}](#{}).`{}` ({})\n",
+                    fk.column_name,
+                    fk.foreign_table_name,
+                    markdown_anchor(&fk.foreign_table_name),
+                    fk.foreign_column_name,
+                    fk.constraint_name
+                ));
+            }
+            doc.push('\n');
+        }
+
+        let referenced_by = incoming_foreign_keys(schema, &table.table_name);
+        if !referenced_by.is_empty() {
+            doc.push_str("**Referenced by**\n\n");
+            for fk in referenced_by {
+                doc.push_str(&format!(
+                    "- [{}](#{}).`{}` → `{}` ({})\n",
+                    fk.table_name,
+                    markdown_anchor(&fk.table_name),
+                    fk.column_name,
+                    fk.foreign_column_name,
+                    fk.constraint_name
+                ));
+            }
+            doc.push('\n');
+        }
+    }
+
+    if !schema.views.is_empty() {
+        doc.push_str("## Views\n\n");
+        for view in &schema.views {
+            doc.push_str(&format!("### {}\n\n", view.view_name));
+            if let Some(comment) = &view.comment {
+                doc.push_str(&format!("{}\n\n", comment));
+            }
+            doc.push_str(&format!("```sql\n{}\n```\n\n", view.definition.trim()));
+        }
+    }
+
+    if !schema.routines.is_empty() {
+        doc.push_str("## Functions/Procedures\n\n");
+        doc.push_str("| Name | Type | Returns | Comment |\n");
+        doc.push_str("|---|---|---|---|\n");
+        for routine in &schema.routines {
+            doc.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                markdown_cell(&routine.routine_name),
+                routine.routine_type,
+                markdown_cell(routine.return_type.as_deref().unwrap_or("")),
+                markdown_cell(routine.comment.as_deref().unwrap_or("")),
+            ));
+        }
+        doc.push('\n');
+    }
+
+    doc
+}
+
+fn render_html(schema: &EnhancedDatabaseSchema, title: &str) -> String {
+    let mut doc = String::new();
+
+    doc.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    doc.push_str(&format!("<title>{}</title>\n", escape_html(title)));
+    doc.push_str(
+        "<style>\n\
+         body { font-family: -apple-system, BlinkMacSystemFont, sans-serif; margin: 2rem; color: #1f2933; }\n\
+         table { border-collapse: collapse; margin-bottom: 1rem; }\n\
+         th, td { border: 1px solid #d9e2ec; padding: 0.25rem 0.5rem; text-align: left; vertical-align: top; }\n\
+         th { background: #f0f4f8; }\n\
+         code, pre { background: #f0f4f8; padding: 0.1rem 0.25rem; }\n\
+         pre { padding: 0.5rem; overflow-x: auto; }\n\
+         .comment { color: #52606d; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+
+    doc.push_str(&format!("<h1>{}</h1>\n", escape_html(title)));
+
+    if !schema.tables.is_empty() {
+        doc.push_str("<h2>Tables</h2>\n<ul>\n");
+        for table in &schema.tables {
+            doc.push_str(&format!(
+                "<li><a href=\"#table-{}\">{}</a></li>\n",
+                escape_html(&table.table_name),
+                escape_html(&table.table_name)
+            ));
+        }
+        doc.push_str("</ul>\n");
+    }
+
+    for table in &schema.tables {
+        doc.push_str(&format!(
+            "<h3 id=\"table-{}\">{}</h3>\n",
+            escape_html(&table.table_name),
+            escape_html(&table.table_name)
+        ));
+
+        if let Some(comment) = &table.comment {
+            doc.push_str(&format!("<p class=\"comment\">{}</p>\n", escape_html(comment)));
+        }
+
+        doc.push_str(
+            "<table>\n<tr><th>Column</th><th>Type</th><th>Nullable</th><th>Default</th><th>Key</th><th>Comment</th></tr>\n",
+        );
+        for column in &table.columns {
+            doc.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&column.column_name),
+                escape_html(&display_type(column)),
+                if column.is_nullable == SQL_NULLABLE_YES { "YES" } else { "NO" },
+                escape_html(column.column_default.as_deref().unwrap_or("")),
+                column_keys(table, column).join(", "),
+                escape_html(column.comment.as_deref().unwrap_or("")),
+            ));
+        }
+        doc.push_str("</table>\n");
+
+        if !table.indexes.is_empty() {
+            doc.push_str("<p><strong>Indexes</strong></p>\n<ul>\n");
+            for index in &table.indexes {
+                doc.push_str(&format!(
+                    "<li><code>{}</code>: <code>{}</code></li>\n",
+                    escape_html(&index.index_name),
+                    escape_html(&index.definition)
+                ));
+            }
+            doc.push_str("</ul>\n");
+        }
+
+        if !table.foreign_keys.is_empty() {
+            doc.push_str("<p><strong>References</strong></p>\n<ul>\n");
+            for fk in &table.foreign_keys {
+                doc.push_str(&format!(
+                    "<li><code>{}</code> → <a href=\"#table-{}\">{}</a>.<code>{}</code> ({})</li>\n",
+                    escape_html(&fk.column_name),
+                    escape_html(&fk.foreign_table_name),
+                    escape_html(&fk.foreign_table_name),
+                    escape_html(&fk.foreign_column_name),
+                    escape_html(&fk.constraint_name)
+                ));
+            }
+            doc.push_str("</ul>\n");
+        }
+
+        let referenced_by = incoming_foreign_keys(schema, &table.table_name);
+        if !referenced_by.is_empty() {
+            doc.push_str("<p><strong>Referenced by</strong></p>\n<ul>\n");
+            for fk in referenced_by {
+                doc.push_str(&format!(
+                    "<li><a href=\"#table-{}\">{}</a>.<code>{}</code> → <code>{}</code> ({})</li>\n",
+                    escape_html(&fk.table_name),
+                    escape_html(&fk.table_name),
+                    escape_html(&fk.column_name),
+                    escape_html(&fk.foreign_column_name),
+                    escape_html(&fk.constraint_name)
+                ));
+            }
+            doc.push_str("</ul>\n");
+        }
+    }
+
+    if !schema.views.is_empty() {
+        doc.push_str("<h2>Views</h2>\n");
+        for view in &schema.views {
+            doc.push_str(&format!("<h3>{}</h3>\n", escape_html(&view.view_name)));
+            if let Some(comment) = &view.comment {
+                doc.push_str(&format!("<p class=\"comment\">{}</p>\n", escape_html(comment)));
+            }
+            doc.push_str(&format!("<pre>{}</pre>\n", escape_html(view.definition.trim())));
+        }
+    }
+
+    if !schema.routines.is_empty() {
+        doc.push_str("<h2>Functions/Procedures</h2>\n");
+        doc.push_str("<table>\n<tr><th>Name</th><th>Type</th><th>Returns</th><th>Comment</th></tr>\n");
+        for routine in &schema.routines {
+            doc.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&routine.routine_name),
+                escape_html(&routine.routine_type),
+                escape_html(routine.return_type.as_deref().unwrap_or("")),
+                escape_html(routine.comment.as_deref().unwrap_or("")),
+            ));
+        }
+        doc.push_str("</table>\n");
+    }
+
+    doc.push_str("</body>\n</html>\n");
+    doc
+}
+
+// Type with length/precision, e.g. "character varying(255)" or "numeric(10,2)"
+fn display_type(column: &EnhancedColumnInfo) -> String {
+    if let Some(length) = column.character_maximum_length {
+        format!("{}({})", column.data_type, length)
+    } else if column.data_type == "numeric" {
+        match (column.numeric_precision, column.numeric_scale) {
+            (Some(precision), Some(scale)) => format!("numeric({},{})", precision, scale),
+            (Some(precision), None) => format!("numeric({})", precision),
+            _ => column.data_type.clone(),
+        }
+    } else {
+        column.data_type.clone()
+    }
+}
+
+// Key markers for a column: PK, FK, UQ
+fn column_keys(table: &EnhancedTableInfo, column: &EnhancedColumnInfo) -> Vec<&'static str> {
+    let mut keys = Vec::new();
+    if column.is_primary_key {
+        keys.push("PK");
+    }
+    if table
+        .foreign_keys
+        .iter()
+        .any(|fk| fk.column_name == column.column_name)
+    {
+        keys.push("FK");
+    }
+    if table.indexes.iter().any(|idx| {
+        idx.is_unique && !idx.is_primary && idx.columns == [column.column_name.clone()]
+    }) {
+        keys.push("UQ");
+    }
+    keys
+}
+
+// Foreign keys in other tables that point at this table
+fn incoming_foreign_keys<'a>(
+    schema: &'a EnhancedDatabaseSchema,
+    table_name: &str,
+) -> Vec<&'a ForeignKeyInfo> {
+    schema
+        .tables
+        .iter()
+        .flat_map(|t| &t.foreign_keys)
+        .filter(|fk| fk.foreign_table_name == table_name)
+        .collect()
+}
+
+fn markdown_anchor(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == ' ')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+pub(super) fn markdown_cell(value: &str) -> String {
+    single_line(value).replace('|', "\\|")
+}
+
+fn single_line(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub(super) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{IndexInfo, RoutineInfo, ViewInfo};
+
+    fn column(name: &str, data_type: &str, length: Option<i32>, comment: Option<&str>) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            column_name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: "NO".to_string(),
+            is_primary_key: name == "id",
+            column_default: None,
+            character_maximum_length: length,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: 1,
+            comment: comment.map(|c| c.to_string()),
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        }
+    }
+
+    fn table(name: &str, columns: Vec<EnhancedColumnInfo>, comment: Option<&str>) -> EnhancedTableInfo {
+        EnhancedTableInfo {
+            table_name: name.to_string(),
+            columns,
+            foreign_keys: Vec::new(),
+            indexes: Vec::new(),
+            comment: comment.map(|c| c.to_string()),
+            check_constraints: Vec::new(),
+        }
+    }
+
+    // Customer Accounts(id, email UNIQUE) is referenced by orders.customer_id
+    fn schema() -> EnhancedDatabaseSchema {
+        let mut customers = table(
+            "Customer Accounts",
+            vec![
+                column("id", "integer", None, None),
+                column("email", "character varying", Some(255), Some("login | contact\naddress")),
+            ],
+            Some("People who <buy>\nthings"),
+        );
+        customers.indexes.push(IndexInfo {
+            index_name: "customers_email_key".to_string(),
+            table_name: "Customer Accounts".to_string(),
+            columns: vec!["email".to_string()],
+            is_unique: true,
+            is_primary: false,
+            definition: "CREATE UNIQUE INDEX customers_email_key ON public.\"Customer Accounts\" USING btree (email)"
+                .to_string(),
+            access_method: "btree".to_string(),
+            key_columns: Vec::new(),
+            include_columns: Vec::new(),
+            predicate: None,
+            is_valid: true,
+        });
+        let mut orders = table(
+            "orders",
+            vec![column("id", "integer", None, None), column("customer_id", "integer", None, None)],
+            None,
+        );
+        orders.foreign_keys.push(ForeignKeyInfo {
+            constraint_name: "orders_customer_fk".to_string(),
+            table_name: "orders".to_string(),
+            column_name: "customer_id".to_string(),
+            foreign_table_name: "Customer Accounts".to_string(),
+            foreign_column_name: "id".to_string(),
+        });
+
+        EnhancedDatabaseSchema {
+            tables: vec![customers, orders],
+            views: vec![ViewInfo {
+                view_name: "big_orders".to_string(),
+                definition: " SELECT id FROM orders WHERE id > 100;\n".to_string(),
+                comment: None,
+                depends_on: Vec::new(),
+                column_dependencies: Vec::new(),
+            }],
+            routines: vec![RoutineInfo {
+                routine_name: "order_total".to_string(),
+                routine_type: "FUNCTION".to_string(),
+                definition: None,
+                return_type: Some("numeric".to_string()),
+                comment: Some("Sum of <lines>".to_string()),
+                identity_arguments: Some("integer".to_string()),
+            }],
+            extensions: Vec::new(),
+            types: Vec::new(),
+            server_version_num: None,
+        }
+    }
+
+    #[test]
+    fn markdown_lists_tables_keys_and_references() {
+        let markdown = render_schema_docs(&schema(), "Shop & Co", DocsFormat::Markdown);
+
+        assert!(markdown.starts_with(
+            "# Shop & Co\n\n## Tables\n\n\
+             - [Customer Accounts](#customer-accounts) — People who <buy> things\n\
+             - [orders](#orders)\n\n"
+        ));
+        assert!(markdown.contains("| id | integer | NO |  | PK |  |\n"));
+        assert!(markdown.contains(
+            "| email | character varying(255) | NO |  | UQ | login \\| contact address |\n"
+        ));
+        assert!(markdown.contains("| customer_id | integer | NO |  | FK |  |\n"));
+        assert!(markdown.contains(
+            "**References**\n\n- `customer_id` → [Customer Accounts](#customer-accounts).`id` (orders_customer_fk)\n"
+        ));
+        assert!(markdown.contains(
+            "**Referenced by**\n\n- [orders](#orders).`customer_id` → `id` (orders_customer_fk)\n"
+        ));
+        assert!(markdown.contains("```sql\nSELECT id FROM orders WHERE id > 100;\n```\n"));
+        assert!(markdown.contains("| order_total | FUNCTION | numeric | Sum of <lines> |\n"));
+        assert!(!markdown.contains("Generated"));
+    }
+
+    #[test]
+    fn html_escapes_names_comments_and_definitions() {
+        let html = render_schema_docs(&schema(), "Shop & Co", DocsFormat::Html);
+
+        assert!(html.contains("<title>Shop &amp; Co</title>"));
+        assert!(html.contains("<h1>Shop &amp; Co</h1>\n<h2>Tables</h2>"));
+        assert!(html.contains("<p class=\"comment\">People who &lt;buy&gt;\nthings</p>"));
+        assert!(html.contains(
+            "<li><code>customers_email_key</code>: <code>CREATE UNIQUE INDEX customers_email_key \
+             ON public.&quot;Customer Accounts&quot; USING btree (email)</code></li>"
+        ));
+        assert!(html.contains("<tr><td>order_total</td><td>FUNCTION</td><td>numeric</td><td>Sum of &lt;lines&gt;</td></tr>"));
+        assert!(!html.contains("<buy>") && !html.contains("<lines>"));
+        assert!(!html.contains("Generated"));
+    }
+
+    #[test]
+    fn formats_parse_case_insensitively() {
+        assert_eq!(DocsFormat::parse("MD"), Ok(DocsFormat::Markdown));
+        assert_eq!(DocsFormat::parse("Html"), Ok(DocsFormat::Html));
+        assert!(DocsFormat::parse("pdf").is_err());
+    }
+}
@@ -0,0 +1,831 @@
+use crate::utils::migration::column_type_name;
+use crate::utils::schema_diff::{
+    comparison_summary, CheckConstraintChange, ColumnChange, DiffStatus, ForeignKeyChange,
+    IndexChange, SchemaComparison, TableDifference,
+};
+use crate::utils::warning_rules::generate_warnings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const SELECT_TABLE: &str = "table";
+pub const SELECT_COLUMN: &str = "column";
+pub const SELECT_INDEX: &str = "index";
+pub const SELECT_FOREIGN_KEY: &str = "fk";
+pub const SELECT_CHECK: &str = "check";
+pub const SELECT_VIEW: &str = "view";
+pub const SELECT_ROUTINE: &str = "routine";
+pub const SELECT_EXTENSION: &str = "extension";
+pub const SELECT_TYPE: &str = "type";
+
+/// Which differences to migrate, given as object keys: `table:users`,
+/// `column:users.email`, `index:users.users_email_idx`,
+/// `fk:orders.orders_user_id_fkey`, `check:orders.orders_total_check`,
+/// `view:active_users`, `routine:refresh_stats`, `extension:pgcrypto` or
+/// `type:order_status`. A table key covers the table and every change
+/// inside it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct MigrationSelection {
+    /// Keys to migrate; None migrates every difference
+    pub include: Option<Vec<String>>,
+    /// Keys to leave out, even when `include` covers them
+    pub exclude: Vec<String>,
+}
+
+impl MigrationSelection {
+    fn covers(&self, key: &str, table: Option<&str>) -> bool {
+        let table_key = table.map(|t| object_key(SELECT_TABLE, t));
+        let listed = |keys: &[String]| {
+            keys.iter()
+                .any(|k| k == key || table_key.as_deref() == Some(k.as_str()))
+        };
+        self.include.as_deref().is_none_or(listed) && !listed(&self.exclude)
+    }
+}
+
+/// Reduce a comparison to the selected differences, plus whatever they can't
+/// be migrated without (e.g. the new table a selected foreign key points to).
+/// Returns one note per object pulled in that way. Unselected changes to
+/// existing objects are reset to their target state rather than removed, so
+/// the planner still knows about them (views may need rebuilding, say).
+pub(crate) fn apply_selection(
+    comparison: &SchemaComparison,
+    selection: &MigrationSelection,
+) -> (SchemaComparison, Vec<String>) {
+    let mut selected = initial_selection(comparison, selection);
+    let notes = add_dependencies(comparison, &mut selected);
+
+    let mut filtered = comparison.clone();
+    filtered.table_differences = filtered
+        .table_differences
+        .into_iter()
+        .filter_map(|t| select_table(t, &selected))
+        .collect();
+
+    filtered.view_differences = filtered
+        .view_differences
+        .into_iter()
+        .filter_map(|mut view| {
+            if matches!(view.status, DiffStatus::Identical)
+                || selected.contains(&object_key(SELECT_VIEW, &view.view_name))
+            {
+                return Some(view);
+            }
+            if matches!(view.status, DiffStatus::Added) {
+                return None;
+            }
+            view.status = DiffStatus::Identical;
+            view.source_definition = view.target_definition.clone();
+            view.definition_changed = false;
+            view.source_comment = view.target_comment.clone();
+            view.source_depends_on = view.target_depends_on.clone();
+            view.source_column_dependencies = view.target_column_dependencies.clone();
+            Some(view)
+        })
+        .collect();
+
+    filtered.routine_differences = filtered
+        .routine_differences
+        .into_iter()
+        .filter_map(|mut routine| {
+            if matches!(routine.status, DiffStatus::Identical)
+                || selected.contains(&object_key(SELECT_ROUTINE, &routine.routine_name))
+            {
+                return Some(routine);
+            }
+            if matches!(routine.status, DiffStatus::Added) {
+                return None;
+            }
+            routine.status = DiffStatus::Identical;
+            routine.source_definition = routine.target_definition.clone();
+            routine.definition_changed = false;
+            routine.comment_changed = false;
+            Some(routine)
+        })
+        .collect();
+
+    filtered.extension_differences = filtered
+        .extension_differences
+        .into_iter()
+        .filter_map(|mut extension| {
+            if matches!(extension.status, DiffStatus::Identical)
+                || selected.contains(&object_key(SELECT_EXTENSION, &extension.extension_name))
+            {
+                return Some(extension);
+            }
+            if matches!(extension.status, DiffStatus::Added) {
+                return None;
+            }
+            extension.status = DiffStatus::Identical;
+            extension.source_definition = extension.target_definition.clone();
+            Some(extension)
+        })
+        .collect();
+
+    filtered.type_differences = filtered
+        .type_differences
+        .into_iter()
+        .filter_map(|mut type_change| {
+            if matches!(type_change.status, DiffStatus::Identical)
+                || selected.contains(&object_key(SELECT_TYPE, &type_change.type_name))
+            {
+                return Some(type_change);
+            }
+            if matches!(type_change.status, DiffStatus::Added) {
+                return None;
+            }
+            type_change.status = DiffStatus::Identical;
+            type_change.source_definition = type_change.target_definition.clone();
+            Some(type_change)
+        })
+        .collect();
+
+    filtered.summary = comparison_summary(
+        &filtered.table_differences,
+        &filtered.view_differences,
+        &filtered.routine_differences,
+        &filtered.extension_differences,
+        &filtered.type_differences,
+    );
+    filtered.warnings = generate_warnings(
+        &filtered.schema_name,
+        &filtered.table_differences,
+        &filtered.view_differences,
+        &filtered.routine_differences,
+        &filtered.extension_differences,
+    );
+
+    (filtered, notes)
+}
+
+// Keys of the changed objects the selection covers. A table key stands for
+// the table itself (create, drop, rename, comment); its columns, indexes,
+// foreign keys and check constraints get keys of their own.
+fn initial_selection(comparison: &SchemaComparison, selection: &MigrationSelection) -> HashSet<String> {
+    let mut selected = HashSet::new();
+
+    for table_diff in &comparison.table_differences {
+        if matches!(table_diff.status, DiffStatus::Identical) {
+            continue;
+        }
+        let table = table_diff.table_name.as_str();
+        let mut select = |key: String, parent: Option<&str>| {
+            if selection.covers(&key, parent) {
+                selected.insert(key);
+            }
+        };
+
+        select(object_key(SELECT_TABLE, table), None);
+        for col_change in changed(&table_diff.column_changes, |c| &c.status) {
+            select(member_key(SELECT_COLUMN, table, &col_change.column_name), Some(table));
+        }
+        for idx_change in changed(&table_diff.index_changes, |i| &i.status) {
+            select(member_key(SELECT_INDEX, table, &idx_change.index_name), Some(table));
+        }
+        for fk_change in changed(&table_diff.fk_changes, |f| &f.status) {
+            select(member_key(SELECT_FOREIGN_KEY, table, &fk_change.constraint_name), Some(table));
+        }
+        for check_change in changed(&table_diff.check_changes, |c| &c.status) {
+            select(member_key(SELECT_CHECK, table, &check_change.constraint_name), Some(table));
+        }
+    }
+
+    for view_change in changed(&comparison.view_differences, |v| &v.status) {
+        let key = object_key(SELECT_VIEW, &view_change.view_name);
+        if selection.covers(&key, None) {
+            selected.insert(key);
+        }
+    }
+    for routine_change in changed(&comparison.routine_differences, |r| &r.status) {
+        let key = object_key(SELECT_ROUTINE, &routine_change.routine_name);
+        if selection.covers(&key, None) {
+            selected.insert(key);
+        }
+    }
+    for type_change in changed(&comparison.type_differences, |t| &t.status) {
+        let key = object_key(SELECT_TYPE, &type_change.type_name);
+        if selection.covers(&key, None) {
+            selected.insert(key);
+        }
+    }
+    for extension_change in changed(&comparison.extension_differences, |e| &e.status) {
+        let key = object_key(SELECT_EXTENSION, &extension_change.extension_name);
+        // New extensions are prerequisites rather than changes of interest, so
+        // they are kept unless excluded explicitly
+        let kept = if matches!(extension_change.status, DiffStatus::Added) {
+            !selection.exclude.contains(&key)
+        } else {
+            selection.covers(&key, None)
+        };
+        if kept {
+            selected.insert(key);
+        }
+    }
+
+    selected
+}
+
+// Add what the selected changes need until nothing new is required
+fn add_dependencies(comparison: &SchemaComparison, selected: &mut HashSet<String>) -> Vec<String> {
+    let tables = &comparison.table_differences;
+    let find_table = |name: &str| tables.iter().find(|t| t.table_name == name);
+    // A table that doesn't exist on the target under this name until its own change runs
+    let table_pending = |table: &TableDifference| {
+        matches!(table.status, DiffStatus::Added) || table.renamed_from.is_some()
+    };
+    let column_added = |table: &TableDifference, column: &str| {
+        table
+            .column_changes
+            .iter()
+            .any(|c| c.column_name == column && matches!(c.status, DiffStatus::Added))
+    };
+    // A type that is new, or gets new enum values, before its own change runs
+    let type_pending = |name: &str| {
+        comparison
+            .type_differences
+            .iter()
+            .any(|t| t.type_name == name && matches!(t.status, DiffStatus::Added | DiffStatus::Modified))
+    };
+
+    let mut notes = Vec::new();
+    loop {
+        let mut required: Vec<(String, String)> = Vec::new();
+
+        for table_diff in tables {
+            let table = table_diff.table_name.as_str();
+            let table_key = object_key(SELECT_TABLE, table);
+            let member_selected = |kind: &str, name: &str| selected.contains(&member_key(kind, table, name));
+
+            let any_member_selected = table_diff
+                .column_changes
+                .iter()
+                .any(|c| member_selected(SELECT_COLUMN, &c.column_name))
+                || table_diff
+                    .index_changes
+                    .iter()
+                    .any(|i| member_selected(SELECT_INDEX, &i.index_name))
+                || table_diff
+                    .fk_changes
+                    .iter()
+                    .any(|f| member_selected(SELECT_FOREIGN_KEY, &f.constraint_name))
+                || table_diff
+                    .check_changes
+                    .iter()
+                    .any(|c| member_selected(SELECT_CHECK, &c.constraint_name));
+
+            if any_member_selected && !selected.contains(&table_key) {
+                let reason = match table_diff.status {
+                    DiffStatus::Added => Some("selected changes are part of creating it"),
+                    DiffStatus::Removed => Some("selected changes are part of dropping it"),
+                    _ if table_diff.renamed_from.is_some() => {
+                        Some("selected changes refer to it by its new name")
+                    }
+                    _ => None,
+                };
+                if let Some(reason) = reason {
+                    required.push((table_key.clone(), reason.to_string()));
+                }
+            }
+
+            // A new table is created with all its columns
+            let creating_table = matches!(table_diff.status, DiffStatus::Added) && selected.contains(&table_key);
+            for col_change in &table_diff.column_changes {
+                if !creating_table && !member_selected(SELECT_COLUMN, &col_change.column_name) {
+                    continue;
+                }
+                let Some(type_name) = col_change.source_definition.as_ref().and_then(column_type_name) else {
+                    continue;
+                };
+                if type_pending(type_name) {
+                    required.push((
+                        object_key(SELECT_TYPE, type_name),
+                        format!("column {}.{} uses it", table, col_change.column_name),
+                    ));
+                }
+            }
+
+            for fk_change in &table_diff.fk_changes {
+                if !member_selected(SELECT_FOREIGN_KEY, &fk_change.constraint_name)
+                    || matches!(fk_change.status, DiffStatus::Removed)
+                {
+                    continue;
+                }
+                let Some(fk) = &fk_change.source_definition else {
+                    continue;
+                };
+                let reason = format!("foreign key {} references it", fk.constraint_name);
+                if let Some(referenced) = find_table(&fk.foreign_table_name) {
+                    if table_pending(referenced) {
+                        required.push((object_key(SELECT_TABLE, &referenced.table_name), reason.clone()));
+                    }
+                    // A new table brings its columns along
+                    if !matches!(referenced.status, DiffStatus::Added)
+                        && column_added(referenced, &fk.foreign_column_name)
+                    {
+                        required.push((
+                            member_key(SELECT_COLUMN, &referenced.table_name, &fk.foreign_column_name),
+                            reason.clone(),
+                        ));
+                    }
+                }
+                if column_added(table_diff, &fk.column_name) {
+                    required.push((
+                        member_key(SELECT_COLUMN, table, &fk.column_name),
+                        format!("foreign key {} is defined on it", fk.constraint_name),
+                    ));
+                }
+            }
+
+            for check_change in &table_diff.check_changes {
+                if !member_selected(SELECT_CHECK, &check_change.constraint_name)
+                    || matches!(check_change.status, DiffStatus::Removed)
+                {
+                    continue;
+                }
+                let Some(check) = &check_change.source_definition else {
+                    continue;
+                };
+                for column in &check.columns {
+                    if column_added(table_diff, column) {
+                        required.push((
+                            member_key(SELECT_COLUMN, table, column),
+                            format!("check constraint {} reads it", check.constraint_name),
+                        ));
+                    }
+                }
+            }
+
+            for idx_change in &table_diff.index_changes {
+                if !member_selected(SELECT_INDEX, &idx_change.index_name)
+                    || matches!(idx_change.status, DiffStatus::Removed)
+                {
+                    continue;
+                }
+                let Some(idx) = &idx_change.source_definition else {
+                    continue;
+                };
+                for column in idx.columns.iter().chain(&idx.include_columns) {
+                    if column_added(table_diff, column) {
+                        required.push((
+                            member_key(SELECT_COLUMN, table, column),
+                            format!("index {} is built on it", idx.index_name),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for view_change in &comparison.view_differences {
+            if !selected.contains(&object_key(SELECT_VIEW, &view_change.view_name))
+                || matches!(view_change.status, DiffStatus::Removed)
+            {
+                continue;
+            }
+            let reason = format!("view {} reads from it", view_change.view_name);
+            for relation in &view_change.source_depends_on {
+                if let Some(table_diff) = find_table(relation) {
+                    if table_pending(table_diff) {
+                        required.push((object_key(SELECT_TABLE, relation), reason.clone()));
+                    }
+                } else if let Some(view) = comparison.view_differences.iter().find(|v| &v.view_name == relation) {
+                    if matches!(view.status, DiffStatus::Added | DiffStatus::Modified) {
+                        required.push((object_key(SELECT_VIEW, relation), reason.clone()));
+                    }
+                }
+            }
+            for dependency in &view_change.source_column_dependencies {
+                let Some((table, column)) = dependency.split_once('.') else {
+                    continue;
+                };
+                if find_table(table).is_some_and(|t| column_added(t, column)) {
+                    required.push((member_key(SELECT_COLUMN, table, column), reason.clone()));
+                }
+            }
+        }
+
+        for type_change in &comparison.type_differences {
+            if !selected.contains(&object_key(SELECT_TYPE, &type_change.type_name)) {
+                continue;
+            }
+            let Some(source_type) = &type_change.source_definition else {
+                continue;
+            };
+            for dependency in &source_type.depends_on {
+                if type_pending(dependency) {
+                    required.push((
+                        object_key(SELECT_TYPE, dependency),
+                        format!("type {} is built on it", type_change.type_name),
+                    ));
+                }
+            }
+        }
+
+        let mut changed = false;
+        for (key, reason) in required {
+            if selected.insert(key.clone()) {
+                notes.push(format!("Also migrating {} because {}", describe_key(&key), reason));
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    notes
+}
+
+fn select_table(mut table_diff: TableDifference, selected: &HashSet<String>) -> Option<TableDifference> {
+    let table = table_diff.table_name.clone();
+    let table_selected = selected.contains(&object_key(SELECT_TABLE, &table));
+
+    match table_diff.status {
+        DiffStatus::Identical => Some(table_diff),
+        // A new table is created with all its columns; its indexes, foreign
+        // keys and check constraints can be left out
+        DiffStatus::Added if table_selected => {
+            table_diff
+                .index_changes
+                .retain(|i| selected.contains(&member_key(SELECT_INDEX, &table, &i.index_name)));
+            table_diff
+                .fk_changes
+                .retain(|f| selected.contains(&member_key(SELECT_FOREIGN_KEY, &table, &f.constraint_name)));
+            table_diff
+                .check_changes
+                .retain(|c| selected.contains(&member_key(SELECT_CHECK, &table, &c.constraint_name)));
+            Some(table_diff)
+        }
+        DiffStatus::Added => None,
+        DiffStatus::Removed if table_selected => Some(table_diff),
+        DiffStatus::Removed | DiffStatus::Modified => {
+            table_diff.column_changes = table_diff
+                .column_changes
+                .into_iter()
+                .filter_map(|c| {
+                    let keep = selected.contains(&member_key(SELECT_COLUMN, &table, &c.column_name));
+                    select_column(c, keep)
+                })
+                .collect();
+            table_diff.index_changes = table_diff
+                .index_changes
+                .into_iter()
+                .filter_map(|i| {
+                    let keep = selected.contains(&member_key(SELECT_INDEX, &table, &i.index_name));
+                    select_index(i, keep)
+                })
+                .collect();
+            table_diff.fk_changes = table_diff
+                .fk_changes
+                .into_iter()
+                .filter_map(|f| {
+                    let keep = selected.contains(&member_key(SELECT_FOREIGN_KEY, &table, &f.constraint_name));
+                    select_foreign_key(f, keep)
+                })
+                .collect();
+            table_diff.check_changes = table_diff
+                .check_changes
+                .into_iter()
+                .filter_map(|c| {
+                    let keep = selected.contains(&member_key(SELECT_CHECK, &table, &c.constraint_name));
+                    select_check_constraint(c, keep)
+                })
+                .collect();
+
+            if !table_selected {
+                table_diff.source_comment = table_diff.target_comment.clone();
+                table_diff.renamed_from = None;
+            }
+
+            let has_changes = table_diff.renamed_from.is_some()
+                || table_diff.source_comment != table_diff.target_comment
+                || changed(&table_diff.column_changes, |c| &c.status).next().is_some()
+                || changed(&table_diff.index_changes, |i| &i.status).next().is_some()
+                || changed(&table_diff.fk_changes, |f| &f.status).next().is_some()
+                || changed(&table_diff.check_changes, |c| &c.status).next().is_some();
+            table_diff.status = if has_changes {
+                DiffStatus::Modified
+            } else {
+                DiffStatus::Identical
+            };
+            Some(table_diff)
+        }
+    }
+}
+
+// Unselected changes: new objects are left out, existing ones keep their
+// target definition
+fn select_column(mut col_change: ColumnChange, keep: bool) -> Option<ColumnChange> {
+    if keep || matches!(col_change.status, DiffStatus::Identical) {
+        return Some(col_change);
+    }
+    let target = col_change.target_definition.clone()?;
+    col_change.status = DiffStatus::Identical;
+    col_change.column_name = target.column_name.clone();
+    col_change.source_definition = Some(target);
+    col_change.changes.clear();
+    Some(col_change)
+}
+
+fn select_index(mut idx_change: IndexChange, keep: bool) -> Option<IndexChange> {
+    if keep || matches!(idx_change.status, DiffStatus::Identical) {
+        return Some(idx_change);
+    }
+    idx_change.target_definition.as_ref()?;
+    idx_change.status = DiffStatus::Identical;
+    idx_change.source_definition = idx_change.target_definition.clone();
+    Some(idx_change)
+}
+
+fn select_foreign_key(mut fk_change: ForeignKeyChange, keep: bool) -> Option<ForeignKeyChange> {
+    if keep || matches!(fk_change.status, DiffStatus::Identical) {
+        return Some(fk_change);
+    }
+    fk_change.target_definition.as_ref()?;
+    fk_change.status = DiffStatus::Identical;
+    fk_change.source_definition = fk_change.target_definition.clone();
+    Some(fk_change)
+}
+
+fn select_check_constraint(
+    mut check_change: CheckConstraintChange,
+    keep: bool,
+) -> Option<CheckConstraintChange> {
+    if keep || matches!(check_change.status, DiffStatus::Identical) {
+        return Some(check_change);
+    }
+    check_change.target_definition.as_ref()?;
+    check_change.status = DiffStatus::Identical;
+    check_change.source_definition = check_change.target_definition.clone();
+    Some(check_change)
+}
+
+fn changed<'a, T>(
+    items: &'a [T],
+    status: impl Fn(&T) -> &DiffStatus + 'a,
+) -> impl Iterator<Item = &'a T> + 'a {
+    items
+        .iter()
+        .filter(move |item| !matches!(status(item), DiffStatus::Identical))
+}
+
+fn object_key(kind: &str, name: &str) -> String {
+    format!("{}:{}", kind, name)
+}
+
+fn member_key(kind: &str, table: &str, name: &str) -> String {
+    format!("{}:{}.{}", kind, table, name)
+}
+
+// "fk:orders.orders_user_id_fkey" -> "foreign key orders.orders_user_id_fkey"
+fn describe_key(key: &str) -> String {
+    let (kind, name) = key.split_once(':').unwrap_or(("object", key));
+    let kind = match kind {
+        SELECT_FOREIGN_KEY => "foreign key",
+        SELECT_CHECK => "check constraint",
+        SELECT_ROUTINE => "function",
+        other => other,
+    };
+    format!("{} {}", kind, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TYPE_KIND_ENUM;
+    use crate::models::{EnhancedColumnInfo, ForeignKeyInfo, TypeInfo};
+    use crate::utils::schema_diff::TypeChange;
+
+    fn column(name: &str, data_type: &str) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            column_name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: "YES".to_string(),
+            is_primary_key: false,
+            column_default: None,
+            character_maximum_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: 1,
+            comment: None,
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        }
+    }
+
+    fn column_change(
+        status: DiffStatus,
+        source: Option<EnhancedColumnInfo>,
+        target: Option<EnhancedColumnInfo>,
+    ) -> ColumnChange {
+        ColumnChange {
+            column_name: source
+                .as_ref()
+                .or(target.as_ref())
+                .unwrap()
+                .column_name
+                .clone(),
+            status,
+            source_definition: source,
+            target_definition: target,
+            changes: Vec::new(),
+        }
+    }
+
+    fn table(name: &str, status: DiffStatus, column_changes: Vec<ColumnChange>) -> TableDifference {
+        TableDifference {
+            table_name: name.to_string(),
+            status,
+            column_changes,
+            index_changes: Vec::new(),
+            fk_changes: Vec::new(),
+            check_changes: Vec::new(),
+            source_comment: None,
+            target_comment: None,
+            renamed_from: None,
+        }
+    }
+
+    fn comparison(
+        table_differences: Vec<TableDifference>,
+        type_differences: Vec<TypeChange>,
+    ) -> SchemaComparison {
+        SchemaComparison {
+            source_connection: "dev".to_string(),
+            target_connection: "prod".to_string(),
+            schema_name: "public".to_string(),
+            summary: comparison_summary(&table_differences, &[], &[], &[], &type_differences),
+            table_differences,
+            view_differences: Vec::new(),
+            routine_differences: Vec::new(),
+            extension_differences: Vec::new(),
+            type_differences,
+            rename_candidates: Vec::new(),
+            warnings: Vec::new(),
+            target_server_version: None,
+        }
+    }
+
+    fn include(keys: &[&str]) -> MigrationSelection {
+        MigrationSelection {
+            include: Some(keys.iter().map(|k| k.to_string()).collect()),
+            exclude: Vec::new(),
+        }
+    }
+
+    // orders gets a new customer_id column and a foreign key to the new customers table
+    fn orders_with_new_customers() -> SchemaComparison {
+        let mut orders = table(
+            "orders",
+            DiffStatus::Modified,
+            vec![
+                column_change(
+                    DiffStatus::Added,
+                    Some(column("customer_id", "integer")),
+                    None,
+                ),
+                column_change(
+                    DiffStatus::Modified,
+                    Some(column("note", "text")),
+                    Some(column("note", "character varying")),
+                ),
+            ],
+        );
+        orders.fk_changes.push(ForeignKeyChange {
+            constraint_name: "orders_customer_fk".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(ForeignKeyInfo {
+                constraint_name: "orders_customer_fk".to_string(),
+                table_name: "orders".to_string(),
+                column_name: "customer_id".to_string(),
+                foreign_table_name: "customers".to_string(),
+                foreign_column_name: "id".to_string(),
+            }),
+            target_definition: None,
+        });
+        let customers = table(
+            "customers",
+            DiffStatus::Added,
+            vec![column_change(
+                DiffStatus::Added,
+                Some(column("id", "integer")),
+                None,
+            )],
+        );
+        comparison(vec![orders, customers], Vec::new())
+    }
+
+    #[test]
+    fn selected_foreign_key_pulls_in_its_column_and_new_table() {
+        let (filtered, notes) = apply_selection(
+            &orders_with_new_customers(),
+            &include(&["fk:orders.orders_customer_fk"]),
+        );
+
+        assert_eq!(
+            notes,
+            vec![
+                "Also migrating table customers because foreign key orders_customer_fk references it",
+                "Also migrating column orders.customer_id because foreign key orders_customer_fk is defined on it",
+            ]
+        );
+        let tables: Vec<(&str, &DiffStatus)> = filtered
+            .table_differences
+            .iter()
+            .map(|t| (t.table_name.as_str(), &t.status))
+            .collect();
+        assert!(matches!(
+            tables.as_slice(),
+            [
+                ("orders", DiffStatus::Modified),
+                ("customers", DiffStatus::Added)
+            ]
+        ));
+    }
+
+    #[test]
+    fn unselected_changes_are_reset_to_the_target() {
+        let (filtered, notes) = apply_selection(
+            &orders_with_new_customers(),
+            &include(&["column:orders.note"]),
+        );
+
+        assert!(notes.is_empty());
+        // The new table is left out and so are the new column and foreign key
+        assert_eq!(filtered.table_differences.len(), 1);
+        let orders = &filtered.table_differences[0];
+        assert!(matches!(orders.status, DiffStatus::Modified));
+        assert!(orders.fk_changes.is_empty());
+        let columns: Vec<&str> = orders
+            .column_changes
+            .iter()
+            .map(|c| c.column_name.as_str())
+            .collect();
+        assert_eq!(columns, vec!["note"]);
+        assert_eq!(filtered.summary.tables_added, 0);
+    }
+
+    #[test]
+    fn excluded_keys_win_over_included_tables() {
+        let selection = MigrationSelection {
+            include: Some(vec!["table:orders".to_string()]),
+            exclude: vec!["column:orders.note".to_string()],
+        };
+        let (filtered, _) = apply_selection(&orders_with_new_customers(), &selection);
+
+        let orders = &filtered.table_differences[0];
+        let note = orders
+            .column_changes
+            .iter()
+            .find(|c| c.column_name == "note")
+            .unwrap();
+        assert!(matches!(note.status, DiffStatus::Identical));
+        assert_eq!(
+            note.source_definition.as_ref().unwrap().data_type,
+            "character varying"
+        );
+        // The foreign key still needs the new table
+        assert!(filtered
+            .table_differences
+            .iter()
+            .any(|t| t.table_name == "customers"));
+    }
+
+    #[test]
+    fn selected_column_pulls_in_the_new_type_it_uses() {
+        let status = EnhancedColumnInfo {
+            udt_name: Some("order_status".to_string()),
+            udt_schema: Some("public".to_string()),
+            ..column("status", "USER-DEFINED")
+        };
+        let orders = table(
+            "orders",
+            DiffStatus::Modified,
+            vec![column_change(DiffStatus::Added, Some(status), None)],
+        );
+        let order_status = TypeInfo {
+            type_name: "order_status".to_string(),
+            kind: TYPE_KIND_ENUM.to_string(),
+            definition: "AS ENUM ('new')".to_string(),
+            enum_labels: vec!["new".to_string()],
+            depends_on: Vec::new(),
+        };
+        let type_change = TypeChange {
+            type_name: "order_status".to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(order_status),
+            target_definition: None,
+        };
+
+        let (filtered, notes) = apply_selection(
+            &comparison(vec![orders], vec![type_change]),
+            &include(&["column:orders.status"]),
+        );
+
+        assert_eq!(
+            notes,
+            vec!["Also migrating type order_status because column orders.status uses it"]
+        );
+        assert_eq!(filtered.type_differences.len(), 1);
+    }
+}
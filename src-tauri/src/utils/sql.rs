@@ -0,0 +1,424 @@
+// Helpers for rendering identifiers and literals into generated SQL
+
+// Keywords that can't be used as bare column/table names
+// (PostgreSQL's reserved, type/function-name and column-name categories)
+const NON_BARE_KEYWORDS: &[&str] = &[
+    "all", "analyse", "analyze", "and", "any", "array", "as", "asc", "asymmetric",
+    "authorization", "between", "bigint", "binary", "bit", "boolean", "both", "case", "cast",
+    "char", "character", "check", "coalesce", "collate", "collation", "column", "concurrently",
+    "constraint", "create", "cross", "current_catalog", "current_date", "current_role",
+    "current_schema", "current_time", "current_timestamp", "current_user", "dec", "decimal",
+    "default", "deferrable", "desc", "distinct", "do", "else", "end", "except", "exists",
+    "extract", "false", "fetch", "float", "for", "foreign", "freeze", "from", "full", "grant",
+    "greatest", "group", "grouping", "having", "ilike", "in", "initially", "inner", "inout",
+    "int", "integer", "intersect", "interval", "into", "is", "isnull", "join", "json",
+    "json_array", "json_arrayagg", "json_exists", "json_object", "json_objectagg", "json_query",
+    "json_scalar", "json_serialize", "json_table", "json_value", "lateral", "leading", "least",
+    "left", "like", "limit", "localtime", "localtimestamp", "merge_action", "national",
+    "natural", "nchar", "none", "normalize", "not", "notnull", "null", "nullif", "numeric",
+    "offset", "on", "only", "or", "order", "out", "outer", "overlaps", "overlay", "placing",
+    "position", "precision", "primary", "real", "references", "returning", "right", "row",
+    "select", "session_user", "setof", "similar", "smallint", "some", "substring", "symmetric",
+    "system_user", "table", "tablesample", "then", "time", "timestamp", "to", "trailing",
+    "treat", "trim", "true", "union", "unique", "user", "using", "values", "varchar",
+    "variadic", "verbose", "when", "where", "window", "with", "xmlattributes", "xmlconcat",
+    "xmlelement", "xmlexists", "xmlforest", "xmlnamespaces", "xmlparse", "xmlpi", "xmlroot",
+    "xmlserialize", "xmltable",
+];
+
+/// Quote an identifier only when it can't be written bare, e.g. `users`
+/// stays as is while `User`, `order` and `my table` become `"User"`,
+/// `"order"` and `"my table"`.
+pub fn quote_ident(name: &str) -> String {
+    if is_bare_identifier(name) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// The name a possibly quoted identifier stands for; the inverse of
+/// `quote_ident` for names Postgres printed
+pub fn unquote_ident(ident: &str) -> String {
+    match ident.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => ident.to_string(),
+    }
+}
+
+/// Schema-qualified, quoted object name, e.g. `public."User"`
+pub fn qualified_name(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_ident(schema), quote_ident(name))
+}
+
+/// Render a value as a SQL string literal
+pub fn quote_literal(value: &str) -> String {
+    let escaped = value.replace('\'', "''");
+    if escaped.contains('\\') {
+        format!("E'{}'", escaped.replace('\\', "\\\\"))
+    } else {
+        format!("'{}'", escaped)
+    }
+}
+
+// Lowercase ASCII letters, digits, '_' and '$', not starting with a digit or
+// '$', and not a keyword. Anything else (uppercase, spaces, quotes, non-ASCII)
+// would be case-folded or rejected by the parser if left unquoted.
+fn is_bare_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let starts_ok = matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c == '_');
+    starts_ok
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$')
+        && !NON_BARE_KEYWORDS.contains(&name)
+}
+
+/// Split a script into statements on top-level semicolons. Semicolons in
+/// string literals, quoted identifiers, dollar-quoted bodies and comments
+/// don't count. Comments outside those are dropped, so comment-only chunks
+/// (script headers, section banners) don't become statements.
+pub fn split_statements(script: &str) -> Vec<String> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '-' && next == Some('-') {
+            // Line comment; the newline itself is kept
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            // Block comments nest in PostgreSQL
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            current.push(' ');
+        } else if c == '\'' || c == '"' {
+            // E'...' strings use backslash escapes; everything else doubles the quote
+            let mut preceding = current.chars().rev();
+            let backslash_escapes = c == '\''
+                && matches!(preceding.next(), Some('E' | 'e'))
+                && !preceding.next().is_some_and(is_identifier_char);
+            current.push(c);
+            i += 1;
+            while i < chars.len() {
+                let ch = chars[i];
+                current.push(ch);
+                i += 1;
+                if backslash_escapes && ch == '\\' {
+                    if let Some(&escaped) = chars.get(i) {
+                        current.push(escaped);
+                        i += 1;
+                    }
+                } else if ch == c {
+                    if chars.get(i) == Some(&c) {
+                        current.push(c);
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+        } else if let Some(tag) = dollar_quote_tag(&chars, i, &current) {
+            // Copy up to and including the matching closing tag
+            let tag: Vec<char> = tag.chars().collect();
+            current.extend(&tag);
+            i += tag.len();
+            while i < chars.len() {
+                if chars[i..].starts_with(&tag) {
+                    current.extend(&tag);
+                    i += tag.len();
+                    break;
+                }
+                current.push(chars[i]);
+                i += 1;
+            }
+        } else if c == ';' {
+            push_statement(&mut statements, &current);
+            current.clear();
+            i += 1;
+        } else {
+            current.push(c);
+            i += 1;
+        }
+    }
+    push_statement(&mut statements, &current);
+
+    statements
+}
+
+/// Statements PostgreSQL refuses to run inside a transaction block
+pub fn runs_outside_transaction(statement: &str) -> bool {
+    let upper = statement.to_uppercase();
+    let words: Vec<&str> = upper.split_whitespace().take(5).collect();
+    match words.as_slice() {
+        ["VACUUM", ..] | ["ALTER", "SYSTEM", ..] => true,
+        ["CREATE" | "DROP", "DATABASE" | "TABLESPACE", ..] => true,
+        ["CREATE" | "DROP" | "REINDEX", ..] => words.contains(&"CONCURRENTLY"),
+        // Refused before PostgreSQL 12; later the new value can't be used
+        // until the transaction that added it commits
+        ["ALTER", "TYPE", _, "ADD", "VALUE", ..] => true,
+        _ => false,
+    }
+}
+
+/// BEGIN, COMMIT and friends
+pub fn is_transaction_control(statement: &str) -> bool {
+    let upper = statement.to_uppercase();
+    let mut words = upper.split_whitespace();
+    match words.next() {
+        Some("BEGIN" | "COMMIT" | "END" | "ABORT") => true,
+        Some("START") => words.next() == Some("TRANSACTION"),
+        Some("ROLLBACK") => words.next() != Some("TO"), // ROLLBACK TO SAVEPOINT stays
+        _ => false,
+    }
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+}
+
+// "$$" or "$tag$" at position `i`, unless the '$' is part of an identifier
+// or a positional parameter such as $1
+fn dollar_quote_tag(chars: &[char], i: usize, preceding: &str) -> Option<String> {
+    if chars[i] != '$' || preceding.chars().last().is_some_and(is_identifier_char) {
+        return None;
+    }
+    let mut end = i + 1;
+    while end < chars.len() && chars[end] != '$' {
+        let c = chars[end];
+        let valid = c.is_alphabetic() || c == '_' || (end > i + 1 && c.is_ascii_digit());
+        if !valid {
+            return None;
+        }
+        end += 1;
+    }
+    if end >= chars.len() {
+        return None;
+    }
+    Some(chars[i..=end].iter().collect())
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{quickcheck, Arbitrary, Gen};
+
+    // Strings drawn mostly from the characters quoting has to get right
+    #[derive(Clone, Debug)]
+    struct Name(String);
+
+    impl Arbitrary for Name {
+        fn arbitrary(g: &mut Gen) -> Self {
+            const CHARS: &[char] = &[
+                'a', 'z', '_', '$', '1', 'A', 'Z', ' ', '"', '\'', '\\', '.', ';', '-', 'é', 'ß',
+                '名', '🙂',
+            ];
+            let len = usize::arbitrary(g) % 8;
+            let mut name: String = (0..len).map(|_| *g.choose(CHARS).unwrap()).collect();
+            if bool::arbitrary(g) {
+                name.push_str(&String::arbitrary(g));
+            }
+            Name(name)
+        }
+    }
+
+    // What Postgres reads an identifier as: quoted ones verbatim with "" as
+    // a quote, bare ones case-folded. None if it wouldn't parse as one token.
+    fn read_ident(sql: &str) -> Option<String> {
+        if let Some(inner) = sql
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        {
+            if inner.replace("\"\"", "").contains('"') {
+                return None;
+            }
+            return Some(inner.replace("\"\"", "\""));
+        }
+        let mut chars = sql.chars();
+        let starts_ok = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_');
+        let rest_ok = chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        let keyword = NON_BARE_KEYWORDS.contains(&sql.to_lowercase().as_str());
+        (starts_ok && rest_ok && !keyword).then(|| sql.to_lowercase())
+    }
+
+    // Split a qualified name on the dot outside quotes
+    fn read_qualified(sql: &str) -> Option<(String, String)> {
+        let mut quoted = false;
+        for (i, c) in sql.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                '.' if !quoted => {
+                    return Some((read_ident(&sql[..i])?, read_ident(&sql[i + 1..])?))
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // What Postgres reads a literal as, with standard_conforming_strings on
+    fn read_literal(sql: &str) -> Option<String> {
+        let (escapes, body) = match sql.strip_prefix("E'") {
+            Some(body) => (true, body),
+            None => (false, sql.strip_prefix('\'')?),
+        };
+        let mut chars = body.chars().peekable();
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    value.push('\'');
+                }
+                '\'' => return chars.next().is_none().then_some(value),
+                '\\' if escapes => value.push(chars.next()?),
+                c => value.push(c),
+            }
+        }
+        None
+    }
+
+    quickcheck! {
+        fn quote_ident_reads_back_as_the_name(name: Name) -> bool {
+            read_ident(&quote_ident(&name.0)) == Some(name.0)
+        }
+
+        fn qualified_name_reads_back_as_both_parts(schema: Name, name: Name) -> bool {
+            read_qualified(&qualified_name(&schema.0, &name.0)) == Some((schema.0, name.0))
+        }
+
+        fn quote_literal_reads_back_as_the_value(value: Name) -> bool {
+            read_literal(&quote_literal(&value.0)) == Some(value.0)
+        }
+
+        fn unquote_ident_undoes_quote_ident(name: Name) -> bool {
+            unquote_ident(&quote_ident(&name.0)) == name.0
+        }
+    }
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(quote_ident("users"), "users");
+        assert_eq!(quote_ident("_tmp$1"), "_tmp$1");
+        assert_eq!(quote_ident("User"), "\"User\"");
+        assert_eq!(quote_ident("order"), "\"order\"");
+        assert_eq!(quote_ident("my table"), "\"my table\"");
+        assert_eq!(quote_ident("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote_ident("café"), "\"café\"");
+        assert_eq!(quote_ident("1st"), "\"1st\"");
+        assert_eq!(quote_ident(""), "\"\"");
+        assert_eq!(qualified_name("public", "User"), "public.\"User\"");
+        assert_eq!(qualified_name("my.schema", "t"), "\"my.schema\".t");
+    }
+
+    #[test]
+    fn escapes_literals() {
+        assert_eq!(quote_literal("it's"), "'it''s'");
+        assert_eq!(quote_literal("C:\\temp"), "E'C:\\\\temp'");
+        assert_eq!(quote_literal("名前"), "'名前'");
+        assert_eq!(quote_literal(""), "''");
+    }
+
+    #[test]
+    fn splits_on_top_level_semicolons_only() {
+        let script = "CREATE TABLE \"a;b\" (x text DEFAULT ';');\n\
+                      INSERT INTO t VALUES (E'it\\'s;'), ('x'';y')";
+        assert_eq!(
+            split_statements(script),
+            vec![
+                "CREATE TABLE \"a;b\" (x text DEFAULT ';')",
+                "INSERT INTO t VALUES (E'it\\'s;'), ('x'';y')",
+            ]
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_bodies_are_kept_whole() {
+        let script =
+            "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $$ $body$ LANGUAGE sql;\n\
+                      DO $$ BEGIN PERFORM 1; END $$;\n\
+                      SELECT $1, a$b FROM t";
+        assert_eq!(
+            split_statements(script),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $$ $body$ LANGUAGE sql",
+                "DO $$ BEGIN PERFORM 1; END $$",
+                "SELECT $1, a$b FROM t",
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_dropped_including_nested_ones() {
+        let script = "-- header; not a statement\n\
+                      /* outer /* inner; */ still comment; */ SELECT 1;\n\
+                      -- ====\n\
+                      ;;\n";
+        assert_eq!(split_statements(script), vec!["SELECT 1"]);
+        assert!(split_statements("").is_empty());
+        assert!(split_statements("  -- only a comment").is_empty());
+    }
+
+    #[test]
+    fn recognizes_statements_that_need_no_transaction() {
+        assert!(runs_outside_transaction(
+            "create index concurrently i on t (x)"
+        ));
+        assert!(runs_outside_transaction("DROP INDEX CONCURRENTLY i"));
+        assert!(runs_outside_transaction("VACUUM ANALYZE t"));
+        assert!(runs_outside_transaction("CREATE DATABASE scratch"));
+        assert!(runs_outside_transaction("ALTER TYPE s ADD VALUE 'x'"));
+        assert!(!runs_outside_transaction("CREATE INDEX i ON t (x)"));
+        assert!(!runs_outside_transaction(
+            "ALTER TYPE s RENAME VALUE 'x' TO 'y'"
+        ));
+        assert!(!runs_outside_transaction(""));
+    }
+
+    #[test]
+    fn recognizes_transaction_control() {
+        for statement in [
+            "BEGIN",
+            "commit",
+            "END",
+            "ABORT",
+            "START TRANSACTION READ ONLY",
+            "ROLLBACK",
+        ] {
+            assert!(is_transaction_control(statement), "{}", statement);
+        }
+        for statement in [
+            "ROLLBACK TO SAVEPOINT s",
+            "SAVEPOINT s",
+            "START",
+            "SELECT 1",
+            "",
+        ] {
+            assert!(!is_transaction_control(statement), "{}", statement);
+        }
+    }
+}
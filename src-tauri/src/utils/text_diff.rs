@@ -0,0 +1,183 @@
+use crate::constants::TEXT_DIFF_MAX_CELLS;
+use crate::utils::schema_diff::DiffStatus;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffLine {
+    pub status: DiffStatus, // identical, added (only in new) or removed (only in old)
+    pub text: String,
+    pub old_line: Option<usize>, // 1-based
+    pub new_line: Option<usize>, // 1-based
+}
+
+/// Line diff of two texts along their longest common subsequence. Removed
+/// lines come before the added lines that replace them. A changed section too
+/// big to align (TEXT_DIFF_MAX_CELLS) is shown as replaced wholesale.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // Only the middle that differs goes through the quadratic table
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+    // common[i][j]: LCS length of old_middle[i..] and new_middle[j..]. Not
+    // built when too big; the walk below then removes old lines first.
+    let aligned = old_middle.len().saturating_mul(new_middle.len()) <= TEXT_DIFF_MAX_CELLS;
+    let mut common = Vec::new();
+    if aligned {
+        common = vec![vec![0usize; new_middle.len() + 1]; old_middle.len() + 1];
+    }
+    for i in (0..common.len().saturating_sub(1)).rev() {
+        for j in (0..new_middle.len()).rev() {
+            common[i][j] = if old_middle[i] == new_middle[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old_lines.len().max(new_lines.len()));
+    let mut push = |status: DiffStatus, text: &str, old_line: Option<usize>, new_line: Option<usize>| {
+        lines.push(DiffLine {
+            status,
+            text: text.to_string(),
+            old_line,
+            new_line,
+        });
+    };
+
+    for (offset, text) in old_lines[..prefix].iter().enumerate() {
+        push(DiffStatus::Identical, text, Some(offset + 1), Some(offset + 1));
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() || j < new_middle.len() {
+        let (old_line, new_line) = (prefix + i + 1, prefix + j + 1);
+        if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+            push(DiffStatus::Identical, old_middle[i], Some(old_line), Some(new_line));
+            i += 1;
+            j += 1;
+        } else if i < old_middle.len()
+            && (j == new_middle.len() || !aligned || common[i + 1][j] >= common[i][j + 1])
+        {
+            push(DiffStatus::Removed, old_middle[i], Some(old_line), None);
+            i += 1;
+        } else {
+            push(DiffStatus::Added, new_middle[j], None, Some(new_line));
+            j += 1;
+        }
+    }
+
+    let old_start = old_lines.len() - suffix;
+    let new_start = new_lines.len() - suffix;
+    for (offset, text) in old_lines[old_start..].iter().enumerate() {
+        push(
+            DiffStatus::Identical,
+            text,
+            Some(old_start + offset + 1),
+            Some(new_start + offset + 1),
+        );
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "  text", "- text" or "+ text" with both line numbers
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                let marker = match line.status {
+                    DiffStatus::Added => '+',
+                    DiffStatus::Removed => '-',
+                    _ => ' ',
+                };
+                let number =
+                    |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_else(|| "_".to_string());
+                format!(
+                    "{}{} {}:{}",
+                    marker,
+                    line.text,
+                    number(line.old_line),
+                    number(line.new_line)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_texts_have_only_identical_lines() {
+        assert_eq!(
+            render(&diff_lines("a\nb", "a\nb")),
+            vec![" a 1:1", " b 2:2"]
+        );
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn replaced_lines_come_removed_then_added() {
+        let lines = diff_lines(
+            "SELECT *\nFROM users\nWHERE id = 1",
+            "SELECT *\nFROM accounts\nWHERE id = 1",
+        );
+        assert_eq!(
+            render(&lines),
+            vec![
+                " SELECT * 1:1",
+                "-FROM users 2:_",
+                "+FROM accounts _:2",
+                " WHERE id = 1 3:3"
+            ]
+        );
+    }
+
+    #[test]
+    fn insertions_and_deletions_keep_line_numbers_in_step() {
+        let lines = diff_lines("a\nb\nc\nd", "x\na\nc\nd\ne");
+        assert_eq!(
+            render(&lines),
+            vec!["+x _:1", " a 1:2", "-b 2:_", " c 3:3", " d 4:4", "+e _:5"]
+        );
+    }
+
+    #[test]
+    fn changes_too_big_to_align_are_a_plain_replacement() {
+        let body = |prefix: &str| -> String {
+            (0..3000).map(|n| format!("{} {}\n", prefix, n)).collect()
+        };
+        let old = format!("first\n{}shared", body("old"));
+        let new = format!("first\n{}shared", body("new"));
+
+        let lines = diff_lines(&old, &new);
+        let statuses: Vec<&DiffStatus> = lines.iter().map(|line| &line.status).collect();
+
+        assert_eq!(lines.len(), 6002);
+        assert!(matches!(statuses[0], DiffStatus::Identical));
+        assert!(statuses[1..3001].iter().all(|s| matches!(s, DiffStatus::Removed)));
+        assert!(statuses[3001..6001].iter().all(|s| matches!(s, DiffStatus::Added)));
+        assert_eq!(render(&lines[6001..]), vec![" shared 3002:3002"]);
+    }
+
+    #[test]
+    fn one_side_empty() {
+        assert_eq!(render(&diff_lines("", "a\nb")), vec!["+a _:1", "+b _:2"]);
+        assert_eq!(render(&diff_lines("a", "")), vec!["-a 1:_"]);
+    }
+}
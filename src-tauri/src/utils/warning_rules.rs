@@ -0,0 +1,1091 @@
+use crate::constants::{
+    SQL_NULLABLE_YES, WARNING_TYPE_BREAKING_CHANGE, WARNING_TYPE_DATA_LOSS, WARNING_TYPE_LOCKING,
+};
+use crate::models::{CheckConstraintInfo, EnhancedColumnInfo, ExtensionInfo, IndexInfo};
+use crate::utils::schema_diff::{
+    ColumnChange, ComparisonWarning, DiffStatus, ExtensionChange, RoutineChange, TableDifference,
+    ViewChange, WarningSeverity,
+};
+use crate::utils::sql::{qualified_name, quote_ident};
+use std::collections::HashSet;
+
+// Everything a rule can look at
+struct RuleContext<'a> {
+    schema: &'a str,
+    table_diffs: &'a [TableDifference],
+    view_diffs: &'a [ViewChange],
+    routine_diffs: &'a [RoutineChange],
+    extension_diffs: &'a [ExtensionChange],
+    /// "table.column" for every column that goes away, including columns of dropped tables
+    dropped_columns: HashSet<String>,
+    dropped_tables: HashSet<String>,
+}
+
+type Rule = fn(&RuleContext) -> Vec<ComparisonWarning>;
+
+// Each rule looks at one kind of change; the order here is the order of the
+// resulting warnings
+const RULES: &[Rule] = &[
+    extension_rule,
+    dropped_table_rule,
+    dropped_column_rule,
+    column_type_rule,
+    column_length_rule,
+    not_null_rule,
+    required_column_rule,
+    unique_index_rule,
+    foreign_key_rule,
+    check_constraint_rule,
+    dependent_view_rule,
+    dependent_routine_rule,
+    removed_view_rule,
+    removed_routine_rule,
+    locking_rule,
+];
+
+/// Classify the changes in a comparison by risk. Warnings whose outcome
+/// depends on the data carry a `check_query` that counts the offending rows
+/// on the target.
+pub(crate) fn generate_warnings(
+    schema: &str,
+    table_diffs: &[TableDifference],
+    view_diffs: &[ViewChange],
+    routine_diffs: &[RoutineChange],
+    extension_diffs: &[ExtensionChange],
+) -> Vec<ComparisonWarning> {
+    let mut dropped_columns = HashSet::new();
+    let mut dropped_tables = HashSet::new();
+    for table_diff in table_diffs {
+        let table_removed = matches!(table_diff.status, DiffStatus::Removed);
+        if table_removed {
+            dropped_tables.insert(table_diff.table_name.clone());
+        }
+        for col_change in &table_diff.column_changes {
+            if table_removed || matches!(col_change.status, DiffStatus::Removed) {
+                dropped_columns.insert(format!("{}.{}", table_diff.table_name, col_change.column_name));
+            }
+        }
+    }
+
+    let context = RuleContext {
+        schema,
+        table_diffs,
+        view_diffs,
+        routine_diffs,
+        extension_diffs,
+        dropped_columns,
+        dropped_tables,
+    };
+
+    RULES.iter().flat_map(|rule| rule(&context)).collect()
+}
+
+fn warning(
+    severity: WarningSeverity,
+    warning_type: &str,
+    message: String,
+    affected_object: String,
+    details: &str,
+) -> ComparisonWarning {
+    ComparisonWarning {
+        severity,
+        warning_type: warning_type.to_string(),
+        message,
+        affected_object,
+        details: Some(details.to_string()),
+        check_query: None,
+    }
+}
+
+// Tables that exist on both sides; changes to new or dropped tables have no
+// existing rows to worry about (or are covered by the table itself)
+fn modified_tables<'a>(context: &'a RuleContext) -> impl Iterator<Item = &'a TableDifference> {
+    context
+        .table_diffs
+        .iter()
+        .filter(|t| matches!(t.status, DiffStatus::Modified))
+}
+
+fn has_change(col_change: &ColumnChange, prefix: &str) -> bool {
+    col_change.changes.iter().any(|c| c.starts_with(prefix))
+}
+
+fn modified_column(col_change: &ColumnChange) -> Option<(&EnhancedColumnInfo, &EnhancedColumnInfo)> {
+    if !matches!(col_change.status, DiffStatus::Modified) {
+        return None;
+    }
+    match (&col_change.source_definition, &col_change.target_definition) {
+        (Some(source), Some(target)) => Some((source, target)),
+        _ => None,
+    }
+}
+
+fn extension_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for extension_diff in context.extension_diffs {
+        match extension_diff.status {
+            DiffStatus::Removed => warnings.push(warning(
+                WarningSeverity::High,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!(
+                    "Extension '{}' is installed on the target but not on the source",
+                    extension_diff.extension_name
+                ),
+                extension_diff.extension_name.clone(),
+                "Dropping it will fail or break objects that use its types and functions",
+            )),
+            DiffStatus::Modified => {
+                let version = |ext: &Option<ExtensionInfo>| {
+                    ext.as_ref().map(|e| e.version.clone()).unwrap_or_default()
+                };
+                warnings.push(warning(
+                    WarningSeverity::Low,
+                    WARNING_TYPE_BREAKING_CHANGE,
+                    format!(
+                        "Extension '{}' version differs: {} → {}",
+                        extension_diff.extension_name,
+                        version(&extension_diff.target_definition),
+                        version(&extension_diff.source_definition)
+                    ),
+                    extension_diff.extension_name.clone(),
+                    "The update script must be available on the target server",
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    warnings
+}
+
+fn dropped_table_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    context
+        .table_diffs
+        .iter()
+        .filter(|t| matches!(t.status, DiffStatus::Removed))
+        .map(|table_diff| {
+            let mut warning = warning(
+                WarningSeverity::High,
+                WARNING_TYPE_DATA_LOSS,
+                format!("Dropping table '{}' will result in data loss", table_diff.table_name),
+                table_diff.table_name.clone(),
+                "Consider backing up data before proceeding",
+            );
+            warning.check_query = Some(format!(
+                "SELECT count(*) FROM {}",
+                qualified_name(context.schema, &table_diff.table_name)
+            ));
+            warning
+        })
+        .collect()
+}
+
+fn dropped_column_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for col_change in &table_diff.column_changes {
+            if !matches!(col_change.status, DiffStatus::Removed) {
+                continue;
+            }
+            let mut warning = warning(
+                WarningSeverity::High,
+                WARNING_TYPE_DATA_LOSS,
+                format!(
+                    "Dropping column '{}.{}' will result in data loss",
+                    table_diff.table_name, col_change.column_name
+                ),
+                format!("{}.{}", table_diff.table_name, col_change.column_name),
+                "Consider backing up column data first",
+            );
+            warning.check_query = Some(format!(
+                "SELECT count(*) FROM {} WHERE {} IS NOT NULL",
+                qualified_name(context.schema, &table_diff.table_name),
+                quote_ident(&col_change.column_name)
+            ));
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+fn column_type_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for col_change in &table_diff.column_changes {
+            if modified_column(col_change).is_some() && has_change(col_change, "type:") {
+                warnings.push(warning(
+                    WarningSeverity::Medium,
+                    WARNING_TYPE_BREAKING_CHANGE,
+                    format!(
+                        "Changing data type for column '{}.{}' may cause issues",
+                        table_diff.table_name, col_change.column_name
+                    ),
+                    format!("{}.{}", table_diff.table_name, col_change.column_name),
+                    "Ensure data is compatible with new type",
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+// varchar(100) → varchar(50): the ALTER fails if any value is longer
+fn column_length_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for col_change in &table_diff.column_changes {
+            let Some((source, target)) = modified_column(col_change) else {
+                continue;
+            };
+            let Some(new_length) = source.character_maximum_length else {
+                continue;
+            };
+            let narrows = target
+                .character_maximum_length
+                .is_none_or(|old_length| old_length > new_length);
+            if !narrows || !is_character_type(&target.data_type) {
+                continue;
+            }
+
+            let mut warning = warning(
+                WarningSeverity::High,
+                WARNING_TYPE_DATA_LOSS,
+                format!(
+                    "Shortening '{}.{}' to {} characters fails for longer values",
+                    table_diff.table_name, col_change.column_name, new_length
+                ),
+                format!("{}.{}", table_diff.table_name, col_change.column_name),
+                "Trim or fix the longer values before migrating",
+            );
+            warning.check_query = Some(format!(
+                "SELECT count(*) FROM {} WHERE char_length({}) > {}",
+                qualified_name(context.schema, &table_diff.table_name),
+                quote_ident(&col_change.column_name),
+                new_length
+            ));
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+fn not_null_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for col_change in &table_diff.column_changes {
+            let Some((source, _)) = modified_column(col_change) else {
+                continue;
+            };
+            if !has_change(col_change, "nullable:") || source.is_nullable == SQL_NULLABLE_YES {
+                continue;
+            }
+
+            let mut warning = warning(
+                WarningSeverity::Medium,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!(
+                    "Setting '{}.{}' NOT NULL fails if it contains NULLs",
+                    table_diff.table_name, col_change.column_name
+                ),
+                format!("{}.{}", table_diff.table_name, col_change.column_name),
+                "Backfill the NULL values before migrating",
+            );
+            warning.check_query = Some(null_check_query(
+                context.schema,
+                &table_diff.table_name,
+                &col_change.column_name,
+            ));
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+// A NOT NULL column without a default can only be added to an empty table
+fn required_column_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for col_change in &table_diff.column_changes {
+            let Some(source) = &col_change.source_definition else {
+                continue;
+            };
+            if !matches!(col_change.status, DiffStatus::Added)
+                || source.is_nullable == SQL_NULLABLE_YES
+                || source.column_default.is_some()
+            {
+                continue;
+            }
+
+            let mut warning = warning(
+                WarningSeverity::High,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!(
+                    "Adding NOT NULL column '{}.{}' without a default fails if the table has rows",
+                    table_diff.table_name, col_change.column_name
+                ),
+                format!("{}.{}", table_diff.table_name, col_change.column_name),
+                "Add a default, or add the column as nullable and backfill it first",
+            );
+            warning.check_query = Some(existing_rows_query(context.schema, &table_diff.table_name));
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+fn unique_index_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for idx_change in &table_diff.index_changes {
+            let Some(idx) = &idx_change.source_definition else {
+                continue;
+            };
+            if !matches!(idx_change.status, DiffStatus::Added | DiffStatus::Modified) || !idx.is_unique {
+                continue;
+            }
+
+            let mut warning = warning(
+                WarningSeverity::Medium,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!(
+                    "Unique index '{}' fails to build if '{}' has duplicate values",
+                    idx_change.index_name, table_diff.table_name
+                ),
+                format!("{}.{}", table_diff.table_name, idx_change.index_name),
+                "Remove the duplicates before migrating",
+            );
+            warning.check_query = duplicate_check_query(context.schema, &table_diff.table_name, idx);
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+pub(crate) fn null_check_query(schema: &str, table: &str, column: &str) -> String {
+    format!(
+        "SELECT count(*) FROM {} WHERE {} IS NULL",
+        qualified_name(schema, table),
+        quote_ident(column)
+    )
+}
+
+// 1 if the table has any rows, without counting all of them
+pub(crate) fn existing_rows_query(schema: &str, table: &str) -> String {
+    format!(
+        "SELECT count(*) FROM (SELECT 1 FROM {} LIMIT 1) AS existing_rows",
+        qualified_name(schema, table)
+    )
+}
+
+pub(crate) fn orphan_check_query(
+    schema: &str,
+    table: &str,
+    column: &str,
+    foreign_table: &str,
+    foreign_column: &str,
+) -> String {
+    format!(
+        "SELECT count(*) FROM {} AS child WHERE child.{} IS NOT NULL \
+         AND NOT EXISTS (SELECT 1 FROM {} AS parent WHERE parent.{} = child.{})",
+        qualified_name(schema, table),
+        quote_ident(column),
+        qualified_name(schema, foreign_table),
+        quote_ident(foreign_column),
+        quote_ident(column)
+    )
+}
+
+// Rows the check's expression is false for; NULL passes a CHECK, as it does here
+pub(crate) fn check_violation_query(schema: &str, table: &str, check: &CheckConstraintInfo) -> Option<String> {
+    let expression = check.definition.strip_prefix("CHECK ")?;
+    let expression = expression.strip_suffix(" NO INHERIT").unwrap_or(expression);
+    Some(format!(
+        "SELECT count(*) FROM {} WHERE NOT ({})",
+        qualified_name(schema, table),
+        expression
+    ))
+}
+
+// Count groups of rows that share the index key; NULLs never collide.
+// Expression indexes aren't checked.
+pub(crate) fn duplicate_check_query(schema: &str, table: &str, idx: &IndexInfo) -> Option<String> {
+    let key_columns: Vec<String> = if idx.key_columns.is_empty() {
+        idx.columns.iter().map(|c| quote_ident(c)).collect()
+    } else if idx.key_columns.iter().any(|k| k.is_expression) {
+        return None;
+    } else {
+        idx.key_columns.iter().map(|k| quote_ident(&k.expression)).collect()
+    };
+    if key_columns.is_empty() {
+        return None;
+    }
+
+    let mut conditions: Vec<String> = key_columns.iter().map(|c| format!("{} IS NOT NULL", c)).collect();
+    if let Some(predicate) = &idx.predicate {
+        conditions.push(format!("({})", predicate));
+    }
+
+    Some(format!(
+        "SELECT count(*) FROM (SELECT 1 FROM {} WHERE {} GROUP BY {} HAVING count(*) > 1) AS duplicates",
+        qualified_name(schema, table),
+        conditions.join(" AND "),
+        key_columns.join(", ")
+    ))
+}
+
+fn foreign_key_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for fk_change in &table_diff.fk_changes {
+            let Some(fk) = &fk_change.source_definition else {
+                continue;
+            };
+            if !matches!(fk_change.status, DiffStatus::Added | DiffStatus::Modified) {
+                continue;
+            }
+
+            let mut warning = warning(
+                WarningSeverity::Medium,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!(
+                    "Foreign key '{}' fails if '{}' has rows without a match in '{}'",
+                    fk_change.constraint_name, table_diff.table_name, fk.foreign_table_name
+                ),
+                format!("{}.{}", table_diff.table_name, fk_change.constraint_name),
+                "Delete or fix the orphaned rows before migrating",
+            );
+            // A referenced table that doesn't exist yet can't be checked
+            let referenced_exists = context
+                .table_diffs
+                .iter()
+                .any(|t| t.table_name == fk.foreign_table_name && !matches!(t.status, DiffStatus::Added));
+            if referenced_exists {
+                warning.check_query = Some(orphan_check_query(
+                    context.schema,
+                    &table_diff.table_name,
+                    &fk.column_name,
+                    &fk.foreign_table_name,
+                    &fk.foreign_column_name,
+                ));
+            }
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+fn check_constraint_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        for check_change in &table_diff.check_changes {
+            let Some(check) = &check_change.source_definition else {
+                continue;
+            };
+            if !matches!(check_change.status, DiffStatus::Added | DiffStatus::Modified) {
+                continue;
+            }
+
+            let mut warning = warning(
+                WarningSeverity::Medium,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!(
+                    "Check constraint '{}' fails if '{}' has rows that violate it",
+                    check_change.constraint_name, table_diff.table_name
+                ),
+                format!("{}.{}", table_diff.table_name, check_change.constraint_name),
+                "Fix the violating rows before migrating",
+            );
+            // Columns added by the migration don't exist on the target yet
+            let reads_new_column = check.columns.iter().any(|column| {
+                table_diff
+                    .column_changes
+                    .iter()
+                    .any(|c| &c.column_name == column && matches!(c.status, DiffStatus::Added))
+            });
+            if !reads_new_column && table_diff.renamed_from.is_none() {
+                warning.check_query = check_violation_query(context.schema, &table_diff.table_name, check);
+            }
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+// A view that keeps its definition can't be recreated once a column or table it reads is gone
+fn dependent_view_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for view_change in context.view_diffs {
+        let keeps_definition = matches!(view_change.status, DiffStatus::Identical)
+            || (matches!(view_change.status, DiffStatus::Modified) && !view_change.definition_changed);
+        if !keeps_definition {
+            continue;
+        }
+
+        let mut missing: Vec<&String> = view_change
+            .target_column_dependencies
+            .iter()
+            .filter(|c| context.dropped_columns.contains(*c))
+            .collect();
+        missing.extend(
+            view_change
+                .target_depends_on
+                .iter()
+                .filter(|t| context.dropped_tables.contains(*t)),
+        );
+        if missing.is_empty() {
+            continue;
+        }
+
+        let names: Vec<&str> = missing.iter().map(|m| m.as_str()).collect();
+        warnings.push(warning(
+            WarningSeverity::High,
+            WARNING_TYPE_BREAKING_CHANGE,
+            format!(
+                "View '{}' depends on {}, which will be dropped",
+                view_change.view_name,
+                names.join(", ")
+            ),
+            view_change.view_name.clone(),
+            "The migration will fail unless the view is changed or dropped as well",
+        ));
+    }
+
+    warnings
+}
+
+// Routine bodies aren't tracked as dependencies, so look for the names in the source text
+fn dependent_routine_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for routine_change in context.routine_diffs {
+        if matches!(routine_change.status, DiffStatus::Removed | DiffStatus::Added) {
+            continue;
+        }
+        // The version being installed is what has to work after the migration
+        let Some(definition) = routine_change
+            .source_definition
+            .as_ref()
+            .and_then(|r| r.definition.as_deref())
+        else {
+            continue;
+        };
+
+        let mut referenced: Vec<&str> = context
+            .dropped_columns
+            .iter()
+            .filter(|qualified| {
+                let (table, column) = qualified.split_once('.').unwrap_or(("", qualified));
+                !context.dropped_tables.contains(table)
+                    && contains_word(definition, table)
+                    && contains_word(definition, column)
+            })
+            .map(|c| c.as_str())
+            .collect();
+        referenced.extend(
+            context
+                .dropped_tables
+                .iter()
+                .filter(|t| contains_word(definition, t))
+                .map(|t| t.as_str()),
+        );
+        if referenced.is_empty() {
+            continue;
+        }
+        referenced.sort();
+
+        warnings.push(warning(
+            WarningSeverity::Medium,
+            WARNING_TYPE_BREAKING_CHANGE,
+            format!(
+                "Routine '{}' may reference {}, which will be dropped",
+                routine_change.routine_name,
+                referenced.join(", ")
+            ),
+            routine_change.routine_name.clone(),
+            "Calls will fail at runtime if the routine still uses them",
+        ));
+    }
+
+    warnings
+}
+
+fn removed_view_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    context
+        .view_diffs
+        .iter()
+        .filter(|v| matches!(v.status, DiffStatus::Removed))
+        .map(|view_change| {
+            warning(
+                WarningSeverity::Medium,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!("Dropping view '{}' breaks queries that use it", view_change.view_name),
+                view_change.view_name.clone(),
+                "Check applications and reports that read from this view",
+            )
+        })
+        .collect()
+}
+
+fn removed_routine_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    context
+        .routine_diffs
+        .iter()
+        .filter(|r| matches!(r.status, DiffStatus::Removed))
+        .map(|routine_change| {
+            warning(
+                WarningSeverity::Medium,
+                WARNING_TYPE_BREAKING_CHANGE,
+                format!("Dropping routine '{}' breaks its callers", routine_change.routine_name),
+                routine_change.routine_name.clone(),
+                "Check triggers, other routines and applications that call it",
+            )
+        })
+        .collect()
+}
+
+// Operations on existing tables that hold locks blocking reads or writes
+// for as long as they take to run
+fn locking_rule(context: &RuleContext) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+
+    for table_diff in modified_tables(context) {
+        let table = &table_diff.table_name;
+
+        for col_change in &table_diff.column_changes {
+            let Some((source, target)) = modified_column(col_change) else {
+                continue;
+            };
+            let object = format!("{}.{}", table, col_change.column_name);
+
+            if (has_change(col_change, "type:") || has_change(col_change, "max_length:"))
+                && type_change_rewrites(source, target)
+            {
+                warnings.push(warning(
+                    WarningSeverity::Medium,
+                    WARNING_TYPE_LOCKING,
+                    format!("Changing the type of '{}' rewrites the whole table", object),
+                    object.clone(),
+                    "The table is locked against reads and writes (ACCESS EXCLUSIVE) until the rewrite finishes",
+                ));
+            }
+
+            if has_change(col_change, "nullable:") && source.is_nullable != SQL_NULLABLE_YES {
+                warnings.push(warning(
+                    WarningSeverity::Medium,
+                    WARNING_TYPE_LOCKING,
+                    format!("Setting '{}' NOT NULL scans the whole table", object),
+                    object,
+                    "The table is locked against reads and writes (ACCESS EXCLUSIVE) during the scan. \
+                     On PostgreSQL 12+, NOT VALID constraints move the scan to a validation that doesn't block writes",
+                ));
+            }
+        }
+
+        for idx_change in &table_diff.index_changes {
+            if matches!(idx_change.status, DiffStatus::Added | DiffStatus::Modified) {
+                warnings.push(warning(
+                    WarningSeverity::Low,
+                    WARNING_TYPE_LOCKING,
+                    format!(
+                        "Creating index '{}' blocks writes to '{}' while it builds",
+                        idx_change.index_name, table
+                    ),
+                    format!("{}.{}", table, idx_change.index_name),
+                    "Generate the script with concurrent index builds to avoid this",
+                ));
+            }
+        }
+
+        for fk_change in &table_diff.fk_changes {
+            if matches!(fk_change.status, DiffStatus::Added | DiffStatus::Modified) {
+                warnings.push(warning(
+                    WarningSeverity::Low,
+                    WARNING_TYPE_LOCKING,
+                    format!(
+                        "Adding foreign key '{}' checks every row of '{}' while blocking writes",
+                        fk_change.constraint_name, table
+                    ),
+                    format!("{}.{}", table, fk_change.constraint_name),
+                    "Generate the script with NOT VALID constraints to validate them without blocking writes",
+                ));
+            }
+        }
+
+        for check_change in &table_diff.check_changes {
+            if matches!(check_change.status, DiffStatus::Added | DiffStatus::Modified) {
+                warnings.push(warning(
+                    WarningSeverity::Low,
+                    WARNING_TYPE_LOCKING,
+                    format!(
+                        "Adding check constraint '{}' checks every row of '{}' while blocking reads and writes",
+                        check_change.constraint_name, table
+                    ),
+                    format!("{}.{}", table, check_change.constraint_name),
+                    "Generate the script with NOT VALID constraints to validate them without blocking writes",
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+// PostgreSQL skips the rewrite for binary-compatible changes: widening or
+// removing a varchar limit, varchar → text, and raising numeric precision
+// without changing the scale
+pub(crate) fn type_change_rewrites(source: &EnhancedColumnInfo, target: &EnhancedColumnInfo) -> bool {
+    let (from, to) = (target.data_type.as_str(), source.data_type.as_str());
+
+    let widens_length = match (target.character_maximum_length, source.character_maximum_length) {
+        (_, None) => true,
+        (Some(old), Some(new)) => new >= old,
+        (None, Some(_)) => false,
+    };
+
+    match (from, to) {
+        ("character varying", "character varying") => !widens_length,
+        ("character varying", "text") => false,
+        ("numeric", "numeric") => {
+            let same_scale = target.numeric_scale == source.numeric_scale;
+            let wider = match (target.numeric_precision, source.numeric_precision) {
+                (_, None) => true,
+                (Some(old), Some(new)) => new >= old,
+                (None, Some(_)) => false,
+            };
+            !(same_scale && wider)
+        }
+        _ => from != to || !widens_length,
+    }
+}
+
+fn is_character_type(data_type: &str) -> bool {
+    matches!(data_type, "character varying" | "character" | "text")
+}
+
+// Case-insensitive match of an identifier that isn't part of a longer identifier
+fn contains_word(text: &str, word: &str) -> bool {
+    if word.is_empty() {
+        return false;
+    }
+    let text = text.to_lowercase();
+    let word = word.to_lowercase();
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    text.match_indices(&word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{IndexKeyColumn, RoutineInfo};
+    use crate::utils::schema_diff::{compare_columns, IndexChange};
+
+    fn column(name: &str, data_type: &str, length: Option<i32>) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            column_name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: "YES".to_string(),
+            is_primary_key: false,
+            column_default: None,
+            character_maximum_length: length,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal_position: 1,
+            comment: None,
+            collation_name: None,
+            udt_name: None,
+            udt_schema: None,
+        }
+    }
+
+    fn numeric(precision: i32, scale: i32) -> EnhancedColumnInfo {
+        EnhancedColumnInfo {
+            numeric_precision: Some(precision),
+            numeric_scale: Some(scale),
+            ..column("total", "numeric", None)
+        }
+    }
+
+    // The change from the target's column to the source's
+    fn changed(target: EnhancedColumnInfo, source: EnhancedColumnInfo) -> ColumnChange {
+        compare_columns(&[source], &[target]).pop().unwrap()
+    }
+
+    fn orders(column_changes: Vec<ColumnChange>) -> TableDifference {
+        TableDifference {
+            table_name: "orders".to_string(),
+            status: DiffStatus::Modified,
+            column_changes,
+            index_changes: Vec::new(),
+            fk_changes: Vec::new(),
+            check_changes: Vec::new(),
+            source_comment: None,
+            target_comment: None,
+            renamed_from: None,
+        }
+    }
+
+    fn index(columns: &[&str], is_expression: bool, predicate: Option<&str>) -> IndexInfo {
+        IndexInfo {
+            index_name: "orders_key".to_string(),
+            table_name: "orders".to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            is_unique: true,
+            is_primary: false,
+            definition: String::new(),
+            access_method: "btree".to_string(),
+            key_columns: columns
+                .iter()
+                .map(|c| IndexKeyColumn {
+                    expression: c.to_string(),
+                    is_expression,
+                    descending: false,
+                    nulls_first: false,
+                    opclass: None,
+                })
+                .collect(),
+            include_columns: Vec::new(),
+            predicate: predicate.map(|p| p.to_string()),
+            is_valid: true,
+        }
+    }
+
+    fn warnings_for(tables: &[TableDifference]) -> Vec<ComparisonWarning> {
+        generate_warnings("public", tables, &[], &[], &[])
+    }
+
+    #[test]
+    fn only_narrowing_a_text_column_is_checked_for_long_values() {
+        let length_warnings = |target, source| {
+            warnings_for(&[orders(vec![changed(target, source)])])
+                .into_iter()
+                .filter(|w| w.warning_type == WARNING_TYPE_DATA_LOSS)
+                .map(|w| w.check_query.unwrap())
+                .collect::<Vec<_>>()
+        };
+        let varchar = |length| column("note", "character varying", Some(length));
+
+        assert_eq!(
+            length_warnings(varchar(100), varchar(50)),
+            vec!["SELECT count(*) FROM public.orders WHERE char_length(note) > 50"]
+        );
+        assert_eq!(
+            length_warnings(column("note", "text", None), varchar(20)),
+            vec!["SELECT count(*) FROM public.orders WHERE char_length(note) > 20"]
+        );
+        assert!(length_warnings(varchar(50), varchar(100)).is_empty());
+        assert!(length_warnings(varchar(50), column("note", "text", None)).is_empty());
+        assert!(length_warnings(column("note", "integer", None), varchar(10)).is_empty());
+    }
+
+    #[test]
+    fn binary_compatible_type_changes_do_not_rewrite() {
+        let varchar = |length| column("note", "character varying", length);
+        let cases = [
+            (varchar(Some(50)), varchar(Some(100)), false),
+            (varchar(Some(50)), varchar(None), false),
+            (varchar(Some(100)), varchar(Some(50)), true),
+            (varchar(Some(50)), column("note", "text", None), false),
+            (column("note", "text", None), varchar(Some(50)), true),
+            (column("id", "integer", None), column("id", "bigint", None), true),
+        ];
+        for (target, source, rewrites) in cases {
+            assert_eq!(type_change_rewrites(&source, &target), rewrites, "{:?} → {:?}", target, source);
+
+            let locking = warnings_for(&[orders(vec![changed(target.clone(), source.clone())])])
+                .into_iter()
+                .any(|w| w.warning_type == WARNING_TYPE_LOCKING && w.message.contains("rewrites the whole table"));
+            assert_eq!(locking, rewrites, "{:?} → {:?}", target, source);
+        }
+
+        assert!(!type_change_rewrites(&numeric(12, 2), &numeric(10, 2)));
+        assert!(type_change_rewrites(&numeric(10, 2), &numeric(12, 2)));
+        assert!(type_change_rewrites(&numeric(12, 3), &numeric(10, 2)));
+    }
+
+    #[test]
+    fn new_unique_indexes_are_checked_for_duplicates() {
+        let mut table = orders(Vec::new());
+        let added = |name: &str, idx: IndexInfo| IndexChange {
+            index_name: name.to_string(),
+            status: DiffStatus::Added,
+            source_definition: Some(IndexInfo {
+                index_name: name.to_string(),
+                ..idx
+            }),
+            target_definition: None,
+        };
+        table.index_changes = vec![
+            added("orders_number_key", index(&["customer_id", "Number"], false, None)),
+            added("orders_open_key", index(&["number"], false, Some("closed_at IS NULL"))),
+            added("orders_lower_key", index(&["lower(email)"], true, None)),
+            added("orders_plain", IndexInfo { is_unique: false, ..index(&["number"], false, None) }),
+        ];
+
+        let warnings = warnings_for(&[table]);
+        let unique: Vec<_> = warnings
+            .iter()
+            .filter(|w| w.message.starts_with("Unique index"))
+            .collect();
+        assert_eq!(
+            unique.iter().map(|w| w.check_query.as_deref()).collect::<Vec<_>>(),
+            vec![
+                Some(
+                    "SELECT count(*) FROM (SELECT 1 FROM public.orders WHERE customer_id IS NOT NULL \
+                     AND \"Number\" IS NOT NULL GROUP BY customer_id, \"Number\" HAVING count(*) > 1) AS duplicates"
+                ),
+                Some(
+                    "SELECT count(*) FROM (SELECT 1 FROM public.orders WHERE number IS NOT NULL \
+                     AND (closed_at IS NULL) GROUP BY number HAVING count(*) > 1) AS duplicates"
+                ),
+                None,
+            ]
+        );
+        assert!(unique.iter().all(|w| w.severity == WarningSeverity::Medium));
+        // Every new index still blocks writes while it builds
+        assert_eq!(
+            warnings.iter().filter(|w| w.warning_type == WARNING_TYPE_LOCKING).count(),
+            4
+        );
+    }
+
+    #[test]
+    fn views_and_routines_that_use_dropped_objects_are_flagged() {
+        let mut dropped_note = changed(column("note", "text", None), column("note", "text", None));
+        dropped_note.status = DiffStatus::Removed;
+        dropped_note.source_definition = None;
+        let legacy = TableDifference {
+            table_name: "legacy".to_string(),
+            status: DiffStatus::Removed,
+            ..orders(Vec::new())
+        };
+        let tables = [orders(vec![dropped_note]), legacy];
+
+        let view = |name: &str, status: DiffStatus, definition_changed: bool| ViewChange {
+            view_name: name.to_string(),
+            status,
+            source_definition: None,
+            target_definition: None,
+            definition_changed,
+            source_comment: None,
+            target_comment: None,
+            source_depends_on: Vec::new(),
+            target_depends_on: vec!["orders".to_string(), "legacy".to_string()],
+            source_column_dependencies: Vec::new(),
+            target_column_dependencies: vec!["orders.id".to_string(), "orders.note".to_string()],
+        };
+        let views = [
+            view("order_notes", DiffStatus::Identical, false),
+            view("rewritten", DiffStatus::Modified, true),
+        ];
+
+        let routine = |name: &str, body: &str| RoutineChange {
+            routine_name: name.to_string(),
+            status: DiffStatus::Modified,
+            source_definition: Some(RoutineInfo {
+                routine_name: name.to_string(),
+                routine_type: "FUNCTION".to_string(),
+                definition: Some(body.to_string()),
+                return_type: None,
+                comment: None,
+                identity_arguments: None,
+            }),
+            target_definition: None,
+            definition_changed: true,
+            comment_changed: false,
+        };
+        let routines = [
+            routine("order_note", "SELECT Note FROM orders WHERE id = $1"),
+            routine("note_count", "SELECT count(*) FROM orders_notes WHERE notes IS NOT NULL"),
+            routine("legacy_count", "SELECT count(*) FROM public.legacy"),
+        ];
+
+        let warnings = generate_warnings("public", &tables, &views, &routines, &[]);
+        let dependents: Vec<(WarningSeverity, &str)> = warnings
+            .iter()
+            .filter(|w| w.message.contains("which will be dropped"))
+            .map(|w| (w.severity, w.message.as_str()))
+            .collect();
+        assert_eq!(
+            dependents,
+            vec![
+                (
+                    WarningSeverity::High,
+                    "View 'order_notes' depends on orders.note, legacy, which will be dropped"
+                ),
+                (
+                    WarningSeverity::Medium,
+                    "Routine 'order_note' may reference orders.note, which will be dropped"
+                ),
+                (
+                    WarningSeverity::Medium,
+                    "Routine 'legacy_count' may reference legacy, which will be dropped"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_queries_quote_names_and_count_offending_rows() {
+        assert_eq!(
+            null_check_query("Sales", "Order Items", "unit price"),
+            "SELECT count(*) FROM \"Sales\".\"Order Items\" WHERE \"unit price\" IS NULL"
+        );
+        assert_eq!(
+            existing_rows_query("public", "orders"),
+            "SELECT count(*) FROM (SELECT 1 FROM public.orders LIMIT 1) AS existing_rows"
+        );
+        assert_eq!(
+            orphan_check_query("public", "orders", "customer_id", "Customers", "id"),
+            "SELECT count(*) FROM public.orders AS child WHERE child.customer_id IS NOT NULL \
+             AND NOT EXISTS (SELECT 1 FROM public.\"Customers\" AS parent WHERE parent.id = child.customer_id)"
+        );
+
+        let check = |definition: &str| CheckConstraintInfo {
+            constraint_name: "orders_total_check".to_string(),
+            definition: definition.to_string(),
+            columns: vec!["total".to_string()],
+        };
+        assert_eq!(
+            check_violation_query("public", "orders", &check("CHECK ((total >= 0)) NO INHERIT")).as_deref(),
+            Some("SELECT count(*) FROM public.orders WHERE NOT (((total >= 0)))")
+        );
+        assert_eq!(check_violation_query("public", "orders", &check("total >= 0")), None);
+
+        // Indexes read from old snapshots have no key_columns
+        let from_columns = IndexInfo {
+            key_columns: Vec::new(),
+            ..index(&["number"], false, None)
+        };
+        assert_eq!(
+            duplicate_check_query("public", "orders", &from_columns).as_deref(),
+            Some(
+                "SELECT count(*) FROM (SELECT 1 FROM public.orders WHERE number IS NOT NULL \
+                 GROUP BY number HAVING count(*) > 1) AS duplicates"
+            )
+        );
+        assert_eq!(duplicate_check_query("public", "orders", &index(&[], false, None)), None);
+    }
+}
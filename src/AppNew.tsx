@@ -204,7 +204,7 @@ export default function AppNew() {
   );
 
   const runQuery = useCallback(async () => {
-    const { status } = await queryExecution.runQuery(
+    const { success, status } = await queryExecution.runQuery(
       connection.config,
       connection.connectedRef,
       layout.readOnlyMode,
@@ -213,10 +213,18 @@ export default function AppNew() {
           queryExecution.query,
           connection.config.name,
           result.execution_time_ms,
-          result.row_count
+          result.row_count,
+          {
+            database_name: connection.config.database,
+            affected_rows: result.affected_rows ?? undefined,
+          }
         );
       }
     );
+    // Failed runs are recorded by the backend
+    if (!success) {
+      await storage.loadQueryHistory();
+    }
     connection.setStatus(status);
   }, [queryExecution, connection, layout.readOnlyMode, storage]);
 
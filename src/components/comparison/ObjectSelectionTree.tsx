@@ -62,6 +62,11 @@ export function ObjectSelectionTree({
         allChanges.add(`routine:${routine.routine_name}`);
       }
     });
+    comparison.type_differences.forEach((type) => {
+      if (type.status !== DIFF_STATUS.IDENTICAL) {
+        allChanges.add(`type:${type.type_name}`);
+      }
+    });
     onSelectionChange(allChanges);
   };
 
@@ -90,6 +95,12 @@ export function ObjectSelectionTree({
     return false;
   });
 
+  const filteredTypes = comparison.type_differences.filter((type) => {
+    if (filterMode === "all") return true;
+    if (filterMode === "differences") return type.status !== DIFF_STATUS.IDENTICAL;
+    return false;
+  });
+
   return (
     <div className="flex flex-col h-full">
       {/* Header Actions */}
@@ -224,6 +235,28 @@ export function ObjectSelectionTree({
                           ))}
                       </div>
                     )}
+
+                  {/* Check Constraint Changes */}
+                  {table.check_changes &&
+                    table.check_changes.filter((c) => c.status !== DIFF_STATUS.IDENTICAL)
+                      .length > 0 && (
+                      <div className="space-y-1 mt-2">
+                        <div className="text-xs font-medium text-muted-foreground">
+                          Check Constraint Changes:
+                        </div>
+                        {table.check_changes
+                          .filter((c) => c.status !== DIFF_STATUS.IDENTICAL)
+                          .map((check, i) => (
+                            <div
+                              key={i}
+                              className="flex items-center gap-2 text-xs pl-2"
+                            >
+                              <StatusIndicator status={check.status} />
+                              <strong>{check.constraint_name}</strong>
+                            </div>
+                          ))}
+                      </div>
+                    )}
                 </CollapsibleContent>
               </Collapsible>
             </div>
@@ -287,10 +320,38 @@ export function ObjectSelectionTree({
         </div>
       )}
 
+      {/* Types Section */}
+      {filteredTypes.length > 0 && (
+        <div className="space-y-2">
+          <div className="flex items-center gap-2 px-2 py-1 bg-muted/50 rounded">
+            <span className="text-sm font-medium">📁 Types</span>
+            <span className="text-xs text-muted-foreground">
+              ({filteredTypes.length})
+            </span>
+          </div>
+
+          {filteredTypes.map((type) => (
+            <div
+              key={type.type_name}
+              className="ml-4 flex items-center gap-2 p-2 hover:bg-accent/50 rounded transition-colors"
+            >
+              <Checkbox
+                checked={selectedChanges.has(`type:${type.type_name}`)}
+                onCheckedChange={() => toggleSelection(`type:${type.type_name}`)}
+                disabled={type.status === DIFF_STATUS.IDENTICAL}
+              />
+              <span className="font-mono text-sm">{type.type_name}</span>
+              <StatusIndicator status={type.status} />
+            </div>
+          ))}
+        </div>
+      )}
+
       {/* Empty State */}
       {filteredTables.length === 0 &&
         filteredViews.length === 0 &&
-        filteredRoutines.length === 0 && (
+        filteredRoutines.length === 0 &&
+        filteredTypes.length === 0 && (
           <div className="text-center py-12 text-muted-foreground">
             No objects to display with current filter
           </div>
@@ -346,5 +407,9 @@ function getTableSummary(table: TableDifference): string {
     table.fk_changes?.filter((f) => f.status !== DIFF_STATUS.IDENTICAL).length || 0;
   if (fkChanges > 0) changes.push(`${fkChanges} FK${fkChanges > 1 ? "s" : ""}`);
 
+  const checkChanges =
+    table.check_changes?.filter((c) => c.status !== DIFF_STATUS.IDENTICAL).length || 0;
+  if (checkChanges > 0) changes.push(`${checkChanges} check${checkChanges > 1 ? "s" : ""}`);
+
   return changes.length > 0 ? changes.join(", ") : table.status;
 }
@@ -0,0 +1,94 @@
+import type { RenameCandidate } from "../../types";
+import { ArrowRight, Info } from "lucide-react";
+import { cn } from "../../lib/utils";
+import { Button } from "../ui/button";
+
+interface RenameCandidatesPanelProps {
+  candidates: RenameCandidate[];
+  onChange: (candidates: RenameCandidate[]) => void;
+}
+
+export function RenameCandidatesPanel({
+  candidates,
+  onChange,
+}: RenameCandidatesPanelProps) {
+  if (candidates.length === 0) {
+    return (
+      <div className="flex flex-col items-center justify-center py-12 text-muted-foreground">
+        <Info className="h-12 w-12 mb-4" />
+        <p className="text-lg font-medium">No Renames Detected</p>
+        <p className="text-sm">Dropped and added objects don't look alike</p>
+      </div>
+    );
+  }
+
+  const setAccepted = (index: number, accepted: boolean) => {
+    onChange(
+      candidates.map((candidate, idx) =>
+        idx === index ? { ...candidate, accepted } : candidate
+      )
+    );
+  };
+
+  return (
+    <div className="flex flex-col h-full">
+      <div className="flex-shrink-0 mb-6">
+        <h2 className="text-lg font-semibold">Possible Renames</h2>
+        <p className="text-sm text-muted-foreground">
+          Accepted renames are migrated with RENAME instead of dropping and
+          recreating the object, so existing data is kept.
+        </p>
+      </div>
+
+      <div className="flex-1 overflow-auto min-h-0 space-y-2">
+        {candidates.map((candidate, idx) => {
+          const prefix =
+            candidate.object_type === "column" ? `${candidate.table_name}.` : "";
+          return (
+            <div
+              key={`${candidate.object_type}:${candidate.table_name}:${candidate.old_name}`}
+              className={cn(
+                "border rounded-lg p-4 flex items-center justify-between gap-4",
+                candidate.accepted && "border-green-500/50 bg-green-500/10"
+              )}
+            >
+              <div className="flex-1 space-y-1">
+                <div className="flex items-center gap-2 text-sm">
+                  <span className="px-2 py-0.5 rounded text-xs bg-muted capitalize">
+                    {candidate.object_type}
+                  </span>
+                  <code className="font-mono">
+                    {prefix}
+                    {candidate.old_name}
+                  </code>
+                  <ArrowRight className="h-3.5 w-3.5 text-muted-foreground" />
+                  <code className="font-mono">
+                    {prefix}
+                    {candidate.new_name}
+                  </code>
+                </div>
+                <p className="text-xs text-muted-foreground">{candidate.reason}</p>
+              </div>
+              <div className="flex gap-2">
+                <Button
+                  size="sm"
+                  variant={candidate.accepted ? "default" : "outline"}
+                  onClick={() => setAccepted(idx, true)}
+                >
+                  Rename
+                </Button>
+                <Button
+                  size="sm"
+                  variant={candidate.accepted ? "outline" : "default"}
+                  onClick={() => setAccepted(idx, false)}
+                >
+                  Drop &amp; Create
+                </Button>
+              </div>
+            </div>
+          );
+        })}
+      </div>
+    </div>
+  );
+}
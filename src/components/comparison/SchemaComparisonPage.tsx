@@ -1,8 +1,9 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { WARNING_SEVERITY, MACOS_TITLEBAR_TOP_PADDING } from "../../constants";
 import { Button } from "../ui/button";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "../ui/tabs";
 import { Label } from "../ui/label";
+import { Input } from "../ui/input";
 import {
   Select,
   SelectContent,
@@ -14,12 +15,23 @@ import { ArrowLeft } from "lucide-react";
 import { toast } from "sonner";
 import type {
   ConnectionConfig,
+  RenameCandidate,
   SchemaComparison,
+  SchemaSnapshotInfo,
+  SchemaSource,
 } from "../../types";
-import { compareSchemas, generateMigrationSql, getConnectionPassword } from "../../utils/tauri";
+import {
+  checkResolvedWarnings,
+  compareSchemas,
+  generateMigrationSql,
+  getConnectionPassword,
+  listSchemaSnapshots,
+  snapshotSchema,
+} from "../../utils/tauri";
 import { DiffViewer } from "./DiffViewer";
 import { ObjectSelectionTree } from "./ObjectSelectionTree";
 import { WarningsPanel } from "./WarningsPanel";
+import { RenameCandidatesPanel } from "./RenameCandidatesPanel";
 import { MigrationScriptEditor } from "./MigrationScriptEditor";
 
 interface SchemaComparisonPageProps {
@@ -29,42 +41,118 @@ interface SchemaComparisonPageProps {
 
 type FilterMode = "all" | "differences" | "conflicts";
 
+// Select values are prefixed so connections and snapshots can share a list
+const CONNECTION_PREFIX = "connection:";
+const SNAPSHOT_PREFIX = "snapshot:";
+const DDL_SELECTION = "ddl:";
+
 export function SchemaComparisonPage({
   connections,
   onClose,
 }: SchemaComparisonPageProps) {
-  const [sourceConnection, setSourceConnection] =
-    useState<ConnectionConfig | null>(null);
-  const [targetConnection, setTargetConnection] =
-    useState<ConnectionConfig | null>(null);
+  const [sourceSelection, setSourceSelection] = useState<string>("");
+  const [targetSelection, setTargetSelection] = useState<string>("");
+  const [snapshots, setSnapshots] = useState<SchemaSnapshotInfo[]>([]);
+  const [ddlDirectory, setDdlDirectory] = useState<string>("schema");
+  const [scratchSelection, setScratchSelection] = useState<string>("");
   const [comparison, setComparison] = useState<SchemaComparison | null>(null);
   const [migrationScript, setMigrationScript] = useState<string>("");
   const [loading, setLoading] = useState(false);
+  const [snapshotting, setSnapshotting] = useState(false);
   const [filterMode, setFilterMode] = useState<FilterMode>("all");
   const [selectedChanges, setSelectedChanges] = useState<Set<string>>(
     new Set()
   );
 
+  const refreshSnapshots = async () => {
+    try {
+      setSnapshots(await listSchemaSnapshots());
+    } catch (error) {
+      console.error("Failed to load schema snapshots:", error);
+    }
+  };
+
+  useEffect(() => {
+    refreshSnapshots();
+  }, []);
+
+  const selectedConnection = (selection: string) =>
+    selection.startsWith(CONNECTION_PREFIX)
+      ? connections.find(
+          (c) => c.name === selection.slice(CONNECTION_PREFIX.length)
+        ) || null
+      : null;
+
+  const withPassword = async (selection: string): Promise<ConnectionConfig> => {
+    const connection = selectedConnection(selection);
+    if (!connection) {
+      throw new Error("Selected connection no longer exists");
+    }
+    const password = await getConnectionPassword(connection.name);
+    return { ...connection, password: password || "" };
+  };
+
+  const toSchemaSource = async (selection: string): Promise<SchemaSource> => {
+    if (selection.startsWith(SNAPSHOT_PREFIX)) {
+      return { type: "snapshot", file_name: selection.slice(SNAPSHOT_PREFIX.length) };
+    }
+    if (selection === DDL_SELECTION) {
+      return {
+        type: "ddl_directory",
+        path: ddlDirectory,
+        scratch: await withPassword(scratchSelection),
+      };
+    }
+    return { type: "connection", config: await withPassword(selection) };
+  };
+
+  const usesDdl =
+    sourceSelection === DDL_SELECTION || targetSelection === DDL_SELECTION;
+
+  const selectionLabel = (selection: string) => {
+    if (selection === DDL_SELECTION) {
+      return `${ddlDirectory} (SQL files)`;
+    }
+    if (selection.startsWith(SNAPSHOT_PREFIX)) {
+      const fileName = selection.slice(SNAPSHOT_PREFIX.length);
+      const snapshot = snapshots.find((s) => s.file_name === fileName);
+      return snapshot
+        ? `${snapshot.connection_name} (snapshot ${snapshot.created_at})`
+        : fileName;
+    }
+    return selection.slice(CONNECTION_PREFIX.length);
+  };
+
+  const takeSnapshot = async () => {
+    if (!selectedConnection(sourceSelection)) return;
+    setSnapshotting(true);
+    try {
+      const path = await snapshotSchema(await withPassword(sourceSelection));
+      await refreshSnapshots();
+      toast.success(`Snapshot saved to ${path}`);
+    } catch (error) {
+      console.error("Failed to snapshot schema:", error);
+      toast.error(`Snapshot failed: ${error}`);
+    } finally {
+      setSnapshotting(false);
+    }
+  };
+
   const runComparison = async () => {
-    if (!sourceConnection || !targetConnection) {
-      toast.error("Please select both source and target connections");
+    if (!sourceSelection || !targetSelection) {
+      toast.error("Please select both source and target schemas");
+      return;
+    }
+    if (usesDdl && (!ddlDirectory || !scratchSelection)) {
+      toast.error("Please enter a SQL directory and select a scratch server");
       return;
     }
-    const sourcePassword = await getConnectionPassword(sourceConnection.name);
-    const targetPassword = await getConnectionPassword(targetConnection.name);
-
-    const sourceConfigWithPassword = {
-      ...sourceConnection,
-      password: sourcePassword || "",
-    };
-    const targetConfigWithPassword = {
-      ...targetConnection,
-      password: targetPassword || "",
-    };
 
     setLoading(true);
     try {
-      const result = await compareSchemas(sourceConfigWithPassword, targetConfigWithPassword);
+      const source = await toSchemaSource(sourceSelection);
+      const target = await toSchemaSource(targetSelection);
+      const result = await compareSchemas(source, target);
       setComparison(result);
 
       // Generate migration script
@@ -78,6 +166,21 @@ export function SchemaComparisonPage({
           allChanges.add(`table:${table.table_name}`);
         }
       });
+      result.view_differences.forEach((view) => {
+        if (view.status !== "identical") {
+          allChanges.add(`view:${view.view_name}`);
+        }
+      });
+      result.routine_differences.forEach((routine) => {
+        if (routine.status !== "identical") {
+          allChanges.add(`routine:${routine.routine_name}`);
+        }
+      });
+      result.type_differences.forEach((type) => {
+        if (type.status !== "identical") {
+          allChanges.add(`type:${type.type_name}`);
+        }
+      });
       setSelectedChanges(allChanges);
 
       toast.success("Schema comparison completed");
@@ -89,6 +192,47 @@ export function SchemaComparisonPage({
     }
   };
 
+  const regenerateScript = async (
+    current: SchemaComparison,
+    selection: Set<string>
+  ) => {
+    try {
+      const script = await generateMigrationSql(current, {
+        selection: { include: Array.from(selection) },
+      });
+      setMigrationScript(script);
+    } catch (error) {
+      console.error("Failed to generate migration script:", error);
+      toast.error(`Failed to generate migration script: ${error}`);
+    }
+  };
+
+  // Accepting or rejecting a rename changes the generated script, and which
+  // warnings apply, so those are checked against the target again
+  const updateRenameCandidates = async (candidates: RenameCandidate[]) => {
+    if (!comparison) return;
+    const updated = { ...comparison, rename_candidates: candidates };
+    setComparison(updated);
+    await regenerateScript(updated, selectedChanges);
+
+    try {
+      const target = await toSchemaSource(targetSelection);
+      const warnings = await checkResolvedWarnings(target, updated);
+      setComparison({ ...updated, warnings });
+    } catch (error) {
+      console.error("Failed to check warnings:", error);
+      toast.error(`Failed to check warnings: ${error}`);
+    }
+  };
+
+  // Only the selected objects (and what they depend on) are migrated
+  const updateSelectedChanges = async (selection: Set<string>) => {
+    setSelectedChanges(selection);
+    if (comparison) {
+      await regenerateScript(comparison, selection);
+    }
+  };
+
   const getDifferenceCount = () => {
     if (!comparison) return 0;
     return (
@@ -97,6 +241,8 @@ export function SchemaComparisonPage({
       comparison.view_differences.filter((v) => v.status !== "identical")
         .length +
       comparison.routine_differences.filter((r) => r.status !== "identical")
+        .length +
+      comparison.type_differences.filter((t) => t.status !== "identical")
         .length
     );
   };
@@ -122,7 +268,7 @@ export function SchemaComparisonPage({
             <h1 className="text-lg font-semibold">Schema Comparison</h1>
             {comparison && (
               <p className="text-xs text-muted-foreground">
-                {sourceConnection?.name} → {targetConnection?.name}
+                {selectionLabel(sourceSelection)} → {selectionLabel(targetSelection)}
               </p>
             )}
           </div>
@@ -163,53 +309,103 @@ export function SchemaComparisonPage({
             <div className="grid grid-cols-2 gap-6">
               <div className="space-y-2">
                 <Label>Source Schema</Label>
-                <Select
-                  value={sourceConnection?.name || ""}
-                  onValueChange={(value) => {
-                    const conn = connections.find((c) => c.name === value);
-                    setSourceConnection(conn || null);
-                  }}
-                >
+                <Select value={sourceSelection} onValueChange={setSourceSelection}>
                   <SelectTrigger>
                     <SelectValue placeholder="Select source..." />
                   </SelectTrigger>
                   <SelectContent>
                     {connections.map((conn) => (
-                      <SelectItem key={conn.name} value={conn.name}>
+                      <SelectItem key={conn.name} value={`${CONNECTION_PREFIX}${conn.name}`}>
                         {conn.name}
                       </SelectItem>
                     ))}
+                    {snapshots.map((snapshot) => (
+                      <SelectItem
+                        key={snapshot.file_name}
+                        value={`${SNAPSHOT_PREFIX}${snapshot.file_name}`}
+                      >
+                        {snapshot.connection_name} (snapshot {snapshot.created_at})
+                      </SelectItem>
+                    ))}
+                    <SelectItem value={DDL_SELECTION}>SQL files...</SelectItem>
                   </SelectContent>
                 </Select>
               </div>
 
               <div className="space-y-2">
                 <Label>Target Schema</Label>
-                <Select
-                  value={targetConnection?.name || ""}
-                  onValueChange={(value) => {
-                    const conn = connections.find((c) => c.name === value);
-                    setTargetConnection(conn || null);
-                  }}
-                >
+                <Select value={targetSelection} onValueChange={setTargetSelection}>
                   <SelectTrigger>
                     <SelectValue placeholder="Select target..." />
                   </SelectTrigger>
                   <SelectContent>
                     {connections.map((conn) => (
-                      <SelectItem key={conn.name} value={conn.name}>
+                      <SelectItem key={conn.name} value={`${CONNECTION_PREFIX}${conn.name}`}>
                         {conn.name}
                       </SelectItem>
                     ))}
+                    {snapshots.map((snapshot) => (
+                      <SelectItem
+                        key={snapshot.file_name}
+                        value={`${SNAPSHOT_PREFIX}${snapshot.file_name}`}
+                      >
+                        {snapshot.connection_name} (snapshot {snapshot.created_at})
+                      </SelectItem>
+                    ))}
+                    <SelectItem value={DDL_SELECTION}>SQL files...</SelectItem>
                   </SelectContent>
                 </Select>
               </div>
             </div>
 
-            <div className="flex justify-center">
+            {usesDdl && (
+              <div className="grid grid-cols-2 gap-6">
+                <div className="space-y-2">
+                  <Label>SQL Directory</Label>
+                  <Input
+                    value={ddlDirectory}
+                    onChange={(e) => setDdlDirectory(e.target.value)}
+                    placeholder="schema"
+                  />
+                  <p className="text-xs text-muted-foreground">
+                    Relative to the project directory. Files are applied in
+                    path order.
+                  </p>
+                </div>
+                <div className="space-y-2">
+                  <Label>Scratch Server</Label>
+                  <Select value={scratchSelection} onValueChange={setScratchSelection}>
+                    <SelectTrigger>
+                      <SelectValue placeholder="Select scratch server..." />
+                    </SelectTrigger>
+                    <SelectContent>
+                      {connections.map((conn) => (
+                        <SelectItem key={conn.name} value={`${CONNECTION_PREFIX}${conn.name}`}>
+                          {conn.name}
+                        </SelectItem>
+                      ))}
+                    </SelectContent>
+                  </Select>
+                  <p className="text-xs text-muted-foreground">
+                    A temporary database is created here and dropped after
+                    the comparison.
+                  </p>
+                </div>
+              </div>
+            )}
+
+            <div className="flex justify-center gap-2">
+              <Button
+                variant="outline"
+                onClick={takeSnapshot}
+                disabled={snapshotting || !selectedConnection(sourceSelection)}
+                size="lg"
+              >
+                {snapshotting ? "Saving Snapshot..." : "Snapshot Source"}
+              </Button>
               <Button
                 onClick={runComparison}
-                disabled={loading || !sourceConnection || !targetConnection}
+                disabled={loading || !sourceSelection || !targetSelection}
                 size="lg"
               >
                 {loading ? "Comparing..." : "Compare Schemas"}
@@ -230,6 +426,9 @@ export function SchemaComparisonPage({
               <TabsTrigger value="warnings">
                 Warnings ({comparison.warnings?.length || 0})
               </TabsTrigger>
+              <TabsTrigger value="renames">
+                Renames ({comparison.rename_candidates?.length || 0})
+              </TabsTrigger>
               <TabsTrigger value="script">Migration Script</TabsTrigger>
             </TabsList>
 
@@ -279,7 +478,7 @@ export function SchemaComparisonPage({
                 <ObjectSelectionTree
                   comparison={comparison}
                   selectedChanges={selectedChanges}
-                  onSelectionChange={setSelectedChanges}
+                  onSelectionChange={updateSelectedChanges}
                   filterMode={filterMode}
                 />
               </TabsContent>
@@ -295,6 +494,13 @@ export function SchemaComparisonPage({
                 <WarningsPanel warnings={comparison.warnings || []} />
               </TabsContent>
 
+              <TabsContent value="renames" className="mt-0 h-full overflow-hidden">
+                <RenameCandidatesPanel
+                  candidates={comparison.rename_candidates || []}
+                  onChange={updateRenameCandidates}
+                />
+              </TabsContent>
+
               <TabsContent value="script" className="mt-0 h-full overflow-hidden">
                 <MigrationScriptEditor
                   migrationScript={migrationScript}
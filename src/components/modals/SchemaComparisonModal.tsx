@@ -74,7 +74,10 @@ export const SchemaComparisonModal = memo(function SchemaComparisonModal({
         password: targetPassword || "",
       };
 
-      const result = await compareSchemas(sourceConfigWithPassword, targetConfigWithPassword);
+      const result = await compareSchemas(
+        { type: "connection", config: sourceConfigWithPassword },
+        { type: "connection", config: targetConfigWithPassword }
+      );
       setComparison(result);
 
       // Generate migration script
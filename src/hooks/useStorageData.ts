@@ -14,7 +14,7 @@ import {
   deleteConnectionPassword,
 } from "../utils/tauri";
 import { DEFAULTS } from "../constants";
-import type { ConnectionConfig, QueryHistoryEntry, SavedQuery } from "../types";
+import type { ConnectionConfig, HistoryEntryDetails, QueryHistoryEntry, SavedQuery } from "../types";
 
 interface UseStorageDataReturn {
   // State
@@ -30,7 +30,7 @@ interface UseStorageDataReturn {
 
   // History operations
   loadQueryHistory: () => Promise<void>;
-  addToHistory: (query: string, connectionName: string, timeMs: number, rowCount: number) => Promise<void>;
+  addToHistory: (query: string, connectionName: string, timeMs: number, rowCount: number, details?: HistoryEntryDetails) => Promise<void>;
   clearHistory: () => Promise<void>;
 
   // Saved queries operations
@@ -109,9 +109,10 @@ export function useStorageData(): UseStorageDataReturn {
     query: string,
     connectionName: string,
     timeMs: number,
-    rowCount: number
+    rowCount: number,
+    details?: HistoryEntryDetails
   ) => {
-    await saveQueryToHistory(query, connectionName, timeMs, rowCount);
+    await saveQueryToHistory(query, connectionName, timeMs, rowCount, details);
     await loadQueryHistory();
   }, [loadQueryHistory]);
 
@@ -35,6 +35,14 @@ export interface ConnectionConfig {
 
 // Enhanced schema types for schema comparison feature
 
+export interface IndexKeyColumn {
+  expression: string; // column name, or expression such as lower(email)
+  is_expression: boolean;
+  descending: boolean;
+  nulls_first: boolean;
+  opclass?: string; // only set for non-default operator classes
+}
+
 export interface IndexInfo {
   index_name: string;
   table_name: string;
@@ -42,18 +50,42 @@ export interface IndexInfo {
   is_unique: boolean;
   is_primary: boolean;
   definition: string;
+  access_method: string; // 'btree', 'gin', 'gist', ...
+  key_columns: IndexKeyColumn[];
+  include_columns: string[];
+  predicate?: string; // WHERE clause of a partial index
+  is_valid: boolean;
 }
 
 export interface ViewInfo {
   view_name: string;
   definition: string;
+  comment?: string;
+  depends_on: string[]; // tables/views the view reads from
+  column_dependencies: string[]; // "table.column" entries the view reads
 }
 
 export interface RoutineInfo {
   routine_name: string;
   routine_type: string; // 'FUNCTION' or 'PROCEDURE'
-  definition?: string;
+  definition?: string; // CREATE OR REPLACE statement, without the schema
   return_type?: string;
+  comment?: string;
+  identity_arguments?: string; // e.g. 'integer, text'; tells overloads apart
+}
+
+export interface ExtensionInfo {
+  extension_name: string;
+  version: string;
+  schema_name: string;
+}
+
+export interface TypeInfo {
+  type_name: string;
+  kind: "enum" | "composite" | "domain";
+  definition: string; // what follows the name in CREATE TYPE / CREATE DOMAIN
+  enum_labels: string[];
+  depends_on: string[]; // other types of the schema it is built from
 }
 
 export interface EnhancedColumnInfo {
@@ -66,6 +98,16 @@ export interface EnhancedColumnInfo {
   numeric_precision?: number;
   numeric_scale?: number;
   ordinal_position: number;
+  comment?: string;
+  collation_name?: string;
+  udt_name?: string;
+  udt_schema?: string;
+}
+
+export interface CheckConstraintInfo {
+  constraint_name: string;
+  definition: string; // e.g. "CHECK (price > 0::numeric)"
+  columns: string[];
 }
 
 export interface EnhancedTableInfo {
@@ -73,14 +115,36 @@ export interface EnhancedTableInfo {
   columns: EnhancedColumnInfo[];
   foreign_keys: ForeignKeyInfo[];
   indexes: IndexInfo[];
+  comment?: string;
+  check_constraints: CheckConstraintInfo[];
 }
 
 export interface EnhancedDatabaseSchema {
   tables: EnhancedTableInfo[];
   views: ViewInfo[];
   routines: RoutineInfo[];
+  extensions: ExtensionInfo[];
+  types: TypeInfo[];
+  server_version_num?: number; // e.g. 160002
+}
+
+// Schema snapshots
+
+export interface SchemaSnapshotInfo {
+  file_name: string;
+  created_at: string;
+  connection_name: string;
+  database: string;
+  schema_name: string;
 }
 
+// One side of a schema comparison
+export type SchemaSource =
+  | { type: 'connection'; config: ConnectionConfig }
+  | { type: 'snapshot'; file_name: string }
+  // .sql files applied to a throwaway database on the scratch server
+  | { type: 'ddl_directory'; path: string; scratch: ConnectionConfig };
+
 // Schema comparison types
 
 export type DiffStatus = 'identical' | 'modified' | 'added' | 'removed';
@@ -109,12 +173,23 @@ export interface ForeignKeyChange {
   target_definition?: ForeignKeyInfo;
 }
 
+export interface CheckConstraintChange {
+  constraint_name: string;
+  status: DiffStatus;
+  source_definition?: CheckConstraintInfo;
+  target_definition?: CheckConstraintInfo;
+}
+
 export interface TableDifference {
   table_name: string;
   status: DiffStatus;
   column_changes: ColumnChange[];
   index_changes: IndexChange[];
   fk_changes: ForeignKeyChange[];
+  check_changes: CheckConstraintChange[];
+  source_comment?: string;
+  target_comment?: string;
+  renamed_from?: string;
 }
 
 export interface ViewChange {
@@ -123,6 +198,12 @@ export interface ViewChange {
   source_definition?: string;
   target_definition?: string;
   definition_changed: boolean;
+  source_comment?: string;
+  target_comment?: string;
+  source_depends_on: string[];
+  target_depends_on: string[];
+  source_column_dependencies: string[];
+  target_column_dependencies: string[];
 }
 
 export interface RoutineChange {
@@ -131,6 +212,21 @@ export interface RoutineChange {
   source_definition?: string;
   target_definition?: string;
   definition_changed: boolean;
+  comment_changed: boolean;
+}
+
+export interface ExtensionChange {
+  extension_name: string;
+  status: DiffStatus;
+  source_definition?: ExtensionInfo;
+  target_definition?: ExtensionInfo;
+}
+
+export interface TypeChange {
+  type_name: string;
+  status: DiffStatus;
+  source_definition?: TypeInfo;
+  target_definition?: TypeInfo;
 }
 
 export interface ComparisonWarning {
@@ -139,6 +235,7 @@ export interface ComparisonWarning {
   message: string;
   affected_object: string;
   details?: string;
+  check_query?: string; // counts the target rows that trigger the warning
 }
 
 export interface ComparisonSummary {
@@ -148,14 +245,113 @@ export interface ComparisonSummary {
   indexes_missing: number;
   views_changed: number;
   routines_changed: number;
+  extensions_changed: number;
+  types_changed: number;
 }
 
 export interface SchemaComparison {
   source_connection: string;
   target_connection: string;
+  schema_name: string;
   summary: ComparisonSummary;
   table_differences: TableDifference[];
   view_differences: ViewChange[];
   routine_differences: RoutineChange[];
+  extension_differences: ExtensionChange[];
+  type_differences: TypeChange[];
+  rename_candidates: RenameCandidate[];
   warnings: ComparisonWarning[];
+  target_server_version?: number;
+}
+
+export interface RenameCandidate {
+  object_type: "table" | "column";
+  table_name: string;
+  old_name: string;
+  new_name: string;
+  reason: string;
+  accepted: boolean;
+}
+
+export interface MigrationScriptOptions {
+  transactional?: boolean;
+  lock_timeout?: string; // e.g. "5s"
+  statement_timeout?: string; // e.g. "15min"
+  concurrent_indexes?: boolean;
+  not_valid_constraints?: boolean;
+  selection?: MigrationSelection; // omit to migrate every difference
+}
+
+// Object keys: "table:users", "column:users.email", "index:users.users_email_idx",
+// "fk:orders.orders_user_id_fkey", "check:orders.orders_total_check", "view:active_users", "routine:refresh_stats",
+// "extension:pgcrypto", "type:order_status". A table key covers every change inside the table.
+export interface MigrationSelection {
+  include?: string[]; // omit to include everything
+  exclude?: string[];
+}
+
+export type PreflightProbeKind =
+  | "null_values"
+  | "invalid_casts"
+  | "duplicate_keys"
+  | "orphaned_rows"
+  | "check_violations"
+  | "existing_rows"
+  | "table_size";
+
+export interface PreflightFinding {
+  step_index: number;
+  step_description: string;
+  object_name: string;
+  kind: PreflightProbeKind;
+  severity: WarningSeverity;
+  message: string;
+  value?: number; // rows, or bytes for table_size
+  query: string;
+}
+
+export interface PreflightReport {
+  findings: PreflightFinding[];
+  blocking_count: number;
+}
+
+// Table data comparison types
+
+export interface DataCompareOptions {
+  chunk_size?: number; // rows per hashed chunk, default 1000
+  max_row_differences?: number; // cap on row_differences, default 1000
+}
+
+export interface ColumnValueChange {
+  column_name: string;
+  source_value: string | null;
+  target_value: string | null;
+}
+
+export interface RowDifference {
+  status: DiffStatus; // 'added': only in source, 'removed': only in target
+  key: string[]; // primary key values
+  source_values: (string | null)[] | null; // in `columns` order
+  target_values: (string | null)[] | null;
+  column_changes: ColumnValueChange[];
+}
+
+export interface TableDataComparison {
+  source_connection: string;
+  target_connection: string;
+  schema_name: string;
+  table_name: string;
+  primary_key: string[];
+  columns: string[];
+  ignored_columns: string[]; // only on one side, or generated
+  source_row_count: number;
+  target_row_count: number;
+  chunks_total: number;
+  chunks_different: number;
+  rows_added: number;
+  rows_removed: number;
+  rows_changed: number;
+  row_differences: RowDifference[];
+  truncated: boolean; // row_differences was capped; counts and sync_sql cover every row
+  sync_sql: string; // makes the target's rows match the source's
 }
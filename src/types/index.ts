@@ -5,12 +5,16 @@ export type {
   DatabaseSchema,
   ConnectionConfig,
   IndexInfo,
+  IndexKeyColumn,
   ViewInfo,
   RoutineInfo,
   EnhancedColumnInfo,
   EnhancedTableInfo,
   EnhancedDatabaseSchema,
+  ExtensionInfo,
   ForeignKeyInfo,
+  SchemaSnapshotInfo,
+  SchemaSource,
   // Schema comparison types
   DiffStatus,
   WarningSeverity,
@@ -20,18 +24,51 @@ export type {
   TableDifference,
   ViewChange,
   RoutineChange,
+  ExtensionChange,
   ComparisonWarning,
   ComparisonSummary,
   SchemaComparison,
+  RenameCandidate,
+  MigrationScriptOptions,
+  MigrationSelection,
+  PreflightProbeKind,
+  PreflightFinding,
+  PreflightReport,
+  DataCompareOptions,
+  ColumnValueChange,
+  RowDifference,
+  TableDataComparison,
 } from './database';
 
 // Query types
 export type {
   QueryResult,
   QueryHistoryEntry,
+  HistoryStatus,
+  HistoryEntryDetails,
+  HistorySearch,
+  HistorySearchResult,
+  HistorySearchPage,
+  HistoryRetention,
   SavedQuery,
+  SavedQueryInput,
+  SavedQueryRevision,
+  DiffLine,
+  SavedQueryRevisionDiff,
+  SavedQuerySearch,
 } from './query';
 
+// Migration types
+export type {
+  MigrationFile,
+  MigrationState,
+  MigrationStatus,
+  StatementStatus,
+  MigrationProgress,
+  MigrationRunStatus,
+  MigrationRun,
+} from './migration';
+
 // Git types
 export type {
   GitStatus,
@@ -0,0 +1,53 @@
+// Migration file types
+
+export interface MigrationFile {
+  version: number;
+  name: string;
+  up_file: string;
+  down_file: string | null; // null when the migration can't be reverted
+  checksum: string; // SHA-256 of the up file
+  down_checksum: string | null;
+}
+
+// 'modified': applied, but the up or down file changed since
+// 'missing': applied, but the file is gone from the project
+export type MigrationState = 'pending' | 'applied' | 'modified' | 'missing';
+
+export interface MigrationStatus {
+  version: number;
+  name: string;
+  state: MigrationState;
+  applied_at: string | null;
+  applied_by: string | null;
+  can_revert: boolean;
+}
+
+// Script runs with apply_migration
+
+// 'rolled_back': succeeded, then undone when a later statement in its transaction failed
+export type StatementStatus = 'running' | 'succeeded' | 'failed' | 'rolled_back';
+
+// Payload of the "migration-progress" event
+export interface MigrationProgress {
+  index: number; // 0-based position in the script
+  total: number;
+  statement: string;
+  status: StatementStatus;
+  elapsed_ms: number;
+  error: string | null;
+}
+
+export type MigrationRunStatus = 'succeeded' | 'rolled_back' | 'partially_applied';
+
+export interface MigrationRun {
+  id: number;
+  connection_name: string;
+  database: string;
+  script: string;
+  status: MigrationRunStatus;
+  error: string | null;
+  statements_total: number;
+  statements_committed: number;
+  started_at: string;
+  duration_ms: number;
+}
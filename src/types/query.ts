@@ -3,6 +3,7 @@ export interface QueryResult {
   rows: unknown[][];
   row_count: number;
   execution_time_ms: number;
+  affected_rows: number | null; // rows changed, when the statement returned none
 }
 
 export interface QueryHistoryEntry {
@@ -12,6 +13,54 @@ export interface QueryHistoryEntry {
   execution_time_ms: number;
   row_count: number;
   executed_at: string;
+  status: HistoryStatus;
+  error_message: string | null;
+  sqlstate: string | null;
+  database_name: string | null;
+  schema_name: string | null;
+  project_path: string | null;
+  affected_rows: number | null;
+}
+
+// Optional fields for saveQueryToHistory; status defaults to 'success'
+export interface HistoryEntryDetails {
+  status?: HistoryStatus;
+  error_message?: string;
+  sqlstate?: string;
+  database_name?: string;
+  schema_name?: string;
+  affected_rows?: number;
+}
+
+export type HistoryStatus = 'success' | 'error';
+
+// Every filter is optional
+export interface HistorySearch {
+  text?: string; // full-text terms, each matched as a prefix
+  connection_name?: string;
+  executed_after?: string; // ISO timestamp, inclusive
+  executed_before?: string; // ISO timestamp, exclusive
+  min_execution_time_ms?: number;
+  status?: HistoryStatus;
+  distinct?: boolean; // one entry per statement, with its run count
+  offset?: number;
+  limit?: number; // default 50
+}
+
+export interface HistorySearchResult extends QueryHistoryEntry {
+  run_count: number;
+}
+
+export interface HistorySearchPage {
+  results: HistorySearchResult[];
+  total: number; // matches across all pages
+}
+
+// Limits enforced after every insert; null or absent means unlimited
+export interface HistoryRetention {
+  max_entries?: number | null;
+  max_age_days?: number | null;
+  max_entries_per_connection?: number | null;
 }
 
 export interface SavedQuery {
@@ -20,6 +69,56 @@ export interface SavedQuery {
   query: string;
   description: string | null;
   is_pinned: boolean;
+  folder: string | null; // '/'-separated path, null at the top level
+  tags: string[];
+  default_connection: string | null;
+  parameters: string[];
+  file_path: string | null; // under queries/, when saved queries are kept as files
   created_at: string;
   updated_at: string;
 }
+
+// The editable fields of a saved query
+export interface SavedQueryInput {
+  name: string;
+  query: string;
+  description?: string | null;
+  folder?: string | null;
+  tags?: string[];
+  default_connection?: string | null;
+  parameters?: string[]; // names of the placeholders the query expects, in order
+}
+
+// A saved query as it was after one save or edit
+export interface SavedQueryRevision {
+  query_id: number;
+  revision: number; // 1 is the query as first saved
+  name: string;
+  query: string;
+  description: string | null;
+  folder: string | null;
+  tags: string[];
+  default_connection: string | null;
+  parameters: string[];
+  author: string | null; // "Name <email>" from git config
+  created_at: string;
+}
+
+export interface DiffLine {
+  status: 'identical' | 'added' | 'removed';
+  text: string;
+  old_line: number | null; // 1-based
+  new_line: number | null; // 1-based
+}
+
+export interface SavedQueryRevisionDiff {
+  from: SavedQueryRevision;
+  to: SavedQueryRevision;
+  lines: DiffLine[]; // query body, from -> to
+}
+
+export interface SavedQuerySearch {
+  text?: string | null; // matched against name, description, tags and body
+  folder?: string | null; // includes its subfolders
+  tag?: string | null;
+}
@@ -1,15 +1,35 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
   ConnectionConfig,
   DatabaseSchema,
   EnhancedDatabaseSchema,
   QueryResult,
   QueryHistoryEntry,
+  HistoryEntryDetails,
+  HistorySearch,
+  HistorySearchPage,
+  HistoryRetention,
   SavedQuery,
+  SavedQueryInput,
+  SavedQueryRevision,
+  SavedQueryRevisionDiff,
+  SavedQuerySearch,
   GitStatus,
   GitCommit,
   RecentProject,
   SchemaComparison,
+  ComparisonWarning,
+  MigrationScriptOptions,
+  PreflightReport,
+  SchemaSnapshotInfo,
+  SchemaSource,
+  MigrationFile,
+  MigrationStatus,
+  MigrationProgress,
+  MigrationRun,
+  DataCompareOptions,
+  TableDataComparison,
 } from "../types";
 
 // Connection Management
@@ -40,21 +60,138 @@ export async function getEnhancedDatabaseSchema(
 }
 
 export async function compareSchemas(
-  sourceConfig: ConnectionConfig,
-  targetConfig: ConnectionConfig,
+  source: SchemaSource,
+  target: SchemaSource,
   schema?: string
 ): Promise<SchemaComparison> {
   return await invoke<SchemaComparison>("compare_schemas", {
-    sourceConfig,
-    targetConfig,
+    source,
+    target,
     schema
   });
 }
 
-export async function generateMigrationSql(
+// Schema snapshots
+export async function snapshotSchema(
+  config: ConnectionConfig,
+  schema?: string
+): Promise<string> {
+  return await invoke<string>("snapshot_schema", { config, schema });
+}
+
+export async function listSchemaSnapshots(): Promise<SchemaSnapshotInfo[]> {
+  return await invoke<SchemaSnapshotInfo[]>("list_schema_snapshots");
+}
+
+export async function checkResolvedWarnings(
+  target: SchemaSource,
   comparison: SchemaComparison
+): Promise<ComparisonWarning[]> {
+  return await invoke<ComparisonWarning[]>("check_resolved_warnings", { target, comparison });
+}
+
+export async function generateMigrationSql(
+  comparison: SchemaComparison,
+  options?: MigrationScriptOptions
+): Promise<string> {
+  return await invoke<string>("generate_migration_sql", { comparison, options });
+}
+
+export async function generateRollbackSql(
+  comparison: SchemaComparison,
+  options?: MigrationScriptOptions
+): Promise<string> {
+  return await invoke<string>("generate_rollback_sql", { comparison, options });
+}
+
+export async function preflightMigration(
+  config: ConnectionConfig,
+  comparison: SchemaComparison,
+  options?: MigrationScriptOptions
+): Promise<PreflightReport> {
+  return await invoke<PreflightReport>("preflight_migration", {
+    config,
+    comparison,
+    options,
+  });
+}
+
+export async function compareTableData(
+  source: ConnectionConfig,
+  target: ConnectionConfig,
+  table: string,
+  schema?: string,
+  options?: DataCompareOptions
+): Promise<TableDataComparison> {
+  return await invoke<TableDataComparison>("compare_table_data", {
+    source,
+    target,
+    table,
+    schema,
+    options,
+  });
+}
+
+// Migrations
+export async function createMigration(
+  name: string,
+  comparison: SchemaComparison,
+  options?: MigrationScriptOptions
+): Promise<MigrationFile> {
+  return await invoke<MigrationFile>("create_migration", { name, comparison, options });
+}
+
+export async function migrationStatus(
+  config: ConnectionConfig
+): Promise<MigrationStatus[]> {
+  return await invoke<MigrationStatus[]>("migration_status", { config });
+}
+
+export async function migrateUp(
+  config: ConnectionConfig,
+  targetVersion?: number
+): Promise<MigrationStatus[]> {
+  return await invoke<MigrationStatus[]>("migrate_up", { config, targetVersion });
+}
+
+export async function migrateDown(
+  config: ConnectionConfig,
+  steps?: number
+): Promise<MigrationStatus[]> {
+  return await invoke<MigrationStatus[]>("migrate_down", { config, steps });
+}
+
+// Resolves once the run finishes; a failed run resolves too, with its error
+export async function applyMigration(
+  config: ConnectionConfig,
+  script: string
+): Promise<MigrationRun> {
+  return await invoke<MigrationRun>("apply_migration", { config, script });
+}
+
+export async function onMigrationProgress(
+  handler: (progress: MigrationProgress) => void
+): Promise<UnlistenFn> {
+  return await listen<MigrationProgress>("migration-progress", (event) =>
+    handler(event.payload)
+  );
+}
+
+export async function generateSchemaDocs(
+  config: ConnectionConfig,
+  format: "markdown" | "html",
+  schema?: string
 ): Promise<string> {
-  return await invoke<string>("generate_migration_sql", { comparison });
+  return await invoke<string>("generate_schema_docs", { config, schema, format });
+}
+
+// Writes into the project's reports directory and returns the file path
+export async function exportComparisonReport(
+  comparison: SchemaComparison,
+  format: "markdown" | "html" | "json",
+  options?: MigrationScriptOptions
+): Promise<string> {
+  return await invoke<string>("export_comparison_report", { comparison, format, options });
 }
 
 export async function executeQuery(
@@ -101,13 +238,15 @@ export async function saveQueryToHistory(
   query: string,
   connectionName: string,
   executionTimeMs: number,
-  rowCount: number
+  rowCount: number,
+  details?: HistoryEntryDetails
 ): Promise<void> {
   await invoke("save_query_to_history", {
     query,
     connectionName,
     executionTimeMs,
     rowCount,
+    details,
   });
 }
 
@@ -117,23 +256,66 @@ export async function getQueryHistory(
   return await invoke<QueryHistoryEntry[]>("get_query_history", { limit });
 }
 
+export async function searchQueryHistory(
+  search: HistorySearch = {}
+): Promise<HistorySearchPage> {
+  return await invoke<HistorySearchPage>("search_query_history", { search });
+}
+
 export async function clearQueryHistory(): Promise<void> {
   await invoke("clear_query_history");
 }
 
+export async function deleteHistoryEntries(ids: number[]): Promise<number> {
+  return await invoke<number>("delete_history_entries", { ids });
+}
+
+export async function getHistoryRetention(): Promise<HistoryRetention> {
+  return await invoke<HistoryRetention>("get_history_retention");
+}
+
+// Returns the number of entries the new limits removed
+export async function setHistoryRetention(
+  retention: HistoryRetention
+): Promise<number> {
+  return await invoke<number>("set_history_retention", { retention });
+}
+
+export async function getMigrationRuns(
+  limit: number = 20
+): Promise<MigrationRun[]> {
+  return await invoke<MigrationRun[]>("get_migration_runs", { limit });
+}
+
 // Saved Queries
 export async function saveQuery(
   name: string,
   query: string,
-  description: string | null
-): Promise<void> {
-  await invoke("save_query", { name, query, description });
+  description: string | null,
+  options: Omit<SavedQueryInput, "name" | "query" | "description"> = {}
+): Promise<SavedQuery> {
+  return await invoke<SavedQuery>("save_query", {
+    input: { name, query, description, ...options },
+  });
+}
+
+export async function updateSavedQuery(
+  id: number,
+  input: SavedQueryInput
+): Promise<SavedQuery> {
+  return await invoke<SavedQuery>("update_saved_query", { id, input });
 }
 
 export async function getSavedQueries(): Promise<SavedQuery[]> {
   return await invoke<SavedQuery[]>("get_saved_queries");
 }
 
+export async function searchSavedQueries(
+  search: SavedQuerySearch
+): Promise<SavedQuery[]> {
+  return await invoke<SavedQuery[]>("search_saved_queries", { search });
+}
+
 export async function deleteSavedQuery(id: number): Promise<void> {
   await invoke("delete_saved_query", { id });
 }
@@ -142,6 +324,47 @@ export async function togglePinQuery(id: number): Promise<void> {
   await invoke("toggle_pin_query", { id });
 }
 
+export async function listSavedQueryRevisions(
+  id: number
+): Promise<SavedQueryRevision[]> {
+  return await invoke<SavedQueryRevision[]>("list_saved_query_revisions", { id });
+}
+
+export async function diffSavedQueryRevisions(
+  id: number,
+  fromRevision: number,
+  toRevision: number
+): Promise<SavedQueryRevisionDiff> {
+  return await invoke<SavedQueryRevisionDiff>("diff_saved_query_revisions", {
+    id,
+    fromRevision,
+    toRevision,
+  });
+}
+
+export async function restoreSavedQueryRevision(
+  id: number,
+  revision: number
+): Promise<SavedQuery> {
+  return await invoke<SavedQuery>("restore_saved_query_revision", { id, revision });
+}
+
+// Keep saved queries as .sql files under {project_path}/queries
+export async function getSavedQueryFilesEnabled(): Promise<boolean> {
+  return await invoke<boolean>("get_saved_query_files_enabled");
+}
+
+export async function setSavedQueryFilesEnabled(enabled: boolean): Promise<void> {
+  await invoke("set_saved_query_files_enabled", { enabled });
+}
+
+// Fires when edits made outside the app to saved query files are picked up
+export async function onSavedQueriesChanged(
+  handler: () => void
+): Promise<UnlistenFn> {
+  return await listen("saved-queries-changed", () => handler());
+}
+
 // Settings/Project
 export async function loadProjectSettings(): Promise<void> {
   await invoke("load_project_settings");